@@ -0,0 +1,474 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+use log::{info, warn};
+use tungstenite::Message;
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+
+use crate::mapping::ChargeState;
+use crate::state::{SharedState, Status, Modes};
+use crate::config::AppConfig;
+use crate::settings;
+
+// --- Local Control API ---
+// Localhost-only HTTP + WebSocket server so scripts/overlays can drive DX3 without Tauri.
+//
+// REST:  GET  /api/status          GET  /api/profiles
+//        POST /api/profile {name}  POST /api/led {r,g,b,brightness}
+//        POST /api/pause           POST /api/resume
+//        POST /api/hide {hide?}    (omit `hide` to toggle)
+//        POST /api/command {cmd, ...}
+// Stream Deck: bodyless twins under /api/streamdeck/ for "web request" style buttons (POST to
+//        change something, GET to read), see handle_streamdeck. /api/streamdeck/battery answers
+//        plain text for the key title.
+// WS:    send {"cmd": "...", ...}, receive {"ok": .., "data"/"error": ..}
+//        plus {"event": "status", "data": ..} pushed whenever the status changes
+// WS /overlay: read-only input event stream for OBS overlays (see overlay.rs)
+// Pipe:  same JSON commands, one per line (see pipe.rs)
+//
+// Listening on 127.0.0.1 doesn't keep web pages out: the user's browser can reach it too. Requests
+// from a page carry an `Origin`, which has to be local, and `Host` has to name this server, which
+// stops DNS rebinding (an attacker's domain resolving to 127.0.0.1). No CORS headers are sent.
+
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum ApiCommand {
+    Status,
+    ListProfiles,
+    LoadProfile { name: String },
+    SetLed {
+        r: u8,
+        g: u8,
+        b: u8,
+        #[serde(default = "default_brightness")]
+        brightness: u8,
+    },
+    Pause,
+    Resume,
+    SetHide {
+        #[serde(default)]
+        hide: Option<bool>,
+    },
+}
+
+fn default_brightness() -> u8 { 255 }
+
+#[derive(Serialize, Clone, PartialEq)]
+pub struct ApiStatus {
+    pub status: Status,
+    pub message: String,
+    pub device_name: String,
+    pub connection_mode: String,
+    pub connected: bool,
+    pub battery: u8,
+    pub is_charging: bool,
+    pub charge_state: ChargeState,
+    pub headphones: bool,
+    pub mic: bool,
+    pub profile: String,
+    pub paused: bool,
+    pub hide_controller: bool,
+    pub foreground_process: String,
+    pub modes: Modes,
+}
+
+impl ApiStatus {
+    pub fn from_state(s: &SharedState) -> Self {
+        let (profile, hide_controller) = {
+            let settings = settings::read();
+            (settings.current_profile_name.clone(), settings.hide_controller)
+        };
+        Self {
+            status: s.status.clone(),
+            message: s.status.to_string(),
+            device_name: s.device_name.clone(),
+            connection_mode: s.connection_mode.clone(),
+            connected: s.device_name != "None",
+            battery: s.gamepad.battery,
+            is_charging: s.gamepad.is_charging,
+            charge_state: s.gamepad.charge_state,
+            headphones: s.gamepad.headphones,
+            mic: s.gamepad.mic,
+            profile,
+            paused: s.is_paused,
+            hide_controller,
+            foreground_process: s.foreground_process.clone(),
+            modes: Modes::from_state(s),
+        }
+    }
+}
+
+/// Shared by every transport (HTTP, WebSocket, ...)
+pub fn execute(state: &Arc<Mutex<SharedState>>, cmd: ApiCommand) -> Result<Value, String> {
+    let mut s = state.lock().unwrap();
+    match cmd {
+        ApiCommand::Status => {}
+        ApiCommand::ListProfiles => {
+            return serde_json::to_value(AppConfig::list_profiles()).map_err(|e| e.to_string());
+        }
+        ApiCommand::LoadProfile { name } => {
+            if !crate::load_profile_internal(&mut s, name.clone()) {
+                return Err(format!("Unknown profile: {}", name));
+            }
+        }
+        ApiCommand::SetLed { r, g, b, brightness } => {
+            settings::update(true, |st| {
+                st.rgb_r = r;
+                st.rgb_g = g;
+                st.rgb_b = b;
+                st.rgb_brightness = brightness;
+            });
+        }
+        ApiCommand::Pause => crate::set_paused_internal(&mut s, true),
+        ApiCommand::Resume => crate::set_paused_internal(&mut s, false),
+        ApiCommand::SetHide { hide } => {
+            // Worker picks up the change on its next HidHide check
+            settings::update(false, |st| st.hide_controller = hide.unwrap_or(!st.hide_controller));
+        }
+    }
+    serde_json::to_value(ApiStatus::from_state(&s)).map_err(|e| e.to_string())
+}
+
+/// One JSON command in, one JSON reply out (message-based transports)
+pub fn execute_json(state: &Arc<Mutex<SharedState>>, text: &str) -> String {
+    match serde_json::from_str::<ApiCommand>(text) {
+        Ok(cmd) => response_json(execute(state, cmd)),
+        Err(e) => response_json(Err(format!("Invalid command: {}", e))),
+    }
+}
+
+fn response_json(res: Result<Value, String>) -> String {
+    let body = match res {
+        Ok(data) => serde_json::json!({ "ok": true, "data": data }),
+        Err(e) => serde_json::json!({ "ok": false, "error": e }),
+    };
+    body.to_string()
+}
+
+/// Spawns the listener. It stops by itself once the API is disabled, the port changes or the app exits.
+pub fn start(state: Arc<Mutex<SharedState>>, port: u16) {
+    thread::spawn(move || {
+        let listener = match TcpListener::bind(("127.0.0.1", port)) {
+            Ok(l) => l,
+            Err(e) => {
+                warn!("Control API: failed to bind 127.0.0.1:{}: {}", port, e);
+                return;
+            }
+        };
+        // Non-blocking accept so we can notice when we should shut down
+        let _ = listener.set_nonblocking(true);
+        info!("Control API listening on 127.0.0.1:{}", port);
+
+        loop {
+            {
+                let exit = state.lock().unwrap().should_exit;
+                let settings = settings::read();
+                if exit || !settings.api_enabled || settings.api_port != port {
+                    break;
+                }
+            }
+
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let state = state.clone();
+                    thread::spawn(move || handle_connection(stream, state));
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(100));
+                }
+                Err(e) => {
+                    warn!("Control API: accept failed: {}", e);
+                    thread::sleep(Duration::from_millis(100));
+                }
+            }
+        }
+        info!("Control API on port {} stopped.", port);
+    });
+}
+
+/// `allow_file`: also accept pages opened from disk (Origin "null" or file://), read-only overlays only
+pub fn is_allowed_request(host: Option<&str>, origin: Option<&str>, port: u16, allow_file: bool) -> bool {
+    let is_local_host = |host: &str| matches!(host, "127.0.0.1" | "localhost" | "[::1]");
+    let host_ok = host.map(str::to_ascii_lowercase).is_some_and(|h| {
+        let (name, p) = h.rsplit_once(':').filter(|(_, p)| p.parse::<u16>().is_ok()).unwrap_or((&h, "80"));
+        is_local_host(name) && p.parse::<u16>() == Ok(port)
+    });
+    let origin_ok = match origin.map(str::to_ascii_lowercase) {
+        None => true,
+        Some(o) if allow_file && (o == "null" || o.starts_with("file://")) => true,
+        // Any local page, whatever port its own server uses
+        Some(o) => o.strip_prefix("http://").or_else(|| o.strip_prefix("https://")).is_some_and(|a| {
+            let name = a.rsplit_once(':').filter(|(_, p)| p.parse::<u16>().is_ok()).map_or(a, |(n, _)| n);
+            is_local_host(name)
+        }),
+    };
+    host_ok && origin_ok
+}
+
+/// WebSocket handshake that turns away requests `is_allowed_request` rejects
+pub fn accept_local(stream: TcpStream, allow_file: bool) -> Result<tungstenite::WebSocket<TcpStream>, String> {
+    let port = stream.local_addr().map_err(|e| e.to_string())?.port();
+    let check = |req: &Request, res: Response| {
+        let header = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok());
+        if is_allowed_request(header("host"), header("origin"), port, allow_file) {
+            Ok(res)
+        } else {
+            let mut reject = ErrorResponse::new(Some("Forbidden origin".to_string()));
+            *reject.status_mut() = tungstenite::http::StatusCode::FORBIDDEN;
+            Err(reject)
+        }
+    };
+    tungstenite::accept_hdr(stream, check).map_err(|e| e.to_string())
+}
+
+fn handle_connection(stream: TcpStream, state: Arc<Mutex<SharedState>>) {
+    // Accepted sockets inherit non-blocking mode from the listener on Windows
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+
+    // Peek (don't consume) so the WebSocket handshake can still read the request
+    let mut peek_buf = [0u8; 2048];
+    let n = stream.peek(&mut peek_buf).unwrap_or(0);
+    let head = String::from_utf8_lossy(&peek_buf[..n]).to_ascii_lowercase();
+
+    if head.contains("upgrade: websocket") {
+        if head.starts_with("get /overlay") {
+            crate::overlay::handle_websocket(stream, state);
+        } else {
+            handle_websocket(stream, state);
+        }
+    } else if let Err(e) = handle_http(stream, state) {
+        warn!("Control API: HTTP error: {}", e);
+    }
+}
+
+fn handle_http(mut stream: TcpStream, state: Arc<Mutex<SharedState>>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let path = parts.next().unwrap_or("").to_string();
+
+    let mut content_length = 0usize;
+    let (mut host, mut origin) = (None, None);
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((key, val)) = line.split_once(':') {
+            let key = key.trim();
+            if key.eq_ignore_ascii_case("content-length") {
+                content_length = val.trim().parse().unwrap_or(0);
+            } else if key.eq_ignore_ascii_case("host") {
+                host = Some(val.trim().to_string());
+            } else if key.eq_ignore_ascii_case("origin") {
+                origin = Some(val.trim().to_string());
+            }
+        }
+    }
+
+    let port = stream.local_addr()?.port();
+    if !is_allowed_request(host.as_deref(), origin.as_deref(), port, false) {
+        warn!("Control API: refused {} {} (host {:?}, origin {:?})", method, path, host, origin);
+        return write_http(&mut stream, "403 Forbidden", &response_json(Err("Forbidden origin".to_string())));
+    }
+
+    let mut body = vec![0u8; content_length.min(64 * 1024)];
+    reader.read_exact(&mut body)?;
+
+    if let Some(action) = path.strip_prefix("/api/streamdeck/") {
+        return handle_streamdeck(&mut stream, &state, &method, action);
+    }
+
+    let cmd_name = match (method.as_str(), path.as_str()) {
+        ("GET", "/api/status") => "status",
+        ("GET", "/api/profiles") => "list_profiles",
+        ("POST", "/api/profile") => "load_profile",
+        ("POST", "/api/led") => "set_led",
+        ("POST", "/api/pause") => "pause",
+        ("POST", "/api/resume") => "resume",
+        ("POST", "/api/hide") => "set_hide",
+        ("POST", "/api/command") => "",
+        _ => {
+            return write_http(&mut stream, "404 Not Found", &response_json(Err(format!("No route for {} {}", method, path))));
+        }
+    };
+
+    let (status, reply) = match parse_command(cmd_name, &body) {
+        Ok(cmd) => {
+            let res = execute(&state, cmd);
+            (if res.is_ok() { "200 OK" } else { "400 Bad Request" }, response_json(res))
+        }
+        Err(e) => ("400 Bad Request", response_json(Err(e))),
+    };
+    write_http(&mut stream, status, &reply)
+}
+
+/// Route-specific endpoints just inject the `cmd` tag into the JSON body
+fn parse_command(cmd_name: &str, body: &[u8]) -> Result<ApiCommand, String> {
+    let mut value: Value = if body.is_empty() {
+        serde_json::json!({})
+    } else {
+        serde_json::from_slice(body).map_err(|e| format!("Invalid JSON: {}", e))?
+    };
+    if !cmd_name.is_empty() {
+        match value.as_object_mut() {
+            Some(obj) => { obj.insert("cmd".to_string(), Value::from(cmd_name)); }
+            None => return Err("Body must be a JSON object".to_string()),
+        }
+    }
+    serde_json::from_value(value).map_err(|e| format!("Invalid command: {}", e))
+}
+
+/// Stream Deck "web request" plugins send bodyless requests and show the response as the key
+/// title, so everything is encoded in the path and answered with short plain text. Anything that
+/// changes state must be a POST: a web page can fire a GET at us with a plain <img> tag, and those
+/// carry no Origin to check.
+///
+///   POST: profile/<name>   toggle-pause    hide/toggle|on|off   led/<RRGGBB>
+///   GET:  battery          status
+fn handle_streamdeck(stream: &mut TcpStream, state: &Arc<Mutex<SharedState>>, method: &str, action: &str) -> io::Result<()> {
+    let (action, arg) = action.split_once('/').unwrap_or((action, ""));
+    let read_only = matches!(action, "battery" | "status");
+    match method {
+        "GET" if read_only => {}
+        "POST" if !read_only => {}
+        "GET" | "POST" => return write_http_text(stream, "405 Method Not Allowed", if read_only { "Use GET" } else { "Use POST" }),
+        _ => return write_http_text(stream, "404 Not Found", "Unknown action"),
+    }
+
+    let cmd = match (action, arg) {
+        ("battery", _) | ("status", _) => ApiCommand::Status,
+        ("profile", name) if !name.is_empty() => ApiCommand::LoadProfile { name: percent_decode(name) },
+        ("toggle-pause", _) => {
+            if state.lock().unwrap().is_paused { ApiCommand::Resume } else { ApiCommand::Pause }
+        }
+        ("hide", "toggle") => ApiCommand::SetHide { hide: None },
+        ("hide", "on") => ApiCommand::SetHide { hide: Some(true) },
+        ("hide", "off") => ApiCommand::SetHide { hide: Some(false) },
+        ("led", hex) => match parse_hex_color(hex) {
+            Some((r, g, b)) => ApiCommand::SetLed { r, g, b, brightness: default_brightness() },
+            None => return write_http_text(stream, "400 Bad Request", "Bad color"),
+        },
+        _ => return write_http_text(stream, "404 Not Found", "Unknown action"),
+    };
+
+    if let Err(e) = execute(state, cmd) {
+        return write_http_text(stream, "400 Bad Request", &e);
+    }
+
+    let s = state.lock().unwrap();
+    let settings = settings::read();
+    let text = match action {
+        "battery" if s.device_name == "None" => "--".to_string(),
+        "battery" if s.gamepad.is_charging => format!("{}% +", s.gamepad.battery),
+        "battery" => format!("{}%", s.gamepad.battery),
+        "profile" => settings.current_profile_name.clone(),
+        "hide" => (if settings.hide_controller { "Hidden" } else { "Visible" }).to_string(),
+        _ => s.status.to_string(),
+    };
+    drop(settings);
+    drop(s);
+    write_http_text(stream, "200 OK", &text)
+}
+
+fn parse_hex_color(hex: &str) -> Option<(u8, u8, u8)> {
+    let hex = hex.trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+    let r = u8::from_str_radix(&hex[0..2], 16).ok()?;
+    let g = u8::from_str_radix(&hex[2..4], 16).ok()?;
+    let b = u8::from_str_radix(&hex[4..6], 16).ok()?;
+    Some((r, g, b))
+}
+
+/// Profile names may contain spaces (%20)
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(v) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(v);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(if bytes[i] == b'+' { b' ' } else { bytes[i] });
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn write_http(stream: &mut TcpStream, status: &str, body: &str) -> io::Result<()> {
+    write_http_with_type(stream, status, "application/json", body)
+}
+
+fn write_http_text(stream: &mut TcpStream, status: &str, body: &str) -> io::Result<()> {
+    write_http_with_type(stream, status, "text/plain; charset=utf-8", body)
+}
+
+fn write_http_with_type(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) -> io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, content_type, body.len(), body
+    );
+    stream.write_all(response.as_bytes())?;
+    stream.flush()
+}
+
+fn handle_websocket(stream: TcpStream, state: Arc<Mutex<SharedState>>) {
+    let mut ws = match accept_local(stream, false) {
+        Ok(ws) => ws,
+        Err(e) => {
+            warn!("Control API: WebSocket handshake failed: {}", e);
+            return;
+        }
+    };
+    // Short read timeout doubles as the push interval
+    let _ = ws.get_ref().set_read_timeout(Some(Duration::from_millis(100)));
+
+    let mut last_status: Option<ApiStatus> = None;
+    loop {
+        let status = {
+            let s = state.lock().unwrap();
+            if s.should_exit || !settings::read().api_enabled {
+                break;
+            }
+            ApiStatus::from_state(&s)
+        };
+
+        if last_status.as_ref() != Some(&status) {
+            let event = serde_json::json!({ "event": "status", "data": status });
+            if ws.send(Message::Text(event.to_string())).is_err() {
+                break;
+            }
+            last_status = Some(status);
+        }
+
+        match ws.read() {
+            Ok(Message::Text(text)) => {
+                let reply = execute_json(&state, &text);
+                if ws.send(Message::Text(reply)).is_err() {
+                    break;
+                }
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(ref e))
+                if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => {}
+            Err(_) => break,
+        }
+    }
+    let _ = ws.close(None);
+}
@@ -0,0 +1,94 @@
+use crate::config::APP_NAME;
+
+/// Passed by the Run entry so login doesn't pop the window up
+pub const MINIMIZED_ARG: &str = "--minimized";
+
+pub use sys::{is_enabled, enable, disable};
+
+#[cfg(windows)]
+mod sys {
+    use std::process::Command;
+    use std::os::windows::process::CommandExt;
+    use super::{APP_NAME, MINIMIZED_ARG};
+
+    const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    pub fn is_enabled() -> bool {
+        Command::new("reg")
+            .args(["query", RUN_KEY, "/v", APP_NAME])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    }
+
+    pub fn enable() -> anyhow::Result<()> {
+        let current_exe = std::env::current_exe()?;
+        // Always rewrite the value so a moved/updated exe gets picked up
+        let command_line = format!("\"{}\" {}", current_exe.display(), MINIMIZED_ARG);
+
+        run_reg(&["add", RUN_KEY, "/v", APP_NAME, "/t", "REG_SZ", "/d", &command_line, "/f"])
+    }
+
+    pub fn disable() -> anyhow::Result<()> {
+        if !is_enabled() {
+            return Ok(());
+        }
+        run_reg(&["delete", RUN_KEY, "/v", APP_NAME, "/f"])
+    }
+
+    fn run_reg(args: &[&str]) -> anyhow::Result<()> {
+        let output = Command::new("reg")
+            .args(args)
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()?;
+
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr);
+            log::error!("reg.exe error. Args: {:?}. Status: {}. Stderr: {}", args, output.status, err);
+            return Err(anyhow::anyhow!("Registry error: {}", err.trim()));
+        }
+
+        Ok(())
+    }
+}
+
+// XDG autostart entry, picked up by every desktop environment that follows the spec
+#[cfg(not(windows))]
+mod sys {
+    use std::path::PathBuf;
+    use super::{APP_NAME, MINIMIZED_ARG};
+
+    fn entry_path() -> Option<PathBuf> {
+        let config = std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+        Some(config.join("autostart").join(format!("{}.desktop", APP_NAME.to_lowercase())))
+    }
+
+    pub fn is_enabled() -> bool {
+        entry_path().is_some_and(|p| p.exists())
+    }
+
+    pub fn enable() -> anyhow::Result<()> {
+        let current_exe = std::env::current_exe()?;
+        let path = entry_path().ok_or_else(|| anyhow::anyhow!("No home directory"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // Always rewrite the entry so a moved/updated binary gets picked up
+        let entry = format!(
+            "[Desktop Entry]\nType=Application\nName={}\nExec=\"{}\" {}\nX-GNOME-Autostart-enabled=true\n",
+            APP_NAME, current_exe.display(), MINIMIZED_ARG,
+        );
+        std::fs::write(&path, entry)?;
+        Ok(())
+    }
+
+    pub fn disable() -> anyhow::Result<()> {
+        match entry_path() {
+            Some(path) if path.exists() => Ok(std::fs::remove_file(path)?),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::{info, warn};
+
+// --- Traffic Capture ---
+// Records raw HID traffic (input reports and everything DX3 writes) while enabled from the debug
+// panel, and exports it by file extension:
+//
+// .pcapng  One interface with LINKTYPE_USER0 (147), microsecond timestamps. Packet data is
+//          [direction][report bytes...], direction 0x00 = input (controller -> PC),
+//          0x01 = output report, 0x02 = feature report (PC -> controller).
+// .jsonl   One {"ts_us": <unix µs>, "dir": "in"|"out"|"feature", "hex": "31 02 .."} per line.
+
+const LINKTYPE_USER0: u16 = 147;
+// Worst case ~40 MB; recording stops by itself after this
+const MAX_PACKETS: usize = 500_000;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Direction {
+    In,
+    Out,
+    Feature,
+}
+
+impl Direction {
+    fn code(self) -> u8 {
+        match self {
+            Self::In => 0x00,
+            Self::Out => 0x01,
+            Self::Feature => 0x02,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::In => "in",
+            Self::Out => "out",
+            Self::Feature => "feature",
+        }
+    }
+}
+
+struct Packet {
+    ts_us: u64,
+    dir: Direction,
+    data: Vec<u8>,
+}
+
+// Checked on every report, so no lock when idle
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static PACKETS: Mutex<Vec<Packet>> = Mutex::new(Vec::new());
+
+/// Starts a new capture, discarding the previous one
+pub fn start() {
+    PACKETS.lock().unwrap().clear();
+    ACTIVE.store(true, Ordering::SeqCst);
+    info!("Traffic capture started");
+}
+
+pub fn stop() {
+    if ACTIVE.swap(false, Ordering::SeqCst) {
+        info!("Traffic capture stopped ({} packets)", packet_count());
+    }
+}
+
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+pub fn packet_count() -> usize {
+    PACKETS.lock().unwrap().len()
+}
+
+pub fn record(dir: Direction, data: &[u8]) {
+    if !is_active() {
+        return;
+    }
+    let ts_us = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_micros() as u64).unwrap_or(0);
+    let mut packets = PACKETS.lock().unwrap();
+    if packets.len() >= MAX_PACKETS {
+        ACTIVE.store(false, Ordering::SeqCst);
+        warn!("Traffic capture reached {} packets, stopped", MAX_PACKETS);
+        return;
+    }
+    packets.push(Packet { ts_us, dir, data: data.to_vec() });
+}
+
+/// Writes the current capture to `path` (.pcapng or .jsonl). Returns the packet count.
+pub fn export(path: &Path) -> anyhow::Result<usize> {
+    let packets = PACKETS.lock().unwrap();
+    let mut out = BufWriter::new(File::create(path)?);
+    let is_jsonl = path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("jsonl"));
+
+    if is_jsonl {
+        for p in packets.iter() {
+            let hex = p.data.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ");
+            writeln!(out, "{}", serde_json::json!({ "ts_us": p.ts_us, "dir": p.dir.name(), "hex": hex }))?;
+        }
+    } else {
+        write_pcapng(&mut out, &packets)?;
+    }
+    out.flush()?;
+    info!("Exported {} captured packets to {:?}", packets.len(), path);
+    Ok(packets.len())
+}
+
+fn write_block(out: &mut impl Write, block_type: u32, body: &[u8]) -> std::io::Result<()> {
+    let padded = body.len().div_ceil(4) * 4;
+    let total = (12 + padded) as u32;
+    out.write_all(&block_type.to_le_bytes())?;
+    out.write_all(&total.to_le_bytes())?;
+    out.write_all(body)?;
+    out.write_all(&vec![0u8; padded - body.len()])?;
+    out.write_all(&total.to_le_bytes())
+}
+
+fn write_pcapng(out: &mut impl Write, packets: &[Packet]) -> std::io::Result<()> {
+    // Section Header Block
+    let mut shb = Vec::new();
+    shb.extend_from_slice(&0x1A2B3C4Du32.to_le_bytes()); // Byte-order magic
+    shb.extend_from_slice(&1u16.to_le_bytes());
+    shb.extend_from_slice(&0u16.to_le_bytes());
+    shb.extend_from_slice(&(-1i64).to_le_bytes()); // Section length unknown
+    write_block(out, 0x0A0D0D0A, &shb)?;
+
+    // Interface Description Block (default if_tsresol = microseconds)
+    let mut idb = Vec::new();
+    idb.extend_from_slice(&LINKTYPE_USER0.to_le_bytes());
+    idb.extend_from_slice(&0u16.to_le_bytes());
+    idb.extend_from_slice(&0u32.to_le_bytes()); // No snap length
+    write_block(out, 0x00000001, &idb)?;
+
+    // Enhanced Packet Blocks
+    for p in packets {
+        let len = (p.data.len() + 1) as u32;
+        let mut epb = Vec::with_capacity(21 + p.data.len());
+        epb.extend_from_slice(&0u32.to_le_bytes()); // Interface 0
+        epb.extend_from_slice(&((p.ts_us >> 32) as u32).to_le_bytes());
+        epb.extend_from_slice(&(p.ts_us as u32).to_le_bytes());
+        epb.extend_from_slice(&len.to_le_bytes());
+        epb.extend_from_slice(&len.to_le_bytes());
+        epb.push(p.dir.code());
+        epb.extend_from_slice(&p.data);
+        write_block(out, 0x00000006, &epb)?;
+    }
+    Ok(())
+}
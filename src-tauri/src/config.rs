@@ -1,9 +1,36 @@
 use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
-use crate::mapping::{ButtonMapping, PhysicalButton, MappingTarget};
+use std::path::{Component, Path, PathBuf};
+use crate::mapping::{ButtonMapping, PhysicalButton, MappingTarget, Orientation, AutoSprint, TriggerCurve, ResponseCurve, DeadzoneShape, InputBackend, MAX_TURBO_HZ};
+use crate::layout::ReportLayout;
+use crate::touch::TouchCalibration;
+use crate::kbm::KbmGamepad;
+use crate::focus::FocusPause;
+use crate::quiet::QuietHours;
+use crate::platform::PadKind;
 
 pub const APP_NAME: &str = "DX3";
+// Longest parent chain a profile may inherit through
+const MAX_PROFILE_DEPTH: usize = 16;
+
+// Manual Override packet parameters (debug panel), also saved as named templates
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ManualParams {
+    pub report_id: u8,
+    pub flag_off: usize,
+    pub rgb_off: usize,
+    pub player_led: u8,
+    pub pled_bright: u8,
+    pub pled_bright_off: usize,
+    pub bt_flags: u8,
+    pub bt_flags2: u8,
+    pub bt_len: usize,
+    pub as_feature: bool,
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Profile {
@@ -12,6 +39,14 @@ pub struct Profile {
     pub deadzone_left: f32,
     #[serde(default = "default_deadzone")]
     pub deadzone_right: f32,
+    #[serde(default)]
+    pub anti_deadzone_left: f32, // Output magnitude the stick starts at once it leaves the deadzone
+    #[serde(default)]
+    pub anti_deadzone_right: f32,
+    #[serde(default)]
+    pub deadzone_shape_left: DeadzoneShape,
+    #[serde(default)]
+    pub deadzone_shape_right: DeadzoneShape,
     #[serde(default = "default_mouse_sens")]
     pub mouse_sens_left: f32,
     #[serde(default = "default_mouse_sens")]
@@ -42,6 +77,29 @@ pub struct Profile {
     pub trigger_r2_force: u8,
     #[serde(default)]
     pub player_led_brightness: u8,
+    #[serde(default)]
+    pub auto_sprint: Option<AutoSprint>,
+    #[serde(default)]
+    pub trigger_curve_l: TriggerCurve,
+    #[serde(default)]
+    pub trigger_curve_r: TriggerCurve,
+    #[serde(default)]
+    pub stick_curve_l: ResponseCurve,
+    #[serde(default)]
+    pub stick_curve_r: ResponseCurve,
+    #[serde(default)]
+    pub trackball_friction: Option<f32>,
+    #[serde(default)]
+    pub input_backend: InputBackend,
+    #[serde(default)]
+    pub pad_kind: PadKind,
+    #[serde(default)]
+    pub swap_sticks: bool,
+    #[serde(default)]
+    pub shift_button: Option<PhysicalButton>,
+    // Profile this one inherits from; only the fields that differ from it are saved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
 }
 
 impl Default for Profile {
@@ -50,6 +108,10 @@ impl Default for Profile {
             mappings: AppConfig::default_mappings(),
             deadzone_left: 0.1,
             deadzone_right: 0.1,
+            anti_deadzone_left: 0.0,
+            anti_deadzone_right: 0.0,
+            deadzone_shape_left: DeadzoneShape::default(),
+            deadzone_shape_right: DeadzoneShape::default(),
             mouse_sens_left: 25.0,
             mouse_sens_right: 25.0,
             mouse_sens_touchpad: 25.0,
@@ -65,6 +127,158 @@ impl Default for Profile {
             trigger_r2_start: 0,
             trigger_r2_force: 0,
             player_led_brightness: 0,
+            auto_sprint: None,
+            trigger_curve_l: TriggerCurve::default(),
+            trigger_curve_r: TriggerCurve::default(),
+            stick_curve_l: ResponseCurve::default(),
+            stick_curve_r: ResponseCurve::default(),
+            trackball_friction: None,
+            input_backend: InputBackend::SendInput,
+            pad_kind: PadKind::Xbox360,
+            swap_sticks: false,
+            shift_button: None,
+            parent: None,
+        }
+    }
+}
+
+impl Profile {
+    /// Rejects values the UI can't produce, so a hand-edited or damaged file fails on import
+    /// instead of driving the sticks with a NaN deadzone
+    pub fn validate(&self) -> Result<(), String> {
+        let unit = |name: &str, v: f32| match (0.0..=1.0).contains(&v) {
+            true => Ok(()),
+            false => Err(format!("{} must be between 0 and 1 (got {})", name, v)),
+        };
+        let non_negative = |name: &str, v: f32| match v.is_finite() && v >= 0.0 {
+            true => Ok(()),
+            false => Err(format!("{} must be a number of 0 or more (got {})", name, v)),
+        };
+
+        if self.mappings.is_empty() {
+            return Err("Profile has no button mappings".to_string());
+        }
+        for m in &self.mappings {
+            if let Some(hz) = m.turbo_hz.filter(|hz| !(*hz > 0.0 && *hz <= MAX_TURBO_HZ)) {
+                return Err(format!("{:?}: turbo rate must be above 0 and at most {} Hz (got {})", m.source, MAX_TURBO_HZ, hz));
+            }
+            for t in m.targets.iter().chain(&m.shift_targets) {
+                if let MappingTarget::XboxDpad { threshold } = t {
+                    if !(*threshold > 0.0 && *threshold < 1.0) {
+                        return Err(format!("{:?}: D-pad threshold must be between 0 and 1 (got {})", m.source, threshold));
+                    }
+                }
+            }
+        }
+        unit("deadzone_left", self.deadzone_left)?;
+        unit("deadzone_right", self.deadzone_right)?;
+        unit("anti_deadzone_left", self.anti_deadzone_left)?;
+        unit("anti_deadzone_right", self.anti_deadzone_right)?;
+        non_negative("mouse_sens_left", self.mouse_sens_left)?;
+        non_negative("mouse_sens_right", self.mouse_sens_right)?;
+        non_negative("mouse_sens_touchpad", self.mouse_sens_touchpad)?;
+        for (name, c) in [("trigger_curve_l", &self.trigger_curve_l), ("trigger_curve_r", &self.trigger_curve_r)] {
+            unit(&format!("{}.min", name), c.min)?;
+            unit(&format!("{}.max", name), c.max)?;
+            non_negative(&format!("{}.curve", name), c.curve)?;
+            for p in &c.points {
+                unit(&format!("{}.points", name), p[0])?;
+                unit(&format!("{}.points", name), p[1])?;
+            }
+        }
+        for (name, c) in [("stick_curve_l", &self.stick_curve_l), ("stick_curve_r", &self.stick_curve_r)] {
+            match c {
+                ResponseCurve::Linear => {}
+                ResponseCurve::Exponential(e) => non_negative(name, *e)?,
+                ResponseCurve::Custom(points) => for p in points {
+                    unit(name, p[0])?;
+                    unit(name, p[1])?;
+                },
+            }
+        }
+        if let Some(friction) = self.trackball_friction {
+            unit("trackball_friction", friction)?;
+        }
+        if let Some(button) = self.shift_button.filter(|b| b.is_axis()) {
+            return Err(format!("{:?} can't be the shift button", button));
+        }
+        Ok(())
+    }
+}
+
+// --- Shareable Profile Files ---
+// Export/import format for passing a profile around. The parent chain is merged in, so the file
+// loads the same on a PC that doesn't have the parents. The header tells an import apart from any
+// other JSON file, and from a file written by a newer DX3 with fields this one doesn't know.
+const PROFILE_FILE_FORMAT: &str = "dx3-profile";
+const PROFILE_FILE_VERSION: u64 = 1;
+
+#[derive(Serialize, Deserialize)]
+pub struct ProfileFile {
+    // Checked on the raw JSON, before the profile is parsed
+    #[allow(dead_code)]
+    format: String,
+    #[allow(dead_code)]
+    version: u64,
+    pub name: String,
+    pub profile: Profile,
+}
+
+impl ProfileFile {
+    pub fn new(name: &str, mut profile: Profile) -> Self {
+        profile.parent = None;
+        Self { format: PROFILE_FILE_FORMAT.to_string(), version: PROFILE_FILE_VERSION, name: name.to_string(), profile }
+    }
+
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let value: serde_json::Value = serde_json::from_str(&content).map_err(|e| format!("Not a JSON file: {}", e))?;
+        // Header first, so other JSON gets a clear error rather than a missing field
+        if value.get("format").and_then(|f| f.as_str()) != Some(PROFILE_FILE_FORMAT) {
+            return Err("Not a DX3 profile file".to_string());
+        }
+        match value.get("version").and_then(|v| v.as_u64()) {
+            Some(v) if v > PROFILE_FILE_VERSION => return Err(format!("Profile file version {} needs a newer DX3", v)),
+            Some(v) if v >= 1 => {}
+            _ => return Err("Profile file has no valid version".to_string()),
+        }
+        let file: Self = serde_json::from_value(value).map_err(|e| format!("Invalid profile: {}", e))?;
+        file.profile.validate()?;
+        Ok(file)
+    }
+}
+
+// MQTT broker settings (opt-in battery/connection publishing)
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct MqttConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_mqtt_host")]
+    pub host: String,
+    #[serde(default = "default_mqtt_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default = "default_mqtt_topic")]
+    pub topic: String,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: default_mqtt_host(),
+            port: default_mqtt_port(),
+            username: String::new(),
+            password: String::new(),
+            topic: default_mqtt_topic(),
         }
     }
 }
@@ -79,6 +293,14 @@ pub struct AppConfig {
     pub deadzone_left: f32,
     #[serde(default = "default_deadzone")]
     pub deadzone_right: f32,
+    #[serde(default)]
+    pub anti_deadzone_left: f32, // Output magnitude the stick starts at once it leaves the deadzone
+    #[serde(default)]
+    pub anti_deadzone_right: f32,
+    #[serde(default)]
+    pub deadzone_shape_left: DeadzoneShape,
+    #[serde(default)]
+    pub deadzone_shape_right: DeadzoneShape,
     #[serde(default = "default_mouse_sens")]
     pub mouse_sens_left: f32,
     #[serde(default = "default_mouse_sens")]
@@ -112,14 +334,117 @@ pub struct AppConfig {
     pub trigger_r2_force: u8,
     #[serde(default)]
     pub player_led_brightness: u8, // 0=High, 1=Med, 2=Low
+    #[serde(default)]
+    pub auto_sprint: Option<AutoSprint>,
+    #[serde(default)]
+    pub trigger_curve_l: TriggerCurve,
+    #[serde(default)]
+    pub trigger_curve_r: TriggerCurve,
+    #[serde(default)]
+    pub stick_curve_l: ResponseCurve,
+    #[serde(default)]
+    pub stick_curve_r: ResponseCurve,
+    #[serde(default)]
+    pub trackball_friction: Option<f32>, // Touchpad flick coasting; share of speed lost per second. None = off
+    #[serde(default)]
+    pub input_backend: InputBackend, // Keyboard/mouse injection (interception.rs)
+    #[serde(default)]
+    pub pad_kind: PadKind, // Virtual Xbox 360 or DS4 (platform::PadKind)
+    #[serde(default)]
+    pub swap_sticks: bool, // Left-handed: the sticks (and L3/R3) trade roles
+    #[serde(default)]
+    pub shift_button: Option<PhysicalButton>, // Held: mappings switch to their shift_targets
+    // Local Control API (localhost only, opt-in)
+    #[serde(default)]
+    pub api_enabled: bool,
+    #[serde(default = "default_api_port")]
+    pub api_port: u16,
+    // Global pause/resume hotkey (Tauri accelerator, e.g. "Ctrl+Alt+P"). Empty = disabled.
+    #[serde(default)]
+    pub pause_hotkey: String,
+    // error / warn / info / debug / trace
+    #[serde(default = "default_log_level")]
+    pub log_level: String,
+    // Discord Rich Presence (opt-in, needs a Discord application id)
+    #[serde(default)]
+    pub discord_enabled: bool,
+    #[serde(default)]
+    pub discord_app_id: String,
+    #[serde(default)]
+    pub mqtt: MqttConfig,
+    // Power off an idle Bluetooth DualSense after N minutes (0 = never)
+    #[serde(default)]
+    pub idle_power_off_min: u32,
+    // Hold PS for 2s to show the window (PS goes to the pad on release instead)
+    #[serde(default = "default_true")]
+    pub ps_hold_opens_window: bool,
+    // Buttons held together to power the controller off (empty = disabled)
+    #[serde(default)]
+    pub power_off_combo: Vec<PhysicalButton>,
+    #[serde(default = "default_power_off_hold")]
+    pub power_off_hold_ms: u32,
+    // Buttons pressed together to switch to the next profile (empty = disabled)
+    #[serde(default)]
+    pub profile_cycle_combo: Vec<PhysicalButton>,
+    // Commands run when a controller attaches / detaches (see hooks.rs)
+    #[serde(default)]
+    pub on_connect_cmd: String,
+    #[serde(default)]
+    pub on_disconnect_cmd: String,
+    // Custom input report layouts for clones, keyed by "VID:PID" (see layout.rs)
+    #[serde(default)]
+    pub report_layouts: HashMap<String, ReportLayout>,
+    // Parse input reports using the offsets from the HID report descriptor (experimental)
+    #[serde(default)]
+    pub descriptor_parsing: bool,
+    // Learned touchpad ranges, keyed by "VID:PID" (see touch.rs)
+    #[serde(default)]
+    pub touch_calibrations: HashMap<String, TouchCalibration>,
+    #[serde(default)]
+    pub orientation: Orientation,
+    // Resting stick positions [LX, LY, RX, RY], keyed by controller serial (see stick_cal.rs)
+    #[serde(default)]
+    pub stick_centers: HashMap<String, [f32; 4]>,
+    // Resting gyro rates [pitch, yaw, roll] in deg/s, keyed by controller serial (see gyro_cal.rs)
+    #[serde(default)]
+    pub gyro_biases: HashMap<String, [f32; 3]>,
+    // Keyboard/mouse drive the virtual pad while no controller is connected (see kbm.rs)
+    #[serde(default)]
+    pub kbm_gamepad: KbmGamepad,
+    // Mapped keyboard/mouse outputs only while one of these games is focused (see focus.rs)
+    #[serde(default)]
+    pub focus_pause: FocusPause,
+    // Rumble pulses on profile switch and low battery (DualSense)
+    #[serde(default = "default_true")]
+    pub haptic_alerts: bool,
+    // vJoy device (1-16) driven next to the virtual pad, 0 = off (see vjoy.rs)
+    #[serde(default)]
+    pub vjoy_device: u8,
+    // Don't attach controllers as they show up; list them and wait for connect_device
+    #[serde(default)]
+    pub manual_connect: bool,
+    // LED/trigger output is only re-sent when it changes, plus every this many seconds (0 = never)
+    #[serde(default = "default_output_keepalive")]
+    pub output_keepalive_secs: u32,
+    // Dim LEDs and skip rumble at night, over any profile (see quiet.rs)
+    #[serde(default)]
+    pub quiet_hours: QuietHours,
 }
 
 fn default_deadzone() -> f32 { 0.1 }
+fn default_output_keepalive() -> u32 { 10 }
 fn default_mouse_sens() -> f32 { 25.0 }
 fn default_rgb_r() -> u8 { 0 }
 fn default_rgb_g() -> u8 { 0 }
 fn default_rgb_b() -> u8 { 255 }
 fn default_rgb_bright() -> u8 { 255 }
+fn default_api_port() -> u16 { 7437 }
+fn default_log_level() -> String { "info".to_string() }
+fn default_true() -> bool { true }
+fn default_power_off_hold() -> u32 { 3000 }
+fn default_mqtt_host() -> String { "localhost".to_string() }
+fn default_mqtt_port() -> u16 { 1883 }
+fn default_mqtt_topic() -> String { "dx3".to_string() }
 
 impl Default for AppConfig {
     fn default() -> Self {
@@ -129,6 +454,10 @@ impl Default for AppConfig {
             mappings: Self::default_mappings(),
             deadzone_left: 0.1,
             deadzone_right: 0.1,
+            anti_deadzone_left: 0.0,
+            anti_deadzone_right: 0.0,
+            deadzone_shape_left: DeadzoneShape::default(),
+            deadzone_shape_right: DeadzoneShape::default(),
             mouse_sens_left: 25.0,
             mouse_sens_right: 25.0,
             mouse_sens_touchpad: 25.0,
@@ -145,75 +474,86 @@ impl Default for AppConfig {
             trigger_r2_start: 0,
             trigger_r2_force: 0,
             player_led_brightness: 0,
+            auto_sprint: None,
+            trigger_curve_l: TriggerCurve::default(),
+            trigger_curve_r: TriggerCurve::default(),
+            stick_curve_l: ResponseCurve::default(),
+            stick_curve_r: ResponseCurve::default(),
+            trackball_friction: None,
+            input_backend: InputBackend::SendInput,
+            pad_kind: PadKind::Xbox360,
+            swap_sticks: false,
+            shift_button: None,
+            api_enabled: false,
+            api_port: default_api_port(),
+            pause_hotkey: String::new(),
+            log_level: default_log_level(),
+            discord_enabled: false,
+            discord_app_id: String::new(),
+            mqtt: MqttConfig::default(),
+            idle_power_off_min: 0,
+            ps_hold_opens_window: true,
+            power_off_combo: Vec::new(),
+            power_off_hold_ms: default_power_off_hold(),
+            profile_cycle_combo: Vec::new(),
+            on_connect_cmd: String::new(),
+            on_disconnect_cmd: String::new(),
+            report_layouts: HashMap::new(),
+            descriptor_parsing: false,
+            touch_calibrations: HashMap::new(),
+            orientation: Orientation::Normal,
+            stick_centers: HashMap::new(),
+            gyro_biases: HashMap::new(),
+            kbm_gamepad: KbmGamepad::default(),
+            focus_pause: FocusPause::default(),
+            haptic_alerts: true,
+            vjoy_device: 0,
+            manual_connect: false,
+            output_keepalive_secs: default_output_keepalive(),
+            quiet_hours: QuietHours::default(),
         }
     }
 }
 
 impl AppConfig {
-    pub fn save_internal(
-        hide: bool, min: bool, mappings: Vec<ButtonMapping>, 
-        dl: f32, dr: f32, p: String, msl: f32, msr: f32, mst: f32,
-        r: u8, g: u8, b: u8, bright: u8, bat_led: bool,
-        tl2_mode: u8, tl2_start: u8, tl2_force: u8,
-        tr2_mode: u8, tr2_start: u8, tr2_force: u8,
-        pled_bright: u8,
-    ) {
-        let config = AppConfig { 
-            hide_controller: hide,
-            start_minimized: min,
-            mappings: mappings.clone(),
-            deadzone_left: dl,
-            deadzone_right: dr,
-            mouse_sens_left: msl,
-            mouse_sens_right: msr,
-            mouse_sens_touchpad: mst,
-            active_profile: p.clone(),
-            rgb_r: r,
-            rgb_g: g,
-            rgb_b: b,
-            rgb_brightness: bright,
-            show_battery_led: bat_led,
-            trigger_l2_mode: tl2_mode,
-            trigger_l2_start: tl2_start,
-            trigger_l2_force: tl2_force,
-            trigger_r2_mode: tr2_mode,
-            trigger_r2_start: tr2_start,
-            trigger_r2_force: tr2_force,
-            player_led_brightness: pled_bright,
-        };
-        config.save();
-    }
     pub fn default_mappings() -> Vec<ButtonMapping> {
         vec![
-            ButtonMapping { source: PhysicalButton::Cross, targets: vec![MappingTarget::Xbox(0x1000)] },    // A
-            ButtonMapping { source: PhysicalButton::Circle, targets: vec![MappingTarget::Xbox(0x2000)] },   // B
-            ButtonMapping { source: PhysicalButton::Square, targets: vec![MappingTarget::Xbox(0x4000)] },   // X
-            ButtonMapping { source: PhysicalButton::Triangle, targets: vec![MappingTarget::Xbox(0x8000)] }, // Y
-            ButtonMapping { source: PhysicalButton::L1, targets: vec![MappingTarget::Xbox(0x0100)] },       // LB
-            ButtonMapping { source: PhysicalButton::R1, targets: vec![MappingTarget::Xbox(0x0200)] },       // RB
-            ButtonMapping { source: PhysicalButton::L3, targets: vec![MappingTarget::Xbox(0x0040)] },       // LThumb
-            ButtonMapping { source: PhysicalButton::R3, targets: vec![MappingTarget::Xbox(0x0080)] },       // RThumb
-            ButtonMapping { source: PhysicalButton::Options, targets: vec![MappingTarget::Xbox(0x0010)] },  // Start
-            ButtonMapping { source: PhysicalButton::Share, targets: vec![MappingTarget::Xbox(0x0020)] },    // Back
-            ButtonMapping { source: PhysicalButton::PS, targets: vec![MappingTarget::Xbox(0x0400)] },       // Guide
-            ButtonMapping { source: PhysicalButton::DpadUp, targets: vec![MappingTarget::Xbox(0x0001)] },
-            ButtonMapping { source: PhysicalButton::DpadDown, targets: vec![MappingTarget::Xbox(0x0002)] },
-            ButtonMapping { source: PhysicalButton::DpadLeft, targets: vec![MappingTarget::Xbox(0x0004)] },
-            ButtonMapping { source: PhysicalButton::DpadRight, targets: vec![MappingTarget::Xbox(0x0008)] },
-            ButtonMapping { source: PhysicalButton::LeftStick, targets: vec![MappingTarget::XboxLS] },
-            ButtonMapping { source: PhysicalButton::RightStick, targets: vec![MappingTarget::XboxRS] },
-            ButtonMapping { source: PhysicalButton::L2, targets: vec![MappingTarget::XboxLT] },
-            ButtonMapping { source: PhysicalButton::R2, targets: vec![MappingTarget::XboxRT] },
-            ButtonMapping { source: PhysicalButton::Touchpad, targets: vec![] },
-            ButtonMapping { source: PhysicalButton::TouchpadLeft, targets: vec![] },
-            ButtonMapping { source: PhysicalButton::TouchpadRight, targets: vec![] },
-            ButtonMapping { source: PhysicalButton::Mute, targets: vec![] },
+            ButtonMapping::new(PhysicalButton::Cross, vec![MappingTarget::Xbox(0x1000)]),    // A
+            ButtonMapping::new(PhysicalButton::Circle, vec![MappingTarget::Xbox(0x2000)]),   // B
+            ButtonMapping::new(PhysicalButton::Square, vec![MappingTarget::Xbox(0x4000)]),   // X
+            ButtonMapping::new(PhysicalButton::Triangle, vec![MappingTarget::Xbox(0x8000)]), // Y
+            ButtonMapping::new(PhysicalButton::L1, vec![MappingTarget::Xbox(0x0100)]),       // LB
+            ButtonMapping::new(PhysicalButton::R1, vec![MappingTarget::Xbox(0x0200)]),       // RB
+            ButtonMapping::new(PhysicalButton::L3, vec![MappingTarget::Xbox(0x0040)]),       // LThumb
+            ButtonMapping::new(PhysicalButton::R3, vec![MappingTarget::Xbox(0x0080)]),       // RThumb
+            ButtonMapping::new(PhysicalButton::Options, vec![MappingTarget::Xbox(0x0010)]),  // Start
+            ButtonMapping::new(PhysicalButton::Share, vec![MappingTarget::Xbox(0x0020)]),    // Back
+            ButtonMapping::new(PhysicalButton::PS, vec![MappingTarget::Xbox(0x0400)]),       // Guide
+            ButtonMapping::new(PhysicalButton::DpadUp, vec![MappingTarget::Xbox(0x0001)]),
+            ButtonMapping::new(PhysicalButton::DpadDown, vec![MappingTarget::Xbox(0x0002)]),
+            ButtonMapping::new(PhysicalButton::DpadLeft, vec![MappingTarget::Xbox(0x0004)]),
+            ButtonMapping::new(PhysicalButton::DpadRight, vec![MappingTarget::Xbox(0x0008)]),
+            ButtonMapping::new(PhysicalButton::LeftStick, vec![MappingTarget::XboxLS]),
+            ButtonMapping::new(PhysicalButton::RightStick, vec![MappingTarget::XboxRS]),
+            ButtonMapping::new(PhysicalButton::L2, vec![MappingTarget::XboxLT]),
+            ButtonMapping::new(PhysicalButton::R2, vec![MappingTarget::XboxRT]),
+            ButtonMapping::new(PhysicalButton::Touchpad, vec![]),
+            ButtonMapping::new(PhysicalButton::TouchpadLeft, vec![]),
+            ButtonMapping::new(PhysicalButton::TouchpadRight, vec![]),
+            ButtonMapping::new(PhysicalButton::Mute, vec![]),
+            ButtonMapping::new(PhysicalButton::FnLeft, vec![]),
+            ButtonMapping::new(PhysicalButton::FnRight, vec![]),
+            ButtonMapping::new(PhysicalButton::PaddleLeft, vec![]),
+            ButtonMapping::new(PhysicalButton::PaddleRight, vec![]),
         ]
     }
 
     pub fn config_path() -> PathBuf {
+        // %APPDATA% on Windows, $XDG_CONFIG_HOME (or ~/.config) elsewhere
         let mut path = std::env::var("APPDATA")
+            .or_else(|_| std::env::var("XDG_CONFIG_HOME"))
             .map(PathBuf::from)
+            .or_else(|_| std::env::var("HOME").map(|h| PathBuf::from(h).join(".config")))
             .unwrap_or_else(|_| PathBuf::from("."));
         path.push(APP_NAME);
         if !path.exists() {
@@ -245,46 +585,60 @@ impl AppConfig {
         path
     }
 
+    /// Profiles in subfolders are listed as "Folder/Name" (the folder is the category)
     pub fn list_profiles() -> Vec<String> {
-        let dir = Self::profiles_dir();
-        let mut profiles = Vec::new();
-        if let Ok(entries) = fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                if let Ok(file_type) = entry.file_type() {
-                    if file_type.is_file() {
-                        let path = entry.path();
-                        if path.extension().and_then(|s| s.to_str()) == Some("json") {
-                            if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
-                                profiles.push(name.to_string());
-                            }
-                        }
-                    }
-                }
-            }
+        crate::persist::flush();
+        let mut names = Vec::new();
+        list_profile_names(&Self::profiles_dir(), "", &mut names);
+        names
+    }
+
+    /// File for a profile name, "Shooters/CoD" being CoD.json in the Shooters folder.
+    /// None unless every part is a plain file name (see is_file_name): "..", a drive ("C:") or a
+    /// root ("\\server") would end up outside the profiles folder.
+    pub fn profile_path(name: &str) -> Option<PathBuf> {
+        let parts: Vec<&str> = name.split(['/', '\\']).collect();
+        if !parts.iter().all(|p| is_file_name(p)) {
+            return None;
         }
-        profiles
+        let relative: PathBuf = parts.iter().collect();
+        if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            return None;
+        }
+        let mut path = Self::profiles_dir();
+        path.extend(&parts[..parts.len() - 1]);
+        path.push(format!("{}.json", parts[parts.len() - 1]));
+        Some(path)
     }
 
     pub fn save_profile(name: &str, profile: &Profile) {
-        let mut path = Self::profiles_dir();
-        path.push(format!("{}.json", name));
-        if let Ok(s) = serde_json::to_string_pretty(profile) {
+        let Some(path) = Self::profile_path(name) else { return };
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let Ok(mut value) = serde_json::to_value(profile) else { return };
+        // A child keeps only its overrides, so later changes to the parent still show through
+        if let (Some(parent), serde_json::Value::Object(fields)) = (&profile.parent, &mut value) {
+            if let Some(serde_json::Value::Object(inherited)) = Self::load_profile_value(parent, &mut vec![name.to_string()]) {
+                fields.retain(|k, v| k == "parent" || inherited.get(k) != Some(v));
+            }
+        }
+        if let Ok(s) = serde_json::to_string_pretty(&value) {
             let _ = fs::write(path, s);
         }
     }
 
     pub fn load_profile(name: &str) -> Option<Profile> {
-        let mut path = Self::profiles_dir();
-        path.push(format!("{}.json", name));
-        let content = fs::read_to_string(path).ok()?;
+        crate::persist::flush();
+        let value = Self::load_profile_value(name, &mut Vec::new())?;
         
-        // 1. Try parsing as new Profile struct
-        if let Ok(p) = serde_json::from_str::<Profile>(&content) {
+        // 1. Try parsing as new Profile struct (parents already merged in)
+        if let Ok(p) = serde_json::from_value::<Profile>(value.clone()) {
             return Some(p);
         }
         
         // 2. Fallback: Legacy Vec<ButtonMapping>
-        if let Ok(mappings) = serde_json::from_str::<Vec<ButtonMapping>>(&content) {
+        if let Ok(mappings) = serde_json::from_value::<Vec<ButtonMapping>>(value) {
             return Some(Profile {
                 mappings,
                 ..Default::default()
@@ -294,9 +648,138 @@ impl AppConfig {
         None
     }
 
+    /// Profile JSON with its parent chain merged in, the child's fields winning.
+    /// `seen` holds the profiles already on the chain, so a cycle ends the lookup.
+    fn load_profile_value(name: &str, seen: &mut Vec<String>) -> Option<serde_json::Value> {
+        if seen.iter().any(|n| n == name) {
+            log::warn!("Profile '{}' inherits from itself (via {})", name, seen.join(" -> "));
+            return None;
+        }
+        let is_parent = !seen.is_empty();
+        seen.push(name.to_string());
+        let Some(content) = Self::profile_path(name).and_then(|p| fs::read_to_string(p).ok()) else {
+            // Default can be inherited from before it has ever been saved
+            return (is_parent && name == "Default").then(|| serde_json::to_value(Profile::default()).ok()).flatten();
+        };
+        let mut value: serde_json::Value = serde_json::from_str(&content).ok()?;
+        let parent = value.get("parent").and_then(|p| p.as_str()).map(str::to_string);
+        if let (Some(parent), serde_json::Value::Object(child)) = (parent, &value) {
+            let inherited = Self::load_profile_value(&parent, seen).or_else(|| {
+                log::warn!("Profile '{}': parent '{}' not found, using defaults for what it doesn't set", name, parent);
+                serde_json::to_value(Profile::default()).ok()
+            });
+            if let Some(serde_json::Value::Object(mut merged)) = inherited {
+                merged.extend(child.clone());
+                value = serde_json::Value::Object(merged);
+            }
+        }
+        Some(value)
+    }
+
+    /// Whether `child` can inherit from `parent`: it exists and isn't `child` or one of its descendants
+    pub fn can_inherit(child: &str, parent: &str) -> bool {
+        crate::persist::flush();
+        if parent != "Default" && !Self::profile_path(parent).is_some_and(|p| p.exists()) {
+            return false;
+        }
+        let mut next = Some(parent.to_string());
+        for _ in 0..MAX_PROFILE_DEPTH {
+            match next {
+                Some(name) if name == child => return false,
+                Some(name) => next = Self::profile_path(&name)
+                    .and_then(|p| fs::read_to_string(p).ok())
+                    .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+                    .and_then(|v| v.get("parent").and_then(|p| p.as_str()).map(str::to_string)),
+                None => return true,
+            }
+        }
+        false
+    }
+
     pub fn delete_profile(name: &str) {
-        let mut path = Self::profiles_dir();
-        path.push(format!("{}.json", name));
-        let _ = fs::remove_file(path);
+        crate::persist::flush();
+        let Some(path) = Self::profile_path(name) else { return };
+        let _ = fs::remove_file(&path);
+        // Drop the folder with its last profile (fails harmlessly while it still has files)
+        if let Some(dir) = path.parent().filter(|d| *d != Self::profiles_dir()) {
+            let _ = fs::remove_dir(dir);
+        }
+    }
+
+    // --- Manual Override Templates (debug panel) ---
+    pub fn templates_dir() -> PathBuf {
+        let mut path = Self::config_path().parent().unwrap().to_path_buf();
+        path.push("templates");
+        if !path.exists() {
+            let _ = fs::create_dir_all(&path);
+        }
+        path
+    }
+    pub fn list_templates() -> Vec<String> {
+        list_json_names(Self::templates_dir())
+    }
+    /// None for names that aren't a plain file name (see is_file_name)
+    fn template_path(name: &str) -> Option<PathBuf> {
+        is_file_name(name).then(|| Self::templates_dir().join(format!("{}.json", name)))
+    }
+    pub fn save_template(name: &str, params: &ManualParams) -> anyhow::Result<()> {
+        let path = Self::template_path(name).ok_or_else(|| anyhow::anyhow!("Invalid template name '{}'", name))?;
+        fs::write(path, serde_json::to_string_pretty(params)?)?;
+        Ok(())
+    }
+    pub fn load_template(name: &str) -> Option<ManualParams> {
+        let path = Self::template_path(name)?;
+        serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+    }
+    pub fn delete_template(name: &str) {
+        if let Some(path) = Self::template_path(name) {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// A name that stays a single file inside the folder it's joined to: no separators, "." or "..",
+/// and no ":" (a drive prefix on Windows, or an alternate data stream)
+fn is_file_name(name: &str) -> bool {
+    !name.trim().is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', ':'])
+}
+
+/// File stems of the *.json files in `dir`
+fn list_profile_names(dir: &Path, prefix: &str, names: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => {
+                if let Some(folder) = path.file_name().and_then(|s| s.to_str()) {
+                    list_profile_names(&path, &format!("{}{}/", prefix, folder), names);
+                }
+            }
+            Ok(t) if t.is_file() && path.extension().and_then(|s| s.to_str()) == Some("json") => {
+                if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                    names.push(format!("{}{}", prefix, name));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn list_json_names(dir: PathBuf) -> Vec<String> {
+    let mut names = Vec::new();
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if let Ok(file_type) = entry.file_type() {
+                if file_type.is_file() {
+                    let path = entry.path();
+                    if path.extension().and_then(|s| s.to_str()) == Some("json") {
+                        if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                            names.push(name.to_string());
+                        }
+                    }
+                }
+            }
+        }
     }
+    names
 }
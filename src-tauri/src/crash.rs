@@ -0,0 +1,82 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, TryLockError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::state::SharedState;
+use crate::config::AppConfig;
+
+// --- Crash Reporter ---
+// Panic hook that dumps a report into %APPDATA%/DX3/crashes. The report name is also written to
+// crashes/pending so the UI can offer to open it on the next launch (release builds have no console).
+
+const PENDING_MARKER: &str = "pending";
+
+pub fn crash_dir() -> PathBuf {
+    let mut path = AppConfig::config_path().parent().unwrap().to_path_buf();
+    path.push("crashes");
+    if !path.exists() {
+        let _ = fs::create_dir_all(&path);
+    }
+    path
+}
+
+pub fn install(state: Arc<Mutex<SharedState>>) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let backtrace = std::backtrace::Backtrace::force_capture();
+        let thread = std::thread::current();
+        let mut report = String::new();
+
+        let _ = writeln!(report, "{} {} crash report", crate::config::APP_NAME, env!("CARGO_PKG_VERSION"));
+        let _ = writeln!(report, "Thread: {}", thread.name().unwrap_or("<unnamed>"));
+        let _ = writeln!(report, "Panic: {}", info);
+        let _ = writeln!(report);
+
+        // The panicking thread may hold the lock (or have poisoned it), never block here
+        let guard = match state.try_lock() {
+            Ok(s) => Some(s),
+            Err(TryLockError::Poisoned(p)) => Some(p.into_inner()),
+            Err(TryLockError::WouldBlock) => None,
+        };
+        match guard {
+            Some(s) => {
+                let _ = writeln!(report, "Status: {}", s.status);
+                let _ = writeln!(report, "Device: {} ({})", s.device_name, s.connection_mode);
+                if let Some(settings) = crate::settings::try_read() {
+                    let _ = writeln!(report, "Profile: {}", settings.current_profile_name);
+                }
+                let hex: Vec<String> = s.raw_report.iter().map(|b| format!("{:02X}", b)).collect();
+                let _ = writeln!(report, "Last raw report: {}", hex.join(" "));
+            }
+            None => {
+                let _ = writeln!(report, "State: <locked by panicking thread>");
+            }
+        }
+
+        let _ = writeln!(report);
+        let _ = writeln!(report, "Backtrace:\n{}", backtrace);
+
+        let ts = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let name = format!("crash-{}.txt", ts);
+        let dir = crash_dir();
+        if fs::write(dir.join(&name), &report).is_ok() {
+            let _ = fs::write(dir.join(PENDING_MARKER), &name);
+        }
+
+        default_hook(info);
+    }));
+}
+
+/// Report left by the previous run that the user hasn't seen yet
+pub fn pending_report() -> Option<PathBuf> {
+    let dir = crash_dir();
+    let name = fs::read_to_string(dir.join(PENDING_MARKER)).ok()?;
+    let path = dir.join(name.trim());
+    if path.is_file() { Some(path) } else { None }
+}
+
+pub fn dismiss_pending() {
+    let _ = fs::remove_file(crash_dir().join(PENDING_MARKER));
+}
@@ -1,4 +1,3 @@
-#[allow(dead_code)]
 pub fn crc32(data: &[u8]) -> u32 {
     let mut crc = 0xFFFFFFFFu32;
     for &byte in data {
@@ -17,11 +16,16 @@ pub fn crc32(data: &[u8]) -> u32 {
 /// CRC-32 for DualSense Bluetooth packets
 /// Includes phantom header 0xA2 (BT HID Output Report header) processing
 pub fn crc32_bt(data: &[u8]) -> u32 {
+    crc32_with_header(0xA2, data)
+}
+
+/// CRC-32 seeded with a BT HID transaction header byte that isn't part of the payload
+/// (0xA1 input, 0xA2 output, 0xA3 feature)
+pub fn crc32_with_header(header: u8, data: &[u8]) -> u32 {
     let mut crc = 0xFFFFFFFFu32;
     
-    // First, process "phantom" BT header 0xA2
-    // This byte is not included in the packet payload but is part of CRC calculation
-    crc ^= 0xA2u32;
+    // First, process the "phantom" header
+    crc ^= header as u32;
     for _ in 0..8 {
         if (crc & 1) != 0 {
             crc = (crc >> 1) ^ 0xEDB88320;
@@ -43,3 +47,40 @@ pub fn crc32_bt(data: &[u8]) -> u32 {
     }
     !crc
 }
+
+/// Checksum variants for manual/fuzzer sends (SharedState::crc_seed_idx)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CrcMode {
+    BtOutput, // 0xA2 header, only on 0x31 reports (what the controller expects)
+    None,
+    Feature,  // 0xA3 header, for BT feature reports
+    Plain,    // CRC-32 over the payload only
+}
+
+impl CrcMode {
+    pub fn from_idx(idx: u8) -> Self {
+        match idx {
+            1 => Self::None,
+            2 => Self::Feature,
+            3 => Self::Plain,
+            _ => Self::BtOutput,
+        }
+    }
+
+    /// Whether a report with this ID gets a trailing checksum
+    pub fn applies_to(self, report_id: u8) -> bool {
+        match self {
+            Self::BtOutput => report_id == 0x31,
+            Self::None => false,
+            Self::Feature | Self::Plain => true,
+        }
+    }
+
+    pub fn checksum(self, data: &[u8]) -> u32 {
+        match self {
+            Self::BtOutput => crc32_with_header(0xA2, data),
+            Self::Feature => crc32_with_header(0xA3, data),
+            Self::Plain | Self::None => crc32(data),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use discord_rich_presence::{activity, DiscordIpc, DiscordIpcClient};
+use log::{info, warn};
+
+use crate::state::SharedState;
+use crate::settings;
+
+// --- Discord Rich Presence ---
+// Shows "Playing DX3 • FPS profile • 65%" via the local Discord client (IPC pipe).
+// Needs the ID of a Discord application; its name is what Discord displays after "Playing".
+
+// Discord drops activity updates sent more often than this
+const UPDATE_INTERVAL: Duration = Duration::from_secs(15);
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+fn presence_text(s: &SharedState) -> (String, String) {
+    let profile = settings::read().current_profile_name.clone();
+    let details = if profile.is_empty() {
+        "Default profile".to_string()
+    } else {
+        format!("{} profile", profile)
+    };
+    let state = if s.is_paused {
+        "Paused".to_string()
+    } else if s.device_name == "None" {
+        "No controller".to_string()
+    } else if s.gamepad.is_charging {
+        format!("{}% (charging)", s.gamepad.battery)
+    } else {
+        format!("{}%", s.gamepad.battery)
+    };
+    (details, state)
+}
+
+/// Spawns the presence thread unless it's already running. It clears the presence and stops
+/// once disabled, the app id changes or the app exits.
+pub fn start(state: Arc<Mutex<SharedState>>) {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    thread::spawn(move || {
+        let started = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
+        let mut client: Option<(DiscordIpcClient, String)> = None;
+        let mut last_sent: Option<(String, String)> = None;
+        let mut next_attempt = Instant::now();
+
+        loop {
+            let (enabled, app_id, text) = {
+                let s = state.lock().unwrap();
+                let text = presence_text(&s);
+                let settings = settings::read();
+                (settings.discord_enabled && !s.should_exit, settings.discord_app_id.trim().to_string(), text)
+            };
+
+            // Drop the connection when disabled or pointed at another application
+            if let Some((mut c, id)) = client.take() {
+                if enabled && id == app_id {
+                    client = Some((c, id));
+                } else {
+                    let _ = c.clear_activity();
+                    let _ = c.close();
+                    last_sent = None;
+                }
+            }
+            if !enabled {
+                break;
+            }
+
+            if client.is_none() && !app_id.is_empty() && Instant::now() >= next_attempt {
+                // Discord may simply not be running; retry quietly
+                match DiscordIpcClient::new(&app_id).and_then(|mut c| c.connect().map(|_| c)) {
+                    Ok(c) => {
+                        info!("Connected to Discord.");
+                        client = Some((c, app_id.clone()));
+                    }
+                    Err(_) => next_attempt = Instant::now() + RETRY_INTERVAL,
+                }
+            }
+
+            if let Some((c, _)) = client.as_mut() {
+                if last_sent.as_ref() != Some(&text) {
+                    let act = activity::Activity::new()
+                        .details(&text.0)
+                        .state(&text.1)
+                        .timestamps(activity::Timestamps::new().start(started));
+                    match c.set_activity(act) {
+                        Ok(_) => last_sent = Some(text),
+                        Err(e) => {
+                            warn!("Discord presence update failed: {}", e);
+                            client = None; // Reconnect on next pass
+                            last_sent = None;
+                            next_attempt = Instant::now() + RETRY_INTERVAL;
+                        }
+                    }
+                }
+            }
+
+            // Short sleeps so disabling takes effect quickly
+            let wake = Instant::now() + UPDATE_INTERVAL;
+            while Instant::now() < wake {
+                thread::sleep(Duration::from_millis(500));
+                if !settings::read().discord_enabled || state.lock().unwrap().should_exit {
+                    break;
+                }
+            }
+        }
+
+        RUNNING.store(false, Ordering::SeqCst);
+        info!("Discord presence stopped.");
+    });
+}
@@ -1,5 +1,76 @@
+use std::sync::atomic::{AtomicU8, Ordering};
 use hidapi::HidDevice;
+use log::{info, warn};
 use crate::crc;
+use crate::capture::{self, Direction};
+use crate::writelog;
+
+/// Every output report goes through here so traffic captures and the write history see it
+pub fn write_report(device: &HidDevice, data: &[u8]) -> hidapi::HidResult<usize> {
+    capture::record(Direction::Out, data);
+    let res = device.write(data);
+    writelog::record(Direction::Out, data, res.as_ref().map(|n| *n).map_err(|e| e.to_string()));
+    res
+}
+
+// --- BT Output Sequence ---
+// DualSense BT output reports (0x31) carry a 4-bit sequence number in the high nibble of byte 1,
+// and the controller ignores a report that repeats the previous number. Every BT write takes its
+// number here, right before the checksum, so no path does its own sequence math. DS4 BT reports
+// (0x11) have no sequence: their byte 1 holds the HWCTL flags and is left as the caller set it.
+static BT_SEQ: AtomicU8 = AtomicU8::new(0);
+
+/// Start over at 0 for a newly connected controller
+pub fn reset_bt_seq() {
+    BT_SEQ.store(0, Ordering::Relaxed);
+}
+
+/// Puts the next sequence number into byte 1 of a 0x31 report, keeping the tag in the low nibble
+pub fn stamp_bt_seq(report: &mut [u8]) {
+    if report.len() > 1 && report[0] == 0x31 {
+        let seq = BT_SEQ.fetch_add(1, Ordering::Relaxed) & 0x0F;
+        report[1] = (seq << 4) | (report[1] & 0x0F);
+    }
+}
+
+/// Sequence number, then CRC32 (0xA2 header) in the last 4 bytes, then write
+pub fn write_bt_report(device: &HidDevice, report: &mut [u8]) -> hidapi::HidResult<usize> {
+    stamp_bt_seq(report);
+    let len = report.len();
+    let checksum = crc::crc32_bt(&report[..len - 4]);
+    report[len - 4..].copy_from_slice(&checksum.to_le_bytes());
+    write_report(device, report)
+}
+
+pub fn send_feature(device: &HidDevice, data: &[u8]) -> hidapi::HidResult<()> {
+    capture::record(Direction::Feature, data);
+    let res = device.send_feature_report(data);
+    writelog::record(Direction::Feature, data, res.as_ref().map(|_| data.len()).map_err(|e| e.to_string()));
+    res
+}
+
+// Not firmware effects: Dynamic goes out as Rigid with the force scaled by how far the trigger is
+// pulled, so resistance builds up along the travel. Rumble scales it by the game's rumble on the
+// virtual pad instead (L2 by the large motor, R2 by the small one), so firing a weapon tightens the
+// trigger. The worker resends both as their input changes.
+pub const TRIGGER_MODE_DYNAMIC: u8 = 0xFF;
+pub const TRIGGER_MODE_RUMBLE: u8 = 0xFE;
+const TRIGGER_MODE_RIGID: u8 = 0x01;
+
+/// Whether `mode` is computed by us and has to be resent as its input changes
+pub fn is_live_trigger_mode(mode: u8) -> bool {
+    matches!(mode, TRIGGER_MODE_DYNAMIC | TRIGGER_MODE_RUMBLE)
+}
+
+/// (mode, start, force) to send for a configured effect at the current pull and rumble (0.0-1.0)
+pub fn trigger_effect(mode: u8, start: u8, force: u8, pull: f32, rumble: f32) -> (u8, u8, u8) {
+    let scaled = |level: f32| (force as f32 * level.clamp(0.0, 1.0)).round() as u8;
+    match mode {
+        TRIGGER_MODE_DYNAMIC => (TRIGGER_MODE_RIGID, start, scaled(pull)),
+        TRIGGER_MODE_RUMBLE => (TRIGGER_MODE_RIGID, start, scaled(rumble)),
+        _ => (mode, start, force),
+    }
+}
 
 pub fn send_dualsense_output(
     device: &HidDevice, 
@@ -7,7 +78,6 @@ pub fn send_dualsense_output(
     red: u8, green: u8, blue: u8, 
     player_led_mask: u8, 
     player_led_brightness: u8, // 0=High, 1=Med, 2=Low
-    seq: u8,
     // Adaptive Triggers
     l2_mode: u8, l2_start: u8, l2_force: u8,
     r2_mode: u8, r2_start: u8, r2_force: u8,
@@ -25,7 +95,7 @@ pub fn send_dualsense_output(
     report[0] = report_id;
     if is_bt {
         // BT Header - IMPORTANT: 0x02 in low nibble is required for LED work!
-        report[1] = (seq << 4) | 0x02; 
+        report[1] = 0x02; // Sequence number goes in the high nibble (write_bt_report)
         // Flags: 0x04 = triggers, 0x08 = LED
         report[2] = 0xFF;  // All flags for triggers and LED
         report[3] = 0x15; 
@@ -73,26 +143,40 @@ pub fn send_dualsense_output(
     report[offset_rgb + 2] = blue;
 
     if is_bt {
-        // CRC32 considering BT phantom header 0xA2
-        let checksum = crc::crc32_bt(&report[0..74]);
-        report[74] = (checksum & 0xFF) as u8;
-        report[75] = ((checksum >> 8) & 0xFF) as u8;
-        report[76] = ((checksum >> 16) & 0xFF) as u8;
-        report[77] = ((checksum >> 24) & 0xFF) as u8;
-        
-        let _ = device.write(&report);
+        let _ = write_bt_report(device, &mut report);
+    } else {
+        let _ = write_report(device, &report[0..64]);
+    }
+}
+
+/// Rumble only, lightbar and triggers are left alone. A later LED/trigger report stops it
+/// (send_dualsense_output sets both motors to 0).
+pub fn send_rumble(device: &HidDevice, is_bt: bool, left: u8, right: u8) {
+    if is_bt {
+        let mut report = [0u8; 78];
+        report[0] = 0x31;
+        report[1] = 0x02;
+        report[2] = 0x03; // Valid flags 0: compatible vibration + haptics select
+        report[4] = right;
+        report[5] = left;
+        let _ = write_bt_report(device, &mut report);
     } else {
-        let _ = device.write(&report[0..64]);
+        let mut report = [0u8; 64];
+        report[0] = 0x02;
+        report[1] = 0x03;
+        report[3] = right;
+        report[4] = left;
+        let _ = write_report(device, &report);
     }
 }
 
-pub fn send_power_off(device: &hidapi::HidDevice, is_bt: bool, seq: u8) {
+pub fn send_power_off(device: &hidapi::HidDevice, is_bt: bool) {
     if is_bt {
         let mut report = [0u8; 78];
         report[0] = 0x31;
         
-        // Byte 1: 0x02 (HID Output) | 0x40 (Disconnect bit) | Seq
-        report[1] = (seq << 4) | 0x02 | 0x40; 
+        // Byte 1: 0x02 (HID Output) | 0x40 (Disconnect bit), Seq is added on write
+        report[1] = 0x02 | 0x40; 
         
         // Bytes 2 and 3: Activation masks forcing the controller to accept the packet
         report[2] = 0xF7; 
@@ -105,13 +189,15 @@ pub fn send_power_off(device: &hidapi::HidDevice, is_bt: bool, seq: u8) {
         // Clear the rest
         for i in 6..74 { report[i] = 0; }
         
-        let checksum = crc::crc32_bt(&report[0..74]);
-        report[74] = (checksum & 0xFF) as u8;
-        report[75] = ((checksum >> 8) & 0xFF) as u8;
-        report[76] = ((checksum >> 16) & 0xFF) as u8;
-        report[77] = ((checksum >> 24) & 0xFF) as u8;
-        
-        let _ = device.write(&report);
+        let _ = write_bt_report(device, &mut report);
+    }
+}
+
+/// BT only. Repeated because a single power-off packet is easily dropped.
+pub fn send_power_off_burst(device: &hidapi::HidDevice) {
+    for _ in 0..10 {
+        send_power_off(device, true);
+        std::thread::sleep(std::time::Duration::from_millis(10));
     }
 }
 
@@ -121,8 +207,7 @@ pub fn send_raw_output(
     flag_off: usize, 
     rgb_off: usize, 
     r: u8, g: u8, b: u8, 
-    seq: u8, 
-    _crc_mode: u8, 
+    crc_mode: u8, 
     player_val: u8, 
     pled_bright: u8,
     pled_bright_off: usize,
@@ -137,7 +222,7 @@ pub fn send_raw_output(
     // BT Headers if 0x31 or 0x11 (DS4)
     if report_id == 0x31 || report_id == 0x11 {
         // IMPORTANT: 0x02 in low nibble required for LED work!
-        report[1] = (seq << 4) | 0x02; 
+        report[1] = 0x02; 
     }
 
     // Set Flags (important for BT LED activation)
@@ -172,72 +257,92 @@ pub fn send_raw_output(
         report[offset_pled_flags] |= 0x01; // Enable brightness control
     }
 
-    // CRC for BT
-    if report_id == 0x31 {
-        let checksum = crc::crc32_bt(&report[0..74]);
-        
-        report[74] = (checksum & 0xFF) as u8;
-        report[75] = ((checksum >> 8) & 0xFF) as u8;
-        report[76] = ((checksum >> 16) & 0xFF) as u8;
-        report[77] = ((checksum >> 24) & 0xFF) as u8;
-    }
-
     let len = if report_id == 0x31 { 78 } else { 64 };
+    stamp_bt_seq(&mut report[0..len]);
+
+    // CRC in the last 4 bytes (BT)
+    let crc_mode = crc::CrcMode::from_idx(crc_mode);
+    if crc_mode.applies_to(report_id) {
+        let checksum = crc_mode.checksum(&report[0..len - 4]);
+        report[len - 4..len].copy_from_slice(&checksum.to_le_bytes());
+    }
     let slice = &report[0..len];
     
     // Hex String Generation
     let hex_str = slice.iter().map(|b| format!("{:02X}", b)).collect::<Vec<String>>().join(" ");
 
     if as_feature {
-        match device.send_feature_report(slice) {
+        match send_feature(device, slice) {
             Ok(_) => Ok((slice.len(), hex_str)),
             Err(e) => Err(format!("{} | Hex: {}", e, hex_str))
         }
     } else {
-        match device.write(slice) {
+        match write_report(device, slice) {
             Ok(n) => Ok((n, hex_str)),
             Err(e) => Err(format!("{} | Hex: {}", e, hex_str))
         }
     }
 }
 
+/// DualSense defaults to Simple Mode (DirectInput) over BT, where LED/Haptics/Triggers are
+/// unavailable. Reading Feature Report 0x09 (serial number) or 0x20 (firmware) activates Enhanced
+/// Mode. Needed again after the controller wakes from sleep.
+pub fn activate_enhanced_mode(device: &HidDevice) {
+    let mut feature_buf = [0u8; 64];
+    feature_buf[0] = 0x09; // Feature Report ID for serial number
+    match device.get_feature_report(&mut feature_buf) {
+        Ok(len) => {
+            info!("DualSense BT: Enhanced Mode activated via Feature Report 0x09 ({} bytes)", len);
+        }
+        Err(e) => {
+            warn!("DualSense BT: Failed to read Feature Report 0x09: {} — LED may not work!", e);
+            // Try alternative Feature Report 0x20
+            feature_buf[0] = 0x20;
+            if let Ok(len) = device.get_feature_report(&mut feature_buf) {
+                info!("DualSense BT: Enhanced Mode activated via Feature Report 0x20 ({} bytes)", len);
+            }
+        }
+    }
+}
+
+/// Liveness check for a quiet BT handle: a dead one fails feature reads immediately
+pub fn is_responding(device: &HidDevice) -> bool {
+    let mut buf = [0u8; 64];
+    buf[0] = 0x20;
+    device.get_feature_report(&mut buf).is_ok()
+}
+
 /// Initializes DualSense LED controller via "wake-up" packet.
 /// After BT reconnection, controller requires a special signal
 /// with flags 0xFF/0xFF/0xFF in bytes 2-4 (like in step 2 of RGB Sweep).
-pub fn send_led_init(device: &HidDevice, seq: u8, target_pled: u8, r: u8, g: u8, b: u8) {
+pub fn send_led_init(device: &HidDevice, target_pled: u8, r: u8, g: u8, b: u8) {
     // 1. Wake-up packet with max flags
-    send_wakeup_packet_bt(device, seq);
+    send_wakeup_packet_bt(device);
     
     // 2. Short pause
     std::thread::sleep(std::time::Duration::from_millis(50));
     
     // 3. Regular LED packet with desired settings
-    send_led_packet_bt(device, seq.wrapping_add(1), target_pled, r, g, b);
+    send_led_packet_bt(device, target_pled, r, g, b);
 }
 
 /// Wake-up packet: flags 0xFF in bytes 2, 3, 4 (mimics RGB Sweep step 2)
-fn send_wakeup_packet_bt(device: &HidDevice, seq: u8) {
+fn send_wakeup_packet_bt(device: &HidDevice) {
     let mut report = [0u8; 78];
     report[0] = 0x31;
-    report[1] = (seq << 4) | 0x02;
+    report[1] = 0x02;
     // Key: bytes 2-4 = 0xFF (max activation flags)
     report[2] = 0xFF;
     report[3] = 0xFF;
     report[4] = 0xFF;
     
-    let checksum = crc::crc32_bt(&report[0..74]);
-    report[74] = (checksum & 0xFF) as u8;
-    report[75] = ((checksum >> 8) & 0xFF) as u8;
-    report[76] = ((checksum >> 16) & 0xFF) as u8;
-    report[77] = ((checksum >> 24) & 0xFF) as u8;
-    
-    let _ = device.write(&report);
+    let _ = write_bt_report(device, &mut report);
 }
 
-fn send_led_packet_bt(device: &HidDevice, seq: u8, pled: u8, r: u8, g: u8, b: u8) {
+fn send_led_packet_bt(device: &HidDevice, pled: u8, r: u8, g: u8, b: u8) {
     let mut report = [0u8; 78];
     report[0] = 0x31;
-    report[1] = (seq << 4) | 0x02;
+    report[1] = 0x02;
     report[2] = 0xFF;  // Feature flags
     report[3] = 0x15;  // LED flags
     report[4] = 0x00;  // No vibration
@@ -248,25 +353,21 @@ fn send_led_packet_bt(device: &HidDevice, seq: u8, pled: u8, r: u8, g: u8, b: u8
     report[47] = g;
     report[48] = b;
     
-    let checksum = crc::crc32_bt(&report[0..74]);
-    report[74] = (checksum & 0xFF) as u8;
-    report[75] = ((checksum >> 8) & 0xFF) as u8;
-    report[76] = ((checksum >> 16) & 0xFF) as u8;
-    report[77] = ((checksum >> 24) & 0xFF) as u8;
-    
-    let _ = device.write(&report);
+    let _ = write_bt_report(device, &mut report);
 }
 
-/// USB Wake-up packet: 0xFF flags in bytes 1-2 to init LED + rumble
-pub fn send_led_init_usb(device: &HidDevice, target_pled: u8, r: u8, g: u8, b: u8) {
+/// USB Wake-up packet: 0xFF flags in bytes 1-2 to init LED + rumble (unless `rumble` is off)
+pub fn send_led_init_usb(device: &HidDevice, target_pled: u8, r: u8, g: u8, b: u8, rumble: bool) {
     // Wake-up packet with max flags
     let mut report = [0u8; 64];
     report[0] = 0x02;
     report[1] = 0xFF;  // All flags (triggers + LED)
     report[2] = 0xFF;  // All LED flags
     // Bytes 3-4: rumble (short pulse)
-    report[3] = 0x20;  // Small rumble left
-    report[4] = 0x20;  // Small rumble right
+    if rumble {
+        report[3] = 0x20;  // Small rumble left
+        report[4] = 0x20;  // Small rumble right
+    }
     
     // Player LED @ offset 44, RGB @ offset 45-47
     report[44] = target_pled;
@@ -274,5 +375,24 @@ pub fn send_led_init_usb(device: &HidDevice, target_pled: u8, r: u8, g: u8, b: u
     report[46] = g;
     report[47] = b;
     
-    let _ = device.write(&report);
+    let _ = write_report(device, &report);
+}
+
+/// Firmware/hardware version from the firmware info feature report (0x20 DualSense, 0xA3 DS4),
+/// e.g. "FW 0x0110002A, HW 0x00000617, built Jun 10 2021 10:54:06"
+pub fn read_firmware_info(device: &HidDevice, is_dualsense: bool) -> Option<String> {
+    let mut buf = [0u8; 64];
+    buf[0] = if is_dualsense { 0x20 } else { 0xA3 };
+    let len = device.get_feature_report(&mut buf).ok()?;
+    let le16 = |o: usize| u16::from_le_bytes([buf[o], buf[o + 1]]) as u32;
+    let le32 = |o: usize| u32::from_le_bytes([buf[o], buf[o + 1], buf[o + 2], buf[o + 3]]);
+    let text = |r: std::ops::Range<usize>| String::from_utf8_lossy(&buf[r]).trim_end_matches('\0').to_string();
+
+    if is_dualsense && len >= 32 {
+        Some(format!("FW 0x{:08X}, HW 0x{:08X}, built {} {}", le32(28), le32(24), text(1..12), text(12..20)))
+    } else if !is_dualsense && len >= 43 {
+        Some(format!("FW 0x{:04X}, HW 0x{:04X}, built {} {}", le16(41), le16(35), text(1..17), text(17..33)))
+    } else {
+        None
+    }
 }
@@ -0,0 +1,105 @@
+use std::process::Command;
+use std::os::windows::process::CommandExt;
+use crate::autostart::MINIMIZED_ARG;
+
+// Elevated autostart = scheduled task at logon with highest privileges, starting DX3 minimized.
+// Elevated, the worker can inject input into admin windows (UIPI blocks SendInput otherwise).
+// It is still the normal DX3 process: the worker runs inside it, so remapping stops with the app,
+// and nothing reaches the secure desktop (lock screen, UAC prompts).
+const TASK_NAME: &str = "DX3 Elevated Autostart";
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+pub fn is_registered() -> bool {
+    Command::new("schtasks")
+        .args(["/Query", "/TN", TASK_NAME])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+pub fn register() -> anyhow::Result<()> {
+    let current_exe = std::env::current_exe()?;
+    let user = format!(
+        "{}\\{}",
+        std::env::var("USERDOMAIN").unwrap_or_default(),
+        std::env::var("USERNAME").unwrap_or_default()
+    );
+
+    // Plain `schtasks /SC ONLOGON` defaults to "stop on battery" and a 72h time limit,
+    // which would kill DX3 on laptops. An XML definition lets us turn those off.
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-16"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <Triggers>
+    <LogonTrigger>
+      <Enabled>true</Enabled>
+      <UserId>{user}</UserId>
+    </LogonTrigger>
+  </Triggers>
+  <Principals>
+    <Principal id="Author">
+      <UserId>{user}</UserId>
+      <LogonType>InteractiveToken</LogonType>
+      <RunLevel>HighestAvailable</RunLevel>
+    </Principal>
+  </Principals>
+  <Settings>
+    <MultipleInstancesPolicy>IgnoreNew</MultipleInstancesPolicy>
+    <DisallowStartIfOnBatteries>false</DisallowStartIfOnBatteries>
+    <StopIfGoingOnBatteries>false</StopIfGoingOnBatteries>
+    <ExecutionTimeLimit>PT0S</ExecutionTimeLimit>
+    <Priority>4</Priority>
+  </Settings>
+  <Actions Context="Author">
+    <Exec>
+      <Command>{exe}</Command>
+      <Arguments>{args}</Arguments>
+    </Exec>
+  </Actions>
+</Task>
+"#,
+        user = xml_escape(&user),
+        exe = xml_escape(&current_exe.to_string_lossy()),
+        args = MINIMIZED_ARG,
+    );
+
+    // schtasks only reliably accepts UTF-16 LE (with BOM) task definitions
+    let mut bytes = vec![0xFF, 0xFE];
+    for unit in xml.encode_utf16() {
+        bytes.extend_from_slice(&unit.to_le_bytes());
+    }
+    let xml_path = std::env::temp_dir().join("dx3_elevated_task.xml");
+    std::fs::write(&xml_path, bytes)?;
+
+    let res = run_schtasks(&["/Create", "/TN", TASK_NAME, "/XML", xml_path.to_str().unwrap_or_default(), "/F"]);
+    let _ = std::fs::remove_file(&xml_path);
+    res
+}
+
+pub fn unregister() -> anyhow::Result<()> {
+    if !is_registered() {
+        return Ok(());
+    }
+    run_schtasks(&["/Delete", "/TN", TASK_NAME, "/F"])
+}
+
+fn run_schtasks(args: &[&str]) -> anyhow::Result<()> {
+    let output = Command::new("schtasks")
+        .args(args)
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()?;
+
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        log::error!("schtasks error. Args: {:?}. Status: {}. Stderr: {}", args, output.status, err);
+        // Creating a HighestAvailable task needs an elevated DX3
+        return Err(anyhow::anyhow!("Task Scheduler error: {} (Run DX3 as Administrator)", err.trim()));
+    }
+
+    Ok(())
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
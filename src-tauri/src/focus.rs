@@ -0,0 +1,168 @@
+use serde::{Serialize, Deserialize};
+
+// --- Foreground Process Tracking ---
+// Keeps SharedState::foreground_process set to the exe name of the focused window ("eldenring.exe").
+// On Windows it's driven by a WinEvent hook, so it only updates when focus actually changes. Our own
+// window is skipped, so opening DX3 keeps showing the game underneath.
+//
+// FocusPause builds on it: with a list of game exes, mapped keyboard/mouse outputs are suspended
+// whenever something else is in front, so a resting stick mapped to the mouse doesn't drag the
+// cursor around the desktop after alt-tabbing. The virtual pad keeps running either way.
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FocusPause {
+    pub enabled: bool,
+    pub games: Vec<String>, // Exe names ("eldenring.exe"), case-insensitive
+}
+
+impl FocusPause {
+    /// Whether keyboard/mouse outputs should be held back with `foreground` in front. Nothing is
+    /// suspended before the first focus change is known.
+    pub fn suspends(&self, foreground: &str) -> bool {
+        self.enabled && !foreground.is_empty() && !self.games.iter().any(|g| g.trim().eq_ignore_ascii_case(foreground))
+    }
+}
+
+pub use sys::start;
+
+#[cfg(windows)]
+mod sys {
+    use std::ffi::c_void;
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+    use std::path::Path;
+    use std::ptr;
+    use std::sync::{Arc, Mutex, OnceLock};
+    use std::thread;
+    use log::{debug, info, warn};
+
+    use crate::state::SharedState;
+
+    const EVENT_SYSTEM_FOREGROUND: u32 = 0x0003;
+    const WINEVENT_OUTOFCONTEXT: u32 = 0x0000;
+    const WINEVENT_SKIPOWNPROCESS: u32 = 0x0002;
+    const PROCESS_QUERY_LIMITED_INFORMATION: u32 = 0x1000;
+
+    type WinEventProc = extern "system" fn(*mut c_void, u32, *mut c_void, i32, i32, u32, u32);
+
+    // MSG; only used as a buffer for the message loop
+    #[repr(C)]
+    struct Msg {
+        hwnd: *mut c_void,
+        message: u32,
+        wparam: usize,
+        lparam: isize,
+        time: u32,
+        pt: [i32; 2],
+        private: u32,
+    }
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn SetWinEventHook(
+            event_min: u32, event_max: u32, module: *mut c_void, callback: WinEventProc,
+            process_id: u32, thread_id: u32, flags: u32,
+        ) -> *mut c_void;
+        fn GetMessageW(msg: *mut Msg, hwnd: *mut c_void, filter_min: u32, filter_max: u32) -> i32;
+        fn GetForegroundWindow() -> *mut c_void;
+        fn GetWindowThreadProcessId(hwnd: *mut c_void, process_id: *mut u32) -> u32;
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn OpenProcess(access: u32, inherit: i32, process_id: u32) -> *mut c_void;
+        fn QueryFullProcessImageNameW(process: *mut c_void, flags: u32, name: *mut u16, size: *mut u32) -> i32;
+        fn CloseHandle(handle: *mut c_void) -> i32;
+    }
+
+    // The hook callback has no user pointer
+    static STATE: OnceLock<Arc<Mutex<SharedState>>> = OnceLock::new();
+
+    /// Exe file name of the process owning `hwnd`. None for our own window and for
+    /// protected/system processes we can't open.
+    fn process_name(hwnd: *mut c_void) -> Option<String> {
+        if hwnd.is_null() {
+            return None;
+        }
+        let mut pid = 0u32;
+        unsafe { GetWindowThreadProcessId(hwnd, &mut pid) };
+        if pid == 0 || pid == std::process::id() {
+            return None;
+        }
+
+        let mut buf = [0u16; 260];
+        let mut len = buf.len() as u32;
+        let ok = unsafe {
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            if process.is_null() {
+                return None;
+            }
+            let ok = QueryFullProcessImageNameW(process, 0, buf.as_mut_ptr(), &mut len);
+            CloseHandle(process);
+            ok
+        };
+        if ok == 0 {
+            return None;
+        }
+
+        let path = OsString::from_wide(&buf[..len as usize]);
+        Path::new(&path).file_name().map(|n| n.to_string_lossy().into_owned())
+    }
+
+    fn set_foreground(name: String) {
+        if let Some(state) = STATE.get() {
+            let mut s = state.lock().unwrap();
+            if s.foreground_process != name {
+                debug!("Foreground process: {}", name);
+                s.foreground_process = name;
+            }
+        }
+    }
+
+    extern "system" fn on_foreground(_hook: *mut c_void, _event: u32, hwnd: *mut c_void, _object: i32, _child: i32, _thread: u32, _time: u32) {
+        if let Some(name) = process_name(hwnd) {
+            set_foreground(name);
+        }
+    }
+
+    /// Spawns the hook thread. Call once at startup.
+    pub fn start(state: Arc<Mutex<SharedState>>) {
+        if STATE.set(state).is_err() {
+            return;
+        }
+
+        thread::spawn(|| {
+            if let Some(name) = process_name(unsafe { GetForegroundWindow() }) {
+                set_foreground(name);
+            }
+
+            // Out-of-context hooks are delivered through this thread's message queue
+            let hook = unsafe {
+                SetWinEventHook(
+                    EVENT_SYSTEM_FOREGROUND, EVENT_SYSTEM_FOREGROUND, ptr::null_mut(), on_foreground,
+                    0, 0, WINEVENT_OUTOFCONTEXT | WINEVENT_SKIPOWNPROCESS,
+                )
+            };
+            if hook.is_null() {
+                warn!("Failed to install foreground window hook");
+                return;
+            }
+            info!("Foreground process tracking started");
+
+            let mut msg = Msg { hwnd: ptr::null_mut(), message: 0, wparam: 0, lparam: 0, time: 0, pt: [0; 2], private: 0 };
+            while unsafe { GetMessageW(&mut msg, ptr::null_mut(), 0, 0) } > 0 {}
+        });
+    }
+}
+
+// No portable way to ask for the focused window's process (X11 and each Wayland compositor differ),
+// foreground_process stays empty, which FocusPause treats as "nothing to suspend"
+#[cfg(not(windows))]
+mod sys {
+    use std::sync::{Arc, Mutex};
+    use crate::state::SharedState;
+
+    pub fn start(_state: Arc<Mutex<SharedState>>) {
+        log::info!("Foreground process tracking isn't available on this platform");
+    }
+}
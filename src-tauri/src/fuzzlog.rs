@@ -0,0 +1,83 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::{info, warn};
+use serde_json::{json, Value};
+
+use crate::config::AppConfig;
+
+// --- Fuzzer Session Log ---
+// Every packet the fuzzer/sweep sends is appended to %APPDATA%/DX3/fuzz/<kind>-<ts>.jsonl, one JSON
+// object per line, so findings survive the step counter wrapping around. "mark" lines record that the
+// user saw the controller react; they carry the last step sent (the few lines before it are the suspects).
+
+struct Session {
+    file: File,
+    last_step: Option<Value>,
+}
+
+static SESSION: Mutex<Option<Session>> = Mutex::new(None);
+
+fn now_ms() -> u128 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0)
+}
+
+pub fn session_dir() -> PathBuf {
+    let mut path = AppConfig::config_path().parent().unwrap().to_path_buf();
+    path.push("fuzz");
+    if !path.exists() {
+        let _ = fs::create_dir_all(&path);
+    }
+    path
+}
+
+fn write_line(session: &mut Session, line: &Value) {
+    if let Err(e) = writeln!(session.file, "{}", line) {
+        warn!("Failed to write fuzzer session log: {}", e);
+    }
+}
+
+/// Starts a new session file (closing any previous one). `kind` is "fuzzer" or "sweep".
+pub fn begin(kind: &str) -> Option<PathBuf> {
+    let path = session_dir().join(format!("{}-{}.jsonl", kind, now_ms() / 1000));
+    let file = match File::create(&path) {
+        Ok(f) => f,
+        Err(e) => {
+            warn!("Failed to create fuzzer session log {:?}: {}", path, e);
+            *SESSION.lock().unwrap() = None;
+            return None;
+        }
+    };
+    info!("Fuzzer session log: {:?}", path);
+    let mut session = Session { file, last_step: None };
+    write_line(&mut session, &json!({ "ts_ms": now_ms(), "type": "begin", "kind": kind }));
+    *SESSION.lock().unwrap() = Some(session);
+    Some(path)
+}
+
+pub fn end() {
+    if let Some(mut session) = SESSION.lock().unwrap().take() {
+        write_line(&mut session, &json!({ "ts_ms": now_ms(), "type": "end" }));
+    }
+}
+
+/// Logs one packet sent by the fuzzer/sweep. No-op outside a session.
+pub fn record(step: usize, desc: &str, hex: &str, result: &str) {
+    let mut guard = SESSION.lock().unwrap();
+    if let Some(session) = guard.as_mut() {
+        let line = json!({ "ts_ms": now_ms(), "type": "step", "step": step, "desc": desc, "hex": hex, "result": result });
+        write_line(session, &line);
+        session.last_step = Some(line);
+    }
+}
+
+/// Flags the most recent step as having done something visible
+pub fn mark(note: &str) -> Result<(), String> {
+    let mut guard = SESSION.lock().unwrap();
+    let session = guard.as_mut().ok_or("No fuzzer session running")?;
+    let line = json!({ "ts_ms": now_ms(), "type": "mark", "note": note, "last_step": session.last_step });
+    write_line(session, &line);
+    Ok(())
+}
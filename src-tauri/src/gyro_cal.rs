@@ -0,0 +1,73 @@
+use std::time::{Duration, Instant};
+
+use crate::mapping::GamepadState;
+
+// --- Gyro Drift Calibration ---
+// A gyro at rest never reads exactly zero, and every unit is off by its own fraction of a deg/s.
+// Integrated into mouse movement that's a steady crawl, worse over BT where the controller warms
+// up. Calibration samples the gyro with the controller lying still and stores the average per
+// controller serial (config `gyro_biases`, [pitch, yaw, roll] in deg/s). The bias is removed right
+// after parsing, so Gyro Aim, the motion readout and anything else reading the gyro get it clean.
+
+pub const SAMPLE_TIME: Duration = Duration::from_secs(3);
+// Sensor noise at rest stays well inside this; more means the controller was moved
+const MAX_SPREAD: f32 = 5.0;
+// Anything further off than this is a controller being held or turned, not drift
+const MAX_OFFSET: f32 = 15.0;
+
+fn axes(g: &GamepadState) -> [f32; 3] {
+    [g.gyro_x, g.gyro_y, g.gyro_z]
+}
+
+pub fn apply(bias: &[f32; 3], state: &mut GamepadState) {
+    state.gyro_x -= bias[0];
+    state.gyro_y -= bias[1];
+    state.gyro_z -= bias[2];
+}
+
+pub struct GyroSampler {
+    started: Instant,
+    sum: [f32; 3],
+    min: [f32; 3],
+    max: [f32; 3],
+    count: u32,
+}
+
+impl GyroSampler {
+    pub fn new() -> Self {
+        Self { started: Instant::now(), sum: [0.0; 3], min: [f32::MAX; 3], max: [f32::MIN; 3], count: 0 }
+    }
+
+    /// Raw (uncalibrated) gyro
+    pub fn feed(&mut self, g: &GamepadState) {
+        for (i, v) in axes(g).into_iter().enumerate() {
+            self.sum[i] += v;
+            self.min[i] = self.min[i].min(v);
+            self.max[i] = self.max[i].max(v);
+        }
+        self.count += 1;
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.started.elapsed() >= SAMPLE_TIME
+    }
+
+    /// Average resting rate, or why the sample can't be used
+    pub fn result(&self) -> Result<[f32; 3], String> {
+        if self.count == 0 {
+            return Err("No input received from the controller".to_string());
+        }
+        // A live sensor is never this quiet; Switch Pro IMU reports aren't enabled
+        if (0..3).all(|i| self.min[i] == 0.0 && self.max[i] == 0.0) {
+            return Err("This controller doesn't report gyro data".to_string());
+        }
+        if (0..3).any(|i| self.max[i] - self.min[i] > MAX_SPREAD) {
+            return Err("Controller moved during calibration, lay it on a flat surface and try again".to_string());
+        }
+        let bias = self.sum.map(|s| s / self.count as f32);
+        if bias.iter().any(|b| b.abs() > MAX_OFFSET) {
+            return Err(format!("Drift too large ({:.1} deg/s), is the controller being turned?", bias.iter().fold(0.0f32, |m, b| m.max(b.abs()))));
+        }
+        Ok(bias)
+    }
+}
@@ -0,0 +1,415 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use crate::mapping::{normalize_axis, normalize_trigger, GamepadState};
+
+// --- HID Report Descriptor Decoder ---
+// Pretty-prints a raw report descriptor (one item per line, indented by collection) followed by
+// the size of every report it declares. Enough to compare clones and new Sony revisions against
+// the layouts the parsers assume; not a full HID parser. DescriptorLayout (below) goes one step
+// further and locates the gamepad controls in the input report.
+
+pub const MAX_DESCRIPTOR_SIZE: usize = 4096;
+
+fn usage_page_name(page: u32) -> String {
+    match page {
+        0x01 => "Generic Desktop".to_string(),
+        0x02 => "Simulation Controls".to_string(),
+        0x06 => "Generic Device Controls".to_string(),
+        0x07 => "Keyboard".to_string(),
+        0x08 => "LED".to_string(),
+        0x09 => "Button".to_string(),
+        0x0C => "Consumer".to_string(),
+        0x0F => "Physical Input Device".to_string(),
+        0xFF00..=0xFFFF => format!("Vendor 0x{:04X}", page),
+        _ => format!("0x{:02X}", page),
+    }
+}
+
+fn usage_name(page: u32, usage: u32) -> String {
+    let name = match (page, usage) {
+        (0x01, 0x01) => "Pointer",
+        (0x01, 0x02) => "Mouse",
+        (0x01, 0x04) => "Joystick",
+        (0x01, 0x05) => "Game Pad",
+        (0x01, 0x30) => "X",
+        (0x01, 0x31) => "Y",
+        (0x01, 0x32) => "Z",
+        (0x01, 0x33) => "Rx",
+        (0x01, 0x34) => "Ry",
+        (0x01, 0x35) => "Rz",
+        (0x01, 0x39) => "Hat Switch",
+        (0x09, _) => return format!("Button {}", usage),
+        _ => return format!("0x{:02X}", usage),
+    };
+    name.to_string()
+}
+
+fn collection_name(kind: u32) -> &'static str {
+    match kind {
+        0x00 => "Physical",
+        0x01 => "Application",
+        0x02 => "Logical",
+        0x03 => "Report",
+        _ => "Vendor",
+    }
+}
+
+fn main_flags(data: u32) -> String {
+    format!(
+        "{},{},{}",
+        if data & 1 != 0 { "Const" } else { "Data" },
+        if data & 2 != 0 { "Var" } else { "Array" },
+        if data & 4 != 0 { "Rel" } else { "Abs" },
+    )
+}
+
+fn signed(data: u32, size: usize) -> i64 {
+    match size {
+        1 => data as u8 as i8 as i64,
+        2 => data as u16 as i16 as i64,
+        _ => data as i32 as i64,
+    }
+}
+
+enum Item<'a> {
+    Short { bytes: &'a [u8], kind: u8, tag: u8, size: usize, data: u32 },
+    Long { len: usize },
+    Truncated { at: usize },
+}
+
+/// Walks the descriptor item by item; stops after a truncated item
+fn items(desc: &[u8]) -> impl Iterator<Item = Item<'_>> {
+    let mut i = 0;
+    std::iter::from_fn(move || {
+        if i >= desc.len() {
+            return None;
+        }
+        let prefix = desc[i];
+        // Long items: 0xFE, data size, tag, data
+        if prefix == 0xFE {
+            let len = desc.get(i + 1).copied().unwrap_or(0) as usize;
+            i += 3 + len;
+            return Some(Item::Long { len });
+        }
+
+        let size = match prefix & 0x03 { 3 => 4, n => n as usize };
+        if i + 1 + size > desc.len() {
+            let at = i;
+            i = desc.len();
+            return Some(Item::Truncated { at });
+        }
+        let bytes = &desc[i..i + 1 + size];
+        let data = bytes[1..].iter().rev().fold(0u32, |acc, &b| (acc << 8) | b as u32);
+        i += 1 + size;
+        Some(Item::Short { bytes, kind: (prefix >> 2) & 0x03, tag: prefix >> 4, size, data })
+    })
+}
+
+pub fn pretty_print(desc: &[u8]) -> String {
+    let mut out = String::new();
+    let mut indent = 0usize;
+    let mut usage_page = 0u32;
+    let mut report_id = 0u8;
+    let mut report_size = 0u32;
+    let mut report_count = 0u32;
+    // (report id, kind) -> bits
+    let mut reports: BTreeMap<(u8, &'static str), u32> = BTreeMap::new();
+
+    for item in items(desc) {
+        let (bytes, kind, tag, size, data) = match item {
+            Item::Short { bytes, kind, tag, size, data } => (bytes, kind, tag, size, data),
+            Item::Long { len } => {
+                let _ = writeln!(out, "{:indent$}Long Item ({} bytes)", "", len, indent = indent * 2);
+                continue;
+            }
+            Item::Truncated { at } => {
+                let _ = writeln!(out, "Truncated item at byte {}", at);
+                break;
+            }
+        };
+        let hex = bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ");
+
+        let text = match (kind, tag) {
+            // Main
+            (0, 0x8) | (0, 0x9) | (0, 0xB) => {
+                let name = match tag { 0x8 => "Input", 0x9 => "Output", _ => "Feature" };
+                *reports.entry((report_id, name)).or_insert(0) += report_size * report_count;
+                format!("{} ({})", name, main_flags(data))
+            }
+            (0, 0xA) => format!("Collection ({})", collection_name(data)),
+            (0, 0xC) => {
+                indent = indent.saturating_sub(1);
+                "End Collection".to_string()
+            }
+            // Global
+            (1, 0x0) => {
+                usage_page = data;
+                format!("Usage Page ({})", usage_page_name(data))
+            }
+            (1, 0x1) => format!("Logical Minimum ({})", signed(data, size)),
+            (1, 0x2) => format!("Logical Maximum ({})", signed(data, size)),
+            (1, 0x3) => format!("Physical Minimum ({})", signed(data, size)),
+            (1, 0x4) => format!("Physical Maximum ({})", signed(data, size)),
+            (1, 0x5) => format!("Unit Exponent ({})", data),
+            (1, 0x6) => format!("Unit (0x{:X})", data),
+            (1, 0x7) => {
+                report_size = data;
+                format!("Report Size ({})", data)
+            }
+            (1, 0x8) => {
+                report_id = data as u8;
+                format!("Report ID (0x{:02X})", data)
+            }
+            (1, 0x9) => {
+                report_count = data;
+                format!("Report Count ({})", data)
+            }
+            (1, 0xA) => "Push".to_string(),
+            (1, 0xB) => "Pop".to_string(),
+            // Local
+            (2, 0x0) => format!("Usage ({})", usage_name(usage_page, data)),
+            (2, 0x1) => format!("Usage Minimum ({})", usage_name(usage_page, data)),
+            (2, 0x2) => format!("Usage Maximum ({})", usage_name(usage_page, data)),
+            _ => format!("Unknown item (type {}, tag 0x{:X})", kind, tag),
+        };
+
+        let _ = writeln!(out, "{:<15}{:indent$}{}", hex, "", text, indent = indent * 2);
+        if kind == 0 && tag == 0xA {
+            indent += 1;
+        }
+    }
+
+    let _ = writeln!(out, "\n>> Reports ({} bytes descriptor):", desc.len());
+    for ((id, kind), bits) in &reports {
+        // Byte count as seen by hidapi: the report ID byte is included when IDs are used
+        let bytes = bits.div_ceil(8) + if *id != 0 { 1 } else { 0 };
+        let _ = writeln!(out, "ID 0x{:02X} {:<7} {} bytes", id, kind, bytes);
+    }
+    out
+}
+
+// --- Descriptor-Driven Input Layout ---
+// Bit positions of the gamepad controls in the input report the descriptor declares, so reports
+// that match no hardcoded layout (simple mode, clones, new firmware) can still be parsed. Sticks
+// follow Sony's usage assignment: X/Y = left stick, Z/Rz = right stick, Rx/Ry = L2/R2, and buttons
+// 1-15 = Square, Cross, Circle, Triangle, L1, R1, L2, R2, Share, Options, L3, R3, PS, Touchpad, Mute.
+
+#[derive(Clone, Copy, Debug)]
+struct Field {
+    bit: usize,
+    size: usize,
+    min: i64,
+    max: i64,
+}
+
+impl Field {
+    fn raw(&self, data: &[u8]) -> Option<i64> {
+        if self.size == 0 || self.size > 32 || (self.bit + self.size).div_ceil(8) > data.len() {
+            return None;
+        }
+        let mut v = 0u32;
+        for i in 0..self.size {
+            let b = self.bit + i;
+            if (data[b / 8] >> (b % 8)) & 1 != 0 {
+                v |= 1 << i;
+            }
+        }
+        // Signed fields when the logical range goes negative
+        if self.min < 0 && self.size < 32 && v & (1 << (self.size - 1)) != 0 {
+            return Some(v as i64 - (1i64 << self.size));
+        }
+        Some(v as i64)
+    }
+
+    /// Scaled to 0-255 so the usual normalize_axis/normalize_trigger apply
+    fn byte(&self, data: &[u8]) -> Option<u8> {
+        let raw = self.raw(data)?;
+        if self.max <= self.min {
+            return Some(raw.clamp(0, 255) as u8);
+        }
+        let unit = (raw - self.min) as f32 / (self.max - self.min) as f32;
+        Some((unit.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+}
+
+const AXIS_USAGES: [(u32, &str); 6] = [(0x30, "X"), (0x31, "Y"), (0x32, "Z"), (0x33, "Rx"), (0x34, "Ry"), (0x35, "Rz")];
+
+#[derive(Clone, Debug, Default)]
+pub struct DescriptorLayout {
+    pub report_id: u8,
+    axes: [Option<Field>; 6], // X, Y, Z, Rx, Ry, Rz
+    hat: Option<Field>,
+    buttons: Vec<Option<Field>>, // Button 1..
+}
+
+#[derive(Clone, Copy, Default)]
+struct Globals {
+    usage_page: u32,
+    min: i64,
+    max: i64,
+    size: usize,
+    count: usize,
+    report_id: u8,
+}
+
+impl DescriptorLayout {
+    /// The first input report with an X axis and a hat or buttons, None if there is none
+    pub fn derive(desc: &[u8]) -> Option<Self> {
+        let mut g = Globals::default();
+        let mut stack: Vec<Globals> = Vec::new();
+        let mut usages: Vec<u32> = Vec::new(); // (page << 16) | usage
+        let mut usage_range: (Option<u32>, Option<u32>) = (None, None);
+        let mut bit_offsets: BTreeMap<u8, usize> = BTreeMap::new();
+        let mut layouts: BTreeMap<u8, DescriptorLayout> = BTreeMap::new();
+
+        for item in items(desc) {
+            let Item::Short { kind, tag, size, data, .. } = item else { continue };
+            let full_usage = |data: u32| if size == 4 { data } else { (g.usage_page << 16) | data };
+            match (kind, tag) {
+                // Input
+                (0, 0x8) => {
+                    let offset = bit_offsets.entry(g.report_id).or_insert(0);
+                    let is_const = data & 0x01 != 0;
+                    let is_variable = data & 0x02 != 0;
+                    if !is_const && is_variable {
+                        let layout = layouts.entry(g.report_id).or_insert_with(|| DescriptorLayout { report_id: g.report_id, ..Default::default() });
+                        for i in 0..g.count {
+                            let usage = match usage_range {
+                                (Some(lo), Some(_)) => Some(lo + i as u32),
+                                _ => usages.get(i).or(usages.last()).copied(),
+                            };
+                            let Some(usage) = usage else { continue };
+                            let field = Field { bit: *offset + i * g.size, size: g.size, min: g.min, max: g.max };
+                            layout.assign(usage, field);
+                        }
+                    }
+                    *offset += g.size * g.count;
+                }
+                // Other main items just clear the locals
+                (0, _) => {}
+                // Global
+                (1, 0x0) => g.usage_page = data,
+                (1, 0x1) => g.min = signed(data, size),
+                (1, 0x2) => g.max = signed(data, size),
+                (1, 0x7) => g.size = data as usize,
+                (1, 0x8) => g.report_id = data as u8,
+                (1, 0x9) => g.count = data as usize,
+                (1, 0xA) => stack.push(g),
+                (1, 0xB) => g = stack.pop().unwrap_or(g),
+                // Local
+                (2, 0x0) => usages.push(full_usage(data)),
+                (2, 0x1) => usage_range.0 = Some(full_usage(data)),
+                (2, 0x2) => usage_range.1 = Some(full_usage(data)),
+                _ => {}
+            }
+            if kind == 0 {
+                usages.clear();
+                usage_range = (None, None);
+            }
+        }
+
+        layouts.into_values().find(|l| l.axes[0].is_some() && (l.hat.is_some() || !l.buttons.is_empty()))
+    }
+
+    fn assign(&mut self, usage: u32, field: Field) {
+        let (page, id) = (usage >> 16, usage & 0xFFFF);
+        match (page, id) {
+            (0x01, 0x30..=0x35) => self.axes[(id - 0x30) as usize] = Some(field),
+            (0x01, 0x39) => self.hat = Some(field),
+            (0x09, 1..=32) => {
+                let idx = id as usize - 1;
+                if self.buttons.len() <= idx {
+                    self.buttons.resize(idx + 1, None);
+                }
+                self.buttons[idx] = Some(field);
+            }
+            _ => {}
+        }
+    }
+
+    /// e.g. "ID 0x01: X Y Z Rx Ry Rz, Hat, 15 buttons"
+    pub fn summary(&self) -> String {
+        let axes: Vec<&str> = AXIS_USAGES.iter().zip(&self.axes).filter(|(_, f)| f.is_some()).map(|((_, n), _)| *n).collect();
+        format!(
+            "ID 0x{:02X}: {}{}, {} buttons",
+            self.report_id,
+            axes.join(" "),
+            if self.hat.is_some() { ", Hat" } else { "" },
+            self.buttons.iter().filter(|b| b.is_some()).count()
+        )
+    }
+
+    /// Controls from the descriptor; battery, touchpad and jack state are kept from `base`
+    pub fn parse(&self, report: &[u8], base: Option<GamepadState>) -> Option<GamepadState> {
+        // hidapi includes the report ID byte only when the device uses IDs
+        let data = if self.report_id != 0 {
+            if report.first() != Some(&self.report_id) {
+                return None;
+            }
+            &report[1..]
+        } else {
+            report
+        };
+
+        let base = base.unwrap_or_default();
+        let mut state = GamepadState {
+            touch_x: base.touch_x,
+            touch_y: base.touch_y,
+            touch_active: base.touch_active,
+            touch2_x: base.touch2_x,
+            touch2_y: base.touch2_y,
+            touch2_active: base.touch2_active,
+            battery: base.battery,
+            is_charging: base.is_charging,
+            charge_state: base.charge_state,
+            headphones: base.headphones,
+            mic: base.mic,
+            gyro_x: base.gyro_x,
+            gyro_y: base.gyro_y,
+            gyro_z: base.gyro_z,
+            accel_x: base.accel_x,
+            accel_y: base.accel_y,
+            accel_z: base.accel_z,
+            ..GamepadState::default()
+        };
+
+        let axis = |i: usize| self.axes[i].and_then(|f| f.byte(data));
+        state.left_x = normalize_axis(axis(0)?);
+        state.left_y = normalize_axis(axis(1).unwrap_or(128));
+        state.right_x = normalize_axis(axis(2).unwrap_or(128));
+        state.right_y = normalize_axis(axis(5).unwrap_or(128));
+        state.l2 = axis(3).map(normalize_trigger).unwrap_or(0.0);
+        state.r2 = axis(4).map(normalize_trigger).unwrap_or(0.0);
+
+        if let Some(hat) = self.hat {
+            // Logical range is 0-7 clockwise from up, anything outside is neutral
+            let v = hat.raw(data).unwrap_or(-1) - hat.min;
+            if (0..8).contains(&v) {
+                state.dpad_up = matches!(v, 7 | 0 | 1);
+                state.dpad_right = matches!(v, 1..=3);
+                state.dpad_down = matches!(v, 3..=5);
+                state.dpad_left = matches!(v, 5..=7);
+            }
+        }
+
+        let pressed = |n: usize| self.buttons.get(n).copied().flatten().and_then(|f| f.raw(data)).is_some_and(|v| v != 0);
+        state.btn_square = pressed(0);
+        state.btn_cross = pressed(1);
+        state.btn_circle = pressed(2);
+        state.btn_triangle = pressed(3);
+        state.btn_l1 = pressed(4);
+        state.btn_r1 = pressed(5);
+        state.btn_share = pressed(8);
+        state.btn_options = pressed(9);
+        state.btn_l3 = pressed(10);
+        state.btn_r3 = pressed(11);
+        state.btn_ps = pressed(12);
+        state.btn_touchpad = pressed(13);
+        state.btn_mute = pressed(14);
+        // Pads without analog triggers only have the digital buttons
+        if self.axes[3].is_none() && pressed(6) { state.l2 = 1.0; }
+        if self.axes[4].is_none() && pressed(7) { state.r2 = 1.0; }
+        Some(state)
+    }
+}
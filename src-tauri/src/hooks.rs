@@ -0,0 +1,73 @@
+use std::process::Command;
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+use std::thread;
+use log::{info, warn};
+
+// --- Connect/Disconnect Hooks ---
+// User commands run through cmd.exe (sh on Linux) when a controller attaches or detaches, e.g.
+// `"C:\Tools\Moonlight.exe"` or `C:\Scripts\switch-audio.bat`.
+// The event ("connect" or "disconnect") is appended as an argument and exported as DX3_EVENT.
+// Device name and serial come from the controller, so they never go into the command line (cmd
+// expands %VAR% even inside quotes); they're only exported as DX3_DEVICE_NAME and DX3_DEVICE_SERIAL.
+
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+#[derive(Clone, Copy)]
+pub enum HookEvent {
+    Connect,
+    Disconnect,
+}
+
+impl HookEvent {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Connect => "connect",
+            Self::Disconnect => "disconnect",
+        }
+    }
+}
+
+/// Runs `command` on its own thread so a slow script never stalls input. Empty command = no-op.
+pub fn run(command: &str, event: HookEvent, device_name: &str, serial: &str) {
+    let command = command.trim();
+    if command.is_empty() {
+        return;
+    }
+    let name = device_name.to_string();
+    let serial = serial.to_string();
+    let mut shell = shell_command(command, event);
+    let command = command.to_string();
+
+    thread::spawn(move || {
+        info!("Running {} hook: {}", event.as_str(), command);
+        let result = shell
+            .env("DX3_EVENT", event.as_str())
+            .env("DX3_DEVICE_NAME", &name)
+            .env("DX3_DEVICE_SERIAL", &serial)
+            .status();
+        match result {
+            Ok(status) if !status.success() => warn!("{} hook exited with {}", event.as_str(), status),
+            Ok(_) => {}
+            Err(e) => warn!("Failed to run {} hook: {}", event.as_str(), e),
+        }
+    });
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str, event: HookEvent) -> Command {
+    // /S: cmd strips exactly the outer quote pair, leaving the user's own quoting intact
+    let line = format!("/D /S /C \"{} {}\"", command, event.as_str());
+    let mut shell = Command::new("cmd");
+    shell.raw_arg(line).creation_flags(CREATE_NO_WINDOW);
+    shell
+}
+
+#[cfg(not(windows))]
+fn shell_command(command: &str, event: HookEvent) -> Command {
+    // The event goes in as a positional parameter, so it needs no quoting
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(format!("{} \"$@\"", command)).args(["sh", event.as_str()]);
+    shell
+}
@@ -0,0 +1,184 @@
+use std::ffi::c_void;
+use std::sync::Mutex;
+use log::info;
+
+use crate::platform;
+
+// --- Interception Backend ---
+// Some games and anti-cheats drop keyboard/mouse events synthesized with SendInput (they carry the
+// LLKHF_INJECTED flag). The Interception driver inserts strokes below that, as if they came from a
+// real keyboard and mouse. Profiles opt in with `input_backend`; while it's active the worker's
+// send_* functions go through here first and fall back to SendInput when this returns false.
+// interception.dll comes with the driver's release and is loaded at runtime.
+
+const DLL_PATHS: &[&str] = &["interception.dll", r"C:\Program Files\Interception\interception.dll"];
+
+// Interception device numbers: keyboards first, then mice
+const KEYBOARDS: std::ops::RangeInclusive<i32> = 1..=10;
+const MICE: std::ops::RangeInclusive<i32> = 11..=20;
+
+// InterceptionKeyState
+const KEY_UP: u16 = 0x01;
+const KEY_E0: u16 = 0x02;
+// InterceptionMouseState
+const MOUSE_LEFT_DOWN: u16 = 0x001;
+const MOUSE_LEFT_UP: u16 = 0x002;
+const MOUSE_RIGHT_DOWN: u16 = 0x004;
+const MOUSE_RIGHT_UP: u16 = 0x008;
+const MOUSE_MIDDLE_DOWN: u16 = 0x010;
+const MOUSE_MIDDLE_UP: u16 = 0x020;
+const MOUSE_WHEEL: u16 = 0x400;
+const MOUSE_MOVE_RELATIVE: u16 = 0x000;
+
+type CreateContextFn = unsafe extern "C" fn() -> *mut c_void;
+type DestroyContextFn = unsafe extern "C" fn(*mut c_void);
+type SendFn = unsafe extern "C" fn(*mut c_void, i32, *const c_void, u32) -> i32;
+type HardwareIdFn = unsafe extern "C" fn(*mut c_void, i32, *mut c_void, u32) -> u32;
+
+#[repr(C)]
+struct KeyStroke {
+    code: u16,
+    state: u16,
+    information: u32,
+}
+
+#[repr(C)]
+struct MouseStroke {
+    state: u16,
+    flags: u16,
+    rolling: i16,
+    x: i32,
+    y: i32,
+    information: u32,
+}
+
+struct Interception {
+    module: *mut c_void,
+    context: *mut c_void,
+    destroy: DestroyContextFn,
+    send: SendFn,
+    keyboard: i32, // Devices the strokes are attributed to
+    mouse: i32,
+}
+
+// The context is only touched under CONTEXT's lock
+unsafe impl Send for Interception {}
+
+static CONTEXT: Mutex<Option<Interception>> = Mutex::new(None);
+
+/// Loads the driver's DLL and opens a context. Returns a short status for the UI.
+pub fn enable() -> Result<String, String> {
+    let mut context = CONTEXT.lock().unwrap();
+    if context.is_some() {
+        return Ok("Interception active".to_string());
+    }
+    let module = DLL_PATHS.iter().find_map(|p| {
+        let m = unsafe { platform::load_library(p) };
+        (!m.is_null()).then_some(m)
+    }).ok_or("Interception is not installed (interception.dll not found)")?;
+
+    match unsafe { open(module) } {
+        Ok(i) => {
+            info!("Interception backend enabled (keyboard {}, mouse {})", i.keyboard, i.mouse);
+            *context = Some(i);
+            Ok("Interception active".to_string())
+        }
+        Err(e) => {
+            unsafe { platform::free_library(module); }
+            Err(e)
+        }
+    }
+}
+
+/// Back to SendInput
+pub fn disable() {
+    if CONTEXT.lock().unwrap().take().is_some() {
+        info!("Interception backend disabled");
+    }
+}
+
+unsafe fn open(module: *mut c_void) -> Result<Interception, String> {
+    macro_rules! proc {
+        ($name:literal, $ty:ty) => {{
+            let f = platform::get_proc_address(module, concat!($name, "\0").as_ptr());
+            if f.is_null() {
+                return Err(format!("interception.dll has no {}", $name));
+            }
+            std::mem::transmute::<*mut c_void, $ty>(f)
+        }};
+    }
+    let create = proc!("interception_create_context", CreateContextFn);
+    let hardware_id = proc!("interception_get_hardware_id", HardwareIdFn);
+    let destroy = proc!("interception_destroy_context", DestroyContextFn);
+    let send = proc!("interception_send", SendFn);
+
+    let context = create();
+    if context.is_null() {
+        return Err("Interception driver isn't running (install it and reboot)".to_string());
+    }
+    // Strokes have to name a device that exists
+    let mut buf = [0u8; 512];
+    let mut present = |range: std::ops::RangeInclusive<i32>| {
+        range.into_iter().find(|&d| hardware_id(context, d, buf.as_mut_ptr() as *mut c_void, buf.len() as u32) > 0)
+    };
+    let (Some(keyboard), Some(mouse)) = (present(KEYBOARDS), present(MICE)) else {
+        destroy(context);
+        return Err("Interception found no keyboard or mouse to send as".to_string());
+    };
+    Ok(Interception { module, context, destroy, send, keyboard, mouse })
+}
+
+impl Drop for Interception {
+    fn drop(&mut self) {
+        unsafe {
+            (self.destroy)(self.context);
+            platform::free_library(self.module);
+        }
+    }
+}
+
+fn with<T>(f: impl FnOnce(&Interception) -> T) -> Option<T> {
+    CONTEXT.lock().unwrap().as_ref().map(f)
+}
+
+fn send_mouse_stroke(state: u16, rolling: i16, x: i32, y: i32) -> bool {
+    with(|i| {
+        let stroke = MouseStroke { state, flags: MOUSE_MOVE_RELATIVE, rolling, x, y, information: 0 };
+        unsafe { (i.send)(i.context, i.mouse, &stroke as *const _ as *const c_void, 1) > 0 }
+    }).unwrap_or(false)
+}
+
+/// `scan` as in keys::KeyStroke (0xE0 in the high byte for extended keys)
+pub fn send_key(scan: u16, down: bool) -> bool {
+    with(|i| {
+        let mut state = if down { 0 } else { KEY_UP };
+        if scan >> 8 == 0xE0 {
+            state |= KEY_E0;
+        }
+        let stroke = KeyStroke { code: scan & 0xFF, state, information: 0 };
+        unsafe { (i.send)(i.context, i.keyboard, &stroke as *const _ as *const c_void, 1) > 0 }
+    }).unwrap_or(false)
+}
+
+/// 0: Left, 1: Middle, 2: Right (same as the worker's send_mouse)
+pub fn send_mouse_button(btn: u8, down: bool) -> bool {
+    let state = match (btn, down) {
+        (0, true) => MOUSE_LEFT_DOWN,
+        (0, false) => MOUSE_LEFT_UP,
+        (1, true) => MOUSE_MIDDLE_DOWN,
+        (1, false) => MOUSE_MIDDLE_UP,
+        (2, true) => MOUSE_RIGHT_DOWN,
+        (2, false) => MOUSE_RIGHT_UP,
+        _ => return false,
+    };
+    send_mouse_stroke(state, 0, 0, 0)
+}
+
+pub fn send_mouse_move(dx: i32, dy: i32) -> bool {
+    send_mouse_stroke(0, 0, dx, dy)
+}
+
+/// Positive notches scroll up
+pub fn send_wheel(notches: i32) -> bool {
+    send_mouse_stroke(MOUSE_WHEEL, (notches * 120) as i16, 0, 0)
+}
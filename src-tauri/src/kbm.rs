@@ -0,0 +1,255 @@
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use serde::{Serialize, Deserialize};
+
+// --- Keyboard/Mouse → Virtual Pad ---
+// For controller-only games without a pad at hand. While enabled and no controller is connected, the
+// worker drives the virtual Xbox pad from the keyboard and mouse: WASD move the left stick (ramped,
+// so taps give partial deflection), mouse movement the right stick and a fixed set of keys the
+// buttons. Keys are polled; mouse movement comes from a low-level hook, which swallows it while
+// capturing so the cursor stays put and deltas keep coming at the screen edge. Capture is off while
+// DX3 itself is focused.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KbmGamepad {
+    pub enabled: bool,
+    pub mouse_sensitivity: f32, // 1.0 = full right stick at 2000 px/s
+    pub ramp_ms: u32,           // WASD time from center to full deflection
+}
+
+impl Default for KbmGamepad {
+    fn default() -> Self {
+        Self { enabled: false, mouse_sensitivity: 1.0, ramp_ms: 80 }
+    }
+}
+
+const VK_LBUTTON: i32 = 0x01;
+const VK_RBUTTON: i32 = 0x02;
+const VK_MBUTTON: i32 = 0x04;
+const VK_TAB: i32 = 0x09;
+const VK_RETURN: i32 = 0x0D;
+const VK_SHIFT: i32 = 0x10;
+const VK_CONTROL: i32 = 0x11;
+const VK_ESCAPE: i32 = 0x1B;
+const VK_SPACE: i32 = 0x20;
+const VK_LEFT: i32 = 0x25;
+const VK_UP: i32 = 0x26;
+const VK_RIGHT: i32 = 0x27;
+const VK_DOWN: i32 = 0x28;
+
+// Pixels per second of mouse movement for full right stick at sensitivity 1.0
+const MOUSE_FULL_SPEED: f32 = 2000.0;
+
+// (VK, XButtons bit)
+const BUTTONS: &[(i32, u16)] = &[
+    (VK_SPACE, 0x1000),   // A
+    (b'C' as i32, 0x2000), // B
+    (b'R' as i32, 0x4000), // X (reload)
+    (b'E' as i32, 0x8000), // Y (interact)
+    (b'Q' as i32, 0x0100), // LB
+    (b'F' as i32, 0x0200), // RB
+    (VK_SHIFT, 0x0040),   // LThumb (sprint)
+    (VK_CONTROL, 0x0080), // RThumb (crouch/melee)
+    (VK_MBUTTON, 0x0080),
+    (VK_ESCAPE, 0x0010),  // Start
+    (VK_RETURN, 0x0010),
+    (VK_TAB, 0x0020),     // Back
+    (VK_UP, 0x0001),
+    (VK_DOWN, 0x0002),
+    (VK_LEFT, 0x0004),
+    (VK_RIGHT, 0x0008),
+];
+
+static CAPTURING: AtomicBool = AtomicBool::new(false);
+static MOUSE_DX: AtomicI32 = AtomicI32::new(0);
+static MOUSE_DY: AtomicI32 = AtomicI32::new(0);
+
+use sys::{start_hook, is_down, own_window_focused};
+
+#[cfg(windows)]
+mod sys {
+    use std::ffi::c_void;
+    use std::ptr;
+    use std::sync::atomic::Ordering;
+    use std::sync::Once;
+    use std::thread;
+    use log::{info, warn};
+    use super::{CAPTURING, MOUSE_DX, MOUSE_DY};
+
+    const WH_MOUSE_LL: i32 = 14;
+    const WM_MOUSEMOVE: usize = 0x0200;
+    const LLMHF_INJECTED: u32 = 0x01;
+
+    static HOOK_STARTED: Once = Once::new();
+
+    // MSLLHOOKSTRUCT
+    #[repr(C)]
+    struct MouseHookData {
+        pt: [i32; 2],
+        mouse_data: u32,
+        flags: u32,
+        time: u32,
+        extra_info: usize,
+    }
+
+    // MSG; only used as a buffer for the message loop
+    #[repr(C)]
+    struct Msg {
+        hwnd: *mut c_void,
+        message: u32,
+        wparam: usize,
+        lparam: isize,
+        time: u32,
+        pt: [i32; 2],
+        private: u32,
+    }
+
+    type HookProc = extern "system" fn(i32, usize, isize) -> isize;
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn SetWindowsHookExW(id: i32, callback: HookProc, module: *mut c_void, thread_id: u32) -> *mut c_void;
+        fn CallNextHookEx(hook: *mut c_void, code: i32, wparam: usize, lparam: isize) -> isize;
+        fn GetMessageW(msg: *mut Msg, hwnd: *mut c_void, filter_min: u32, filter_max: u32) -> i32;
+        fn GetCursorPos(pt: *mut [i32; 2]) -> i32;
+        fn GetAsyncKeyState(vk: i32) -> i16;
+        fn GetForegroundWindow() -> *mut c_void;
+        fn GetWindowThreadProcessId(hwnd: *mut c_void, process_id: *mut u32) -> u32;
+    }
+
+    extern "system" fn on_mouse(code: i32, wparam: usize, lparam: isize) -> isize {
+        if code >= 0 && wparam == WM_MOUSEMOVE && CAPTURING.load(Ordering::Relaxed) {
+            let data = unsafe { &*(lparam as *const MouseHookData) };
+            let mut cursor = [0i32; 2];
+            if data.flags & LLMHF_INJECTED == 0 && unsafe { GetCursorPos(&mut cursor) } != 0 {
+                MOUSE_DX.fetch_add(data.pt[0] - cursor[0], Ordering::Relaxed);
+                MOUSE_DY.fetch_add(data.pt[1] - cursor[1], Ordering::Relaxed);
+                return 1; // Swallow, the cursor stays where it is
+            }
+        }
+        unsafe { CallNextHookEx(ptr::null_mut(), code, wparam, lparam) }
+    }
+
+    /// Installs the mouse hook on its own thread. Only done once KBM mode is first used, a low-level
+    /// hook sits in the path of every mouse move on the system.
+    pub fn start_hook() {
+        HOOK_STARTED.call_once(|| {
+            thread::spawn(|| {
+                let hook = unsafe { SetWindowsHookExW(WH_MOUSE_LL, on_mouse, ptr::null_mut(), 0) };
+                if hook.is_null() {
+                    warn!("Failed to install mouse hook, mouse won't drive the right stick");
+                    return;
+                }
+                info!("Mouse hook for keyboard/mouse mode installed");
+
+                // Low-level hooks are called through this thread's message queue
+                let mut msg = Msg { hwnd: ptr::null_mut(), message: 0, wparam: 0, lparam: 0, time: 0, pt: [0; 2], private: 0 };
+                while unsafe { GetMessageW(&mut msg, ptr::null_mut(), 0, 0) } > 0 {}
+            });
+        });
+    }
+
+    pub fn is_down(vk: i32) -> bool {
+        (unsafe { GetAsyncKeyState(vk) } as u16 & 0x8000) != 0
+    }
+
+    pub fn own_window_focused() -> bool {
+        let mut pid = 0u32;
+        unsafe { GetWindowThreadProcessId(GetForegroundWindow(), &mut pid) };
+        pid == std::process::id()
+    }
+}
+
+// No global keyboard state or pointer hook to read without a display server connection, keyboard/mouse
+// mode is Windows-only for now (the worker doesn't start it elsewhere)
+#[cfg(not(windows))]
+mod sys {
+    pub fn start_hook() {}
+
+    pub fn is_down(_vk: i32) -> bool {
+        false
+    }
+
+    pub fn own_window_focused() -> bool {
+        true
+    }
+}
+
+fn axis(neg: bool, pos: bool) -> f32 {
+    (pos as i32 - neg as i32) as f32
+}
+
+fn ramp(current: f32, target: f32, step: f32) -> f32 {
+    if (target - current).abs() <= step {
+        target
+    } else {
+        current + step * (target - current).signum()
+    }
+}
+
+/// Virtual pad state built from keyboard and mouse, one `poll` per output frame
+#[derive(Default)]
+pub struct KbmPad {
+    left: (f32, f32),
+    right: (f32, f32),
+}
+
+/// Stick values as -1.0..1.0 (Y up), like GamepadState
+pub struct KbmFrame {
+    pub buttons: u16,
+    pub left_trigger: f32,
+    pub right_trigger: f32,
+    pub left: (f32, f32),
+    pub right: (f32, f32),
+}
+
+impl KbmPad {
+    pub fn new() -> Self {
+        start_hook();
+        MOUSE_DX.store(0, Ordering::Relaxed);
+        MOUSE_DY.store(0, Ordering::Relaxed);
+        Self::default()
+    }
+
+    pub fn poll(&mut self, config: &KbmGamepad, dt: f32) -> KbmFrame {
+        let capturing = !own_window_focused();
+        CAPTURING.store(capturing, Ordering::Relaxed);
+        let dx = MOUSE_DX.swap(0, Ordering::Relaxed) as f32;
+        let dy = MOUSE_DY.swap(0, Ordering::Relaxed) as f32;
+        if !capturing {
+            // Keys typed into our own window shouldn't move the pad
+            *self = Self::default();
+            return KbmFrame { buttons: 0, left_trigger: 0.0, right_trigger: 0.0, left: (0.0, 0.0), right: (0.0, 0.0) };
+        }
+
+        let step = if config.ramp_ms == 0 { 2.0 } else { dt * 1000.0 / config.ramp_ms as f32 };
+        let (mut tx, mut ty) = (axis(is_down(b'A' as i32), is_down(b'D' as i32)), axis(is_down(b'S' as i32), is_down(b'W' as i32)));
+        let len = (tx * tx + ty * ty).sqrt();
+        if len > 1.0 {
+            tx /= len;
+            ty /= len;
+        }
+        self.left = (ramp(self.left.0, tx, step), ramp(self.left.1, ty, step));
+
+        // Same smoothing as the physical sticks, mouse deltas arrive in uneven bursts
+        let scale = config.mouse_sensitivity / (MOUSE_FULL_SPEED * dt.max(0.001));
+        let (mx, my) = ((dx * scale).clamp(-1.0, 1.0), (-dy * scale).clamp(-1.0, 1.0));
+        let alpha = 0.25f32;
+        self.right.0 += alpha * (mx - self.right.0);
+        self.right.1 += alpha * (my - self.right.1);
+
+        let buttons = BUTTONS.iter().filter(|(vk, _)| is_down(*vk)).fold(0u16, |b, (_, bit)| b | bit);
+        KbmFrame {
+            buttons,
+            left_trigger: if is_down(VK_RBUTTON) { 1.0 } else { 0.0 },
+            right_trigger: if is_down(VK_LBUTTON) { 1.0 } else { 0.0 },
+            left: self.left,
+            right: self.right,
+        }
+    }
+}
+
+impl Drop for KbmPad {
+    fn drop(&mut self) {
+        CAPTURING.store(false, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,217 @@
+use serde::Serialize;
+
+use crate::mapping::KeyInjection;
+
+// --- Physical Keys ---
+// Key targets recorded as VK codes follow the layout they were recorded on: VK_Q sits where A is on
+// AZERTY, VK_Z and VK_Y swap on QWERTZ. `Key` targets instead store the physical key (the browser's
+// KeyboardEvent.code, "KeyQ", "Semicolon", ...) and are translated through the foreground window's
+// layout when pressed, so a WASD profile stays WASD-shaped everywhere.
+// On Linux there's no layout to ask without a display server connection; the scancode is what gets
+// injected there anyway, VKs and names follow US QWERTY.
+
+/// code → set 1 scancode, 0xE0xx for extended keys
+const KEYS: &[(&str, u16)] = &[
+    ("Escape", 0x01),
+    ("Digit1", 0x02), ("Digit2", 0x03), ("Digit3", 0x04), ("Digit4", 0x05), ("Digit5", 0x06),
+    ("Digit6", 0x07), ("Digit7", 0x08), ("Digit8", 0x09), ("Digit9", 0x0A), ("Digit0", 0x0B),
+    ("Minus", 0x0C), ("Equal", 0x0D), ("Backspace", 0x0E), ("Tab", 0x0F),
+    ("KeyQ", 0x10), ("KeyW", 0x11), ("KeyE", 0x12), ("KeyR", 0x13), ("KeyT", 0x14),
+    ("KeyY", 0x15), ("KeyU", 0x16), ("KeyI", 0x17), ("KeyO", 0x18), ("KeyP", 0x19),
+    ("BracketLeft", 0x1A), ("BracketRight", 0x1B), ("Enter", 0x1C), ("ControlLeft", 0x1D),
+    ("KeyA", 0x1E), ("KeyS", 0x1F), ("KeyD", 0x20), ("KeyF", 0x21), ("KeyG", 0x22),
+    ("KeyH", 0x23), ("KeyJ", 0x24), ("KeyK", 0x25), ("KeyL", 0x26),
+    ("Semicolon", 0x27), ("Quote", 0x28), ("Backquote", 0x29), ("ShiftLeft", 0x2A), ("Backslash", 0x2B),
+    ("KeyZ", 0x2C), ("KeyX", 0x2D), ("KeyC", 0x2E), ("KeyV", 0x2F), ("KeyB", 0x30),
+    ("KeyN", 0x31), ("KeyM", 0x32),
+    ("Comma", 0x33), ("Period", 0x34), ("Slash", 0x35), ("ShiftRight", 0x36),
+    ("NumpadMultiply", 0x37), ("AltLeft", 0x38), ("Space", 0x39), ("CapsLock", 0x3A),
+    ("F1", 0x3B), ("F2", 0x3C), ("F3", 0x3D), ("F4", 0x3E), ("F5", 0x3F),
+    ("F6", 0x40), ("F7", 0x41), ("F8", 0x42), ("F9", 0x43), ("F10", 0x44),
+    ("NumLock", 0xE045), ("ScrollLock", 0x46),
+    ("Numpad7", 0x47), ("Numpad8", 0x48), ("Numpad9", 0x49), ("NumpadSubtract", 0x4A),
+    ("Numpad4", 0x4B), ("Numpad5", 0x4C), ("Numpad6", 0x4D), ("NumpadAdd", 0x4E),
+    ("Numpad1", 0x4F), ("Numpad2", 0x50), ("Numpad3", 0x51), ("Numpad0", 0x52), ("NumpadDecimal", 0x53),
+    ("IntlBackslash", 0x56), ("F11", 0x57), ("F12", 0x58),
+    ("F13", 0x64), ("F14", 0x65), ("F15", 0x66), ("F16", 0x67), ("F17", 0x68), ("F18", 0x69),
+    ("F19", 0x6A), ("F20", 0x6B), ("F21", 0x6C), ("F22", 0x6D), ("F23", 0x6E), ("F24", 0x76),
+    ("IntlRo", 0x73), ("IntlYen", 0x7D),
+    ("NumpadEnter", 0xE01C), ("ControlRight", 0xE01D), ("NumpadDivide", 0xE035), ("PrintScreen", 0xE037),
+    ("AltRight", 0xE038), ("Home", 0xE047), ("ArrowUp", 0xE048), ("PageUp", 0xE049),
+    ("ArrowLeft", 0xE04B), ("ArrowRight", 0xE04D), ("End", 0xE04F), ("ArrowDown", 0xE050),
+    ("PageDown", 0xE051), ("Insert", 0xE052), ("Delete", 0xE053),
+    ("MetaLeft", 0xE05B), ("MetaRight", 0xE05C), ("ContextMenu", 0xE05D),
+];
+
+/// One key as it's injected. `scan` carries 0xE0 in its high byte for extended keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyStroke {
+    pub vk: u16,
+    pub scan: u16,
+    pub via: KeyInjection,
+}
+
+impl KeyStroke {
+    /// Legacy VK targets: the scancode comes from this thread's layout
+    pub fn from_vk(vk: u16, via: KeyInjection) -> Self {
+        Self { vk, scan: layout::vk_to_scan(vk), via }
+    }
+
+    /// Physical key targets, with the VK the foreground window's layout gives that key
+    pub fn from_code(code: &str, via: KeyInjection) -> Option<Self> {
+        let scan = scancode(code)?;
+        Some(Self { vk: layout::scan_to_vk(scan, layout::current()), scan, via })
+    }
+
+    #[cfg(windows)]
+    pub fn is_extended(&self) -> bool {
+        self.scan >> 8 == 0xE0
+    }
+}
+
+pub fn scancode(code: &str) -> Option<u16> {
+    KEYS.iter().find(|(c, _)| *c == code).map(|&(_, s)| s)
+}
+
+#[derive(Clone, Serialize)]
+pub struct KeyInfo {
+    pub code: &'static str,
+    pub scancode: u16,
+    pub vk: u16,      // In the current layout
+    pub name: String, // What the key cap says in the current layout
+}
+
+/// Every key a `Key` target can use, labelled for the active layout (for the mapping editor)
+pub fn list() -> Vec<KeyInfo> {
+    let layout = layout::current();
+    KEYS.iter().map(|&(code, scan)| {
+        let vk = layout::scan_to_vk(scan, layout);
+        KeyInfo { code, scancode: scan, vk, name: layout::key_name(vk, scan, layout).unwrap_or_else(|| code.to_string()) }
+    }).collect()
+}
+
+#[cfg(windows)]
+mod layout {
+    use std::ffi::c_void;
+
+    const MAPVK_VK_TO_VSC: u32 = 0;
+    const MAPVK_VSC_TO_VK_EX: u32 = 3;
+    const TOUNICODE_NO_STATE_CHANGE: u32 = 0x4; // Don't disturb pending dead keys
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetForegroundWindow() -> *mut c_void;
+        fn GetWindowThreadProcessId(hwnd: *mut c_void, process_id: *mut u32) -> u32;
+        fn GetKeyboardLayout(thread_id: u32) -> *mut c_void;
+        fn MapVirtualKeyExW(code: u32, map_type: u32, layout: *mut c_void) -> u32;
+        fn ToUnicodeEx(vk: u32, scancode: u32, key_state: *const u8, buf: *mut u16, len: i32, flags: u32, layout: *mut c_void) -> i32;
+        fn GetKeyNameTextW(lparam: i32, buf: *mut u16, len: i32) -> i32;
+    }
+
+    pub type Layout = *mut c_void;
+
+    /// The foreground window's layout
+    pub fn current() -> Layout {
+        unsafe {
+            let thread = GetWindowThreadProcessId(GetForegroundWindow(), std::ptr::null_mut());
+            GetKeyboardLayout(thread)
+        }
+    }
+
+    /// Legacy VK targets: the scancode comes from this thread's layout
+    pub fn vk_to_scan(vk: u16) -> u16 {
+        let mut scan = unsafe { MapVirtualKeyExW(vk as u32, MAPVK_VK_TO_VSC, std::ptr::null_mut()) } as u16;
+        // Some keys need extended flag (arrows, numpad enter, etc)
+        if (33..=46).contains(&vk) || (91..=93).contains(&vk) || (106..=111).contains(&vk) {
+            scan |= 0xE000;
+        }
+        scan
+    }
+
+    pub fn scan_to_vk(scan: u16, layout: Layout) -> u16 {
+        unsafe { MapVirtualKeyExW(scan as u32, MAPVK_VSC_TO_VK_EX, layout) as u16 }
+    }
+
+    fn code_is_numpad(scan: u16) -> bool {
+        (0x47..=0x53).contains(&scan) || scan == 0x37
+    }
+
+    pub fn key_name(vk: u16, scan: u16, layout: Layout) -> Option<String> {
+        let mut buf = [0u16; 32];
+        // Character keys: whatever the layout types there, numpad keys keep their system name
+        if !code_is_numpad(scan) {
+            let state = [0u8; 256];
+            let n = unsafe { ToUnicodeEx(vk as u32, scan as u32, state.as_ptr(), buf.as_mut_ptr(), buf.len() as i32, TOUNICODE_NO_STATE_CHANGE, layout) };
+            if n > 0 {
+                let s = String::from_utf16_lossy(&buf[..n as usize]);
+                if s.chars().all(|c| !c.is_control() && !c.is_whitespace()) {
+                    return Some(s.to_uppercase());
+                }
+            }
+        }
+        let lparam = ((scan as i32 & 0xFF) << 16) | if scan >> 8 == 0xE0 { 1 << 24 } else { 0 };
+        let n = unsafe { GetKeyNameTextW(lparam, buf.as_mut_ptr(), buf.len() as i32) };
+        (n > 0).then(|| String::from_utf16_lossy(&buf[..n as usize]))
+    }
+}
+
+#[cfg(not(windows))]
+mod layout {
+    use super::KEYS;
+
+    // US QWERTY VKs for keys whose code doesn't spell them out
+    const VK_CODES: &[(u16, &str)] = &[
+        (0x08, "Backspace"), (0x09, "Tab"), (0x0D, "Enter"), (0x10, "ShiftLeft"), (0x11, "ControlLeft"),
+        (0x12, "AltLeft"), (0x14, "CapsLock"), (0x1B, "Escape"), (0x20, "Space"),
+        (0x21, "PageUp"), (0x22, "PageDown"), (0x23, "End"), (0x24, "Home"),
+        (0x25, "ArrowLeft"), (0x26, "ArrowUp"), (0x27, "ArrowRight"), (0x28, "ArrowDown"),
+        (0x2C, "PrintScreen"), (0x2D, "Insert"), (0x2E, "Delete"),
+        (0x5B, "MetaLeft"), (0x5C, "MetaRight"), (0x5D, "ContextMenu"),
+        (0x6A, "NumpadMultiply"), (0x6B, "NumpadAdd"), (0x6D, "NumpadSubtract"), (0x6E, "NumpadDecimal"), (0x6F, "NumpadDivide"),
+        (0x90, "NumLock"), (0x91, "ScrollLock"),
+        (0xA0, "ShiftLeft"), (0xA1, "ShiftRight"), (0xA2, "ControlLeft"), (0xA3, "ControlRight"), (0xA4, "AltLeft"), (0xA5, "AltRight"),
+        (0xBA, "Semicolon"), (0xBB, "Equal"), (0xBC, "Comma"), (0xBD, "Minus"), (0xBE, "Period"), (0xBF, "Slash"),
+        (0xC0, "Backquote"), (0xDB, "BracketLeft"), (0xDC, "Backslash"), (0xDD, "BracketRight"), (0xDE, "Quote"),
+        (0xE2, "IntlBackslash"), (0x0D, "NumpadEnter"),
+    ];
+
+    #[derive(Clone, Copy)]
+    pub struct Layout;
+
+    pub fn current() -> Layout {
+        Layout
+    }
+
+    fn vk_for_code(code: &str) -> Option<u16> {
+        let single = |rest: &str| (rest.len() == 1).then(|| rest.as_bytes()[0] as u16);
+        if let Some(c) = code.strip_prefix("Key").and_then(single) {
+            return Some(c); // VK_A.. = 'A'..
+        }
+        if let Some(c) = code.strip_prefix("Digit").and_then(single) {
+            return Some(c);
+        }
+        if let Some(c) = code.strip_prefix("Numpad").and_then(single) {
+            return Some(0x60 + c - b'0' as u16);
+        }
+        if let Some(n) = code.strip_prefix('F').and_then(|n| n.parse::<u16>().ok()) {
+            return Some(0x6F + n);
+        }
+        VK_CODES.iter().find(|(_, c)| *c == code).map(|&(vk, _)| vk)
+    }
+
+    pub fn vk_to_scan(vk: u16) -> u16 {
+        VK_CODES.iter().find(|&&(v, _)| v == vk).and_then(|(_, code)| super::scancode(code))
+            .or_else(|| KEYS.iter().find(|(code, _)| vk_for_code(code) == Some(vk)).map(|&(_, scan)| scan))
+            .unwrap_or(0)
+    }
+
+    pub fn scan_to_vk(scan: u16, _layout: Layout) -> u16 {
+        KEYS.iter().find(|&&(_, s)| s == scan).and_then(|(code, _)| vk_for_code(code)).unwrap_or(0)
+    }
+
+    /// The code without its "Key"/"Digit" prefix
+    pub fn key_name(_vk: u16, scan: u16, _layout: Layout) -> Option<String> {
+        let (code, _) = KEYS.iter().find(|&&(_, s)| s == scan)?;
+        Some(code.strip_prefix("Key").or_else(|| code.strip_prefix("Digit")).unwrap_or(code).to_string())
+    }
+}
@@ -0,0 +1,204 @@
+use serde::{Serialize, Deserialize};
+
+use crate::mapping::{normalize_axis, normalize_trigger, GamepadState};
+
+// --- Input Report Layouts ---
+// Byte offsets of the fields in a DualSense/DS4-style input report. The parsers in mapping.rs
+// hardcode Sony's layouts; licensed pads and clones sometimes pad or shift them, which shows up as
+// inverted sticks and ghost presses. A layout saved for a VID:PID (config `report_layouts`) takes
+// over parsing for that device, and `LayoutProbe` catches the common case of the whole report
+// being shifted by a byte or two.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportLayout {
+    pub report_id: u8,
+    pub lx: usize,
+    pub ly: usize,
+    pub rx: usize,
+    pub ry: usize,
+    pub l2: usize, // Analog
+    pub r2: usize,
+    // D-pad (low nibble) + face buttons. L1/R1/Share/Options/L3/R3 follow at +1, PS/Touchpad at +2.
+    pub buttons: usize,
+    #[serde(default)]
+    pub mute: bool, // Bit 0x04 at buttons + 2 is Mute (DualSense only, DS4 has a counter there)
+    #[serde(default)]
+    pub invert_y: bool,
+}
+
+impl ReportLayout {
+    fn sony(report_id: u8, sticks: usize, triggers: usize, buttons: usize, mute: bool) -> Self {
+        Self {
+            report_id,
+            lx: sticks, ly: sticks + 1, rx: sticks + 2, ry: sticks + 3,
+            l2: triggers, r2: triggers + 1,
+            buttons,
+            mute,
+            invert_y: false,
+        }
+    }
+
+    /// What the built-in parsers assume for this report
+    pub fn builtin(is_dualsense: bool, is_bt: bool, report_id: u8) -> Option<Self> {
+        match (is_dualsense, is_bt, report_id) {
+            (true, false, 0x01) => Some(Self::sony(0x01, 1, 5, 8, true)),
+            (true, true, 0x31) => Some(Self::sony(0x31, 2, 6, 9, true)),
+            (true, true, 0x01) => Some(Self::sony(0x01, 1, 8, 5, false)), // Simple mode
+            (false, _, 0x01) => Some(Self::sony(0x01, 1, 8, 5, false)),
+            (false, _, 0x11) => Some(Self::sony(0x11, 3, 10, 7, false)),
+            _ => None,
+        }
+    }
+
+    /// Every offset moved by `by`, None if that would reach the report ID byte
+    pub fn shifted(&self, by: isize) -> Option<Self> {
+        let mv = |o: usize| o.checked_add_signed(by).filter(|&o| o >= 1);
+        Some(Self {
+            lx: mv(self.lx)?, ly: mv(self.ly)?, rx: mv(self.rx)?, ry: mv(self.ry)?,
+            l2: mv(self.l2)?, r2: mv(self.r2)?,
+            buttons: mv(self.buttons)?,
+            ..self.clone()
+        })
+    }
+
+    fn max_offset(&self) -> usize {
+        [self.lx, self.ly, self.rx, self.ry, self.l2, self.r2, self.buttons + 2].into_iter().max().unwrap_or(0)
+    }
+
+    fn fits(&self, report: &[u8]) -> bool {
+        report.first() == Some(&self.report_id) && report.len() > self.max_offset()
+    }
+
+    /// Sticks, triggers and buttons from this layout. Battery, touchpad and jack state are kept
+    /// from `base` (the built-in parser's result).
+    pub fn parse(&self, report: &[u8], base: Option<GamepadState>) -> Option<GamepadState> {
+        if !self.fits(report) {
+            return None;
+        }
+        let base = base.unwrap_or_default();
+        let mut state = GamepadState {
+            touch_x: base.touch_x,
+            touch_y: base.touch_y,
+            touch_active: base.touch_active,
+            touch2_x: base.touch2_x,
+            touch2_y: base.touch2_y,
+            touch2_active: base.touch2_active,
+            battery: base.battery,
+            is_charging: base.is_charging,
+            charge_state: base.charge_state,
+            headphones: base.headphones,
+            mic: base.mic,
+            gyro_x: base.gyro_x,
+            gyro_y: base.gyro_y,
+            gyro_z: base.gyro_z,
+            accel_x: base.accel_x,
+            accel_y: base.accel_y,
+            accel_z: base.accel_z,
+            ..GamepadState::default()
+        };
+        let y = |v: u8| if self.invert_y { -normalize_axis(v) } else { normalize_axis(v) };
+        state.left_x = normalize_axis(report[self.lx]);
+        state.left_y = y(report[self.ly]);
+        state.right_x = normalize_axis(report[self.rx]);
+        state.right_y = y(report[self.ry]);
+        state.l2 = normalize_trigger(report[self.l2]);
+        state.r2 = normalize_trigger(report[self.r2]);
+
+        let b1 = report[self.buttons];
+        match b1 & 0x0F {
+            0 => state.dpad_up = true,
+            1 => { state.dpad_up = true; state.dpad_right = true; },
+            2 => state.dpad_right = true,
+            3 => { state.dpad_right = true; state.dpad_down = true; },
+            4 => state.dpad_down = true,
+            5 => { state.dpad_down = true; state.dpad_left = true; },
+            6 => state.dpad_left = true,
+            7 => { state.dpad_left = true; state.dpad_up = true; },
+            _ => {}
+        }
+        state.btn_square = (b1 & 0x10) != 0;
+        state.btn_cross = (b1 & 0x20) != 0;
+        state.btn_circle = (b1 & 0x40) != 0;
+        state.btn_triangle = (b1 & 0x80) != 0;
+
+        let b2 = report[self.buttons + 1];
+        state.btn_l1 = (b2 & 0x01) != 0;
+        state.btn_r1 = (b2 & 0x02) != 0;
+        state.btn_share = (b2 & 0x10) != 0;
+        state.btn_options = (b2 & 0x20) != 0;
+        state.btn_l3 = (b2 & 0x40) != 0;
+        state.btn_r3 = (b2 & 0x80) != 0;
+
+        let b3 = report[self.buttons + 2];
+        state.btn_ps = (b3 & 0x01) != 0;
+        state.btn_touchpad = (b3 & 0x02) != 0;
+        state.btn_mute = self.mute && (b3 & 0x04) != 0;
+        Some(state)
+    }
+
+    /// A pad lying on the desk: sticks near center, D-pad neutral, no buttons, triggers released
+    fn looks_at_rest(&self, report: &[u8]) -> bool {
+        if !self.fits(report) {
+            return false;
+        }
+        let centered = |o: usize| (0x40..=0xC0).contains(&report[o]);
+        centered(self.lx) && centered(self.ly) && centered(self.rx) && centered(self.ry)
+            && report[self.l2] < 0x20 && report[self.r2] < 0x20
+            && report[self.buttons] == 0x08
+            && report[self.buttons + 1] == 0
+    }
+}
+
+const PROBE_REPORTS: u32 = 20;
+const PROBE_SHIFTS: [isize; 4] = [-2, -1, 1, 2];
+
+pub enum ProbeResult {
+    Pending,
+    Builtin,
+    Shifted(ReportLayout, isize),
+}
+
+/// Watches the first reports after connecting. If the built-in layout never looks like a resting
+/// pad but a shifted copy of it does, the device is a clone with a shifted report.
+pub struct LayoutProbe {
+    builtin: ReportLayout,
+    seen: u32,
+    shift_hits: [u32; 4],
+}
+
+impl LayoutProbe {
+    pub fn new(builtin: ReportLayout) -> Self {
+        Self { builtin, seen: 0, shift_hits: [0; 4] }
+    }
+
+    pub fn report_id(&self) -> u8 {
+        self.builtin.report_id
+    }
+
+    pub fn feed(&mut self, report: &[u8]) -> ProbeResult {
+        if report.first() != Some(&self.builtin.report_id) {
+            return ProbeResult::Pending;
+        }
+        if self.builtin.looks_at_rest(report) {
+            return ProbeResult::Builtin;
+        }
+        self.seen += 1;
+        for (i, &by) in PROBE_SHIFTS.iter().enumerate() {
+            if self.builtin.shifted(by).is_some_and(|l| l.looks_at_rest(report)) {
+                self.shift_hits[i] += 1;
+            }
+        }
+        if self.seen < PROBE_REPORTS {
+            return ProbeResult::Pending;
+        }
+
+        // Most reports must agree, a user holding buttons while connecting shouldn't trigger this
+        let (best, hits) = self.shift_hits.iter().enumerate().max_by_key(|(_, &h)| h).map(|(i, &h)| (i, h)).unwrap_or((0, 0));
+        if hits * 4 >= PROBE_REPORTS * 3 {
+            if let Some(layout) = self.builtin.shifted(PROBE_SHIFTS[best]) {
+                return ProbeResult::Shifted(layout, PROBE_SHIFTS[best]);
+            }
+        }
+        ProbeResult::Builtin
+    }
+}
@@ -0,0 +1,101 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use log::LevelFilter;
+
+use crate::config::AppConfig;
+
+// --- File Logging ---
+// Release builds use the Windows subsystem (no console), so logs go to %APPDATA%/DX3/logs/dx3.log.
+// The file rotates at MAX_FILE_SIZE into dx3.1.log .. dx3.<MAX_ROTATED>.log (oldest dropped).
+
+const LOG_FILE: &str = "dx3";
+const MAX_FILE_SIZE: u64 = 2 * 1024 * 1024;
+const MAX_ROTATED: usize = 3;
+
+pub fn log_dir() -> PathBuf {
+    let mut path = AppConfig::config_path().parent().unwrap().to_path_buf();
+    path.push("logs");
+    if !path.exists() {
+        let _ = fs::create_dir_all(&path);
+    }
+    path
+}
+
+fn log_path(index: usize) -> PathBuf {
+    if index == 0 {
+        log_dir().join(format!("{}.log", LOG_FILE))
+    } else {
+        log_dir().join(format!("{}.{}.log", LOG_FILE, index))
+    }
+}
+
+struct RotatingFile {
+    file: Option<File>,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open() -> Self {
+        let file = OpenOptions::new().create(true).append(true).open(log_path(0)).ok();
+        let size = file.as_ref().and_then(|f| f.metadata().ok()).map(|m| m.len()).unwrap_or(0);
+        Self { file, size }
+    }
+
+    fn rotate(&mut self) {
+        self.file = None; // Close before renaming (Windows)
+        let _ = fs::remove_file(log_path(MAX_ROTATED));
+        for i in (0..MAX_ROTATED).rev() {
+            let _ = fs::rename(log_path(i), log_path(i + 1));
+        }
+        *self = Self::open();
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size + buf.len() as u64 > MAX_FILE_SIZE {
+            self.rotate();
+        }
+        // Dev builds also keep the console output
+        if cfg!(debug_assertions) {
+            let _ = io::stderr().write_all(buf);
+        }
+        match self.file.as_mut() {
+            Some(f) => {
+                let n = f.write(buf)?;
+                self.size += n as u64;
+                Ok(n)
+            }
+            None => Ok(buf.len()), // Log dir not writable: drop silently rather than failing the app
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.file.as_mut() {
+            Some(f) => f.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+pub fn parse_level(level: &str) -> Option<LevelFilter> {
+    level.trim().parse().ok()
+}
+
+/// Records everything and gates on log::max_level(), so set_level() works at runtime
+pub fn init(level: &str) {
+    env_logger::Builder::new()
+        .filter_level(LevelFilter::Trace)
+        // Suppress noisy warnings from TAO (windowing) and WRY (webview)
+        .filter_module("tao", LevelFilter::Error)
+        .filter_module("wry", LevelFilter::Error)
+        .format_timestamp_millis()
+        .target(env_logger::Target::Pipe(Box::new(RotatingFile::open())))
+        .init();
+    log::set_max_level(parse_level(level).unwrap_or(LevelFilter::Info));
+}
+
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+}
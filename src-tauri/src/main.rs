@@ -4,21 +4,65 @@
 )]
 
 use std::sync::{Arc, Mutex};
-use tauri::{CustomMenuItem, SystemTray, SystemTrayEvent, SystemTrayMenu, SystemTrayMenuItem, Manager, WindowBuilder, WindowUrl};
+use tauri::{CustomMenuItem, SystemTray, SystemTrayEvent, SystemTrayMenu, SystemTrayMenuItem, Manager, WindowBuilder, WindowUrl, GlobalShortcutManager};
 use std::thread;
-use serde::Deserialize;
 
 mod state;
 mod worker;
-mod dualsense; 
-mod hidhide;   
+mod dualsense;
+mod switch_pro;
+#[cfg(windows)]
+mod hidhide;
 mod mapping;   
 mod crc;       
 mod config;
+mod autostart;
+#[cfg(windows)]
+mod elevated;
+mod api;
+#[cfg(windows)]
+mod pipe;
+mod overlay;
+mod tray;
+mod crash;
+mod logging;
+mod discord;
+mod mqtt;
+mod hooks;
+mod focus;
+mod fuzzlog;
+mod hid_descriptor;
+mod capture;
+mod script_fuzz;
+mod writelog;
+mod output_layout;
+mod layout;
+mod touch;
+mod stick_cal;
+mod gyro_cal;
+mod kbm;
+mod stats;
+mod recording;
+mod persist;
+mod settings;
+mod vigem;
+mod vjoy;
+mod keys;
+#[cfg_attr(not(windows), allow(dead_code))] // Only the Windows injector sends through it
+mod interception;
+#[cfg(windows)]
+mod power;
+mod quiet;
+mod platform;
 
-use state::SharedState;
-use config::AppConfig;
+// Windows-only modules, the Linux build gets stand-ins with the same interface
+#[cfg(not(windows))]
+use platform::linux::{elevated, pipe, power};
+
+use state::{SharedState, Status};
+use config::{AppConfig, ManualParams};
 use worker::controller_thread;
+use platform::{Hider, DeviceHider};
 
 // --- Helper Functions ---
 
@@ -52,168 +96,489 @@ fn show_window(app: &tauri::AppHandle) {
 
 // --- Commands ---
 
-fn save_config_internal(s: &SharedState, persist_profile: bool) {
-    // 1. Save to global config (Always save app state/active profile name)
-    AppConfig::save_internal(
-        s.hide_controller,
-        s.start_minimized,
-        s.mappings.clone(),
-        s.deadzone_left,
-        s.deadzone_right,
-        s.current_profile_name.clone(),
-        s.mouse_sens_left,
-        s.mouse_sens_right,
-        s.mouse_sens_touchpad,
-        s.rgb_r,
-        s.rgb_g,
-        s.rgb_b,
-        s.rgb_brightness,
-        s.show_battery_led,
-        s.trigger_l2_mode,
-        s.trigger_l2_start,
-        s.trigger_l2_force,
-        s.trigger_r2_mode,
-        s.trigger_r2_start,
-        s.trigger_r2_force,
-        s.player_led_brightness,
-    );
-
-    // 2. Only save to specific profile JSON if explicitly requested (Autosave changes)
-    if persist_profile && !s.current_profile_name.is_empty() {
-        let profile = crate::config::Profile {
-            mappings: s.mappings.clone(),
-            deadzone_left: s.deadzone_left,
-            deadzone_right: s.deadzone_right,
-            mouse_sens_left: s.mouse_sens_left,
-            mouse_sens_right: s.mouse_sens_right,
-            mouse_sens_touchpad: s.mouse_sens_touchpad,
-            rgb_r: s.rgb_r,
-            rgb_g: s.rgb_g,
-            rgb_b: s.rgb_b,
-            rgb_brightness: s.rgb_brightness,
-            show_battery_led: s.show_battery_led,
-            trigger_l2_mode: s.trigger_l2_mode,
-            trigger_l2_start: s.trigger_l2_start,
-            trigger_l2_force: s.trigger_l2_force,
-            trigger_r2_mode: s.trigger_r2_mode,
-            trigger_r2_start: s.trigger_r2_start,
-            trigger_r2_force: s.trigger_r2_force,
-            player_led_brightness: s.player_led_brightness,
-        };
-        AppConfig::save_profile(&s.current_profile_name, &profile);
-    }
-}
-
 #[tauri::command]
 fn trigger_driver_refresh(state: tauri::State<Arc<Mutex<SharedState>>>) {
     let mut s = state.lock().unwrap();
     s.should_reinit = true;
-    s.status = "Refreshing drivers...".to_string();
+    s.status = Status::RefreshingDrivers;
 }
 
 #[tauri::command]
 fn resume_scanning(state: tauri::State<Arc<Mutex<SharedState>>>) {
     let mut s = state.lock().unwrap();
     s.is_paused = false;
-    s.status = "Searching...".to_string();
+    s.status = Status::Searching;
 }
 
-#[tauri::command]
-fn disconnect_controller(state: tauri::State<Arc<Mutex<SharedState>>>) {
-    state.lock().unwrap().should_disconnect = true;
+/// Lets the worker unplug the pad, release held keys, unhide the controller and restore its LEDs before exiting
+fn exit_and_restore_internal(state: &Arc<Mutex<SharedState>>) -> ! {
+    log::info!("Shutting down...");
+    state.lock().unwrap().should_exit = true;
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(4);
+    while std::time::Instant::now() < deadline {
+        if state.lock().unwrap().worker_stopped {
+            break;
+        }
+        thread::sleep(std::time::Duration::from_millis(50));
+    }
+
+    // Worker stuck (e.g. in a blocking HID call): at least don't leave the controller cloaked
+    let hidden = state.lock().unwrap().hidden_device_id.take();
+    if let Some(inst_id) = hidden {
+        log::warn!("Worker did not finish cleanup, unhiding {} directly", inst_id);
+        let _ = Hider::unhide(&inst_id);
+    }
+    recording::stop(); // Flush the buffered tail
+    persist::flush(); // Settings changed in the last debounce window
+    std::process::exit(0);
 }
 
 #[tauri::command]
-fn set_show_battery_led(state: tauri::State<Arc<Mutex<SharedState>>>, val: bool) {
+fn open_crash_report(state: tauri::State<Arc<Mutex<SharedState>>>) {
     let mut s = state.lock().unwrap();
-    s.show_battery_led = val;
-    s.should_send_leds = true;
-    save_config_internal(&s, true);
+    if let Some(path) = s.pending_crash_report.take() {
+        // Select the file in Explorer so it can be dragged straight into an issue
+        platform::show_in_folder(&path);
+    }
+    crash::dismiss_pending();
 }
 
 #[tauri::command]
-fn set_player_led_brightness(state: tauri::State<Arc<Mutex<SharedState>>>, val: u8) {
-    let mut s = state.lock().unwrap();
-    s.player_led_brightness = val;
-    s.should_send_leds = true;
-    save_config_internal(&s, true);
+fn dismiss_crash_report(state: tauri::State<Arc<Mutex<SharedState>>>) {
+    state.lock().unwrap().pending_crash_report = None;
+    crash::dismiss_pending();
+}
+
+#[tauri::command]
+fn set_log_level(level: String) -> Result<(), String> {
+    let filter = logging::parse_level(&level).ok_or_else(|| format!("Unknown log level: {}", level))?;
+    logging::set_level(filter);
+    log::info!("Log level set to {}", filter);
+
+    settings::update(false, |s| s.log_level = filter.to_string().to_lowercase());
+    Ok(())
+}
+
+#[tauri::command]
+fn set_discord_settings(state: tauri::State<Arc<Mutex<SharedState>>>, enabled: bool, app_id: String) {
+    settings::update(false, |s| {
+        s.discord_enabled = enabled;
+        s.discord_app_id = app_id.trim().to_string();
+    });
+
+    if enabled {
+        discord::start(state.inner().clone());
+    }
 }
 
 #[tauri::command]
-fn set_rgb(state: tauri::State<Arc<Mutex<SharedState>>>, r: u8, g: u8, b: u8, brightness: u8) {
+fn set_mqtt_settings(state: tauri::State<Arc<Mutex<SharedState>>>, settings: crate::config::MqttConfig) {
+    let enabled = settings.enabled;
+    settings::update(false, |s| s.mqtt = settings);
+
+    // A running publisher picks up the new broker settings by itself
+    if enabled {
+        mqtt::start(state.inner().clone());
+    }
+}
+
+#[tauri::command]
+fn set_idle_power_off(minutes: u32) {
+    settings::update(false, |s| s.idle_power_off_min = minutes);
+}
+
+#[tauri::command]
+fn set_orientation(val: crate::mapping::Orientation) {
+    settings::update(false, |s| s.orientation = val);
+}
+
+#[tauri::command]
+fn set_kbm_gamepad(config: crate::kbm::KbmGamepad) {
+    settings::update(false, |s| s.kbm_gamepad = config);
+}
+
+#[tauri::command]
+fn set_focus_pause(config: crate::focus::FocusPause) {
+    settings::update(false, |s| s.focus_pause = config);
+}
+
+#[tauri::command]
+fn set_haptic_alerts(val: bool) {
+    settings::update(false, |s| s.haptic_alerts = val);
+}
+
+/// 0 turns vJoy output off
+#[tauri::command]
+fn set_vjoy_device(device: u8) {
+    settings::update(false, |s| s.vjoy_device = device.min(16));
+}
+
+#[tauri::command]
+fn set_ps_hold_opens_window(val: bool) {
+    settings::update(false, |s| s.ps_hold_opens_window = val);
+}
+
+#[tauri::command]
+fn set_power_off_combo(buttons: Vec<crate::mapping::PhysicalButton>, hold_ms: u32) {
+    settings::update(false, |s| {
+        s.power_off_combo = buttons;
+        s.power_off_hold_ms = hold_ms.max(500); // Avoid accidental taps
+    });
+}
+
+#[tauri::command]
+fn set_profile_cycle_combo(buttons: Vec<crate::mapping::PhysicalButton>) {
+    settings::update(false, |s| s.profile_cycle_combo = buttons);
+}
+
+#[tauri::command]
+fn set_hook_commands(on_connect: String, on_disconnect: String) {
+    settings::update(false, |s| {
+        s.on_connect_cmd = on_connect.trim().to_string();
+        s.on_disconnect_cmd = on_disconnect.trim().to_string();
+    });
+}
+
+/// Saves a custom input layout for the connected controller's VID:PID (None = back to built-in/auto)
+#[tauri::command]
+fn set_report_layout(state: tauri::State<Arc<Mutex<SharedState>>>, layout: Option<crate::layout::ReportLayout>) -> Result<(), String> {
+    if let Some(l) = &layout {
+        let offsets = [l.lx, l.ly, l.rx, l.ry, l.l2, l.r2, l.buttons + 2];
+        if offsets.iter().any(|o| !(1..128).contains(o)) {
+            return Err("Offsets must be between 1 and 127 (byte 0 is the report ID)".to_string());
+        }
+    }
+    let key = state.lock().unwrap().device_key.clone();
+    if key.is_empty() {
+        return Err("No controller connected".to_string());
+    }
+    settings::update(false, |s| match layout {
+        Some(l) => { s.report_layouts.insert(key, l); }
+        None => { s.report_layouts.remove(&key); }
+    });
+    Ok(())
+}
+
+/// Starts learning the touchpad range; stopping saves it for the connected controller's VID:PID
+#[tauri::command]
+fn set_touch_calibrating(state: tauri::State<Arc<Mutex<SharedState>>>, val: bool) -> Result<Option<crate::touch::TouchCalibration>, String> {
     let mut s = state.lock().unwrap();
-    s.rgb_r = r;
-    s.rgb_g = g;
-    s.rgb_b = b;
-    s.rgb_brightness = brightness;
-    s.should_send_leds = true;
-    save_config_internal(&s, true);
+    if s.device_key.is_empty() {
+        return Err("No controller connected".to_string());
+    }
+    s.touch_calibrating = val;
+    let learned = s.touch_cal_progress.take();
+    if val {
+        return Ok(None);
+    }
+    match learned {
+        Some(cal) if cal.is_plausible() => {
+            log::info!("Touchpad calibration for {}: X {}-{}, Y {}-{}", s.device_key, cal.min_x, cal.max_x, cal.min_y, cal.max_y);
+            let key = s.device_key.clone();
+            settings::update(false, |st| st.touch_calibrations.insert(key, cal));
+            Ok(Some(cal))
+        }
+        _ => Err("Not enough of the touchpad was covered, drag a finger along all four edges".to_string()),
+    }
+}
+
+#[tauri::command]
+fn reset_touch_calibration(state: tauri::State<Arc<Mutex<SharedState>>>) {
+    let key = state.lock().unwrap().device_key.clone();
+    settings::update(false, |s| s.touch_calibrations.remove(&key));
 }
 
+/// Samples the resting sticks for a few seconds; the worker stores the result
 #[tauri::command]
-fn set_trigger_l2(state: tauri::State<Arc<Mutex<SharedState>>>, mode: u8, start: u8, force: u8) {
+fn calibrate_sticks(state: tauri::State<Arc<Mutex<SharedState>>>) -> Result<(), String> {
     let mut s = state.lock().unwrap();
-    s.trigger_l2_mode = mode;
-    s.trigger_l2_start = start;
-    s.trigger_l2_force = force;
-    s.should_send_triggers = true;
-    save_config_internal(&s, true);
+    if s.stick_cal_key.is_empty() {
+        return Err("No controller connected".to_string());
+    }
+    s.should_calibrate_sticks = true;
+    s.stick_cal_status = format!("Sampling for {} s, don't touch the sticks...", crate::stick_cal::SAMPLE_TIME.as_secs());
+    Ok(())
 }
 
 #[tauri::command]
-fn set_trigger_r2(state: tauri::State<Arc<Mutex<SharedState>>>, mode: u8, start: u8, force: u8) {
+fn reset_stick_calibration(state: tauri::State<Arc<Mutex<SharedState>>>) {
     let mut s = state.lock().unwrap();
-    s.trigger_r2_mode = mode;
-    s.trigger_r2_start = start;
-    s.trigger_r2_force = force;
-    s.should_send_triggers = true;
-    save_config_internal(&s, true);
+    let key = s.stick_cal_key.clone();
+    if settings::update(false, |st| st.stick_centers.remove(&key).is_some()) {
+        s.stick_cal_status = "Reset".to_string();
+    }
 }
 
+/// Samples the gyro at rest for a few seconds; the worker stores the result
 #[tauri::command]
-fn set_deadzones(state: tauri::State<Arc<Mutex<SharedState>>>, left: f32, right: f32) {
+fn calibrate_gyro(state: tauri::State<Arc<Mutex<SharedState>>>) -> Result<(), String> {
     let mut s = state.lock().unwrap();
-    s.deadzone_left = left;
-    s.deadzone_right = right;
-    save_config_internal(&s, true);
+    if s.stick_cal_key.is_empty() {
+        return Err("No controller connected".to_string());
+    }
+    s.should_calibrate_gyro = true;
+    s.gyro_cal_status = format!("Sampling for {} s, keep the controller still...", crate::gyro_cal::SAMPLE_TIME.as_secs());
+    Ok(())
 }
 
 #[tauri::command]
-fn set_mouse_sens(state: tauri::State<Arc<Mutex<SharedState>>>, left: f32, right: f32) {
+fn reset_gyro_calibration(state: tauri::State<Arc<Mutex<SharedState>>>) {
     let mut s = state.lock().unwrap();
-    s.mouse_sens_left = left;
-    s.mouse_sens_right = right;
-    save_config_internal(&s, true);
+    let key = s.stick_cal_key.clone();
+    if settings::update(false, |st| st.gyro_biases.remove(&key).is_some()) {
+        s.gyro_cal_status = "Reset".to_string();
+    }
+}
+
+#[tauri::command]
+fn set_descriptor_parsing(val: bool) {
+    settings::update(false, |s| s.descriptor_parsing = val);
+}
+
+#[tauri::command]
+fn exit_and_restore(state: tauri::State<Arc<Mutex<SharedState>>>) {
+    exit_and_restore_internal(state.inner());
+}
+
+/// Worker drops the virtual pad and releases held keys/mouse buttons while paused
+fn set_paused_internal(s: &mut SharedState, paused: bool) {
+    s.is_paused = paused;
+    s.status = if paused { Status::Paused } else { Status::Searching };
+}
+
+fn register_pause_hotkey(app: &tauri::AppHandle, state: Arc<Mutex<SharedState>>, hotkey: &str) -> Result<(), String> {
+    app.global_shortcut_manager()
+        .register(hotkey, move || {
+            let mut s = state.lock().unwrap();
+            let paused = !s.is_paused;
+            set_paused_internal(&mut s, paused);
+            log::info!("Pause hotkey: emulation {}", if paused { "paused" } else { "resumed" });
+        })
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn set_pause_hotkey(app_handle: tauri::AppHandle, state: tauri::State<Arc<Mutex<SharedState>>>, hotkey: String) -> Result<(), String> {
+    let hotkey = hotkey.trim().to_string();
+    let old = settings::read().pause_hotkey.clone();
+    if old == hotkey {
+        return Ok(());
+    }
+
+    let mut gsm = app_handle.global_shortcut_manager();
+    if !old.is_empty() {
+        let _ = gsm.unregister(&old);
+    }
+    if !hotkey.is_empty() {
+        if let Err(e) = register_pause_hotkey(&app_handle, state.inner().clone(), &hotkey) {
+            log::error!("Failed to register pause hotkey '{}': {}", hotkey, e);
+            // Keep the previous binding working
+            if !old.is_empty() {
+                let _ = register_pause_hotkey(&app_handle, state.inner().clone(), &old);
+            }
+            return Err(e);
+        }
+    }
+
+    settings::update(false, |s| s.pause_hotkey = hotkey);
+    Ok(())
+}
+
+#[tauri::command]
+fn set_quiet_hours(config: crate::quiet::QuietHours) {
+    settings::update(false, |s| s.quiet_hours = crate::quiet::QuietHours { start: config.start % 1440, end: config.end % 1440, ..config });
+}
+
+#[tauri::command]
+fn set_output_keepalive(secs: u32) {
+    settings::update(false, |s| s.output_keepalive_secs = secs.min(600));
 }
 
 #[tauri::command]
-fn set_touchpad_sens(state: tauri::State<Arc<Mutex<SharedState>>>, sens: f32) {
+fn set_manual_connect(enabled: bool) {
+    settings::update(false, |s| s.manual_connect = enabled);
+}
+
+/// Manual-connect mode: attach one of the controllers in `found_controllers`
+#[tauri::command]
+fn connect_device(path: String, state: tauri::State<Arc<Mutex<SharedState>>>) -> Result<(), String> {
     let mut s = state.lock().unwrap();
-    s.mouse_sens_touchpad = sens;
-    save_config_internal(&s, true);
+    if !s.found_controllers.iter().any(|c| c.path == path) {
+        return Err("That controller is no longer connected".to_string());
+    }
+    s.connect_request = Some(path);
+    Ok(())
+}
+
+#[tauri::command]
+fn disconnect_controller(state: tauri::State<Arc<Mutex<SharedState>>>) {
+    state.lock().unwrap().should_disconnect = true;
+}
+
+#[tauri::command]
+fn set_show_battery_led(val: bool) {
+    settings::update(true, |s| s.show_battery_led = val);
 }
 
-#[derive(Deserialize)]
-pub struct ManualParams {
-    pub report_id: u8,
-    pub flag_off: usize,
-    pub rgb_off: usize,
-    pub player_led: u8,
-    pub pled_bright: u8,
-    pub pled_bright_off: usize,
-    pub bt_flags: u8,
-    pub bt_flags2: u8,
-    pub bt_len: usize,
-    pub as_feature: bool,
-    pub r: u8,
-    pub g: u8,
-    pub b: u8,
+#[tauri::command]
+fn set_auto_sprint(config: Option<crate::mapping::AutoSprint>) {
+    settings::update(true, |s| s.auto_sprint = config);
+}
+
+#[tauri::command]
+fn set_trackball(friction: Option<f32>) {
+    settings::update(true, |s| s.trackball_friction = friction.map(|f| f.clamp(0.0, 1.0)));
+}
+
+#[tauri::command]
+fn set_input_backend(backend: crate::mapping::InputBackend) {
+    settings::update(true, |s| s.input_backend = backend);
+}
+
+#[tauri::command]
+fn set_pad_kind(kind: crate::platform::PadKind) {
+    settings::update(true, |s| s.pad_kind = kind);
+}
+
+#[tauri::command]
+fn set_swap_sticks(enabled: bool) {
+    settings::update(true, |s| s.swap_sticks = enabled);
+}
+
+#[tauri::command]
+fn set_player_led_brightness(val: u8) {
+    settings::update(true, |s| s.player_led_brightness = val);
+}
+
+#[tauri::command]
+fn set_rgb(r: u8, g: u8, b: u8, brightness: u8) {
+    settings::update(true, |s| {
+        s.rgb_r = r;
+        s.rgb_g = g;
+        s.rgb_b = b;
+        s.rgb_brightness = brightness;
+    });
+}
+
+#[tauri::command]
+fn set_trigger_l2(mode: u8, start: u8, force: u8) {
+    settings::update(true, |s| {
+        s.trigger_l2_mode = mode;
+        s.trigger_l2_start = start;
+        s.trigger_l2_force = force;
+    });
+}
+
+#[tauri::command]
+fn set_trigger_r2(mode: u8, start: u8, force: u8) {
+    settings::update(true, |s| {
+        s.trigger_r2_mode = mode;
+        s.trigger_r2_start = start;
+        s.trigger_r2_force = force;
+    });
+}
+
+#[tauri::command]
+fn set_trigger_curves(mut left: crate::mapping::TriggerCurve, mut right: crate::mapping::TriggerCurve) {
+    crate::mapping::sanitize_curve_points(&mut left.points);
+    crate::mapping::sanitize_curve_points(&mut right.points);
+    settings::update(true, |s| {
+        s.trigger_curve_l = left;
+        s.trigger_curve_r = right;
+    });
+}
+
+#[tauri::command]
+fn set_stick_curves(mut left: crate::mapping::ResponseCurve, mut right: crate::mapping::ResponseCurve) {
+    for curve in [&mut left, &mut right] {
+        if let crate::mapping::ResponseCurve::Custom(points) = curve {
+            crate::mapping::sanitize_curve_points(points);
+        }
+    }
+    settings::update(true, |s| {
+        s.stick_curve_l = left;
+        s.stick_curve_r = right;
+    });
+}
+
+#[tauri::command]
+fn set_deadzones(left: f32, right: f32) {
+    settings::update(true, |s| {
+        s.deadzone_left = left;
+        s.deadzone_right = right;
+    });
+}
+
+#[tauri::command]
+fn set_deadzone_shapes(left: crate::mapping::DeadzoneShape, right: crate::mapping::DeadzoneShape) {
+    settings::update(true, |s| {
+        s.deadzone_shape_left = left;
+        s.deadzone_shape_right = right;
+    });
+}
+
+#[tauri::command]
+fn set_anti_deadzones(left: f32, right: f32) {
+    // Past 0.9 the stick would be all or nothing
+    let clamp = |v: f32| if v.is_finite() { v.clamp(0.0, 0.9) } else { 0.0 };
+    settings::update(true, |s| {
+        s.anti_deadzone_left = clamp(left);
+        s.anti_deadzone_right = clamp(right);
+    });
+}
+
+#[tauri::command]
+fn set_mouse_sens(left: f32, right: f32) {
+    settings::update(true, |s| {
+        s.mouse_sens_left = left;
+        s.mouse_sens_right = right;
+    });
+}
+
+#[tauri::command]
+fn set_touchpad_sens(sens: f32) {
+    settings::update(true, |s| s.mouse_sens_touchpad = sens);
 }
 
 #[tauri::command]
 fn get_initial_state(state: tauri::State<Arc<Mutex<SharedState>>>) -> String {
-    let s = state.lock().unwrap();
-    serde_json::to_string(&*s).unwrap_or("{}".to_string())
+    // Runtime state and settings as one object, the shape the UI keeps as currentState
+    let mut value = serde_json::to_value(&*state.lock().unwrap()).unwrap_or_default();
+    if let (Some(obj), Ok(serde_json::Value::Object(settings))) = (value.as_object_mut(), serde_json::to_value(&*settings::read())) {
+        obj.extend(settings);
+    }
+    value.to_string()
+}
+
+#[tauri::command]
+fn get_settings() -> settings::Settings {
+    settings::read().clone()
+}
+
+#[tauri::command]
+fn get_gamepad_snapshot(state: tauri::State<Arc<Mutex<SharedState>>>) -> state::GamepadSnapshot {
+    state::GamepadSnapshot::from_state(&state.lock().unwrap())
+}
+
+#[tauri::command]
+fn get_device_info(state: tauri::State<Arc<Mutex<SharedState>>>) -> state::DeviceInfo {
+    state::DeviceInfo::from_state(&state.lock().unwrap())
+}
+
+#[tauri::command]
+fn get_vigem_health() -> vigem::VigemHealth {
+    vigem::health()
+}
+
+#[tauri::command]
+fn get_keys() -> Vec<keys::KeyInfo> {
+    keys::list()
+}
+
+#[tauri::command]
+fn get_debug_info(state: tauri::State<Arc<Mutex<SharedState>>>) -> state::DebugInfo {
+    state::DebugInfo::from_state(&state.lock().unwrap())
 }
 
 #[tauri::command]
@@ -234,17 +599,57 @@ fn toggle_debug(_state: tauri::State<Arc<Mutex<SharedState>>>) {
 }
 
 #[tauri::command]
-fn set_hide_controller(state: tauri::State<Arc<Mutex<SharedState>>>, hide: bool) {
+fn set_hide_controller(hide: bool) {
+    settings::update(false, |s| s.hide_controller = hide);
+}
+
+#[tauri::command]
+fn set_start_minimized(val: bool) {
+    settings::update(false, |s| s.start_minimized = val);
+}
+
+#[tauri::command]
+fn set_autostart(state: tauri::State<Arc<Mutex<SharedState>>>, val: bool) {
+    let res = if val { autostart::enable() } else { autostart::disable() };
     let mut s = state.lock().unwrap();
-    s.hide_controller = hide;
-    save_config_internal(&s, false); // Don't save to profile, global setting
+    match res {
+        Ok(_) => s.autostart = val,
+        Err(e) => log::error!("Failed to update autostart: {}", e),
+    }
 }
 
 #[tauri::command]
-fn set_start_minimized(state: tauri::State<Arc<Mutex<SharedState>>>, val: bool) {
+fn set_elevated_autostart(state: tauri::State<Arc<Mutex<SharedState>>>, val: bool) {
+    let res = if val { elevated::register() } else { elevated::unregister() };
     let mut s = state.lock().unwrap();
-    s.start_minimized = val;
-    save_config_internal(&s, false); // Global setting
+    match res {
+        Ok(_) => {
+            s.elevated_autostart = val;
+            // The logon task replaces the plain Run entry, don't launch twice at login
+            if val && s.autostart && autostart::disable().is_ok() {
+                s.autostart = false;
+            }
+        }
+        Err(e) => log::error!("Failed to update elevated autostart: {}", e),
+    }
+}
+
+#[tauri::command]
+fn set_api_settings(state: tauri::State<Arc<Mutex<SharedState>>>, enabled: bool, port: u16) {
+    let restart = settings::update(false, |s| {
+        let restart = enabled && (!s.api_enabled || s.api_port != port);
+        s.api_enabled = enabled;
+        s.api_port = port;
+        restart
+    });
+
+    // The old listener notices the change and shuts itself down
+    if restart {
+        api::start(state.inner().clone(), port);
+    }
+    if enabled {
+        pipe::start(state.inner().clone());
+    }
 }
 
 #[tauri::command]
@@ -254,8 +659,10 @@ fn set_fuzzer_active(state: tauri::State<Arc<Mutex<SharedState>>>, val: bool) {
     if val {
         s.fuzzer_step = 0;
         s.fuzzer_log = "Starting...".to_string();
+        s.fuzzer_session_path = fuzzlog::begin("fuzzer").map(|p| p.to_string_lossy().into_owned()).unwrap_or_default();
     } else {
         s.fuzzer_log = "Stopped.".to_string();
+        fuzzlog::end();
     }
 }
 
@@ -266,6 +673,72 @@ fn set_sweep_active(state: tauri::State<Arc<Mutex<SharedState>>>, val: bool) {
     if val {
         s.fuzzer_step = 0;
         s.fuzzer_log = "Sweeping...".to_string();
+        s.fuzzer_session_path = fuzzlog::begin("sweep").map(|p| p.to_string_lossy().into_owned()).unwrap_or_default();
+    } else {
+        fuzzlog::end();
+    }
+}
+
+#[tauri::command]
+fn set_fuzz_script(state: tauri::State<Arc<Mutex<SharedState>>>, script: String) -> Result<(), String> {
+    script_fuzz::ScriptFuzzer::compile(&script)?;
+    state.lock().unwrap().fuzz_script = script;
+    Ok(())
+}
+
+#[tauri::command]
+fn set_fuzz_script_active(state: tauri::State<Arc<Mutex<SharedState>>>, val: bool) {
+    let mut s = state.lock().unwrap();
+    s.fuzz_script_active = val;
+    if val {
+        s.fuzzer_step = 0;
+        s.fuzzer_log = "Running script...".to_string();
+        s.fuzzer_session_path = fuzzlog::begin("script").map(|p| p.to_string_lossy().into_owned()).unwrap_or_default();
+    } else {
+        s.fuzzer_log = "Stopped.".to_string();
+        fuzzlog::end();
+    }
+}
+
+#[tauri::command]
+fn set_fuzzer_params(state: tauri::State<Arc<Mutex<SharedState>>>, params: crate::state::FuzzerParams) -> Result<(), String> {
+    if params.step_count() == 0 {
+        return Err("Every list needs at least one entry".to_string());
+    }
+    // send_raw_output writes up to 3 bytes past the RGB offset into a 600-byte buffer
+    if params.flag_offsets.iter().chain(&params.rgb_offsets).any(|&o| o >= 590) {
+        return Err("Offsets must be below 590".to_string());
+    }
+    let mut s = state.lock().unwrap();
+    s.fuzzer_params = params;
+    s.fuzzer_params.burst = s.fuzzer_params.burst.max(1);
+    s.fuzzer_step = 0;
+    Ok(())
+}
+
+/// Next step the fuzzer/sweep sends. Out-of-range steps wrap to 0 like a finished cycle.
+#[tauri::command]
+fn set_fuzzer_step(state: tauri::State<Arc<Mutex<SharedState>>>, step: usize) {
+    let mut s = state.lock().unwrap();
+    s.fuzzer_step = step;
+    s.fuzzer_log = format!("Jumped to step {}", step);
+}
+
+#[tauri::command]
+fn mark_fuzzer_hit(state: tauri::State<Arc<Mutex<SharedState>>>, note: String) -> Result<(), String> {
+    fuzzlog::mark(note.trim())?;
+    let mut s = state.lock().unwrap();
+    s.fuzzer_log = format!("Marked: {}", s.fuzzer_log);
+    Ok(())
+}
+
+#[tauri::command]
+fn open_fuzzer_session(state: tauri::State<Arc<Mutex<SharedState>>>) {
+    let s = state.lock().unwrap();
+    if s.fuzzer_session_path.is_empty() {
+        platform::show_in_folder(&fuzzlog::session_dir().to_string_lossy());
+    } else {
+        platform::show_in_folder(&s.fuzzer_session_path);
     }
 }
 
@@ -275,6 +748,23 @@ fn set_sweep_speed(state: tauri::State<Arc<Mutex<SharedState>>>, val: u64) {
     s.sweep_timeout_ms = val;
 }
 
+/// None restores the full Ultimate sweep
+#[tauri::command]
+fn set_sweep_range(state: tauri::State<Arc<Mutex<SharedState>>>, range: Option<crate::state::SweepRange>) -> Result<(), String> {
+    if let Some(r) = &range {
+        if r.start < 1 || r.start > r.end || r.end >= 74 {
+            return Err("Range must be within 1-73 (byte 0 is the report ID, 74+ the CRC)".to_string());
+        }
+        if r.step == 0 || r.values.is_empty() {
+            return Err("Step must be at least 1 and values must not be empty".to_string());
+        }
+    }
+    let mut s = state.lock().unwrap();
+    s.sweep_range = range;
+    s.fuzzer_step = 0;
+    Ok(())
+}
+
 #[tauri::command]
 fn set_disable_periodic(state: tauri::State<Arc<Mutex<SharedState>>>, val: bool) {
     let mut s = state.lock().unwrap();
@@ -289,7 +779,10 @@ fn set_crc_seed(state: tauri::State<Arc<Mutex<SharedState>>>, val: u8) {
 
 #[tauri::command]
 fn set_manual_params(state: tauri::State<Arc<Mutex<SharedState>>>, params: ManualParams) {
-    let mut s = state.lock().unwrap();
+    apply_manual_params(&mut state.lock().unwrap(), params);
+}
+
+fn apply_manual_params(s: &mut SharedState, params: ManualParams) {
     s.manual_report_id = params.report_id;
     s.manual_flag_offset = params.flag_off;
     s.manual_rgb_offset = params.rgb_off;
@@ -305,6 +798,33 @@ fn set_manual_params(state: tauri::State<Arc<Mutex<SharedState>>>, params: Manua
     s.manual_b = params.b;
 }
 
+#[tauri::command]
+fn list_manual_templates() -> Vec<String> {
+    AppConfig::list_templates()
+}
+
+#[tauri::command]
+fn save_manual_template(name: String, params: ManualParams) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Template name is empty".to_string());
+    }
+    AppConfig::save_template(name, &params).map_err(|e| e.to_string())
+}
+
+/// Applies the template to the manual override and returns it so the UI can fill its inputs
+#[tauri::command]
+fn load_manual_template(state: tauri::State<Arc<Mutex<SharedState>>>, name: String) -> Result<ManualParams, String> {
+    let params = AppConfig::load_template(&name).ok_or(format!("Template '{}' not found", name))?;
+    apply_manual_params(&mut state.lock().unwrap(), params.clone());
+    Ok(params)
+}
+
+#[tauri::command]
+fn delete_manual_template(name: String) {
+    AppConfig::delete_template(&name);
+}
+
 #[tauri::command]
 fn trigger_manual_send(state: tauri::State<Arc<Mutex<SharedState>>>) {
     state.lock().unwrap().should_send_manual = true;
@@ -330,20 +850,171 @@ fn trigger_protocol_scan(state: tauri::State<Arc<Mutex<SharedState>>>) {
 }
 
 #[tauri::command]
-fn update_mappings(state: tauri::State<Arc<Mutex<SharedState>>>, mappings: Vec<crate::mapping::ButtonMapping>) {
+fn set_report_diff(state: tauri::State<Arc<Mutex<SharedState>>>, val: bool) {
+    state.lock().unwrap().report_diff_active = val;
+}
+
+#[tauri::command]
+fn set_stick_preview(state: tauri::State<Arc<Mutex<SharedState>>>, val: bool) {
+    state.lock().unwrap().stick_preview_active = val;
+}
+
+/// Flashes the lightbar of the controller with this serial (or VID:PID, for pads without one) so
+/// it can be told apart from others
+#[tauri::command]
+fn identify_controller(state: tauri::State<Arc<Mutex<SharedState>>>, device: String) -> Result<(), String> {
+    let mut s = state.lock().unwrap();
+    if s.stick_cal_key.is_empty() {
+        return Err("No controller connected".to_string());
+    }
+    if !device.eq_ignore_ascii_case(&s.stick_cal_key) && !device.eq_ignore_ascii_case(&s.device_key) {
+        return Err(format!("{} is not connected", device));
+    }
+    s.should_identify = true;
+    Ok(())
+}
+
+#[tauri::command]
+fn set_stick_stream(state: tauri::State<Arc<Mutex<SharedState>>>, val: bool) {
+    state.lock().unwrap().stick_stream_active = val;
+}
+
+#[tauri::command]
+fn set_capture_active(state: tauri::State<Arc<Mutex<SharedState>>>, val: bool) {
+    if val { capture::start() } else { capture::stop() }
+    let mut s = state.lock().unwrap();
+    s.capture_active = val;
+    s.capture_packets = capture::packet_count();
+}
+
+#[tauri::command]
+fn get_output_layouts() -> Vec<output_layout::OutputLayout> {
+    output_layout::layouts()
+}
+
+#[tauri::command]
+fn get_write_history() -> Vec<writelog::WriteEntry> {
+    writelog::history()
+}
+
+#[tauri::command]
+fn clear_write_history() {
+    writelog::clear();
+}
+
+#[tauri::command]
+fn get_input_stats() -> stats::InputStats {
+    stats::snapshot()
+}
+
+#[tauri::command]
+fn reset_input_stats() {
+    stats::reset();
+}
+
+/// Returns the recording's file path when starting (empty when stopping)
+#[tauri::command]
+fn set_input_recording(state: tauri::State<Arc<Mutex<SharedState>>>, val: bool) -> Result<String, String> {
+    let result = if val {
+        recording::start().map(|p| p.to_string_lossy().into_owned()).map_err(|e| {
+            log::error!("Failed to start input recording: {}", e);
+            e.to_string()
+        })
+    } else {
+        recording::stop();
+        Ok(String::new())
+    };
+    state.lock().unwrap().input_recording = recording::is_active();
+    result
+}
+
+/// Returns the log file path so the UI can show where it goes
+#[tauri::command]
+fn set_write_log_file(val: bool) -> Result<String, String> {
+    writelog::set_file_logging(val).map(|p| p.to_string_lossy().into_owned()).map_err(|e| {
+        log::error!("Failed to open write log: {}", e);
+        e.to_string()
+    })
+}
+
+/// Format follows the extension: .pcapng or .jsonl (see capture.rs)
+#[tauri::command]
+fn export_capture(path: String) -> Result<usize, String> {
+    capture::export(std::path::Path::new(&path)).map_err(|e| {
+        log::error!("Failed to export capture to {}: {}", path, e);
+        e.to_string()
+    })
+}
+
+#[tauri::command]
+fn trigger_descriptor_dump(state: tauri::State<Arc<Mutex<SharedState>>>) {
     let mut s = state.lock().unwrap();
-    s.mappings = mappings;
-    s.mappings_changed = true;
-    save_config_internal(&s, true);
+    s.should_dump_descriptor = true;
+    s.protocol_log = "Reading report descriptor...".to_string();
 }
 
 #[tauri::command]
-fn reset_mappings(state: tauri::State<Arc<Mutex<SharedState>>>) {
+fn trigger_input_report_scan(state: tauri::State<Arc<Mutex<SharedState>>>) {
     let mut s = state.lock().unwrap();
-    s.mappings = AppConfig::default_mappings();
-    s.mappings_changed = true;
-    s.current_profile_name = "Default".to_string();
-    save_config_internal(&s, true);
+    s.should_scan_input_reports = true;
+    s.protocol_log = "Reading input/feature reports... Please wait.".to_string();
+}
+
+#[tauri::command]
+fn export_protocol_log(state: tauri::State<Arc<Mutex<SharedState>>>, path: String) -> Result<(), String> {
+    let log = state.lock().unwrap().protocol_log.clone();
+    std::fs::write(&path, log).map_err(|e| {
+        log::error!("Failed to export protocol log to {}: {}", path, e);
+        e.to_string()
+    })
+}
+
+#[tauri::command]
+fn update_mappings(mappings: Vec<crate::mapping::ButtonMapping>) {
+    settings::update(true, |s| s.mappings = mappings);
+}
+
+/// Replaces what one source is mapped to, leaving the other mappings alone (added if missing)
+#[tauri::command]
+fn update_mapping(source: crate::mapping::PhysicalButton, targets: Vec<crate::mapping::MappingTarget>) {
+    settings::update(true, |s| match s.mappings.iter_mut().find(|m| m.source == source) {
+        Some(m) => m.targets = targets,
+        None => s.mappings.push(crate::mapping::ButtonMapping::new(source, targets)),
+    });
+}
+
+/// Shift layer targets of one button (see ButtonMapping::shift_targets)
+#[tauri::command]
+fn update_shift_mapping(source: crate::mapping::PhysicalButton, targets: Vec<crate::mapping::MappingTarget>) {
+    settings::update(true, |s| match s.mappings.iter_mut().find(|m| m.source == source) {
+        Some(m) => m.shift_targets = targets,
+        None => s.mappings.push(crate::mapping::ButtonMapping { shift_targets: targets, ..crate::mapping::ButtonMapping::new(source, Vec::new()) }),
+    });
+}
+
+/// Turbo rate of one button's mapping (None = held targets stay held)
+#[tauri::command]
+fn set_turbo(source: crate::mapping::PhysicalButton, hz: Option<f32>) {
+    let hz = hz.filter(|hz| *hz > 0.0).map(|hz| hz.min(crate::mapping::MAX_TURBO_HZ));
+    settings::update(true, |s| match s.mappings.iter_mut().find(|m| m.source == source) {
+        Some(m) => m.turbo_hz = hz,
+        None => s.mappings.push(crate::mapping::ButtonMapping { turbo_hz: hz, ..crate::mapping::ButtonMapping::new(source, Vec::new()) }),
+    });
+}
+
+/// Button that switches the mappings to their shift layer while held (None = no shift layer).
+/// Sticks, triggers and the touchpad surface have no on/off state to shift with.
+#[tauri::command]
+fn set_shift_button(button: Option<crate::mapping::PhysicalButton>) {
+    settings::update(true, |s| s.shift_button = button.filter(|b| !b.is_axis()));
+}
+
+#[tauri::command]
+fn reset_mappings() {
+    settings::update(true, |s| {
+        s.mappings = AppConfig::default_mappings();
+        s.current_profile_name = "Default".to_string();
+    });
 }
 
 #[tauri::command]
@@ -352,75 +1023,147 @@ fn get_profiles() -> Vec<String> {
 }
 
 #[tauri::command]
-fn save_profile(state: tauri::State<Arc<Mutex<SharedState>>>, name: String) {
-    let mut s = state.lock().unwrap();
-    s.current_profile_name = name;
-    save_config_internal(&s, true);
+fn save_profile(name: String) {
+    // Same separator list_profiles reports folders with
+    settings::update(true, |s| s.current_profile_name = name.trim().replace('\\', "/"));
 }
 
 #[tauri::command]
 fn load_profile(state: tauri::State<Arc<Mutex<SharedState>>>, name: String) {
     let mut s = state.lock().unwrap();
-    
+    load_profile_internal(&mut s, name);
+}
+
+/// Loads the profile after the active one, in the UI's order (Default first, then by name) and
+/// wrapping around. The list is read before taking the state lock, it flushes pending profile writes.
+pub fn cycle_profile(state: &Arc<Mutex<SharedState>>) -> String {
+    let mut names = AppConfig::list_profiles();
+    names.retain(|n| n != "Default");
+    names.sort_by_key(|n| n.to_lowercase());
+    names.insert(0, "Default".to_string());
+
+    let current = settings::read().current_profile_name.clone();
+    let next = names.iter().position(|n| *n == current)
+        .map_or(0, |i| (i + 1) % names.len());
+    let name = names.swap_remove(next);
+    load_profile_internal(&mut state.lock().unwrap(), name.clone());
+    name
+}
+
+/// Returns false if the profile doesn't exist on disk (state untouched)
+fn load_profile_internal(s: &mut SharedState, name: String) -> bool {
+    let profile = AppConfig::load_profile(&name);
     // Special handling for "Default" if it doesn't exist on disk yet
-    if name == "Default" {
-        // Try to load, if fails, reset to hardcoded defaults
-        if let Some(profile) = AppConfig::load_profile(&name) {
-            apply_profile_to_state(&mut s, profile);
-        } else {
-            s.mappings = AppConfig::default_mappings();
-            // Reset crucial settings to defaults
-            s.deadzone_left = 0.1; s.deadzone_right = 0.1;
-            s.mouse_sens_left = 25.0; s.mouse_sens_right = 25.0; s.mouse_sens_touchpad = 25.0;
-            s.rgb_r = 0; s.rgb_g = 0; s.rgb_b = 255; s.rgb_brightness = 255;
-            s.show_battery_led = false;
-            s.trigger_l2_mode = 0; s.trigger_r2_mode = 0;
-            s.player_led_brightness = 0;
-            
-            s.mappings_changed = true;
-            s.should_send_leds = true;
-            s.should_send_triggers = true;
+    if profile.is_none() && name != "Default" {
+        return false;
+    }
+
+    // DO NOT OVERWRITE PROFILE ON LOAD
+    settings::update(false, |st| {
+        match profile {
+            Some(profile) => st.apply_profile(profile),
+            None => {
+                st.mappings = AppConfig::default_mappings();
+                // Reset crucial settings to defaults
+                st.deadzone_left = 0.1; st.deadzone_right = 0.1;
+                st.anti_deadzone_left = 0.0; st.anti_deadzone_right = 0.0;
+                st.deadzone_shape_left = Default::default(); st.deadzone_shape_right = Default::default();
+                st.mouse_sens_left = 25.0; st.mouse_sens_right = 25.0; st.mouse_sens_touchpad = 25.0;
+                st.rgb_r = 0; st.rgb_g = 0; st.rgb_b = 255; st.rgb_brightness = 255;
+                st.show_battery_led = false;
+                st.trigger_l2_mode = 0; st.trigger_r2_mode = 0;
+                st.player_led_brightness = 0;
+                st.auto_sprint = None;
+                st.trigger_curve_l = Default::default(); st.trigger_curve_r = Default::default();
+                st.stick_curve_l = Default::default(); st.stick_curve_r = Default::default();
+                st.trackball_friction = None;
+                st.input_backend = Default::default();
+                st.pad_kind = Default::default();
+                st.swap_sticks = false;
+                st.shift_button = None;
+                st.profile_parent = None;
+            }
+        }
+        st.current_profile_name = name;
+    });
+    s.should_confirm_profile = true;
+    true
+}
+
+#[tauri::command]
+fn delete_profile(name: String) {
+    AppConfig::delete_profile(&name);
+}
+
+/// Makes the active profile inherit from `parent` (None keeps a full copy of every field)
+#[tauri::command]
+fn set_profile_parent(parent: Option<String>) -> Result<(), String> {
+    let current = settings::read().current_profile_name.clone();
+    if let Some(p) = &parent {
+        if !AppConfig::can_inherit(&current, p) {
+            return Err(format!("'{}' doesn't exist or already inherits from '{}'", p, current));
         }
-        s.current_profile_name = name;
-        save_config_internal(&s, false); // DO NOT OVERWRITE PROFILE ON LOAD
-        return;
     }
+    settings::update(true, |s| s.profile_parent = parent);
+    Ok(())
+}
 
-    if let Some(profile) = AppConfig::load_profile(&name) {
-        apply_profile_to_state(&mut s, profile);
-        s.current_profile_name = name;
-        save_config_internal(&s, false); // DO NOT OVERWRITE PROFILE ON LOAD
+/// Reads a profile from disk without loading it (for editing profiles that aren't active)
+#[tauri::command]
+fn get_profile_contents(name: String) -> Result<crate::config::Profile, String> {
+    match AppConfig::load_profile(&name) {
+        Some(p) => Ok(p),
+        None if name == "Default" => Ok(crate::config::Profile::default()),
+        None => Err(format!("Profile '{}' not found", name)),
     }
 }
 
-fn apply_profile_to_state(s: &mut SharedState, p: crate::config::Profile) {
-    s.mappings = p.mappings;
-    s.deadzone_left = p.deadzone_left;
-    s.deadzone_right = p.deadzone_right;
-    s.mouse_sens_left = p.mouse_sens_left;
-    s.mouse_sens_right = p.mouse_sens_right;
-    s.mouse_sens_touchpad = p.mouse_sens_touchpad;
-    s.rgb_r = p.rgb_r;
-    s.rgb_g = p.rgb_g;
-    s.rgb_b = p.rgb_b;
-    s.rgb_brightness = p.rgb_brightness;
-    s.show_battery_led = p.show_battery_led;
-    s.trigger_l2_mode = p.trigger_l2_mode;
-    s.trigger_l2_start = p.trigger_l2_start;
-    s.trigger_l2_force = p.trigger_l2_force;
-    s.trigger_r2_mode = p.trigger_r2_mode;
-    s.trigger_r2_start = p.trigger_r2_start;
-    s.trigger_r2_force = p.trigger_r2_force;
-    s.player_led_brightness = p.player_led_brightness;
+/// Writes a profile to disk without loading it. Writing the active profile also applies it,
+/// otherwise the next change to the live state would save over the edit.
+#[tauri::command]
+fn put_profile_contents(name: String, profile: crate::config::Profile) -> Result<(), String> {
+    let name = name.trim();
+    if AppConfig::profile_path(name).is_none() {
+        return Err(format!("Invalid profile name '{}'", name));
+    }
+    persist::queue_profile(name, profile.clone());
+    if settings::read().current_profile_name == name {
+        settings::update(false, |s| s.apply_profile(profile));
+    }
+    Ok(())
+}
 
-    s.mappings_changed = true;
-    s.should_send_leds = true;
-    s.should_send_triggers = true;
+/// Writes a profile, parents merged in, to a single shareable file
+#[tauri::command]
+fn export_profile(name: String, path: String) -> Result<(), String> {
+    let profile = get_profile_contents(name.clone())?;
+    crate::config::ProfileFile::new(&name, profile).write(std::path::Path::new(&path)).map_err(|e| {
+        log::error!("Failed to export profile '{}' to {}: {}", name, path, e);
+        e.to_string()
+    })
 }
 
+/// Adds the profile in an exported file under `name` (the file's own name if None). An existing
+/// profile is never overwritten, the import gets a free "Name (2)" instead. Returns the name used.
 #[tauri::command]
-fn delete_profile(name: String) {
-    AppConfig::delete_profile(&name);
+fn import_profile(path: String, name: Option<String>) -> Result<String, String> {
+    let file = crate::config::ProfileFile::read(std::path::Path::new(&path)).map_err(|e| {
+        log::warn!("Failed to import profile from {}: {}", path, e);
+        e
+    })?;
+    let base = name.filter(|n| !n.trim().is_empty()).unwrap_or(file.name).trim().replace('\\', "/");
+    if AppConfig::profile_path(&base).is_none() {
+        return Err(format!("Invalid profile name '{}'", base));
+    }
+    let existing = AppConfig::list_profiles();
+    let taken = |n: &str| n == "Default" || existing.iter().any(|e| e == n);
+    let name = std::iter::once(base.clone())
+        .chain((2..).map(|i| format!("{} ({})", base, i)))
+        .find(|n| !taken(n))
+        .unwrap();
+    persist::queue_profile(&name, file.profile);
+    log::info!("Imported profile '{}' from {}", name, path);
+    Ok(name)
 }
 
 #[tauri::command]
@@ -435,14 +1178,30 @@ fn get_image_asset(name: String) -> Vec<u8> {
 }
 
 fn main() {
-    // Initialize logger: Suppress noisy warnings from TAO (windowing) and WRY (webview)
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
-        .filter_module("tao", log::LevelFilter::Error)
-        .filter_module("wry", log::LevelFilter::Error)
-        .init();
-
     let config = AppConfig::load();
+    logging::init(&config.log_level);
+    let start_hidden = config.start_minimized || std::env::args().any(|a| a == autostart::MINIMIZED_ARG);
+    settings::init(&config);
     let state = Arc::new(Mutex::new(SharedState::new(&config)));
+    crash::install(state.clone());
+    persist::start();
+    if start_hidden {
+        state.lock().unwrap().ui_visible = false;
+    }
+
+    if config.api_enabled {
+        api::start(state.clone(), config.api_port);
+        pipe::start(state.clone());
+    }
+    if config.discord_enabled {
+        discord::start(state.clone());
+    }
+    if config.mqtt.enabled {
+        mqtt::start(state.clone());
+    }
+    focus::start(state.clone());
+    power::start(state.clone());
+    let pause_hotkey = config.pause_hotkey.clone();
     let state_clone = state.clone();
 
     // Tray Setup
@@ -452,17 +1211,12 @@ fn main() {
         .add_item(show)
         .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(quit);
-    let system_tray = SystemTray::new().with_menu(tray_menu);
+    let system_tray = SystemTray::new().with_menu(tray_menu).with_tooltip(config::APP_NAME);
 
     // Global Signal Handler (Ctrl+C, SIGTERM)
     let state_for_signal = state.clone();
     let _ = ctrlc::set_handler(move || {
-        let mut s = state_for_signal.lock().unwrap();
-        s.should_exit = true;
-        // Release lock and wait
-        drop(s);
-        std::thread::sleep(std::time::Duration::from_millis(300));
-        std::process::exit(0);
+        exit_and_restore_internal(&state_for_signal);
     });
 
     tauri::Builder::default()
@@ -479,9 +1233,9 @@ fn main() {
                 let state: tauri::State<Arc<Mutex<SharedState>>> = app.state();
                 match id.as_str() {
                     "quit" => {
-                        state.lock().unwrap().should_exit = true;
-                        std::thread::sleep(std::time::Duration::from_millis(300));
-                        std::process::exit(0);
+                        // Run off the event loop so the worker can still emit while cleaning up
+                        let state = state.inner().clone();
+                        thread::spawn(move || exit_and_restore_internal(&state));
                     }
                     "show" => {
                         if let Some(window) = app.get_window("main") {
@@ -517,11 +1271,23 @@ fn main() {
             
             // Start Background Worker
             thread::spawn(move || {
-                controller_thread(state_clone, app_handle_for_worker);
+                controller_thread(state_clone.clone(), app_handle_for_worker);
+                state_clone.lock().unwrap().worker_stopped = true;
             });
+
+            settings::watch(app_handle.clone());
+
+            let state: tauri::State<Arc<Mutex<SharedState>>> = app_handle.state();
+            tray::spawn_updater(app_handle.clone(), state.inner().clone());
+
+            if !pause_hotkey.is_empty() {
+                if let Err(e) = register_pause_hotkey(&app_handle, state.inner().clone(), &pause_hotkey) {
+                    log::error!("Failed to register pause hotkey '{}': {}", pause_hotkey, e);
+                }
+            }
             
             // Initial Window Logic
-            if config.start_minimized {
+            if start_hidden {
                 // If starting minimized, DESTROY the auto-created window so it doesn't consume RAM
                 // and so main.js doesn't run and force-show it.
                 if let Some(window) = app_handle.get_window("main") {
@@ -534,15 +1300,16 @@ fn main() {
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
-            get_initial_state, toggle_debug, is_dev, set_hide_controller, set_start_minimized,
+            get_initial_state, get_settings, get_gamepad_snapshot, get_device_info, get_vigem_health, get_keys, get_debug_info, toggle_debug, is_dev, set_hide_controller, set_start_minimized, set_autostart, set_elevated_autostart, set_api_settings, set_pause_hotkey, exit_and_restore, set_log_level, set_discord_settings, set_mqtt_settings, set_idle_power_off, set_ps_hold_opens_window, set_haptic_alerts, set_vjoy_device, set_manual_connect, connect_device, set_output_keepalive, set_quiet_hours, set_orientation, set_kbm_gamepad, set_focus_pause, set_power_off_combo, set_profile_cycle_combo, set_hook_commands, set_report_layout, set_descriptor_parsing, set_touch_calibrating, reset_touch_calibration, calibrate_sticks, reset_stick_calibration, calibrate_gyro, reset_gyro_calibration, set_stick_stream, get_input_stats, reset_input_stats,
+            open_crash_report, dismiss_crash_report,
             trigger_driver_refresh,
-            set_fuzzer_active, set_sweep_active, set_sweep_speed, set_disable_periodic, set_crc_seed,
-            set_manual_params, trigger_manual_send,
-            set_pinpoint_params, trigger_pinpoint_send, trigger_protocol_scan,
-            update_mappings, reset_mappings,
-            set_deadzones, set_mouse_sens, set_touchpad_sens, set_rgb, set_show_battery_led, set_player_led_brightness,
-            set_trigger_l2, set_trigger_r2, disconnect_controller, resume_scanning,
-            get_profiles, save_profile, load_profile, delete_profile,
+            set_fuzzer_active, set_sweep_active, set_fuzzer_params, set_fuzzer_step, set_fuzz_script, set_fuzz_script_active, mark_fuzzer_hit, open_fuzzer_session, set_sweep_speed, set_sweep_range, set_disable_periodic, set_crc_seed,
+            set_manual_params, trigger_manual_send, list_manual_templates, save_manual_template, load_manual_template, delete_manual_template,
+            set_pinpoint_params, trigger_pinpoint_send, trigger_protocol_scan, trigger_descriptor_dump, trigger_input_report_scan, export_protocol_log, set_report_diff, set_capture_active, export_capture, get_output_layouts, get_write_history, clear_write_history, set_write_log_file, set_input_recording,
+            update_mappings, update_mapping, update_shift_mapping, set_turbo, set_shift_button, reset_mappings,
+            set_deadzones, set_deadzone_shapes, set_anti_deadzones, set_trigger_curves, set_stick_curves, set_stick_preview, set_mouse_sens, set_touchpad_sens, set_rgb, set_show_battery_led, set_player_led_brightness, set_auto_sprint, set_trackball, set_input_backend, set_pad_kind, set_swap_sticks,
+            set_trigger_l2, set_trigger_r2, disconnect_controller, identify_controller, resume_scanning,
+            get_profiles, save_profile, load_profile, delete_profile, get_profile_contents, put_profile_contents, set_profile_parent, export_profile, import_profile,
             get_image_asset
         ])
         .build(tauri::generate_context!())
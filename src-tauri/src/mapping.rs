@@ -1,5 +1,8 @@
 use serde::{Serialize, Deserialize};
 
+use crate::touch::{TOUCH_WIDTH, TOUCH_HEIGHT};
+use crate::vjoy::VJoyAxis;
+
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct GamepadState {
     pub left_x: f32,
@@ -25,11 +28,91 @@ pub struct GamepadState {
     pub dpad_right: bool,
     pub btn_touchpad: bool,
     pub btn_mute: bool,
+    // DualSense Edge: Fn buttons below the sticks, back paddles
+    pub btn_fn_l: bool,
+    pub btn_fn_r: bool,
+    pub btn_paddle_l: bool,
+    pub btn_paddle_r: bool,
     pub touch_x: u16,
     pub touch_y: u16,
     pub touch_active: bool,
+    pub touch2_x: u16, // Second finger (DualSense BT), for two-finger gestures
+    pub touch2_y: u16,
+    pub touch2_active: bool,
     pub battery: u8, // 0-100
-    pub is_charging: bool,
+    pub is_charging: bool, // On the cable: Charging or Full
+    pub charge_state: ChargeState,
+    pub headphones: bool, // DualSense 3.5mm jack: headphones plugged in
+    pub mic: bool,        // DualSense 3.5mm jack: headset with mic plugged in
+    pub gyro_x: f32, // Angular velocity in deg/s: pitch (nose up = positive)
+    pub gyro_y: f32, // Yaw (turning left = positive)
+    pub gyro_z: f32, // Roll
+    pub accel_x: f32, // Acceleration in g, gravity included: x to the right
+    pub accel_y: f32, // Up (about +1 g lying flat)
+    pub accel_z: f32, // Toward the player
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChargeState {
+    Discharging,
+    Charging,
+    Full,
+    Error, // Voltage/temperature fault or charging error
+}
+
+/// How the controller is held, clockwise from normal. Sticks, D-pad and touchpad are rotated back
+/// so "up" stays up for left-handed grips and accessibility mounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Orientation {
+    #[default]
+    Normal,
+    Cw90,
+    Rotated180,
+    Ccw90,
+}
+
+impl Orientation {
+    /// (x, y) with y pointing down, as the sticks report it
+    fn rotate(self, x: f32, y: f32) -> (f32, f32) {
+        match self {
+            Self::Normal => (x, y),
+            Self::Cw90 => (-y, x),
+            Self::Rotated180 => (-x, -y),
+            Self::Ccw90 => (y, -x),
+        }
+    }
+
+    pub fn apply(self, mut state: GamepadState) -> GamepadState {
+        if self == Self::Normal {
+            return state;
+        }
+        (state.left_x, state.left_y) = self.rotate(state.left_x, state.left_y);
+        (state.right_x, state.right_y) = self.rotate(state.right_x, state.right_y);
+        // The face of the controller spans x (right) and z (toward the player), which line up with
+        // the sticks' x and y. Turning it in that plane leaves yaw alone and swaps pitch and roll.
+        (state.gyro_x, state.gyro_z) = self.rotate(state.gyro_x, state.gyro_z);
+        (state.accel_x, state.accel_z) = self.rotate(state.accel_x, state.accel_z);
+
+        let (up, right, down, left) = (state.dpad_up, state.dpad_right, state.dpad_down, state.dpad_left);
+        (state.dpad_up, state.dpad_right, state.dpad_down, state.dpad_left) = match self {
+            Self::Normal => (up, right, down, left),
+            Self::Cw90 => (left, up, right, down),
+            Self::Rotated180 => (down, left, up, right),
+            Self::Ccw90 => (right, down, left, up),
+        };
+
+        // Touchpad: rotate around the center in normalized coordinates, the pad isn't square
+        let (w, h) = ((TOUCH_WIDTH - 1) as f32, (TOUCH_HEIGHT - 1) as f32);
+        let turn = |x: u16, y: u16| {
+            let (u, v) = self.rotate(x as f32 / w - 0.5, y as f32 / h - 0.5);
+            (((u + 0.5) * w).round() as u16, ((v + 0.5) * h).round() as u16)
+        };
+        (state.touch_x, state.touch_y) = turn(state.touch_x, state.touch_y);
+        (state.touch2_x, state.touch2_y) = turn(state.touch2_x, state.touch2_y);
+        state
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -39,6 +122,7 @@ pub enum PhysicalButton {
     Options, Share, PS, Touchpad, TouchpadLeft, TouchpadRight, Mute,
     DpadUp, DpadDown, DpadLeft, DpadRight,
     LeftStick, RightStick, L2, R2,
+    FnLeft, FnRight, PaddleLeft, PaddleRight, // DualSense Edge
 }
 
 impl PhysicalButton {
@@ -66,6 +150,10 @@ impl PhysicalButton {
             Self::TouchpadLeft => state.btn_touchpad && state.touch_x < 960,
             Self::TouchpadRight => state.btn_touchpad && state.touch_x >= 960,
             Self::Mute => state.btn_mute,
+            Self::FnLeft => state.btn_fn_l,
+            Self::FnRight => state.btn_fn_r,
+            Self::PaddleLeft => state.btn_paddle_l,
+            Self::PaddleRight => state.btn_paddle_r,
             Self::DpadUp => state.dpad_up,
             Self::DpadDown => state.dpad_down,
             Self::DpadLeft => state.dpad_left,
@@ -82,16 +170,202 @@ pub enum MappingTarget {
     XboxRT,         // Right Trigger
     XboxLS,         // Left Stick (Analog)
     XboxRS,         // Right Stick (Analog)
-    Keyboard(u16),  // Virtual Key Code (VK_*)
+    XboxDpad { threshold: f32 }, // Stick only: D-pad directions while pushed past `threshold` along that axis
+    XboxLSDir(StickDirection),   // Pushes the left stick all the way while held (D-pad as a stick)
+    XboxRSDir(StickDirection),
+    Keyboard(u16),  // Virtual Key Code (VK_*), injected as its scancode
+    KeyboardVia { vk: u16, via: KeyInjection }, // Same key, other injection (see KeyInjection)
+    Key { code: String, #[serde(default)] via: KeyInjection }, // Physical key ("KeyW"), follows the active layout (keys.rs)
     Mouse(u8),      // 0: Left, 1: Right, 2: Middle
     MouseMove { x_speed: f32, y_speed: f32 },
+    GyroMouse { sensitivity: f32 }, // While the source is held, turning the controller moves the mouse (pixels per degree)
+    GyroRecenter,                   // Resets gyro aiming on press and holds it still while held, digital sources only
     MouseScroll { speed: f32 },
+    CircularScroll { speed: f32 }, // Touchpad only: circling the center scrolls, clockwise = down
+    PinchZoom { speed: f32 },      // Touchpad only: two-finger pinch sends Ctrl+wheel
+    VJoyAxis(VJoyAxis),            // Trigger travel (or full when pressed) on one vJoy axis (vjoy.rs)
+    VJoyStick { x: VJoyAxis, y: VJoyAxis }, // Stick on two vJoy axes
+    VJoyButton(u8),                // vJoy button 1-128
+    Macro(Vec<MacroStep>),         // Plays the steps once per press (digital sources only)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StickDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl StickDirection {
+    /// Full deflection, y pointing down as the sticks report it
+    pub fn vector(self) -> (f32, f32) {
+        match self {
+            Self::Up => (0.0, -1.0),
+            Self::Down => (0.0, 1.0),
+            Self::Left => (-1.0, 0.0),
+            Self::Right => (1.0, 0.0),
+        }
+    }
+}
+
+/// One step of a macro: press or release `target`, then wait `delay_ms` before the next step
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MacroStep {
+    pub target: MappingTarget, // Xbox button/trigger, key, mouse button or vJoy button
+    pub press: bool,
+    #[serde(default)]
+    pub delay_ms: u32,
+}
+
+/// How a key target reaches the system. DirectInput-era games read scancodes only, while some
+/// launchers and overlays only look at virtual-key codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyInjection {
+    #[default]
+    Scancode, // Scancode only (plain Keyboard targets)
+    Vk,       // Virtual-key code only
+    Both,     // Virtual-key code with the scancode filled in
+}
+
+/// What injects keyboard/mouse targets (per profile)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputBackend {
+    #[default]
+    SendInput,
+    Interception, // Interception driver (interception.rs), for games that ignore injected input
+}
+
+/// Where a stick counts as centered (per stick). Radial keeps diagonals smooth, axial drops each
+/// axis on its own so a mostly-horizontal push stays exactly horizontal (platformers, fighters).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeadzoneShape {
+    #[default]
+    Radial,
+    Axial, // Cross: each axis has its own deadzone
+    Square,
+}
+
+/// Holds `target` while the left stick stays near full deflection (sprint without clicking L3)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AutoSprint {
+    pub target: MappingTarget,
+    pub hold_ms: u32,   // Deflection has to last this long
+    pub threshold: f32, // Stick magnitude, 0.0-1.0
+}
+
+/// Output response of a virtual trigger. Travel below `min` reads as released and above `max` as
+/// fully pressed (worn triggers that stop short, earlier bite point); `curve` shapes what's in
+/// between (1.0 = linear, below 1.0 ramps up early, above 1.0 late). Control `points`, when set,
+/// replace `curve`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TriggerCurve {
+    pub min: f32,
+    pub max: f32,
+    pub curve: f32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub points: Vec<[f32; 2]>,
+}
+
+impl Default for TriggerCurve {
+    fn default() -> Self {
+        Self { min: 0.0, max: 1.0, curve: 1.0, points: Vec::new() }
+    }
+}
+
+impl TriggerCurve {
+    pub fn apply(&self, v: f32) -> f32 {
+        if self.max <= self.min {
+            return if v > self.min { 1.0 } else { 0.0 };
+        }
+        let t = ((v - self.min) / (self.max - self.min)).clamp(0.0, 1.0);
+        if self.points.is_empty() {
+            t.powf(self.curve.max(0.1))
+        } else {
+            interpolate(&self.points, t)
+        }
+    }
+}
+
+/// Stick response, applied to the distance from center after the deadzone: `Exponential` raises it
+/// to a power (above 1.0 = finer aim near center), `Custom` maps it through [input, output] control
+/// points.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseCurve {
+    #[default]
+    Linear,
+    Exponential(f32),
+    Custom(Vec<[f32; 2]>),
+}
+
+impl ResponseCurve {
+    pub fn apply(&self, v: f32) -> f32 {
+        let v = v.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => v,
+            Self::Exponential(e) => v.powf(e.max(0.1)),
+            Self::Custom(points) => interpolate(points, v),
+        }
+    }
+}
+
+/// Straight lines between control points, anchored at (0, 0) and (1, 1). Points must be sorted by input.
+fn interpolate(points: &[[f32; 2]], v: f32) -> f32 {
+    let mut prev = [0.0, 0.0];
+    for &p in points.iter().chain(std::iter::once(&[1.0, 1.0])) {
+        if v <= p[0] {
+            let span = p[0] - prev[0];
+            return if span <= 0.0 { p[1] } else { prev[1] + (p[1] - prev[1]) * (v - prev[0]) / span };
+        }
+        prev = p;
+    }
+    prev[1]
+}
+
+/// Control points clamped to 0-1 and sorted by input, as `interpolate` expects
+pub fn sanitize_curve_points(points: &mut [[f32; 2]]) {
+    for p in points.iter_mut() {
+        *p = p.map(|v| if v.is_finite() { v.clamp(0.0, 1.0) } else { 0.0 });
+    }
+    points.sort_by(|a, b| a[0].total_cmp(&b[0]));
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ButtonMapping {
     pub source: PhysicalButton,
     pub targets: Vec<MappingTarget>,
+    // Shift layer: used instead of `targets` while the profile's shift button is held (empty = no change)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shift_targets: Vec<MappingTarget>,
+    // Turbo: while held, the targets pulse on and off this many times a second (digital sources only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turbo_hz: Option<f32>,
+}
+
+pub const MAX_TURBO_HZ: f32 = 30.0;
+
+impl ButtonMapping {
+    pub fn new(source: PhysicalButton, targets: Vec<MappingTarget>) -> Self {
+        Self { source, targets, shift_targets: Vec::new(), turbo_hz: None }
+    }
+
+    /// Targets in effect: the shift layer's while shifted, unless this button has none there
+    pub fn active_targets(&self, shifted: bool) -> &[MappingTarget] {
+        if shifted && !self.shift_targets.is_empty() { &self.shift_targets } else { &self.targets }
+    }
+}
+
+impl GamepadState {
+    /// Left stick and L3 trade places with the right stick and R3
+    pub fn sticks_swapped(mut self) -> Self {
+        (self.left_x, self.left_y, self.right_x, self.right_y) = (self.right_x, self.right_y, self.left_x, self.left_y);
+        (self.btn_l3, self.btn_r3) = (self.btn_r3, self.btn_l3);
+        self
+    }
 }
 
 impl Default for GamepadState {
@@ -105,9 +379,16 @@ impl Default for GamepadState {
             dpad_up: false, dpad_down: false, dpad_left: false, dpad_right: false,
             btn_touchpad: false,
             btn_mute: false,
+            btn_fn_l: false, btn_fn_r: false, btn_paddle_l: false, btn_paddle_r: false,
             touch_x: 0, touch_y: 0, touch_active: false,
+            touch2_x: 0, touch2_y: 0, touch2_active: false,
             battery: 0,
             is_charging: false,
+            charge_state: ChargeState::Discharging,
+            headphones: false,
+            mic: false,
+            gyro_x: 0.0, gyro_y: 0.0, gyro_z: 0.0,
+            accel_x: 0.0, accel_y: 0.0, accel_z: 0.0,
         }
     }
 }
@@ -120,6 +401,33 @@ pub fn normalize_trigger(val: u8) -> f32 {
     val as f32 / 255.0
 }
 
+// Raw gyro counts per deg/s (±2000 deg/s over the i16 range), DS4 and DualSense alike. The
+// per-unit calibration in feature report 0x05 only moves this by a few percent.
+const GYRO_COUNTS_PER_DEG: f32 = 16.384;
+
+// Raw accelerometer counts per g (±4 g over the i16 range)
+const ACCEL_COUNTS_PER_G: f32 = 8192.0;
+
+/// Three little-endian i16 divided by `scale`
+fn decode_motion(b: &[u8], scale: f32) -> (f32, f32, f32) {
+    let axis = |i: usize| i16::from_le_bytes([b[i * 2], b[i * 2 + 1]]) as f32 / scale;
+    (axis(0), axis(1), axis(2))
+}
+
+/// The 12-byte IMU block (gyro pitch/yaw/roll, then accel x/y/z), same layout on DS4 and DualSense
+fn decode_imu(state: &mut GamepadState, b: &[u8]) {
+    (state.gyro_x, state.gyro_y, state.gyro_z) = decode_motion(&b[0..6], GYRO_COUNTS_PER_DEG);
+    (state.accel_x, state.accel_y, state.accel_z) = decode_motion(&b[6..12], ACCEL_COUNTS_PER_G);
+}
+
+/// Upper half of the third button byte, only set on a DualSense Edge (0 on the regular one)
+fn decode_edge_buttons(state: &mut GamepadState, b3: u8) {
+    state.btn_fn_l = (b3 & 0x10) != 0;
+    state.btn_fn_r = (b3 & 0x20) != 0;
+    state.btn_paddle_l = (b3 & 0x40) != 0;
+    state.btn_paddle_r = (b3 & 0x80) != 0;
+}
+
 // DualSense Parsing
 pub fn parse_dualsense(report: &[u8], is_bt: bool) -> Option<GamepadState> {
     let report_id = report[0];
@@ -150,6 +458,7 @@ fn parse_dualsense_simple(report: &[u8]) -> GamepadState {
     
     // Simple Report Layout (Standard HID)
     // 1: LX, 2: LY, 3: RX, 4: RY
+    // 5-7: Buttons, 8: L2 (Z), 9: R2 (Rz)
     state.left_x = normalize_axis(report[1]);
     state.left_y = normalize_axis(report[2]);
     state.right_x = normalize_axis(report[3]);
@@ -181,12 +490,17 @@ fn parse_dualsense_simple(report: &[u8]) -> GamepadState {
     let misc = report[6];
     state.btn_l1 = (misc & 0x01) != 0;
     state.btn_r1 = (misc & 0x02) != 0;
-    // L2/R2 are digital in simple mode often, or mapped to Z/Rz axes later.
-    // Assuming digital bits 2 and 3 for now as fallback.
-    let l2_dig = (misc & 0x04) != 0;
-    let r2_dig = (misc & 0x08) != 0;
-    state.l2 = if l2_dig { 1.0 } else { 0.0 };
-    state.r2 = if r2_dig { 1.0 } else { 0.0 };
+    // L2/R2 analog are the Z/Rz axes at bytes 8/9. The digital bits 2 and 3 are
+    // only used as fallback for truncated reports.
+    if report.len() > 9 {
+        state.l2 = normalize_trigger(report[8]);
+        state.r2 = normalize_trigger(report[9]);
+    } else {
+        let l2_dig = (misc & 0x04) != 0;
+        let r2_dig = (misc & 0x08) != 0;
+        state.l2 = if l2_dig { 1.0 } else { 0.0 };
+        state.r2 = if r2_dig { 1.0 } else { 0.0 };
+    }
     
     state.btn_share = (misc & 0x10) != 0;
     state.btn_options = (misc & 0x20) != 0;
@@ -260,12 +574,20 @@ fn parse_dualsense_usb(report: &[u8]) -> GamepadState {
     state.btn_ps = (b3 & 0x01) != 0;
     state.btn_touchpad = (b3 & 0x02) != 0;
     state.btn_mute = (b3 & 0x04) != 0;
+    decode_edge_buttons(&mut state, b3);
+
+    // Gyro and accelerometer after 4 reserved bytes (the BT report has them one byte later)
+    if report.len() >= 28 {
+        decode_imu(&mut state, &report[16..28]);
+    }
 
-    // Battery for DualSense USB is typically at index 53 (offset 52 if report[0] is ID)
+    // Battery status for DualSense USB is at index 53 (offset 52 if report[0] is ID)
     if report.len() >= 54 {
-        let b_val = report[53];
-        state.battery = ((b_val & 0x0F) * 10).min(100);
-        state.is_charging = (b_val & 0x10) != 0;
+        (state.battery, state.charge_state) = decode_dualsense_battery(report[53]);
+        state.is_charging = matches!(state.charge_state, ChargeState::Charging | ChargeState::Full);
+    }
+    if report.len() >= 55 {
+        (state.headphones, state.mic) = decode_dualsense_jack(report[54]);
     }
 
     state
@@ -286,7 +608,7 @@ fn parse_dualsense_bt(data: &[u8]) -> GamepadState {
     // 8: Unknown/Padding
     // 9: Buttons 1 (Square, Cross, Circle, Triangle, DPad)
     // 10: Buttons 2 (L1, R1, L2_dig, R2_dig, Create, Options, L3, R3)
-    // 11: Buttons 3 (PS, Mute, Touch)
+    // 11: Buttons 3 (PS, Mute, Touch, Edge Fn/paddles)
 
     if data.len() < 14 { return state; }
 
@@ -336,54 +658,54 @@ fn parse_dualsense_bt(data: &[u8]) -> GamepadState {
     }
     state.btn_mute = (data[11] & 0x04) != 0;
     state.btn_touchpad = (data[11] & 0x02) != 0;
+    decode_edge_buttons(&mut state, data[11]);
+
+    if data.len() >= 29 {
+        decode_imu(&mut state, &data[17..29]);
+    }
 
     // Touchpad Data (DualSense BT Report 0x31)
-    // Starts at byte 33.
+    // Starts at byte 33 (packet counter), then one 4-byte block per finger
     if data.len() >= 38 {
-        // Byte 33: Touch Packet Count (usually)
-        // Byte 34: Touch 1 ID & Active Flag. (Bit 7: 0 = Active, 1 = Inactive)
-        let t1_info = data[34];
-        let t1_active = (t1_info & 0x80) == 0;
-        
-        if t1_active {
+        if let Some((x, y)) = decode_touch_point(&data[34..38]) {
             state.touch_active = true;
-            // Byte 35: X Low
-            // Byte 36: X High (0-3) | Y Low (4-7)
-            // Byte 37: Y High
-            let x_lo = data[35] as u16;
-            let mid = data[36] as u16;
-            let y_hi = data[37] as u16;
-            
-            // X: 12 bits
-            let x_hi = mid & 0x0F;
-            state.touch_x = (x_hi << 8) | x_lo;
-            
-            // Y: 12 bits
-            let y_lo = (mid & 0xF0) >> 4;
-            state.touch_y = (y_hi << 4) | y_lo;
+            state.touch_x = x;
+            state.touch_y = y;
+        }
+    }
+    if data.len() >= 42 {
+        if let Some((x, y)) = decode_touch_point(&data[38..42]) {
+            state.touch2_active = true;
+            state.touch2_x = x;
+            state.touch2_y = y;
         }
     }
 
-    // Battery DualSense BT
+    // Battery DualSense BT (same status byte as USB, shifted by one).
+    // Byte 55 is the headset/USB plug state, not charging.
+    if data.len() >= 55 {
+        (state.battery, state.charge_state) = decode_dualsense_battery(data[54]);
+        state.is_charging = matches!(state.charge_state, ChargeState::Charging | ChargeState::Full);
+    }
     if data.len() >= 56 {
-        let b_info = data[54];
-        let b_level = b_info & 0x0F;
-        state.battery = (b_level * 10).min(100);
-        
-        // In BT mode 0x31, charging status is usually found:
-        // 1. In the upper nibble of byte 54 (0x1 - charging, 0x2 - full)
-        // 2. In the lower nibble of byte 55 (0x1 - charging, 0x2 - full)
-        let b_status = (b_info & 0xF0) >> 4;
-        let power_status = data[55] & 0x0F;
-        
-        state.is_charging = b_status == 0x01 || b_status == 0x02 || 
-                            power_status == 0x01 || power_status == 0x02;
+        (state.headphones, state.mic) = decode_dualsense_jack(data[55]);
     }
 
     state
 }
 
 // DS4 Parsing
+/// One finger of a touch packet, None when lifted.
+/// Byte 0: ID & active flag (bit 7: 0 = active), 1: X low, 2: X high (0-3) | Y low (4-7), 3: Y high.
+fn decode_touch_point(b: &[u8]) -> Option<(u16, u16)> {
+    if b[0] & 0x80 != 0 {
+        return None;
+    }
+    let x = ((b[2] as u16 & 0x0F) << 8) | b[1] as u16;
+    let y = ((b[3] as u16) << 4) | (b[2] as u16 >> 4);
+    Some((x, y))
+}
+
 pub fn parse_ds4(report: &[u8]) -> Option<GamepadState> {
     let report_id = report[0];
 
@@ -452,12 +774,52 @@ fn parse_ds_common(data: &[u8]) -> GamepadState {
         state.r2 = normalize_trigger(data[8]);
     }
 
-    // Battery for DS4 USB (data[11])
-    if data.len() >= 12 {
-        let b_val = data[11];
-        state.battery = ((b_val & 0x0F) * 10).min(100);
-        state.is_charging = (b_val & 0x10) != 0;
+    // Gyro and accelerometer follow the timestamp and temperature bytes
+    if data.len() >= 24 {
+        decode_imu(&mut state, &data[12..24]);
+    }
+
+    // Battery status (data[29], same for USB and BT). data[11] is the temperature byte.
+    if data.len() >= 30 {
+        (state.battery, state.charge_state) = decode_ds4_battery(data[29]);
+        state.is_charging = matches!(state.charge_state, ChargeState::Charging | ChargeState::Full);
     }
 
     state
 }
+
+// Battery decoding follows Sony's firmware as documented by the Linux hid-playstation driver.
+// Levels are tenths, reported +5% the way the PS4/PS5 UI rounds them.
+
+/// DualSense: level 0-10 in the low nibble, high nibble 0 = discharging, 1 = charging, 2 = full,
+/// 0xA/0xB = voltage/temperature error, 0xF = charging error.
+fn decode_dualsense_battery(status: u8) -> (u8, ChargeState) {
+    let level = ((status & 0x0F) * 10 + 5).min(100);
+    match status >> 4 {
+        0x0 => (level, ChargeState::Discharging),
+        0x1 => (level, ChargeState::Charging),
+        0x2 => (100, ChargeState::Full),
+        _ => (0, ChargeState::Error),
+    }
+}
+
+/// DualSense plug state (the byte after the battery status): 0x01 = headphones, 0x02 = mic,
+/// 0x04 = mic muted, 0x08 = USB data, 0x10 = USB power. Returns (headphones, mic).
+fn decode_dualsense_jack(status: u8) -> (bool, bool) {
+    (status & 0x01 != 0, status & 0x02 != 0)
+}
+
+/// DS4: level in the low nibble, bit 0x10 = cable. On battery the level is 0-9; on cable 0-10
+/// where 10 = full and 11 = charging error.
+fn decode_ds4_battery(status: u8) -> (u8, ChargeState) {
+    let level = status & 0x0F;
+    if status & 0x10 == 0 {
+        ((level * 10 + 5).min(100), ChargeState::Discharging)
+    } else if level < 10 {
+        (level * 10 + 5, ChargeState::Charging)
+    } else if level == 10 {
+        (100, ChargeState::Full)
+    } else {
+        (0, ChargeState::Error)
+    }
+}
@@ -0,0 +1,181 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use rumqttc::{Client, Event, LastWill, MqttOptions, Packet, QoS};
+use log::{info, warn};
+
+use crate::state::SharedState;
+use crate::config::MqttConfig;
+use crate::settings;
+
+// --- MQTT Publishing ---
+// Publishes retained battery/charging/connection topics under <topic>/ and announces them via
+// Home Assistant MQTT discovery, so dashboards can alert when the pad needs charging.
+//
+//   <topic>/availability  online | offline (last will)
+//   <topic>/battery       0-100
+//   <topic>/charging      ON | OFF
+//   <topic>/connected     ON | OFF
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const DISCOVERY_PREFIX: &str = "homeassistant";
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, PartialEq)]
+struct Snapshot {
+    battery: u8,
+    charging: bool,
+    connected: bool,
+}
+
+struct Session {
+    client: Client,
+    config: MqttConfig,
+    // Set by the event-loop thread on every (re)connect so retained state gets republished
+    connected: Arc<AtomicBool>,
+    alive: Arc<AtomicBool>,
+}
+
+fn on_off(v: bool) -> &'static str {
+    if v { "ON" } else { "OFF" }
+}
+
+fn connect(config: &MqttConfig) -> Session {
+    let availability = format!("{}/availability", config.topic);
+    let mut opts = MqttOptions::new(format!("{}-{}", crate::config::APP_NAME.to_lowercase(), std::process::id()), config.host.clone(), config.port);
+    opts.set_keep_alive(Duration::from_secs(30));
+    opts.set_last_will(LastWill::new(availability, "offline", QoS::AtLeastOnce, true));
+    if !config.username.is_empty() {
+        opts.set_credentials(config.username.clone(), config.password.clone());
+    }
+
+    let (client, mut connection) = Client::new(opts, 32);
+    let connected = Arc::new(AtomicBool::new(false));
+    let alive = Arc::new(AtomicBool::new(true));
+
+    let (connected_c, alive_c) = (connected.clone(), alive.clone());
+    let host = format!("{}:{}", config.host, config.port);
+    thread::spawn(move || {
+        let mut reported_error = false;
+        // Iterating drives the connection; it reconnects by itself after errors
+        for notification in connection.iter() {
+            if !alive_c.load(Ordering::SeqCst) {
+                break;
+            }
+            match notification {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    info!("MQTT connected to {}", host);
+                    reported_error = false;
+                    connected_c.store(true, Ordering::SeqCst);
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    if !reported_error {
+                        warn!("MQTT connection to {} failed: {}", host, e);
+                        reported_error = true;
+                    }
+                    thread::sleep(Duration::from_secs(5));
+                }
+            }
+        }
+    });
+
+    Session { client, config: config.clone(), connected, alive }
+}
+
+fn publish_discovery(session: &Session) {
+    let topic = &session.config.topic;
+    let device = serde_json::json!({
+        "identifiers": [topic],
+        "name": crate::config::APP_NAME,
+        "model": "DualSense / DualShock 4",
+    });
+    let entities = [
+        ("sensor", "battery", serde_json::json!({ "device_class": "battery", "unit_of_measurement": "%", "state_class": "measurement" })),
+        ("binary_sensor", "charging", serde_json::json!({ "device_class": "battery_charging" })),
+        ("binary_sensor", "connected", serde_json::json!({ "device_class": "connectivity" })),
+    ];
+    for (component, name, mut payload) in entities {
+        if let Some(obj) = payload.as_object_mut() {
+            obj.insert("name".into(), format!("Controller {}", name).into());
+            obj.insert("unique_id".into(), format!("{}_{}", topic, name).into());
+            obj.insert("state_topic".into(), format!("{}/{}", topic, name).into());
+            obj.insert("availability_topic".into(), format!("{}/availability", topic).into());
+            obj.insert("device".into(), device.clone());
+        }
+        let config_topic = format!("{}/{}/{}_{}/config", DISCOVERY_PREFIX, component, topic, name);
+        let _ = session.client.try_publish(config_topic, QoS::AtLeastOnce, true, payload.to_string());
+    }
+}
+
+fn publish_state(session: &Session, snap: &Snapshot) -> bool {
+    let topic = &session.config.topic;
+    let battery = if snap.connected { snap.battery.to_string() } else { String::new() };
+    [
+        (format!("{}/availability", topic), "online".to_string()),
+        (format!("{}/battery", topic), battery),
+        (format!("{}/charging", topic), on_off(snap.connected && snap.charging).to_string()),
+        (format!("{}/connected", topic), on_off(snap.connected).to_string()),
+    ]
+    .into_iter()
+    .all(|(t, payload)| session.client.try_publish(t, QoS::AtLeastOnce, true, payload).is_ok())
+}
+
+fn close(session: Session) {
+    let _ = session.client.try_publish(format!("{}/availability", session.config.topic), QoS::AtLeastOnce, true, "offline");
+    let _ = session.client.try_disconnect();
+    session.alive.store(false, Ordering::SeqCst);
+}
+
+/// Spawns the publisher unless it's already running. It stops once disabled or on exit,
+/// and reconnects when the broker settings change.
+pub fn start(state: Arc<Mutex<SharedState>>) {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    thread::spawn(move || {
+        let mut session: Option<Session> = None;
+        let mut last_sent: Option<Snapshot> = None;
+
+        loop {
+            let (config, exit, snap) = {
+                let s = state.lock().unwrap();
+                let snap = Snapshot {
+                    battery: s.gamepad.battery,
+                    charging: s.gamepad.is_charging,
+                    connected: s.device_name != "None",
+                };
+                (settings::read().mqtt.clone(), s.should_exit, snap)
+            };
+
+            if let Some(sess) = session.take() {
+                if config.enabled && !exit && sess.config == config {
+                    session = Some(sess);
+                } else {
+                    close(sess);
+                    last_sent = None;
+                }
+            }
+            if !config.enabled || exit {
+                break;
+            }
+
+            let sess = session.get_or_insert_with(|| connect(&config));
+            if sess.connected.swap(false, Ordering::SeqCst) {
+                publish_discovery(sess);
+                last_sent = None;
+            }
+            if last_sent.as_ref() != Some(&snap) && publish_state(sess, &snap) {
+                last_sent = Some(snap);
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        RUNNING.store(false, Ordering::SeqCst);
+        info!("MQTT publisher stopped.");
+    });
+}
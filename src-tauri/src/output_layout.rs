@@ -0,0 +1,62 @@
+use serde::Serialize;
+
+// --- Known DualSense Output Report Offsets ---
+// Names for the bytes of output report 0x02 (USB) and 0x31 (BT) that we (or hid-playstation / the
+// community docs) know the meaning of. The debug panel uses this to annotate hex dumps. BT is the
+// USB layout shifted by one (byte 1 is the sequence/tag byte) with a CRC32 at the end.
+
+#[derive(Serialize)]
+pub struct OutputField {
+    pub offset: usize,
+    pub len: usize,
+    pub name: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct OutputLayout {
+    pub report_id: u8,
+    pub name: &'static str,
+    pub fields: Vec<OutputField>,
+}
+
+// (offset, len, name) in the USB report, report ID at 0
+const USB_FIELDS: &[(usize, usize, &str)] = &[
+    (1, 1, "Valid Flags 0 (rumble/triggers)"),
+    (2, 1, "Valid Flags 1 (mic LED/lightbar/player LEDs)"),
+    (3, 1, "Rumble Right (high freq)"),
+    (4, 1, "Rumble Left (low freq)"),
+    (5, 1, "Headphone Volume"),
+    (6, 1, "Speaker Volume"),
+    (7, 1, "Mic Volume"),
+    (8, 1, "Audio Control"),
+    (9, 1, "Mic Mute LED"),
+    (10, 1, "Power Save Control"),
+    (11, 11, "R2 Trigger Effect"),
+    (22, 11, "L2 Trigger Effect"),
+    (33, 6, "Reserved"),
+    (39, 1, "Valid Flags 2 (LED brightness/lightbar setup)"),
+    (40, 2, "Reserved"),
+    (42, 1, "Lightbar Setup"),
+    (43, 1, "Player LED Brightness"),
+    (44, 1, "Player LEDs"),
+    (45, 1, "Lightbar Red"),
+    (46, 1, "Lightbar Green"),
+    (47, 1, "Lightbar Blue"),
+];
+
+pub const BT_CRC_OFFSET: usize = 74;
+
+fn usb_fields(shift: usize) -> Vec<OutputField> {
+    USB_FIELDS.iter().map(|&(offset, len, name)| OutputField { offset: offset + shift, len, name }).collect()
+}
+
+pub fn layouts() -> Vec<OutputLayout> {
+    let mut bt = vec![OutputField { offset: 1, len: 1, name: "Sequence (high nibble) / Tag" }];
+    bt.extend(usb_fields(1));
+    bt.push(OutputField { offset: BT_CRC_OFFSET, len: 4, name: "CRC32" });
+
+    vec![
+        OutputLayout { report_id: 0x02, name: "DualSense USB Output", fields: usb_fields(0) },
+        OutputLayout { report_id: 0x31, name: "DualSense BT Output", fields: bt },
+    ]
+}
@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use serde::Serialize;
+use log::warn;
+use tungstenite::Message;
+
+use crate::state::{SharedState, Modes};
+use crate::mapping::{GamepadState, PhysicalButton};
+use crate::api::ApiStatus;
+use crate::settings;
+
+// --- Overlay Event Stream ---
+// ws://127.0.0.1:<api_port>/overlay, read-only, meant for OBS browser sources.
+// Every message is {"v": SCHEMA_VERSION, "event": <name>, "data": ..}:
+//   "status"  -> same object as GET /api/status (sent on connect and on change)
+//   "input"   -> full InputFrame whenever anything moved
+//   "button"  -> {"button": "cross", "pressed": true} on every edge
+//   "battery" -> {"level": 0-100, "charging": bool} on change
+//   "mode"    -> {"mode": "bypass", "active": true} whenever a mode (see state::Modes) turns on or off
+// Fields are only ever added; bump SCHEMA_VERSION if something is renamed or removed.
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+// Input is copied into SharedState every 32ms, polling twice as fast keeps latency at one frame
+const POLL_INTERVAL: Duration = Duration::from_millis(16);
+
+pub const BUTTONS: [(&str, PhysicalButton); 21] = [
+    ("cross", PhysicalButton::Cross),
+    ("circle", PhysicalButton::Circle),
+    ("square", PhysicalButton::Square),
+    ("triangle", PhysicalButton::Triangle),
+    ("l1", PhysicalButton::L1),
+    ("r1", PhysicalButton::R1),
+    ("l3", PhysicalButton::L3),
+    ("r3", PhysicalButton::R3),
+    ("options", PhysicalButton::Options),
+    ("share", PhysicalButton::Share),
+    ("ps", PhysicalButton::PS),
+    ("touchpad", PhysicalButton::Touchpad),
+    ("mute", PhysicalButton::Mute),
+    ("fn_left", PhysicalButton::FnLeft),
+    ("fn_right", PhysicalButton::FnRight),
+    ("paddle_left", PhysicalButton::PaddleLeft),
+    ("paddle_right", PhysicalButton::PaddleRight),
+    ("dpad_up", PhysicalButton::DpadUp),
+    ("dpad_down", PhysicalButton::DpadDown),
+    ("dpad_left", PhysicalButton::DpadLeft),
+    ("dpad_right", PhysicalButton::DpadRight),
+];
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+struct Stick {
+    x: f32, // -1.0 (left) .. 1.0 (right)
+    y: f32, // -1.0 .. 1.0, same sign as the controller report
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+struct Touch {
+    active: bool,
+    x: u16, // 0..1919
+    y: u16, // 0..1079
+}
+
+#[derive(Serialize, Clone, PartialEq)]
+struct InputFrame {
+    buttons: BTreeMap<&'static str, bool>,
+    left_stick: Stick,
+    right_stick: Stick,
+    l2: f32, // 0.0 .. 1.0
+    r2: f32,
+    touch: Touch,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+struct Battery {
+    level: u8,
+    charging: bool,
+}
+
+// Rounded so sensor noise below what an overlay can show doesn't flood the socket
+fn quantize(v: f32) -> f32 {
+    (v * 1000.0).round() / 1000.0
+}
+
+impl InputFrame {
+    fn from_gamepad(g: &GamepadState) -> Self {
+        Self {
+            buttons: BUTTONS.iter().map(|(name, btn)| (*name, btn.get_value(g))).collect(),
+            left_stick: Stick { x: quantize(g.left_x), y: quantize(g.left_y) },
+            right_stick: Stick { x: quantize(g.right_x), y: quantize(g.right_y) },
+            l2: quantize(g.l2),
+            r2: quantize(g.r2),
+            touch: Touch { active: g.touch_active, x: g.touch_x, y: g.touch_y },
+        }
+    }
+}
+
+fn event<T: Serialize>(name: &str, data: T) -> Message {
+    let body = serde_json::json!({ "v": SCHEMA_VERSION, "event": name, "data": data });
+    Message::Text(body.to_string())
+}
+
+pub fn handle_websocket(stream: TcpStream, state: Arc<Mutex<SharedState>>) {
+    // Read-only, so a page opened from disk (a common OBS browser source) may connect too
+    let mut ws = match crate::api::accept_local(stream, true) {
+        Ok(ws) => ws,
+        Err(e) => {
+            warn!("Overlay: WebSocket handshake failed: {}", e);
+            return;
+        }
+    };
+    let _ = ws.get_ref().set_read_timeout(Some(POLL_INTERVAL));
+
+    let mut last_status: Option<ApiStatus> = None;
+    let mut last_frame: Option<InputFrame> = None;
+    let mut last_battery: Option<Battery> = None;
+    let mut last_modes: Option<Modes> = None;
+
+    loop {
+        let (status, frame, battery, modes) = {
+            let s = state.lock().unwrap();
+            if s.should_exit || !settings::read().api_enabled {
+                break;
+            }
+            let battery = Battery { level: s.gamepad.battery, charging: s.gamepad.is_charging };
+            (ApiStatus::from_state(&s), InputFrame::from_gamepad(&s.gamepad), battery, Modes::from_state(&s))
+        };
+
+        let mut out = Vec::new();
+        if last_status.as_ref() != Some(&status) {
+            out.push(event("status", &status));
+            last_status = Some(status);
+        }
+        // Edges only; the initial state is in "status"
+        if let Some(prev) = last_modes.filter(|m| *m != modes) {
+            for ((name, active), (_, was)) in modes.list().into_iter().zip(prev.list()) {
+                if active != was {
+                    out.push(event("mode", serde_json::json!({ "mode": name, "active": active })));
+                }
+            }
+        }
+        last_modes = Some(modes);
+        if last_battery != Some(battery) {
+            out.push(event("battery", battery));
+            last_battery = Some(battery);
+        }
+        if last_frame.as_ref() != Some(&frame) {
+            if let Some(prev) = &last_frame {
+                for (name, pressed) in &frame.buttons {
+                    if prev.buttons.get(name) != Some(pressed) {
+                        out.push(event("button", serde_json::json!({ "button": name, "pressed": pressed })));
+                    }
+                }
+            }
+            out.push(event("input", &frame));
+            last_frame = Some(frame);
+        }
+
+        for msg in out {
+            if ws.send(msg).is_err() {
+                return;
+            }
+        }
+
+        // Read-only stream; reading just paces the loop and handles close/ping
+        match ws.read() {
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(ref e))
+                if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => {}
+            Err(_) => break,
+        }
+    }
+    let _ = ws.close(None);
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::{AppConfig, Profile};
+
+// --- Debounced Config Writes ---
+// Settings commands fire many times a second while a slider is dragged. They queue the latest
+// config.json / profile contents here and return; a background thread writes them once nothing new
+// has come in for DEBOUNCE. Anything that reads profiles back from disk flushes first, and so does
+// shutdown, so the files are never seen stale.
+
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+struct Pending {
+    config: Option<AppConfig>,
+    profiles: HashMap<String, Profile>,
+    last_change: Option<Instant>,
+}
+
+static PENDING: Mutex<Option<Pending>> = Mutex::new(None);
+static WAKE: Condvar = Condvar::new();
+// Held while writing, so a flush can't overtake the background thread with older contents
+static WRITING: Mutex<()> = Mutex::new(());
+
+pub fn start() {
+    thread::spawn(|| loop {
+        let pending = PENDING.lock().unwrap();
+        // Wait for something to be queued, then for it to stay quiet for DEBOUNCE
+        let Some(since) = pending.as_ref().and_then(|p| p.last_change) else {
+            drop(WAKE.wait(pending).unwrap());
+            continue;
+        };
+        let quiet = since.elapsed();
+        if quiet < DEBOUNCE {
+            drop(WAKE.wait_timeout(pending, DEBOUNCE - quiet).unwrap());
+            continue;
+        }
+        drop(pending);
+        flush();
+    });
+}
+
+fn queue(f: impl FnOnce(&mut Pending)) {
+    let mut pending = PENDING.lock().unwrap();
+    let p = pending.get_or_insert_with(|| Pending { config: None, profiles: HashMap::new(), last_change: None });
+    f(p);
+    p.last_change = Some(Instant::now());
+    WAKE.notify_one();
+}
+
+pub fn queue_config(config: AppConfig) {
+    queue(|p| p.config = Some(config));
+}
+
+pub fn queue_profile(name: &str, profile: Profile) {
+    queue(|p| {
+        p.profiles.insert(name.to_string(), profile);
+    });
+}
+
+/// Writes everything queued so far, now
+pub fn flush() {
+    let _writing = WRITING.lock().unwrap();
+    let Some(pending) = PENDING.lock().unwrap().take() else { return };
+    if let Some(config) = pending.config {
+        config.save();
+    }
+    for (name, profile) in pending.profiles {
+        AppConfig::save_profile(&name, &profile);
+    }
+}
@@ -0,0 +1,115 @@
+use std::ffi::{c_void, OsStr};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::os::windows::ffi::OsStrExt;
+use std::os::windows::io::FromRawHandle;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use log::{info, warn};
+
+use crate::state::SharedState;
+use crate::api;
+use crate::settings;
+
+// --- Named Pipe IPC ---
+// Same command set as the HTTP/WS API, as newline-delimited JSON over \\.\pipe\dx3.
+// Handy for AutoHotkey / launchers: open the pipe, write {"cmd":"status"}\n, read one line back.
+
+pub const PIPE_NAME: &str = r"\\.\pipe\dx3";
+
+const PIPE_ACCESS_DUPLEX: u32 = 0x00000003;
+const PIPE_TYPE_BYTE: u32 = 0x00000000;
+const PIPE_REJECT_REMOTE_CLIENTS: u32 = 0x00000008;
+const PIPE_UNLIMITED_INSTANCES: u32 = 255;
+const ERROR_PIPE_CONNECTED: i32 = 535;
+const BUFFER_SIZE: u32 = 4096;
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn CreateNamedPipeW(
+        name: *const u16, open_mode: u32, pipe_mode: u32, max_instances: u32,
+        out_buffer_size: u32, in_buffer_size: u32, default_timeout: u32, security_attributes: *const c_void,
+    ) -> *mut c_void;
+    fn ConnectNamedPipe(pipe: *mut c_void, overlapped: *mut c_void) -> i32;
+    fn CloseHandle(handle: *mut c_void) -> i32;
+}
+
+// ConnectNamedPipe blocks, so a stale server may still be waiting after the API was toggled off/on
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+pub fn start(state: Arc<Mutex<SharedState>>) {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    thread::spawn(move || {
+        let name: Vec<u16> = OsStr::new(PIPE_NAME).encode_wide().chain(std::iter::once(0)).collect();
+        info!("Named pipe server listening on {}", PIPE_NAME);
+
+        loop {
+            let handle = unsafe {
+                CreateNamedPipeW(
+                    name.as_ptr(),
+                    PIPE_ACCESS_DUPLEX,
+                    PIPE_TYPE_BYTE | PIPE_REJECT_REMOTE_CLIENTS,
+                    PIPE_UNLIMITED_INSTANCES,
+                    BUFFER_SIZE, BUFFER_SIZE, 0,
+                    std::ptr::null(),
+                )
+            };
+            if handle.is_null() || handle as isize == -1 {
+                warn!("Named pipe: CreateNamedPipeW failed: {}", std::io::Error::last_os_error());
+                break;
+            }
+
+            // Blocks until a client connects (ERROR_PIPE_CONNECTED = it connected before we asked)
+            let connected = unsafe { ConnectNamedPipe(handle, std::ptr::null_mut()) } != 0
+                || std::io::Error::last_os_error().raw_os_error() == Some(ERROR_PIPE_CONNECTED);
+
+            let enabled = {
+                settings::read().api_enabled && !state.lock().unwrap().should_exit
+            };
+            if !enabled {
+                unsafe { CloseHandle(handle); }
+                break;
+            }
+            if !connected {
+                unsafe { CloseHandle(handle); }
+                continue;
+            }
+
+            let pipe = unsafe { File::from_raw_handle(handle) };
+            let state = state.clone();
+            thread::spawn(move || handle_client(pipe, state));
+        }
+
+        RUNNING.store(false, Ordering::SeqCst);
+        info!("Named pipe server stopped.");
+    });
+}
+
+fn handle_client(pipe: File, state: Arc<Mutex<SharedState>>) {
+    let mut writer = match pipe.try_clone() {
+        Ok(w) => w,
+        Err(e) => {
+            warn!("Named pipe: failed to clone handle: {}", e);
+            return;
+        }
+    };
+
+    for line in BufReader::new(pipe).lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => break, // Client hung up
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let reply = api::execute_json(&state, &line);
+        if writeln!(writer, "{}", reply).and_then(|_| writer.flush()).is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,468 @@
+use std::ffi::{c_char, c_int, c_long, c_ulong, c_void, CString};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::atomic::Ordering;
+use log::{info, warn};
+
+use super::{PadBus, VirtualPad, PadKind, PadReport, PadFeedback, InputInjector, DeviceHider};
+use crate::keys::KeyStroke;
+
+pub mod elevated;
+pub mod pipe;
+pub mod power;
+
+// --- Linux: uinput, evdev ---
+// The controller is read through hidraw (hidapi's Linux backend). The virtual pad and the
+// keyboard/mouse output are uinput devices; the pad copies what the xpad driver exposes for a wired
+// Xbox 360 pad (same VID/PID, buttons and axes), or what hid-playstation exposes for a DS4, so SDL,
+// Steam and Proton treat it like the real thing. Hiding the controller grabs its evdev nodes, which keeps games and the desktop from
+// reading it there (a game opening the hidraw node itself still sees it).
+// Needs write access to /dev/uinput and read/write access to the controller's hidraw and event
+// nodes, see the udev rules in the README.
+
+const UINPUT_PATH: &str = "/dev/uinput";
+
+// linux/uinput.h, linux/input.h. _IOW(type, nr, size) = 1 << 30 | size << 16 | type << 8 | nr
+const UI_DEV_CREATE: c_ulong = 0x5501;
+const UI_DEV_DESTROY: c_ulong = 0x5502;
+const UI_DEV_SETUP: c_ulong = 0x405C_5503;
+const UI_ABS_SETUP: c_ulong = 0x401C_5504;
+const UI_SET_EVBIT: c_ulong = 0x4004_5564;
+const UI_SET_KEYBIT: c_ulong = 0x4004_5565;
+const UI_SET_RELBIT: c_ulong = 0x4004_5566;
+const UI_SET_ABSBIT: c_ulong = 0x4004_5567;
+const EVIOCGRAB: c_ulong = 0x4004_4590;
+
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const EV_REL: u16 = 0x02;
+const EV_ABS: u16 = 0x03;
+const SYN_REPORT: u16 = 0x00;
+const BUS_USB: u16 = 0x03;
+
+const REL_X: u16 = 0x00;
+const REL_Y: u16 = 0x01;
+const REL_WHEEL: u16 = 0x08;
+const BTN_LEFT: u16 = 0x110;
+const BTN_RIGHT: u16 = 0x111;
+const BTN_MIDDLE: u16 = 0x112;
+const KEY_F24: u16 = 194; // Highest key_code gives out
+
+const ABS_X: u16 = 0x00;
+const ABS_Y: u16 = 0x01;
+const ABS_Z: u16 = 0x02;
+const ABS_RX: u16 = 0x03;
+const ABS_RY: u16 = 0x04;
+const ABS_RZ: u16 = 0x05;
+const ABS_HAT0X: u16 = 0x10;
+const ABS_HAT0Y: u16 = 0x11;
+
+// XInput button bit → key code, as xpad reports them
+const PAD_BUTTONS: &[(u16, u16)] = &[
+    (0x1000, 0x130), // A: BTN_A
+    (0x2000, 0x131), // B: BTN_B
+    (0x4000, 0x133), // X: BTN_X
+    (0x8000, 0x134), // Y: BTN_Y
+    (0x0100, 0x136), // LB: BTN_TL
+    (0x0200, 0x137), // RB: BTN_TR
+    (0x0020, 0x13A), // Back: BTN_SELECT
+    (0x0010, 0x13B), // Start: BTN_START
+    (0x0400, 0x13C), // Guide: BTN_MODE
+    (0x0040, 0x13D), // LS: BTN_THUMBL
+    (0x0080, 0x13E), // RS: BTN_THUMBR
+];
+
+// (code, min, max, fuzz, flat), xpad's ranges
+const PAD_AXES: &[(u16, i32, i32, i32, i32)] = &[
+    (ABS_X, -32768, 32767, 16, 128),
+    (ABS_Y, -32768, 32767, 16, 128),
+    (ABS_RX, -32768, 32767, 16, 128),
+    (ABS_RY, -32768, 32767, 16, 128),
+    (ABS_Z, 0, 255, 0, 0),
+    (ABS_RZ, 0, 255, 0, 0),
+    (ABS_HAT0X, -1, 1, 0, 0),
+    (ABS_HAT0Y, -1, 1, 0, 0),
+];
+
+// The same for a DS4 as hid-playstation reports it. Square/Triangle are BTN_WEST/BTN_NORTH, the
+// opposite of xpad's X/Y codes; L2/R2 also come as buttons.
+const DS4_BUTTONS: &[(u16, u16)] = &[
+    (0x1000, 0x130), // A: Cross, BTN_SOUTH
+    (0x2000, 0x131), // B: Circle, BTN_EAST
+    (0x4000, 0x134), // X: Square, BTN_WEST
+    (0x8000, 0x133), // Y: Triangle, BTN_NORTH
+    (0x0100, 0x136), // LB: L1, BTN_TL
+    (0x0200, 0x137), // RB: R1, BTN_TR
+    (0x0020, 0x13A), // Back: Share, BTN_SELECT
+    (0x0010, 0x13B), // Start: Options, BTN_START
+    (0x0400, 0x13C), // Guide: PS, BTN_MODE
+    (0x0040, 0x13D), // LS: L3, BTN_THUMBL
+    (0x0080, 0x13E), // RS: R3, BTN_THUMBR
+];
+const BTN_TL2: u16 = 0x138;
+const BTN_TR2: u16 = 0x139;
+
+const DS4_AXES: &[(u16, i32, i32, i32, i32)] = &[
+    (ABS_X, 0, 255, 0, 0),
+    (ABS_Y, 0, 255, 0, 0),
+    (ABS_RX, 0, 255, 0, 0),
+    (ABS_RY, 0, 255, 0, 0),
+    (ABS_Z, 0, 255, 0, 0),
+    (ABS_RZ, 0, 255, 0, 0),
+    (ABS_HAT0X, -1, 1, 0, 0),
+    (ABS_HAT0Y, -1, 1, 0, 0),
+];
+
+const RTLD_NOW: c_int = 0x2;
+
+extern "C" {
+    fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+}
+
+#[link(name = "dl")]
+extern "C" {
+    fn dlopen(filename: *const c_char, flags: c_int) -> *mut c_void;
+    fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    fn dlclose(handle: *mut c_void) -> c_int;
+}
+
+#[repr(C)]
+struct UinputSetup {
+    bustype: u16,
+    vendor: u16,
+    product: u16,
+    version: u16,
+    name: [u8; 80],
+    ff_effects_max: u32,
+}
+
+#[repr(C)]
+struct AbsSetup {
+    code: u16,
+    value: i32,
+    minimum: i32,
+    maximum: i32,
+    fuzz: i32,
+    flat: i32,
+    resolution: i32,
+}
+
+// struct input_event; the kernel stamps the time itself
+#[repr(C)]
+struct InputEvent {
+    time: [c_long; 2],
+    kind: u16,
+    code: u16,
+    value: i32,
+}
+
+fn check(ret: c_int, what: &str) -> Result<(), String> {
+    if ret < 0 {
+        return Err(format!("{} failed: {}", what, std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+/// Creates a uinput device, it disappears again when the file is closed
+fn create_device(name: &str, vendor: u16, product: u16, keys: &[u16], rels: &[u16], axes: &[(u16, i32, i32, i32, i32)]) -> Result<File, String> {
+    let file = OpenOptions::new().write(true).open(UINPUT_PATH)
+        .map_err(|e| format!("Can't open {}: {} (see the udev rules in the README)", UINPUT_PATH, e))?;
+    let fd = file.as_raw_fd();
+
+    let mut setup = UinputSetup { bustype: BUS_USB, vendor, product, version: 0x0110, name: [0; 80], ff_effects_max: 0 };
+    let len = name.len().min(setup.name.len() - 1);
+    setup.name[..len].copy_from_slice(&name.as_bytes()[..len]);
+
+    unsafe {
+        if !keys.is_empty() {
+            check(ioctl(fd, UI_SET_EVBIT, EV_KEY as c_int), "UI_SET_EVBIT")?;
+            for &key in keys {
+                check(ioctl(fd, UI_SET_KEYBIT, key as c_int), "UI_SET_KEYBIT")?;
+            }
+        }
+        if !rels.is_empty() {
+            check(ioctl(fd, UI_SET_EVBIT, EV_REL as c_int), "UI_SET_EVBIT")?;
+            for &rel in rels {
+                check(ioctl(fd, UI_SET_RELBIT, rel as c_int), "UI_SET_RELBIT")?;
+            }
+        }
+        if !axes.is_empty() {
+            check(ioctl(fd, UI_SET_EVBIT, EV_ABS as c_int), "UI_SET_EVBIT")?;
+        }
+        for &(code, minimum, maximum, fuzz, flat) in axes {
+            check(ioctl(fd, UI_SET_ABSBIT, code as c_int), "UI_SET_ABSBIT")?;
+            let abs = AbsSetup { code, value: 0, minimum, maximum, fuzz, flat, resolution: 0 };
+            check(ioctl(fd, UI_ABS_SETUP, &abs as *const AbsSetup), "UI_ABS_SETUP")?;
+        }
+        check(ioctl(fd, UI_DEV_SETUP, &setup as *const UinputSetup), "UI_DEV_SETUP")?;
+        check(ioctl(fd, UI_DEV_CREATE), "UI_DEV_CREATE")?;
+    }
+    Ok(file)
+}
+
+/// Writes `events` (type, code, value) followed by a SYN_REPORT
+fn emit(mut file: &File, events: &[(u16, u16, i32)]) -> Result<(), String> {
+    let mut buf = Vec::with_capacity((events.len() + 1) * std::mem::size_of::<InputEvent>());
+    for &(kind, code, value) in events.iter().chain(&[(EV_SYN, SYN_REPORT, 0)]) {
+        let event = InputEvent { time: [0; 2], kind, code, value };
+        let bytes = unsafe { std::slice::from_raw_parts(&event as *const InputEvent as *const u8, std::mem::size_of::<InputEvent>()) };
+        buf.extend_from_slice(bytes);
+    }
+    file.write_all(&buf).map_err(|e| e.to_string())
+}
+
+/// uinput as the pad bus and as keyboard/mouse output
+pub struct Uinput;
+
+pub struct UinputPad {
+    kind: PadKind,
+    device: Option<File>,
+}
+
+impl PadBus for Uinput {
+    type Pad = UinputPad;
+
+    fn connect() -> Result<Self, String> {
+        OpenOptions::new().write(true).open(UINPUT_PATH)
+            .map(|_| Self)
+            .map_err(|e| format!("Can't open {}: {} (see the udev rules in the README)", UINPUT_PATH, e))
+    }
+
+    fn is_installed() -> bool {
+        Path::new(UINPUT_PATH).exists()
+    }
+
+    /// uinput is part of the kernel, so this is the kernel release
+    fn driver_version() -> Option<String> {
+        fs::read_to_string("/proc/sys/kernel/osrelease").ok().map(|v| v.trim().to_string())
+    }
+
+    fn create_pad(&self, kind: PadKind) -> UinputPad {
+        UinputPad { kind, device: None }
+    }
+}
+
+impl VirtualPad for UinputPad {
+    fn plugin(&mut self) -> Result<(), String> {
+        self.device = Some(match self.kind {
+            PadKind::Xbox360 => {
+                let keys: Vec<u16> = PAD_BUTTONS.iter().map(|&(_, key)| key).collect();
+                create_device("Microsoft X-Box 360 pad", 0x045E, 0x028E, &keys, &[], PAD_AXES)?
+            }
+            PadKind::Ds4 => {
+                let keys: Vec<u16> = DS4_BUTTONS.iter().map(|&(_, key)| key).chain([BTN_TL2, BTN_TR2]).collect();
+                create_device("Sony Interactive Entertainment Wireless Controller", 0x054C, 0x09CC, &keys, &[], DS4_AXES)?
+            }
+        });
+        Ok(())
+    }
+
+    /// Sends the whole state, the input core drops values that didn't change
+    fn update(&mut self, report: &PadReport) -> Result<(), String> {
+        let Some(device) = &self.device else { return Err("Virtual pad isn't plugged in".to_string()) };
+        let b = report.buttons;
+        let pressed = |bit: u16| (b & bit != 0) as i32;
+        let buttons = if self.kind == PadKind::Ds4 { DS4_BUTTONS } else { PAD_BUTTONS };
+        let mut events: Vec<(u16, u16, i32)> = buttons.iter().map(|&(bit, key)| (EV_KEY, key, pressed(bit))).collect();
+        // evdev Y axes point down; xpad flips them the same way
+        let sticks = match self.kind {
+            PadKind::Xbox360 => [report.thumb_lx as i32, !report.thumb_ly as i32, report.thumb_rx as i32, !report.thumb_ry as i32],
+            PadKind::Ds4 => {
+                events.push((EV_KEY, BTN_TL2, (report.left_trigger > 0) as i32));
+                events.push((EV_KEY, BTN_TR2, (report.right_trigger > 0) as i32));
+                let axis = |v: i16| (v as i32 + 0x8000) >> 8;
+                [axis(report.thumb_lx), 255 - axis(report.thumb_ly), axis(report.thumb_rx), 255 - axis(report.thumb_ry)]
+            }
+        };
+        events.extend([
+            (EV_ABS, ABS_X, sticks[0]),
+            (EV_ABS, ABS_Y, sticks[1]),
+            (EV_ABS, ABS_RX, sticks[2]),
+            (EV_ABS, ABS_RY, sticks[3]),
+            (EV_ABS, ABS_Z, report.left_trigger as i32),
+            (EV_ABS, ABS_RZ, report.right_trigger as i32),
+            (EV_ABS, ABS_HAT0X, pressed(0x0008) - pressed(0x0004)),
+            (EV_ABS, ABS_HAT0Y, pressed(0x0002) - pressed(0x0001)),
+        ]);
+        emit(device, &events)
+    }
+
+    fn unplug(&mut self) -> Result<(), String> {
+        if let Some(device) = self.device.take() {
+            check(unsafe { ioctl(device.as_raw_fd(), UI_DEV_DESTROY) }, "UI_DEV_DESTROY")?;
+        }
+        Ok(())
+    }
+
+    /// uinput pads don't get told a player number, and rumble would need EV_FF effect uploads
+    /// handled on the device, so the feedback stays at zero
+    fn watch_feedback(&mut self, feedback: &Arc<PadFeedback>) {
+        feedback.player_slot.store(0, Ordering::Relaxed);
+    }
+}
+
+// Keyboard/mouse device, created on first use
+static KBM_DEVICE: OnceLock<Option<File>> = OnceLock::new();
+
+fn send_events(events: &[(u16, u16, i32)]) {
+    let device = KBM_DEVICE.get_or_init(|| {
+        let mut keys: Vec<u16> = (1..=KEY_F24).collect();
+        keys.extend([BTN_LEFT, BTN_RIGHT, BTN_MIDDLE]);
+        match create_device("DX3 Keyboard/Mouse", 0, 0, &keys, &[REL_X, REL_Y, REL_WHEEL], &[]) {
+            Ok(d) => {
+                info!("uinput keyboard/mouse device created");
+                Some(d)
+            }
+            Err(e) => {
+                warn!("Keyboard/mouse mappings disabled: {}", e);
+                None
+            }
+        }
+    });
+    if let Some(device) = device {
+        let _ = emit(device, events);
+    }
+}
+
+/// Set 1 scancode (keys.rs) → evdev key code. The two agree up to F12.
+fn key_code(scan: u16) -> Option<u16> {
+    let code = match scan {
+        0x01..=0x58 => scan,
+        0x64..=0x6E => scan - 0x64 + 183, // F13-F23
+        0x76 => KEY_F24,
+        0x73 => 89,                       // KEY_RO
+        0x7D => 124,                      // KEY_YEN
+        0xE045 => 69,                     // NumLock
+        0xE01C => 96,                     // KEY_KPENTER
+        0xE01D => 97,                     // KEY_RIGHTCTRL
+        0xE035 => 98,                     // KEY_KPSLASH
+        0xE037 => 99,                     // KEY_SYSRQ
+        0xE038 => 100,                    // KEY_RIGHTALT
+        0xE047 => 102,                    // KEY_HOME
+        0xE048 => 103,                    // KEY_UP
+        0xE049 => 104,                    // KEY_PAGEUP
+        0xE04B => 105,                    // KEY_LEFT
+        0xE04D => 106,                    // KEY_RIGHT
+        0xE04F => 107,                    // KEY_END
+        0xE050 => 108,                    // KEY_DOWN
+        0xE051 => 109,                    // KEY_PAGEDOWN
+        0xE052 => 110,                    // KEY_INSERT
+        0xE053 => 111,                    // KEY_DELETE
+        0xE05B => 125,                    // KEY_LEFTMETA
+        0xE05C => 126,                    // KEY_RIGHTMETA
+        0xE05D => 127,                    // KEY_COMPOSE
+        _ => return None,
+    };
+    Some(code)
+}
+
+impl InputInjector for Uinput {
+    /// evdev only has physical keys, so `via` doesn't matter here
+    fn send_key(key: &KeyStroke, down: bool) {
+        if let Some(code) = key_code(key.scan) {
+            send_events(&[(EV_KEY, code, down as i32)]);
+        }
+    }
+
+    fn send_mouse_button(btn: u8, down: bool) {
+        let code = match btn {
+            0 => BTN_LEFT,
+            1 => BTN_MIDDLE,
+            2 => BTN_RIGHT,
+            _ => return,
+        };
+        send_events(&[(EV_KEY, code, down as i32)]);
+    }
+
+    fn send_mouse_move(dx: i32, dy: i32) {
+        send_events(&[(EV_REL, REL_X, dx), (EV_REL, REL_Y, dy)]);
+    }
+
+    fn send_wheel(notches: i32) {
+        send_events(&[(EV_REL, REL_WHEEL, notches)]);
+    }
+}
+
+/// Exclusive grabs on the controller's event nodes; device ids are hidraw paths (/dev/hidraw3)
+pub struct EvdevGrab;
+
+// Dropping the files releases the grabs
+static GRABS: Mutex<Vec<(String, Vec<File>)>> = Mutex::new(Vec::new());
+
+/// /dev/input/eventN nodes the kernel driver made for the same HID device
+fn event_nodes(hidraw: &str) -> Vec<String> {
+    let Some(name) = Path::new(hidraw).file_name().and_then(|n| n.to_str()) else { return Vec::new() };
+    let inputs = Path::new("/sys/class/hidraw").join(name).join("device/input");
+    let Ok(inputs) = fs::read_dir(inputs) else { return Vec::new() };
+    inputs.flatten()
+        .filter_map(|input| fs::read_dir(input.path()).ok())
+        .flat_map(|entries| entries.flatten())
+        .filter_map(|e| e.file_name().to_str().filter(|n| n.starts_with("event")).map(|n| format!("/dev/input/{}", n)))
+        .collect()
+}
+
+impl DeviceHider for EvdevGrab {
+    fn is_installed() -> bool {
+        true
+    }
+
+    fn whitelist_self() -> Result<(), String> {
+        Ok(()) // The hidraw node isn't grabbed, so we keep reading it
+    }
+
+    fn device_id(path: &str) -> Option<String> {
+        path.starts_with("/dev/hidraw").then(|| path.to_string())
+    }
+
+    fn hide(id: &str) -> Result<(), String> {
+        let mut grabs = GRABS.lock().unwrap();
+        if grabs.iter().any(|(grabbed, _)| grabbed == id) {
+            return Ok(());
+        }
+        let mut files = Vec::new();
+        for node in event_nodes(id) {
+            let file = File::open(&node).map_err(|e| format!("Can't open {}: {}", node, e))?;
+            check(unsafe { ioctl(file.as_raw_fd(), EVIOCGRAB, 1 as c_int) }, "EVIOCGRAB")?;
+            files.push(file);
+        }
+        if files.is_empty() {
+            return Err(format!("No event devices for {}", id));
+        }
+        info!("Grabbed {} event device(s) of {}", files.len(), id);
+        grabs.push((id.to_string(), files));
+        Ok(())
+    }
+
+    fn unhide(id: &str) -> Result<(), String> {
+        GRABS.lock().unwrap().retain(|(grabbed, _)| grabbed != id);
+        Ok(())
+    }
+}
+
+/// Same as the Windows loader. vJoy and Interception ship no Linux libraries, so for them this
+/// ends in their "not installed" errors.
+pub unsafe fn load_library(name: &str) -> *mut c_void {
+    match CString::new(name) {
+        Ok(name) => dlopen(name.as_ptr(), RTLD_NOW),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// `name` is NUL-terminated
+pub unsafe fn get_proc_address(module: *mut c_void, name: *const u8) -> *mut c_void {
+    dlsym(module, name as *const c_char)
+}
+
+pub unsafe fn free_library(module: *mut c_void) {
+    dlclose(module);
+}
+
+/// Opens the file manager on a folder, or on a file's folder
+pub fn show_in_folder(path: &str) {
+    let path = Path::new(path);
+    let dir = if path.is_dir() { path } else { path.parent().unwrap_or(path) };
+    let _ = std::process::Command::new("xdg-open").arg(dir).spawn();
+}
@@ -0,0 +1,15 @@
+// --- Elevated Autostart ---
+// The elevated logon task (elevated.rs on Windows) works around UIPI, which Linux doesn't have: uinput
+// output reaches every window. Autostart covers launching at login.
+
+pub fn is_registered() -> bool {
+    false
+}
+
+pub fn register() -> anyhow::Result<()> {
+    Err(anyhow::anyhow!("Elevated autostart is Windows-only, use autostart instead"))
+}
+
+pub fn unregister() -> anyhow::Result<()> {
+    Ok(())
+}
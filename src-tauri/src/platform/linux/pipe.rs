@@ -0,0 +1,89 @@
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use log::{info, warn};
+
+use crate::state::SharedState;
+use crate::api;
+use crate::settings;
+
+// --- Unix Socket IPC ---
+// Linux counterpart of the \\.\pipe\dx3 named pipe: same newline-delimited JSON commands over
+// $XDG_RUNTIME_DIR/dx3.sock, e.g. `echo '{"cmd":"status"}' | socat - UNIX-CONNECT:$XDG_RUNTIME_DIR/dx3.sock`.
+
+const SOCKET_NAME: &str = "dx3.sock";
+
+// accept() blocks, so a stale server may still be waiting after the API was toggled off/on
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+fn socket_path() -> PathBuf {
+    std::env::var("XDG_RUNTIME_DIR").map(PathBuf::from).unwrap_or_else(|_| std::env::temp_dir()).join(SOCKET_NAME)
+}
+
+pub fn start(state: Arc<Mutex<SharedState>>) {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    thread::spawn(move || {
+        let path = socket_path();
+        let _ = std::fs::remove_file(&path); // Left over from a previous run
+        let listener = match UnixListener::bind(&path) {
+            Ok(l) => l,
+            Err(e) => {
+                warn!("Unix socket: failed to bind {}: {}", path.display(), e);
+                RUNNING.store(false, Ordering::SeqCst);
+                return;
+            }
+        };
+        info!("Unix socket server listening on {}", path.display());
+
+        for stream in listener.incoming() {
+            let enabled = {
+                settings::read().api_enabled && !state.lock().unwrap().should_exit
+            };
+            if !enabled {
+                break;
+            }
+            match stream {
+                Ok(stream) => {
+                    let state = state.clone();
+                    thread::spawn(move || handle_client(stream, state));
+                }
+                Err(e) => warn!("Unix socket: accept failed: {}", e),
+            }
+        }
+
+        let _ = std::fs::remove_file(&path);
+        RUNNING.store(false, Ordering::SeqCst);
+        info!("Unix socket server stopped.");
+    });
+}
+
+fn handle_client(stream: UnixStream, state: Arc<Mutex<SharedState>>) {
+    let mut writer = match stream.try_clone() {
+        Ok(w) => w,
+        Err(e) => {
+            warn!("Unix socket: failed to clone stream: {}", e);
+            return;
+        }
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => break, // Client hung up
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let reply = api::execute_json(&state, &line);
+        if writeln!(writer, "{}", reply).and_then(|_| writer.flush()).is_err() {
+            break;
+        }
+    }
+}
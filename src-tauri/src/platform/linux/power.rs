@@ -0,0 +1,9 @@
+use std::sync::{Arc, Mutex};
+
+use crate::state::SharedState;
+
+// --- Sleep / Session Lock ---
+// Not wired up on Linux (it would be logind's PrepareForSleep over D-Bus). A controller handle that
+// died over suspend fails its next read and the worker reconnects; uinput devices survive.
+
+pub fn start(_state: Arc<Mutex<SharedState>>) {}
@@ -0,0 +1,108 @@
+use std::sync::Arc;
+use std::sync::atomic::AtomicU8;
+use serde::{Serialize, Deserialize};
+
+use crate::keys::KeyStroke;
+
+// --- Platform Layer ---
+// What the worker needs from the OS besides reading the controller (hidapi covers that on both):
+// a bus that plugs in virtual Xbox 360 pads, keyboard/mouse injection, and a way to hide the
+// physical controller so games don't see it twice. Windows uses ViGEmBus, SendInput (or
+// Interception) and HidHide; Linux uses uinput for the pad and keyboard/mouse and evdev grabs for
+// hiding. Parsing, mapping and profiles sit above this and don't know which one they run on.
+
+#[cfg(windows)]
+mod windows;
+#[cfg(windows)]
+pub use self::windows::{ViGEm as Bus, SendInput as Input, HidHide as Hider, show_in_folder, load_library, get_proc_address, free_library};
+
+#[cfg(not(windows))]
+pub mod linux;
+#[cfg(not(windows))]
+pub use self::linux::{Uinput as Bus, Uinput as Input, EvdevGrab as Hider, show_in_folder, load_library, get_proc_address, free_library};
+
+/// What `Bus` plugs in
+pub type Pad = <Bus as PadBus>::Pad;
+
+/// Which controller the virtual pad pretends to be (per profile). Mappings always target the
+/// XInput layout; a DS4 pad gets A/B/X/Y as Cross/Circle/Square/Triangle, Back/Start as
+/// Share/Options and Guide as PS, so games with native DS4 support show PlayStation glyphs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PadKind {
+    #[default]
+    Xbox360,
+    Ds4,
+}
+
+impl PadKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Xbox360 => "Xbox 360",
+            Self::Ds4 => "DualShock 4",
+        }
+    }
+}
+
+/// One XInput state (XINPUT_GAMEPAD). `buttons` uses the XInput bits on every platform and for
+/// every `PadKind`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PadReport {
+    pub buttons: u16,
+    pub left_trigger: u8,
+    pub right_trigger: u8,
+    pub thumb_lx: i16, // Y up, like XInput
+    pub thumb_ly: i16,
+    pub thumb_rx: i16,
+    pub thumb_ry: i16,
+}
+
+/// What the host sends back to a virtual pad
+#[derive(Debug, Default)]
+pub struct PadFeedback {
+    pub player_slot: AtomicU8, // 1-4 as the host numbered the pad, 0 while it's unknown
+    pub large_motor: AtomicU8, // Rumble strength 0-255: left, low-frequency motor
+    pub small_motor: AtomicU8, // Right, high-frequency motor
+}
+
+/// Where virtual pads come from
+pub trait PadBus: Sized {
+    type Pad: VirtualPad;
+
+    fn connect() -> Result<Self, String>;
+    /// Whether the driver is there at all, without connecting (diagnostics)
+    fn is_installed() -> bool;
+    fn driver_version() -> Option<String>;
+    /// A virtual pad of `kind`, not plugged in yet
+    fn create_pad(&self, kind: PadKind) -> Self::Pad;
+}
+
+pub trait VirtualPad {
+    /// Plugs the pad in and waits until games can see it
+    fn plugin(&mut self) -> Result<(), String>;
+    fn update(&mut self, report: &PadReport) -> Result<(), String>;
+    fn unplug(&mut self) -> Result<(), String>;
+    /// Keeps `feedback` up to date with what the host sends the pad from now on
+    fn watch_feedback(&mut self, feedback: &Arc<PadFeedback>);
+}
+
+/// Synthesized keyboard and mouse input
+pub trait InputInjector {
+    fn send_key(key: &KeyStroke, down: bool);
+    /// 0: Left, 1: Middle, 2: Right
+    fn send_mouse_button(btn: u8, down: bool);
+    fn send_mouse_move(dx: i32, dy: i32);
+    /// Positive notches scroll up
+    fn send_wheel(notches: i32);
+}
+
+/// Keeps other apps from seeing the physical controller next to the virtual pad
+pub trait DeviceHider {
+    fn is_installed() -> bool;
+    /// Lets this process keep reading devices it hides
+    fn whitelist_self() -> Result<(), String>;
+    /// What `hide`/`unhide` take for the controller at `path` (a hidapi path)
+    fn device_id(path: &str) -> Option<String>;
+    fn hide(id: &str) -> Result<(), String>;
+    fn unhide(id: &str) -> Result<(), String>;
+}
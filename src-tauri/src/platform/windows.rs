@@ -0,0 +1,343 @@
+use std::ffi::{c_void, OsStr};
+use std::os::windows::ffi::OsStrExt;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use log::warn;
+use vigem_client::{Client, TargetId, XButtons, XGamepad, Xbox360Wired, DualShock4Wired, DS4Report};
+
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    SendInput as Win32SendInput, INPUT, INPUT_0, KEYBDINPUT, MOUSEINPUT, KEYBD_EVENT_FLAGS,
+    VIRTUAL_KEY, KEYEVENTF_KEYUP, KEYEVENTF_SCANCODE, KEYEVENTF_EXTENDEDKEY,
+    MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP,
+    MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP,
+    MOUSEEVENTF_MIDDLEDOWN, MOUSEEVENTF_MIDDLEUP,
+    MOUSEEVENTF_MOVE, MOUSEEVENTF_WHEEL, MOUSE_EVENT_FLAGS,
+    INPUT_KEYBOARD, INPUT_MOUSE
+};
+
+use super::{PadBus, VirtualPad, PadKind, PadReport, PadFeedback, InputInjector, DeviceHider};
+use crate::keys::KeyStroke;
+use crate::mapping::KeyInjection;
+use crate::{hidhide, interception};
+
+// --- Windows: ViGEmBus, SendInput, HidHide ---
+
+const DRIVER_PATH: &str = r"C:\Windows\System32\drivers\ViGEmBus.sys";
+const CLIENT_DLL_PATH: &str = r"C:\Windows\System32\vigemclient.dll";
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn LoadLibraryW(name: *const u16) -> *mut c_void;
+    fn GetProcAddress(module: *mut c_void, name: *const u8) -> *mut c_void;
+    fn FreeLibrary(module: *mut c_void) -> i32;
+}
+
+#[link(name = "version")]
+extern "system" {
+    fn GetFileVersionInfoSizeW(filename: *const u16, handle: *mut u32) -> u32;
+    fn GetFileVersionInfoW(filename: *const u16, handle: u32, len: u32, data: *mut c_void) -> i32;
+    fn VerQueryValueW(block: *const c_void, sub_block: *const u16, buffer: *mut *mut c_void, len: *mut u32) -> i32;
+}
+
+pub struct ViGEm {
+    client: Client,
+}
+
+pub enum ViGEmPad {
+    Xbox(Xbox360Wired<Client>),
+    Ds4(DualShock4Wired<Client>),
+}
+
+// XInput button bit → DS4 button bit (DS4_BUTTONS); the d-pad goes into the hat nibble instead
+const DS4_BUTTONS: &[(u16, u16)] = &[
+    (0x1000, 1 << 5),  // A: Cross
+    (0x2000, 1 << 6),  // B: Circle
+    (0x4000, 1 << 4),  // X: Square
+    (0x8000, 1 << 7),  // Y: Triangle
+    (0x0100, 1 << 8),  // LB: L1
+    (0x0200, 1 << 9),  // RB: R1
+    (0x0020, 1 << 12), // Back: Share
+    (0x0010, 1 << 13), // Start: Options
+    (0x0040, 1 << 14), // LS: L3
+    (0x0080, 1 << 15), // RS: R3
+];
+const DS4_L2: u16 = 1 << 10;
+const DS4_R2: u16 = 1 << 11;
+const DS4_SPECIAL_PS: u8 = 0x01;
+
+fn ds4_report(report: &PadReport) -> DS4Report {
+    let b = report.buttons;
+    let (up, down, left, right) = (b & 0x0001 != 0, b & 0x0002 != 0, b & 0x0004 != 0, b & 0x0008 != 0);
+    // Hat: 0 = N, clockwise to 7 = NW, 8 = released
+    let hat = match (up, right, down, left) {
+        (true, true, _, _) => 1,
+        (_, true, true, _) => 3,
+        (_, _, true, true) => 5,
+        (true, _, _, true) => 7,
+        (true, _, _, _) => 0,
+        (_, true, _, _) => 2,
+        (_, _, true, _) => 4,
+        (_, _, _, true) => 6,
+        _ => 8,
+    };
+    let mut buttons = DS4_BUTTONS.iter().filter(|&&(x, _)| b & x != 0).fold(hat, |acc, &(_, d)| acc | d);
+    if report.left_trigger > 0 {
+        buttons |= DS4_L2;
+    }
+    if report.right_trigger > 0 {
+        buttons |= DS4_R2;
+    }
+    // 0-255 with the center at 128; DS4 Y axes point down
+    let axis = |v: i16| ((v as i32 + 0x8000) >> 8) as u8;
+    DS4Report {
+        thumb_lx: axis(report.thumb_lx),
+        thumb_ly: 255 - axis(report.thumb_ly),
+        thumb_rx: axis(report.thumb_rx),
+        thumb_ry: 255 - axis(report.thumb_ry),
+        buttons,
+        special: if b & 0x0400 != 0 { DS4_SPECIAL_PS } else { 0 },
+        trigger_l: report.left_trigger,
+        trigger_r: report.right_trigger,
+    }
+}
+
+impl PadBus for ViGEm {
+    type Pad = ViGEmPad;
+
+    fn connect() -> Result<Self, String> {
+        Client::connect().map(|client| Self { client }).map_err(|e| e.to_string())
+    }
+
+    fn is_installed() -> bool {
+        Path::new(DRIVER_PATH).exists() || Path::new(CLIENT_DLL_PATH).exists()
+    }
+
+    /// File version of ViGEmBus.sys as "a.b.c.d"
+    fn driver_version() -> Option<String> {
+        let path: Vec<u16> = OsStr::new(DRIVER_PATH).encode_wide().chain(Some(0)).collect();
+        let root: Vec<u16> = OsStr::new("\\").encode_wide().chain(Some(0)).collect();
+        unsafe {
+            let size = GetFileVersionInfoSizeW(path.as_ptr(), std::ptr::null_mut());
+            if size == 0 {
+                return None;
+            }
+            let mut data = vec![0u8; size as usize];
+            if GetFileVersionInfoW(path.as_ptr(), 0, size, data.as_mut_ptr() as *mut c_void) == 0 {
+                return None;
+            }
+            let mut info: *mut c_void = std::ptr::null_mut();
+            let mut len = 0u32;
+            // VS_FIXEDFILEINFO: signature, struct version, then the file version as MS/LS dwords
+            if VerQueryValueW(data.as_ptr() as *const c_void, root.as_ptr(), &mut info, &mut len) == 0 || info.is_null() || len < 16 {
+                return None;
+            }
+            let fixed = info as *const u32;
+            let (ms, ls) = (*fixed.add(2), *fixed.add(3));
+            Some(format!("{}.{}.{}.{}", ms >> 16, ms & 0xFFFF, ls >> 16, ls & 0xFFFF))
+        }
+    }
+
+    fn create_pad(&self, kind: PadKind) -> ViGEmPad {
+        let client = self.client.try_clone().unwrap();
+        match kind {
+            PadKind::Xbox360 => ViGEmPad::Xbox(Xbox360Wired::new(client, TargetId::XBOX360_WIRED)),
+            PadKind::Ds4 => ViGEmPad::Ds4(DualShock4Wired::new(client, TargetId::DUALSHOCK4_WIRED)),
+        }
+    }
+}
+
+impl VirtualPad for ViGEmPad {
+    fn plugin(&mut self) -> Result<(), String> {
+        match self {
+            Self::Xbox(pad) => {
+                pad.plugin().map_err(|e| e.to_string())?;
+                let _ = pad.wait_ready();
+            }
+            Self::Ds4(pad) => {
+                pad.plugin().map_err(|e| e.to_string())?;
+                let _ = pad.wait_ready();
+            }
+        }
+        Ok(())
+    }
+
+    fn update(&mut self, report: &PadReport) -> Result<(), String> {
+        match self {
+            Self::Xbox(pad) => {
+                let gamepad = XGamepad {
+                    buttons: XButtons(report.buttons),
+                    left_trigger: report.left_trigger,
+                    right_trigger: report.right_trigger,
+                    thumb_lx: report.thumb_lx,
+                    thumb_ly: report.thumb_ly,
+                    thumb_rx: report.thumb_rx,
+                    thumb_ry: report.thumb_ry,
+                };
+                pad.update(&gamepad).map_err(|e| e.to_string())
+            }
+            Self::Ds4(pad) => pad.update(&ds4_report(report)).map_err(|e| e.to_string()),
+        }
+    }
+
+    fn unplug(&mut self) -> Result<(), String> {
+        match self {
+            Self::Xbox(pad) => pad.unplug().map_err(|e| e.to_string()),
+            Self::Ds4(pad) => pad.unplug().map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Follows the notifications ViGEm sends for an Xbox pad: the LED (the slot XInput gave it) and
+    /// the rumble the game asks for. The notification thread ends by itself once the pad is
+    /// unplugged. vigem-client has no DS4 notifications, so a DS4 pad leaves the feedback at zero.
+    fn watch_feedback(&mut self, feedback: &Arc<PadFeedback>) {
+        feedback.player_slot.store(0, Ordering::Relaxed);
+        let Self::Xbox(pad) = self else { return };
+        match pad.request_notification() {
+            Ok(request) => {
+                let feedback = feedback.clone();
+                request.spawn_thread(move |_, n| {
+                    feedback.player_slot.store(n.led_number.min(3) + 1, Ordering::Relaxed);
+                    feedback.large_motor.store(n.large_motor, Ordering::Relaxed);
+                    feedback.small_motor.store(n.small_motor, Ordering::Relaxed);
+                });
+            }
+            Err(e) => warn!("No player slot or rumble notifications from ViGEm: {}", e),
+        }
+    }
+}
+
+/// SendInput, or Interception while a profile has it enabled (interception.rs)
+pub struct SendInput;
+
+fn send_input(input: INPUT) {
+    unsafe { Win32SendInput(&[input], std::mem::size_of::<INPUT>() as i32); }
+}
+
+fn mouse_input(dx: i32, dy: i32, data: u32, flags: MOUSE_EVENT_FLAGS) -> INPUT {
+    INPUT {
+        r#type: INPUT_MOUSE,
+        Anonymous: INPUT_0 {
+            mi: MOUSEINPUT {
+                dx,
+                dy,
+                mouseData: data,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            }
+        }
+    }
+}
+
+impl InputInjector for SendInput {
+    fn send_key(key: &KeyStroke, down: bool) {
+        // Interception only knows scancodes, so VK-only keys always use SendInput
+        if key.via != KeyInjection::Vk && key.scan & 0xFF != 0 && interception::send_key(key.scan, down) {
+            return;
+        }
+        let scancode = match key.via {
+            KeyInjection::Vk => 0,
+            _ => key.scan & 0xFF,
+        };
+
+        let mut flags = if down { KEYBD_EVENT_FLAGS(0) } else { KEYEVENTF_KEYUP };
+        // Scancode mode falls back to the VK for keys without one
+        if scancode > 0 && key.via == KeyInjection::Scancode {
+            flags |= KEYEVENTF_SCANCODE;
+        }
+
+        if key.is_extended() {
+            flags |= KEYEVENTF_EXTENDEDKEY;
+        }
+
+        send_input(INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: VIRTUAL_KEY(key.vk),
+                    wScan: scancode,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: 0,
+                },
+            },
+        });
+    }
+
+    fn send_mouse_button(btn: u8, down: bool) {
+        if interception::send_mouse_button(btn, down) {
+            return;
+        }
+        let flags = match (btn, down) {
+            (0, true) => MOUSEEVENTF_LEFTDOWN,
+            (0, false) => MOUSEEVENTF_LEFTUP,
+            (1, true) => MOUSEEVENTF_MIDDLEDOWN,
+            (1, false) => MOUSEEVENTF_MIDDLEUP,
+            (2, true) => MOUSEEVENTF_RIGHTDOWN,
+            (2, false) => MOUSEEVENTF_RIGHTUP,
+            _ => return,
+        };
+        send_input(mouse_input(0, 0, 0, flags));
+    }
+
+    fn send_mouse_move(dx: i32, dy: i32) {
+        if interception::send_mouse_move(dx, dy) {
+            return;
+        }
+        send_input(mouse_input(dx, dy, 0, MOUSEEVENTF_MOVE));
+    }
+
+    fn send_wheel(notches: i32) {
+        if interception::send_wheel(notches) {
+            return;
+        }
+        send_input(mouse_input(0, 0, (notches * 120) as u32, MOUSEEVENTF_WHEEL));
+    }
+}
+
+/// hidhide.rs, device ids are instance ids (HID\VID_054C&PID_0CE6\...)
+pub struct HidHide;
+
+impl DeviceHider for HidHide {
+    fn is_installed() -> bool {
+        hidhide::is_installed()
+    }
+
+    fn whitelist_self() -> Result<(), String> {
+        hidhide::whitelist_self().map_err(|e| e.to_string())
+    }
+
+    fn device_id(path: &str) -> Option<String> {
+        hidhide::path_to_instance_id(path)
+    }
+
+    fn hide(id: &str) -> Result<(), String> {
+        hidhide::hide_device(id).map_err(|e| e.to_string())
+    }
+
+    fn unhide(id: &str) -> Result<(), String> {
+        hidhide::unhide_device(id).map_err(|e| e.to_string())
+    }
+}
+
+/// Driver DLLs loaded at runtime (vjoy.rs, interception.rs). Null if it isn't there.
+pub unsafe fn load_library(name: &str) -> *mut c_void {
+    let wide: Vec<u16> = OsStr::new(name).encode_wide().chain(Some(0)).collect();
+    LoadLibraryW(wide.as_ptr())
+}
+
+/// `name` is NUL-terminated
+pub unsafe fn get_proc_address(module: *mut c_void, name: *const u8) -> *mut c_void {
+    GetProcAddress(module, name)
+}
+
+pub unsafe fn free_library(module: *mut c_void) {
+    FreeLibrary(module);
+}
+
+/// Opens Explorer on a folder, or on a file's folder with the file selected
+pub fn show_in_folder(path: &str) {
+    let arg = if Path::new(path).is_dir() { path.to_string() } else { format!("/select,{}", path) };
+    let _ = std::process::Command::new("explorer").arg(arg).spawn();
+}
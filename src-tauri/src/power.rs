@@ -0,0 +1,171 @@
+use std::ffi::{c_void, OsStr};
+use std::os::windows::ffi::OsStrExt;
+use std::ptr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use log::{info, warn};
+
+use crate::state::{SharedState, Status};
+
+// --- Sleep / Session Lock ---
+// Resuming from sleep used to leave the worker stuck on dead HID and ViGEm handles until a manual
+// driver refresh. A hidden window listens for power and session events: on sleep or lock
+// SharedState::system_suspended makes the worker release held keys, unplug the virtual pad and
+// wait; once the machine is awake and unlocked again it gets the same clean re-init as the
+// "Check" button (should_reinit).
+
+const WM_POWERBROADCAST: u32 = 0x0218;
+const WM_WTSSESSION_CHANGE: u32 = 0x02B1;
+const PBT_APMSUSPEND: usize = 0x4;
+const PBT_APMRESUMESUSPEND: usize = 0x7;
+const PBT_APMRESUMEAUTOMATIC: usize = 0x12;
+const WTS_SESSION_LOCK: usize = 0x7;
+const WTS_SESSION_UNLOCK: usize = 0x8;
+const NOTIFY_FOR_THIS_SESSION: u32 = 0;
+
+type WndProc = extern "system" fn(*mut c_void, u32, usize, isize) -> isize;
+
+#[repr(C)]
+struct WndClass {
+    style: u32,
+    wnd_proc: WndProc,
+    cls_extra: i32,
+    wnd_extra: i32,
+    instance: *mut c_void,
+    icon: *mut c_void,
+    cursor: *mut c_void,
+    background: *mut c_void,
+    menu_name: *const u16,
+    class_name: *const u16,
+}
+
+// MSG; only used as a buffer for the message loop
+#[repr(C)]
+struct Msg {
+    hwnd: *mut c_void,
+    message: u32,
+    wparam: usize,
+    lparam: isize,
+    time: u32,
+    pt: [i32; 2],
+    private: u32,
+}
+
+#[link(name = "user32")]
+extern "system" {
+    fn RegisterClassW(class: *const WndClass) -> u16;
+    fn CreateWindowExW(
+        ex_style: u32, class_name: *const u16, window_name: *const u16, style: u32,
+        x: i32, y: i32, width: i32, height: i32,
+        parent: *mut c_void, menu: *mut c_void, instance: *mut c_void, param: *mut c_void,
+    ) -> *mut c_void;
+    fn DefWindowProcW(hwnd: *mut c_void, msg: u32, wparam: usize, lparam: isize) -> isize;
+    fn GetMessageW(msg: *mut Msg, hwnd: *mut c_void, filter_min: u32, filter_max: u32) -> i32;
+    fn DispatchMessageW(msg: *const Msg) -> isize;
+}
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn GetModuleHandleW(name: *const u16) -> *mut c_void;
+}
+
+#[link(name = "wtsapi32")]
+extern "system" {
+    fn WTSRegisterSessionNotification(hwnd: *mut c_void, flags: u32) -> i32;
+}
+
+// The window procedure has no user pointer
+static STATE: OnceLock<Arc<Mutex<SharedState>>> = OnceLock::new();
+
+// Why we're suspended; both have to clear before resuming
+struct Reasons {
+    sleeping: bool,
+    locked: bool,
+}
+
+static REASONS: Mutex<Reasons> = Mutex::new(Reasons { sleeping: false, locked: false });
+
+fn update(f: impl FnOnce(&mut Reasons)) {
+    let Some(state) = STATE.get() else { return };
+    let mut reasons = REASONS.lock().unwrap();
+    f(&mut reasons);
+    let suspended = reasons.sleeping || reasons.locked;
+
+    let mut s = state.lock().unwrap();
+    if s.system_suspended == suspended {
+        return;
+    }
+    s.system_suspended = suspended;
+    if suspended {
+        info!("System {}: releasing the virtual pad", if reasons.sleeping { "going to sleep" } else { "locked" });
+        if !s.is_paused {
+            s.status = Status::Suspended;
+        }
+    } else {
+        // Handles from before the sleep are likely dead, start over like a manual driver refresh
+        info!("System resumed: re-initializing HID and ViGEm");
+        s.should_reinit = true;
+        if !s.is_paused {
+            s.status = Status::RefreshingDrivers;
+        }
+    }
+}
+
+extern "system" fn wnd_proc(hwnd: *mut c_void, msg: u32, wparam: usize, lparam: isize) -> isize {
+    match (msg, wparam) {
+        (WM_POWERBROADCAST, PBT_APMSUSPEND) => update(|r| r.sleeping = true),
+        // Automatic comes first; the user-triggered one follows if someone is at the machine
+        (WM_POWERBROADCAST, PBT_APMRESUMEAUTOMATIC | PBT_APMRESUMESUSPEND) => update(|r| r.sleeping = false),
+        (WM_WTSSESSION_CHANGE, WTS_SESSION_LOCK) => update(|r| r.locked = true),
+        (WM_WTSSESSION_CHANGE, WTS_SESSION_UNLOCK) => update(|r| r.locked = false),
+        _ => {}
+    }
+    unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+}
+
+/// Spawns the listener thread. Call once at startup.
+pub fn start(state: Arc<Mutex<SharedState>>) {
+    if STATE.set(state).is_err() {
+        return;
+    }
+
+    thread::spawn(|| unsafe {
+        let class_name: Vec<u16> = OsStr::new("DX3PowerListener").encode_wide().chain(Some(0)).collect();
+        let instance = GetModuleHandleW(ptr::null());
+        let class = WndClass {
+            style: 0,
+            wnd_proc,
+            cls_extra: 0,
+            wnd_extra: 0,
+            instance,
+            icon: ptr::null_mut(),
+            cursor: ptr::null_mut(),
+            background: ptr::null_mut(),
+            menu_name: ptr::null(),
+            class_name: class_name.as_ptr(),
+        };
+        if RegisterClassW(&class) == 0 {
+            warn!("Failed to register power event window class");
+            return;
+        }
+        // A hidden top-level window: message-only windows don't get power broadcasts
+        let hwnd = CreateWindowExW(
+            0, class_name.as_ptr(), ptr::null(), 0,
+            0, 0, 0, 0,
+            ptr::null_mut(), ptr::null_mut(), instance, ptr::null_mut(),
+        );
+        if hwnd.is_null() {
+            warn!("Failed to create power event window");
+            return;
+        }
+        if WTSRegisterSessionNotification(hwnd, NOTIFY_FOR_THIS_SESSION) == 0 {
+            warn!("Failed to register for session lock notifications");
+        }
+        info!("Sleep/lock handling started");
+
+        let mut msg = Msg { hwnd: ptr::null_mut(), message: 0, wparam: 0, lparam: 0, time: 0, pt: [0; 2], private: 0 };
+        while GetMessageW(&mut msg, ptr::null_mut(), 0, 0) > 0 {
+            DispatchMessageW(&msg);
+        }
+    });
+}
@@ -0,0 +1,101 @@
+use serde::{Serialize, Deserialize};
+
+// --- Quiet Hours ---
+// For late-night play: between `start` and `end` (local time) the lightbar is dimmed to
+// `led_brightness` (0-255), the player LEDs go dark and alert/wake-up rumble is skipped, whatever the
+// active profile says. The worker checks it on every periodic LED update, so the change shows
+// within a second of the boundary.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuietHours {
+    pub enabled: bool,
+    pub start: u16,          // Minutes after local midnight
+    pub end: u16,            // Before `start` = overnight
+    pub led_brightness: u8,  // Lightbar brightness, 0 = off
+    pub player_leds: bool,   // Keep the player LEDs lit
+}
+
+impl Default for QuietHours {
+    fn default() -> Self {
+        Self { enabled: false, start: 22 * 60, end: 7 * 60, led_brightness: 0, player_leds: false }
+    }
+}
+
+#[cfg(windows)]
+fn local_minute() -> u16 {
+    // SYSTEMTIME
+    #[repr(C)]
+    #[derive(Default)]
+    struct SystemTime {
+        year: u16,
+        month: u16,
+        day_of_week: u16,
+        day: u16,
+        hour: u16,
+        minute: u16,
+        second: u16,
+        milliseconds: u16,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetLocalTime(time: *mut SystemTime);
+    }
+
+    let mut t = SystemTime::default();
+    unsafe { GetLocalTime(&mut t) };
+    t.hour * 60 + t.minute
+}
+
+#[cfg(not(windows))]
+fn local_minute() -> u16 {
+    // struct tm, up to the fields we read (glibc adds tm_gmtoff/tm_zone after these)
+    #[repr(C)]
+    #[derive(Default)]
+    struct Tm {
+        sec: i32,
+        min: i32,
+        hour: i32,
+        fields: [i32; 6],
+        gmtoff: std::ffi::c_long,
+        zone: usize,
+    }
+
+    extern "C" {
+        fn time(t: *mut i64) -> i64;
+        fn localtime_r(t: *const i64, tm: *mut Tm) -> *mut Tm;
+    }
+
+    let mut tm = Tm::default();
+    unsafe {
+        let now = time(std::ptr::null_mut());
+        localtime_r(&now, &mut tm);
+    }
+    (tm.hour * 60 + tm.min) as u16
+}
+
+impl QuietHours {
+    pub fn is_active(&self) -> bool {
+        self.enabled && self.contains(local_minute())
+    }
+
+    fn contains(&self, minute: u16) -> bool {
+        if self.start <= self.end {
+            (self.start..self.end).contains(&minute)
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+
+    /// Lightbar color scaled by `led_brightness`
+    pub fn dim(&self, (r, g, b): (u8, u8, u8)) -> (u8, u8, u8) {
+        let f = self.led_brightness as f32 / 255.0;
+        ((r as f32 * f) as u8, (g as f32 * f) as u8, (b as f32 * f) as u8)
+    }
+
+    /// Player LED mask as shown during quiet hours
+    pub fn mask_player_leds(&self, mask: u8) -> u8 {
+        if self.player_leds { mask } else { 0 }
+    }
+}
@@ -0,0 +1,109 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use log::{info, warn};
+
+use crate::config::AppConfig;
+use crate::mapping::GamepadState;
+use crate::overlay::BUTTONS;
+
+// --- Input Recording ---
+// Opt-in recording of the parsed input (after layout/calibration, before mapping) to
+// %APPDATA%/DX3/recordings/<unix ms>.csv, for reviewing runs and reproducing mapping bugs. One row
+// per change, not per report, so an idle pad costs nothing:
+//   t_ms,buttons,lx,ly,rx,ry,l2,r2,touch_x,touch_y
+// t_ms counts from the start of the recording. `buttons` is a hex bitmask, bit N being the Nth
+// entry of the "# buttons:" line at the top of the file. Sticks are -1000..1000 (Y up), triggers
+// 0..1000, touch -1/-1 while no finger is down.
+
+#[derive(PartialEq)]
+struct Sample {
+    buttons: u32,
+    axes: [i16; 6],
+    touch: [i32; 2],
+}
+
+impl Sample {
+    fn from_state(g: &GamepadState) -> Self {
+        let buttons = BUTTONS.iter().enumerate().filter(|(_, (_, b))| b.get_value(g)).fold(0u32, |m, (i, _)| m | 1 << i);
+        let q = |v: f32| (v.clamp(-1.0, 1.0) * 1000.0).round() as i16;
+        let touch = if g.touch_active { [g.touch_x as i32, g.touch_y as i32] } else { [-1, -1] };
+        Self { buttons, axes: [q(g.left_x), q(g.left_y), q(g.right_x), q(g.right_y), q(g.l2), q(g.r2)], touch }
+    }
+}
+
+struct Recorder {
+    out: BufWriter<File>,
+    path: PathBuf,
+    started: Instant,
+    last: Option<Sample>,
+    rows: u64,
+}
+
+static ACTIVE: AtomicBool = AtomicBool::new(false); // Checked per report, no lock while idle
+static RECORDER: Mutex<Option<Recorder>> = Mutex::new(None);
+
+pub fn recordings_dir() -> PathBuf {
+    AppConfig::config_path().parent().unwrap().join("recordings")
+}
+
+/// Starts a new file (ending any recording in progress). Returns its path.
+pub fn start() -> anyhow::Result<PathBuf> {
+    stop();
+    let dir = recordings_dir();
+    fs::create_dir_all(&dir)?;
+    let ts_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+    let path = dir.join(format!("{}.csv", ts_ms));
+
+    let mut out = BufWriter::new(File::create(&path)?);
+    let names: Vec<&str> = BUTTONS.iter().map(|(name, _)| *name).collect();
+    writeln!(out, "# buttons: {}", names.join(" "))?;
+    writeln!(out, "t_ms,buttons,lx,ly,rx,ry,l2,r2,touch_x,touch_y")?;
+
+    *RECORDER.lock().unwrap() = Some(Recorder { out, path: path.clone(), started: Instant::now(), last: None, rows: 0 });
+    ACTIVE.store(true, Ordering::SeqCst);
+    info!("Recording input to {:?}", path);
+    Ok(path)
+}
+
+pub fn stop() {
+    ACTIVE.store(false, Ordering::SeqCst);
+    if let Some(mut rec) = RECORDER.lock().unwrap().take() {
+        if let Err(e) = rec.out.flush() {
+            warn!("Failed to finish {:?}: {}", rec.path, e);
+        }
+        info!("Input recording stopped ({} rows in {:?})", rec.rows, rec.path);
+    }
+}
+
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+pub fn record(g: &GamepadState) {
+    if !is_active() {
+        return;
+    }
+    let mut recorder = RECORDER.lock().unwrap();
+    let Some(rec) = recorder.as_mut() else { return };
+    let sample = Sample::from_state(g);
+    if rec.last.as_ref() == Some(&sample) {
+        return;
+    }
+
+    let a = &sample.axes;
+    let row = writeln!(rec.out, "{},{:X},{},{},{},{},{},{},{},{}",
+        rec.started.elapsed().as_millis(), sample.buttons, a[0], a[1], a[2], a[3], a[4], a[5], sample.touch[0], sample.touch[1]);
+    if let Err(e) = row {
+        // Disk full or the file went away; stop instead of failing on every report
+        warn!("Failed to write {:?}: {}, recording stopped", rec.path, e);
+        ACTIVE.store(false, Ordering::SeqCst);
+        recorder.take();
+        return;
+    }
+    rec.rows += 1;
+    rec.last = Some(sample);
+}
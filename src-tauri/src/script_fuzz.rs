@@ -0,0 +1,116 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+
+use crate::crc::CrcMode;
+
+// --- Script Fuzzer ---
+// Rhai scripts that generate candidate output reports for the debug panel's fuzzer. The worker
+// calls `next(step)` once per step and sends whatever it returns through the normal writer:
+//
+//   fn next(step) {
+//       if step >= 256 { return (); }       // () ends the run
+//       let r = zeros(78);
+//       r[0] = 0x31; r[1] = 0x02; r[2] = 0xF7; r[3] = 0x15;
+//       r[46] = step;                       // Sweep the red channel
+//       r                                   // or #{ data: r, desc: "red", feature: false }
+//   }
+//
+// For 0x31 reports the sequence nibble (byte 1, high) is filled in, and the CRC selected in the
+// debug panel is written to the last 4 bytes. print() output is logged and shown in the status line.
+
+// Keeps a runaway loop from stalling the controller thread
+const MAX_OPERATIONS: u64 = 1_000_000;
+const MAX_REPORT_LEN: usize = 600;
+
+pub const EXAMPLE: &str = r#"// Called once per step. Return the report bytes, or () to stop.
+fn next(step) {
+    if step >= 256 { return (); }
+    let r = zeros(78);
+    r[0] = 0x31; r[1] = 0x02; r[2] = 0xF7; r[3] = 0x15;
+    r[46] = step; // Red channel
+    #{ data: r, desc: `red = ${step}` }
+}
+"#;
+
+pub struct ScriptStep {
+    pub data: Vec<u8>,
+    pub desc: String,
+    pub feature: bool,
+}
+
+pub struct ScriptFuzzer {
+    engine: Engine,
+    ast: AST,
+    output: Rc<RefCell<Vec<String>>>,
+}
+
+fn new_engine(output: Rc<RefCell<Vec<String>>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_array_size(MAX_REPORT_LEN);
+    engine.register_fn("zeros", |len: i64| -> Array { vec![Dynamic::from(0i64); len.clamp(0, MAX_REPORT_LEN as i64) as usize] });
+    engine.on_print(move |msg| output.borrow_mut().push(msg.to_string()));
+    engine
+}
+
+fn to_bytes(arr: Array) -> Result<Vec<u8>, String> {
+    arr.iter()
+        .map(|v| v.as_int().map(|b| b as u8).map_err(|t| format!("report bytes must be integers, got {}", t)))
+        .collect()
+}
+
+impl ScriptFuzzer {
+    pub fn compile(source: &str) -> Result<Self, String> {
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let engine = new_engine(output.clone());
+        let ast = engine.compile(source).map_err(|e| e.to_string())?;
+        if !ast.iter_functions().any(|f| f.name == "next" && f.params.len() == 1) {
+            return Err("Script must define fn next(step)".to_string());
+        }
+        Ok(Self { engine, ast, output })
+    }
+
+    /// Lines print()ed since the last call
+    pub fn take_output(&self) -> Vec<String> {
+        std::mem::take(&mut *self.output.borrow_mut())
+    }
+
+    /// None when the script returns () to end the run
+    pub fn next(&self, step: usize) -> Result<Option<ScriptStep>, String> {
+        let result: Dynamic = self.engine
+            .call_fn(&mut Scope::new(), &self.ast, "next", (step as i64,))
+            .map_err(|e| e.to_string())?;
+
+        let type_name = result.type_name();
+        if result.is_unit() {
+            return Ok(None);
+        }
+        if result.is::<Array>() {
+            let data = to_bytes(result.try_cast::<Array>().unwrap_or_default())?;
+            return Ok(Some(ScriptStep { data, desc: format!("script step {}", step), feature: false }));
+        }
+        if result.is::<Map>() {
+            let mut map = result.try_cast::<Map>().unwrap_or_default();
+            let data = map.remove("data").and_then(|d| d.try_cast::<Array>()).ok_or("map needs a `data` array")?;
+            let desc = map.remove("desc").and_then(|d| d.into_string().ok()).unwrap_or_else(|| format!("script step {}", step));
+            let feature = map.remove("feature").and_then(|d| d.as_bool().ok()).unwrap_or(false);
+            return Ok(Some(ScriptStep { data: to_bytes(data)?, desc, feature }));
+        }
+        Err(format!("next() must return an array, a map or (), got {}", type_name))
+    }
+}
+
+/// Fills in what the script can't know: BT sequence number and checksum
+pub fn finalize(data: &mut [u8], crc_idx: u8) {
+    if data.is_empty() {
+        return;
+    }
+    crate::dualsense::stamp_bt_seq(data);
+    let crc_mode = CrcMode::from_idx(crc_idx);
+    let len = data.len();
+    if len > 4 && crc_mode.applies_to(data[0]) {
+        let checksum = crc_mode.checksum(&data[..len - 4]);
+        data[len - 4..].copy_from_slice(&checksum.to_le_bytes());
+    }
+}
@@ -0,0 +1,399 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex, OnceLock, RwLock, RwLockReadGuard, TryLockError};
+use std::thread;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::config::{AppConfig, MqttConfig, Profile};
+use crate::focus::FocusPause;
+use crate::quiet::QuietHours;
+use crate::kbm::KbmGamepad;
+use crate::layout::ReportLayout;
+use crate::mapping::{AutoSprint, ButtonMapping, DeadzoneShape, InputBackend, Orientation, PhysicalButton, ResponseCurve, TriggerCurve};
+use crate::persist;
+use crate::platform::PadKind;
+use crate::touch::TouchCalibration;
+
+// --- Settings ---
+// Everything the user configures (the active profile plus global settings), kept apart from the
+// runtime SharedState so commands changing settings never wait on the per-packet hot path.
+// Readers take the RwLock briefly; the worker only checks `version()` per loop and re-reads the
+// settings when it moved. Changes go through `update`, which bumps the version, queues the
+// files for writing (persist.rs) and wakes `watch`, which sends them to the UI.
+
+// Lets a burst of changes (a dragged slider) go out to the UI as one event
+const EMIT_COALESCE: Duration = Duration::from_millis(30);
+
+#[derive(Clone, Serialize)]
+pub struct Settings {
+    // Active profile
+    pub current_profile_name: String,
+    pub profile_parent: Option<String>, // Profile the active one inherits from
+    pub mappings: Vec<ButtonMapping>,
+    pub deadzone_left: f32,
+    pub deadzone_right: f32,
+    pub anti_deadzone_left: f32,
+    pub anti_deadzone_right: f32,
+    pub deadzone_shape_left: DeadzoneShape,
+    pub deadzone_shape_right: DeadzoneShape,
+    pub mouse_sens_left: f32,
+    pub mouse_sens_right: f32,
+    pub mouse_sens_touchpad: f32,
+    pub rgb_r: u8,
+    pub rgb_g: u8,
+    pub rgb_b: u8,
+    pub rgb_brightness: u8,
+    pub show_battery_led: bool,
+    pub player_led_brightness: u8,
+    // Adaptive Triggers
+    pub trigger_l2_mode: u8,      // 0=Off, 1=Rigid, 0x21=Section, 0x02=Pulse, 0xFF=Dynamic, 0xFE=Rumble (dualsense::trigger_effect)
+    pub trigger_l2_start: u8,     // 0-255 (resistance start zone)
+    pub trigger_l2_force: u8,     // 0-255 (resistance force)
+    pub trigger_r2_mode: u8,
+    pub trigger_r2_start: u8,
+    pub trigger_r2_force: u8,
+    pub trigger_curve_l: TriggerCurve,
+    pub trigger_curve_r: TriggerCurve,
+    pub stick_curve_l: ResponseCurve,
+    pub stick_curve_r: ResponseCurve,
+    pub auto_sprint: Option<AutoSprint>,
+    pub trackball_friction: Option<f32>,
+    pub input_backend: InputBackend,
+    pub pad_kind: PadKind,
+    pub swap_sticks: bool,
+    pub shift_button: Option<PhysicalButton>,
+    // Global
+    pub hide_controller: bool,
+    pub start_minimized: bool,
+    pub api_enabled: bool,
+    pub api_port: u16,
+    pub pause_hotkey: String,
+    pub log_level: String,
+    pub discord_enabled: bool,
+    pub discord_app_id: String,
+    pub mqtt: MqttConfig,
+    pub idle_power_off_min: u32,
+    pub ps_hold_opens_window: bool,
+    pub power_off_combo: Vec<PhysicalButton>,
+    pub power_off_hold_ms: u32,
+    pub profile_cycle_combo: Vec<PhysicalButton>,
+    pub on_connect_cmd: String,
+    pub on_disconnect_cmd: String,
+    pub report_layouts: HashMap<String, ReportLayout>, // layout.rs
+    pub descriptor_parsing: bool,
+    pub touch_calibrations: HashMap<String, TouchCalibration>, // touch.rs
+    pub orientation: Orientation,
+    pub stick_centers: HashMap<String, [f32; 4]>, // stick_cal.rs
+    pub gyro_biases: HashMap<String, [f32; 3]>, // gyro_cal.rs
+    pub kbm_gamepad: KbmGamepad,
+    pub focus_pause: FocusPause,
+    pub haptic_alerts: bool,
+    pub vjoy_device: u8,
+    pub manual_connect: bool,
+    pub output_keepalive_secs: u32,
+    pub quiet_hours: QuietHours,
+}
+
+impl Settings {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            current_profile_name: config.active_profile.clone(),
+            profile_parent: AppConfig::load_profile(&config.active_profile).and_then(|p| p.parent),
+            mappings: config.mappings.clone(),
+            deadzone_left: config.deadzone_left,
+            deadzone_right: config.deadzone_right,
+            anti_deadzone_left: config.anti_deadzone_left,
+            anti_deadzone_right: config.anti_deadzone_right,
+            deadzone_shape_left: config.deadzone_shape_left,
+            deadzone_shape_right: config.deadzone_shape_right,
+            mouse_sens_left: config.mouse_sens_left,
+            mouse_sens_right: config.mouse_sens_right,
+            mouse_sens_touchpad: config.mouse_sens_touchpad,
+            rgb_r: config.rgb_r,
+            rgb_g: config.rgb_g,
+            rgb_b: config.rgb_b,
+            rgb_brightness: config.rgb_brightness,
+            show_battery_led: config.show_battery_led,
+            player_led_brightness: config.player_led_brightness,
+            trigger_l2_mode: config.trigger_l2_mode,
+            trigger_l2_start: config.trigger_l2_start,
+            trigger_l2_force: config.trigger_l2_force,
+            trigger_r2_mode: config.trigger_r2_mode,
+            trigger_r2_start: config.trigger_r2_start,
+            trigger_r2_force: config.trigger_r2_force,
+            trigger_curve_l: config.trigger_curve_l.clone(),
+            trigger_curve_r: config.trigger_curve_r.clone(),
+            stick_curve_l: config.stick_curve_l.clone(),
+            stick_curve_r: config.stick_curve_r.clone(),
+            auto_sprint: config.auto_sprint.clone(),
+            trackball_friction: config.trackball_friction,
+            input_backend: config.input_backend,
+            pad_kind: config.pad_kind,
+            swap_sticks: config.swap_sticks,
+            shift_button: config.shift_button,
+            hide_controller: config.hide_controller,
+            start_minimized: config.start_minimized,
+            api_enabled: config.api_enabled,
+            api_port: config.api_port,
+            pause_hotkey: config.pause_hotkey.clone(),
+            log_level: config.log_level.clone(),
+            discord_enabled: config.discord_enabled,
+            discord_app_id: config.discord_app_id.clone(),
+            mqtt: config.mqtt.clone(),
+            idle_power_off_min: config.idle_power_off_min,
+            ps_hold_opens_window: config.ps_hold_opens_window,
+            power_off_combo: config.power_off_combo.clone(),
+            power_off_hold_ms: config.power_off_hold_ms,
+            profile_cycle_combo: config.profile_cycle_combo.clone(),
+            on_connect_cmd: config.on_connect_cmd.clone(),
+            on_disconnect_cmd: config.on_disconnect_cmd.clone(),
+            report_layouts: config.report_layouts.clone(),
+            descriptor_parsing: config.descriptor_parsing,
+            touch_calibrations: config.touch_calibrations.clone(),
+            orientation: config.orientation,
+            stick_centers: config.stick_centers.clone(),
+            gyro_biases: config.gyro_biases.clone(),
+            kbm_gamepad: config.kbm_gamepad.clone(),
+            focus_pause: config.focus_pause.clone(),
+            haptic_alerts: config.haptic_alerts,
+            vjoy_device: config.vjoy_device,
+            manual_connect: config.manual_connect,
+            output_keepalive_secs: config.output_keepalive_secs,
+            quiet_hours: config.quiet_hours.clone(),
+        }
+    }
+
+    pub fn to_config(&self) -> AppConfig {
+        AppConfig {
+            hide_controller: self.hide_controller,
+            start_minimized: self.start_minimized,
+            mappings: self.mappings.clone(),
+            deadzone_left: self.deadzone_left,
+            deadzone_right: self.deadzone_right,
+            anti_deadzone_left: self.anti_deadzone_left,
+            anti_deadzone_right: self.anti_deadzone_right,
+            deadzone_shape_left: self.deadzone_shape_left,
+            deadzone_shape_right: self.deadzone_shape_right,
+            mouse_sens_left: self.mouse_sens_left,
+            mouse_sens_right: self.mouse_sens_right,
+            mouse_sens_touchpad: self.mouse_sens_touchpad,
+            active_profile: self.current_profile_name.clone(),
+            rgb_r: self.rgb_r,
+            rgb_g: self.rgb_g,
+            rgb_b: self.rgb_b,
+            rgb_brightness: self.rgb_brightness,
+            show_battery_led: self.show_battery_led,
+            trigger_l2_mode: self.trigger_l2_mode,
+            trigger_l2_start: self.trigger_l2_start,
+            trigger_l2_force: self.trigger_l2_force,
+            trigger_r2_mode: self.trigger_r2_mode,
+            trigger_r2_start: self.trigger_r2_start,
+            trigger_r2_force: self.trigger_r2_force,
+            player_led_brightness: self.player_led_brightness,
+            auto_sprint: self.auto_sprint.clone(),
+            trigger_curve_l: self.trigger_curve_l.clone(),
+            trigger_curve_r: self.trigger_curve_r.clone(),
+            stick_curve_l: self.stick_curve_l.clone(),
+            stick_curve_r: self.stick_curve_r.clone(),
+            trackball_friction: self.trackball_friction,
+            input_backend: self.input_backend,
+            pad_kind: self.pad_kind,
+            swap_sticks: self.swap_sticks,
+            shift_button: self.shift_button,
+            api_enabled: self.api_enabled,
+            api_port: self.api_port,
+            pause_hotkey: self.pause_hotkey.clone(),
+            log_level: self.log_level.clone(),
+            discord_enabled: self.discord_enabled,
+            discord_app_id: self.discord_app_id.clone(),
+            mqtt: self.mqtt.clone(),
+            idle_power_off_min: self.idle_power_off_min,
+            ps_hold_opens_window: self.ps_hold_opens_window,
+            power_off_combo: self.power_off_combo.clone(),
+            power_off_hold_ms: self.power_off_hold_ms,
+            profile_cycle_combo: self.profile_cycle_combo.clone(),
+            on_connect_cmd: self.on_connect_cmd.clone(),
+            on_disconnect_cmd: self.on_disconnect_cmd.clone(),
+            report_layouts: self.report_layouts.clone(),
+            descriptor_parsing: self.descriptor_parsing,
+            touch_calibrations: self.touch_calibrations.clone(),
+            orientation: self.orientation,
+            stick_centers: self.stick_centers.clone(),
+            gyro_biases: self.gyro_biases.clone(),
+            kbm_gamepad: self.kbm_gamepad.clone(),
+            focus_pause: self.focus_pause.clone(),
+            haptic_alerts: self.haptic_alerts,
+            vjoy_device: self.vjoy_device,
+            manual_connect: self.manual_connect,
+            output_keepalive_secs: self.output_keepalive_secs,
+            quiet_hours: self.quiet_hours.clone(),
+        }
+    }
+
+    pub fn to_profile(&self) -> Profile {
+        Profile {
+            mappings: self.mappings.clone(),
+            deadzone_left: self.deadzone_left,
+            deadzone_right: self.deadzone_right,
+            anti_deadzone_left: self.anti_deadzone_left,
+            anti_deadzone_right: self.anti_deadzone_right,
+            deadzone_shape_left: self.deadzone_shape_left,
+            deadzone_shape_right: self.deadzone_shape_right,
+            mouse_sens_left: self.mouse_sens_left,
+            mouse_sens_right: self.mouse_sens_right,
+            mouse_sens_touchpad: self.mouse_sens_touchpad,
+            rgb_r: self.rgb_r,
+            rgb_g: self.rgb_g,
+            rgb_b: self.rgb_b,
+            rgb_brightness: self.rgb_brightness,
+            show_battery_led: self.show_battery_led,
+            trigger_l2_mode: self.trigger_l2_mode,
+            trigger_l2_start: self.trigger_l2_start,
+            trigger_l2_force: self.trigger_l2_force,
+            trigger_r2_mode: self.trigger_r2_mode,
+            trigger_r2_start: self.trigger_r2_start,
+            trigger_r2_force: self.trigger_r2_force,
+            player_led_brightness: self.player_led_brightness,
+            auto_sprint: self.auto_sprint.clone(),
+            trigger_curve_l: self.trigger_curve_l.clone(),
+            trigger_curve_r: self.trigger_curve_r.clone(),
+            stick_curve_l: self.stick_curve_l.clone(),
+            stick_curve_r: self.stick_curve_r.clone(),
+            trackball_friction: self.trackball_friction,
+            input_backend: self.input_backend,
+            pad_kind: self.pad_kind,
+            swap_sticks: self.swap_sticks,
+            shift_button: self.shift_button,
+            parent: self.profile_parent.clone(),
+        }
+    }
+
+    pub fn apply_profile(&mut self, p: Profile) {
+        self.mappings = p.mappings;
+        self.deadzone_left = p.deadzone_left;
+        self.deadzone_right = p.deadzone_right;
+        self.anti_deadzone_left = p.anti_deadzone_left;
+        self.anti_deadzone_right = p.anti_deadzone_right;
+        self.deadzone_shape_left = p.deadzone_shape_left;
+        self.deadzone_shape_right = p.deadzone_shape_right;
+        self.mouse_sens_left = p.mouse_sens_left;
+        self.mouse_sens_right = p.mouse_sens_right;
+        self.mouse_sens_touchpad = p.mouse_sens_touchpad;
+        self.rgb_r = p.rgb_r;
+        self.rgb_g = p.rgb_g;
+        self.rgb_b = p.rgb_b;
+        self.rgb_brightness = p.rgb_brightness;
+        self.show_battery_led = p.show_battery_led;
+        self.trigger_l2_mode = p.trigger_l2_mode;
+        self.trigger_l2_start = p.trigger_l2_start;
+        self.trigger_l2_force = p.trigger_l2_force;
+        self.trigger_r2_mode = p.trigger_r2_mode;
+        self.trigger_r2_start = p.trigger_r2_start;
+        self.trigger_r2_force = p.trigger_r2_force;
+        self.player_led_brightness = p.player_led_brightness;
+        self.auto_sprint = p.auto_sprint;
+        self.trigger_curve_l = p.trigger_curve_l;
+        self.trigger_curve_r = p.trigger_curve_r;
+        self.stick_curve_l = p.stick_curve_l;
+        self.stick_curve_r = p.stick_curve_r;
+        self.trackball_friction = p.trackball_friction;
+        self.input_backend = p.input_backend;
+        self.pad_kind = p.pad_kind;
+        self.swap_sticks = p.swap_sticks;
+        self.shift_button = p.shift_button;
+        self.profile_parent = p.parent;
+    }
+
+    /// Lightbar/player LED output differs (the worker resends the LEDs)
+    pub fn leds_differ(&self, other: &Settings) -> bool {
+        (self.rgb_r, self.rgb_g, self.rgb_b, self.rgb_brightness, self.show_battery_led, self.player_led_brightness)
+            != (other.rgb_r, other.rgb_g, other.rgb_b, other.rgb_brightness, other.show_battery_led, other.player_led_brightness)
+    }
+
+    /// Adaptive trigger effects differ (the worker resends the triggers)
+    pub fn triggers_differ(&self, other: &Settings) -> bool {
+        self.trigger_fx() != other.trigger_fx()
+    }
+
+    /// (mode, start, force) for L2 and R2
+    pub fn trigger_fx(&self) -> [(u8, u8, u8); 2] {
+        [(self.trigger_l2_mode, self.trigger_l2_start, self.trigger_l2_force), (self.trigger_r2_mode, self.trigger_r2_start, self.trigger_r2_force)]
+    }
+}
+
+struct Store {
+    settings: RwLock<Settings>,
+    version: AtomicU64,
+    // `watch` sleeps on this until the version moves
+    changed_lock: Mutex<()>,
+    changed: Condvar,
+}
+
+static STORE: OnceLock<Store> = OnceLock::new();
+
+pub fn init(config: &AppConfig) {
+    let _ = STORE.set(Store {
+        settings: RwLock::new(Settings::from_config(config)),
+        version: AtomicU64::new(0),
+        changed_lock: Mutex::new(()),
+        changed: Condvar::new(),
+    });
+}
+
+fn store() -> &'static Store {
+    STORE.get().expect("settings::init not called")
+}
+
+pub fn read() -> RwLockReadGuard<'static, Settings> {
+    store().settings.read().unwrap()
+}
+
+/// For the panic hook: never blocks, and still reads after a panic poisoned the lock
+pub fn try_read() -> Option<RwLockReadGuard<'static, Settings>> {
+    match STORE.get()?.settings.try_read() {
+        Ok(s) => Some(s),
+        Err(TryLockError::Poisoned(p)) => Some(p.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
+/// Moves on every change, for cheap "anything new?" checks
+pub fn version() -> u64 {
+    store().version.load(Ordering::Acquire)
+}
+
+/// Changes the settings and queues config.json for saving, plus the active profile with
+/// `save_profile` (false for global settings, and for loading a profile, which mustn't overwrite it).
+/// Never lock SharedState inside `f`: callers may already hold it.
+pub fn update<R>(save_profile: bool, f: impl FnOnce(&mut Settings) -> R) -> R {
+    let store = store();
+    let mut s = store.settings.write().unwrap();
+    let result = f(&mut s);
+    persist::queue_config(s.to_config());
+    if save_profile {
+        persist::queue_profile(&s.current_profile_name, s.to_profile());
+    }
+    drop(s);
+    let _changed = store.changed_lock.lock().unwrap();
+    store.version.fetch_add(1, Ordering::AcqRel);
+    store.changed.notify_all();
+    result
+}
+
+/// Sends the settings to the UI ("update-settings") whenever they change
+pub fn watch(app_handle: tauri::AppHandle) {
+    thread::spawn(move || {
+        let store = store();
+        let mut seen = version();
+        loop {
+            let guard = store.changed_lock.lock().unwrap();
+            drop(store.changed.wait_while(guard, |_| version() == seen).unwrap());
+            thread::sleep(EMIT_COALESCE);
+            seen = version();
+            let snapshot = read().clone();
+            let _ = app_handle.emit_all("update-settings", &snapshot);
+        }
+    });
+}
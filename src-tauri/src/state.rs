@@ -1,54 +1,335 @@
 use crate::config::AppConfig;
-use crate::mapping::{GamepadState, ButtonMapping};
-use crate::hidhide;
+use crate::mapping::GamepadState;
+use crate::layout::ReportLayout;
+use crate::touch::TouchCalibration;
+use crate::settings;
+use crate::platform::{Bus, Hider, PadBus, DeviceHider};
+use crate::autostart;
+use crate::elevated;
+use crate::crash;
 use serde::{Serialize, Deserialize};
 use serde_big_array::BigArray;
-use std::path::Path;
+use std::fmt;
 
-// Shared state between Controller Thread and GUI
+// Worker/connection status. Serialized as {"code": "...", "detail": ..} so the UI can translate it;
+// Display gives the English text for logs, tray and the control API.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "code", content = "detail", rename_all = "snake_case")]
+pub enum Status {
+    WaitingForController,
+    InitializingVigem,
+    Scanning,
+    HidError(String),
+    Searching,
+    Active { interface: i32 },
+    VirtualPadError(String),
+    NoVirtualPad(String), // ViGEmBus unavailable: controller read, only keyboard/mouse/vJoy mappings work
+    VirtualPadReady,
+    Reconnecting,
+    RefreshingDrivers,
+    Paused,
+    Disconnected,
+    KeyboardMouse, // No controller, virtual pad driven by keyboard/mouse (kbm.rs)
+    WaitingForConnect { found: usize }, // Manual-connect mode: controllers listed, none picked yet
+    Suspended,     // Sleep or session lock (power.rs)
+}
+
+impl Status {
+    /// No controller is being read, so the last input snapshot is stale
+    pub fn clears_input(&self) -> bool {
+        matches!(self, Self::WaitingForController | Self::Searching | Self::Disconnected | Self::WaitingForConnect { .. })
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::WaitingForController => write!(f, "Waiting for controller..."),
+            Self::InitializingVigem => write!(f, "Initializing ViGEm..."),
+            Self::Scanning => write!(f, "Scanning for controllers..."),
+            Self::HidError(e) => write!(f, "HID Error: {}", e),
+            Self::Searching => write!(f, "Searching for controller..."),
+            Self::Active { interface } => write!(f, "Active (Iface {})", interface),
+            Self::VirtualPadError(e) => write!(f, "ViGEm Error: {}", e),
+            Self::NoVirtualPad(e) => write!(f, "Keyboard/mouse only (ViGEmBus: {})", e),
+            Self::VirtualPadReady => write!(f, "Virtual Pad: Ready"),
+            Self::Reconnecting => write!(f, "Reconnecting..."),
+            Self::RefreshingDrivers => write!(f, "Refreshing drivers..."),
+            Self::Paused => write!(f, "Paused"),
+            Self::Disconnected => write!(f, "Disconnected"),
+            Self::KeyboardMouse => write!(f, "Keyboard/Mouse Mode"),
+            Self::WaitingForConnect { found } => write!(f, "{} controller(s) found, waiting for Connect", found),
+            Self::Suspended => write!(f, "Suspended (sleep/lock)"),
+        }
+    }
+}
+
+// Modes the controller can be in, for overlays ("mode" events, see overlay.rs) and the control API
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct Modes {
+    pub bypass: bool,         // Paused: no virtual pad, games see the real controller
+    pub keyboard_mouse: bool, // Keyboard/mouse driving the virtual pad (kbm.rs)
+    pub auto_sprint: bool,    // Auto-sprint holding its target
+    pub desktop_pause: bool,  // Mapped keyboard/mouse outputs held back, no linked game in front (focus.rs)
+    pub no_virtual_pad: bool, // Controller connected but ViGEmBus unavailable: keyboard/mouse mappings only
+    pub quiet_hours: bool,    // LEDs dimmed and rumble off for the night (quiet.rs)
+    pub shift_layer: bool,    // Shift button held, mappings use their shift targets
+    pub gyro_aim: bool,       // A Gyro Aim target is held, turning the controller moves the mouse
+}
+
+impl Modes {
+    pub fn from_state(s: &SharedState) -> Self {
+        let st = settings::read(); // Once, like DeviceInfo::from_state
+        Self {
+            bypass: s.is_paused,
+            keyboard_mouse: s.status == Status::KeyboardMouse,
+            auto_sprint: s.auto_sprint_engaged,
+            desktop_pause: s.virtual_pad_active && st.focus_pause.suspends(&s.foreground_process),
+            no_virtual_pad: matches!(s.status, Status::NoVirtualPad(_)),
+            quiet_hours: st.quiet_hours.is_active(),
+            shift_layer: s.shift_layer_engaged,
+            gyro_aim: s.gyro_aim_engaged,
+        }
+    }
+
+    /// (name, active) pairs, names as serialized
+    pub fn list(&self) -> [(&'static str, bool); 8] {
+        [("bypass", self.bypass), ("keyboard_mouse", self.keyboard_mouse), ("auto_sprint", self.auto_sprint), ("desktop_pause", self.desktop_pause), ("no_virtual_pad", self.no_virtual_pad), ("quiet_hours", self.quiet_hours), ("shift_layer", self.shift_layer), ("gyro_aim", self.gyro_aim)]
+    }
+}
+
+// Slices of SharedState for the granular query commands (get_gamepad_snapshot, get_device_info,
+// get_debug_info), so reading one part doesn't serialize the whole state
+
+// Latest input and what the controller is doing with it
+#[derive(Clone, Serialize)]
+pub struct GamepadSnapshot {
+    pub gamepad: GamepadState,
+    pub status: Status,
+    pub last_update: u64,
+    pub is_paused: bool,
+    pub modes: Modes,
+    pub foreground_process: String,
+}
+
+impl GamepadSnapshot {
+    pub fn from_state(s: &SharedState) -> Self {
+        Self {
+            gamepad: s.gamepad,
+            status: s.status.clone(),
+            last_update: s.last_update,
+            is_paused: s.is_paused,
+            modes: Modes::from_state(s),
+            foreground_process: s.foreground_process.clone(),
+        }
+    }
+}
+
+// A controller the scanner saw but hasn't attached (manual-connect mode)
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FoundController {
+    pub path: String, // What connect_device takes
+    pub name: String,
+    pub serial: String,
+    pub bluetooth: bool,
+}
+
+// Connected controller, drivers and per-device calibration
+#[derive(Clone, Serialize)]
+pub struct DeviceInfo {
+    pub device_name: String,
+    pub connection_mode: String,
+    pub device_key: String,
+    pub stick_cal_key: String,
+    pub hidhide_available: bool,
+    pub vigembus_available: bool,
+    pub virtual_pad_active: bool,
+    pub hidden_device_id: Option<String>,
+    pub active_layout: Option<ReportLayout>,
+    pub layout_note: String,
+    pub descriptor_layout: Option<String>,
+    pub touch_calibrating: bool,
+    pub touch_calibration: Option<TouchCalibration>,
+    pub stick_center: Option<[f32; 4]>,
+    pub stick_cal_status: String,
+    pub gyro_bias: Option<[f32; 3]>,
+    pub gyro_cal_status: String,
+    pub vjoy_status: String, // vJoy output (vjoy.rs): acquired device or why it failed
+    pub input_backend_status: String, // Interception (interception.rs) when the profile asks for it
+}
+
+impl DeviceInfo {
+    pub fn from_state(s: &SharedState) -> Self {
+        // One guard for all fields: a second read on this thread could wait behind a queued writer
+        let st = settings::read();
+        Self {
+            device_name: s.device_name.clone(),
+            connection_mode: s.connection_mode.clone(),
+            device_key: s.device_key.clone(),
+            stick_cal_key: s.stick_cal_key.clone(),
+            hidhide_available: s.hidhide_available,
+            vigembus_available: s.vigembus_available,
+            virtual_pad_active: s.virtual_pad_active,
+            hidden_device_id: s.hidden_device_id.clone(),
+            active_layout: s.active_layout.clone(),
+            layout_note: s.layout_note.clone(),
+            descriptor_layout: s.descriptor_layout.clone(),
+            touch_calibrating: s.touch_calibrating,
+            touch_calibration: st.touch_calibrations.get(&s.device_key).copied(),
+            stick_center: st.stick_centers.get(&s.stick_cal_key).copied(),
+            stick_cal_status: s.stick_cal_status.clone(),
+            gyro_bias: st.gyro_biases.get(&s.stick_cal_key).copied(),
+            gyro_cal_status: s.gyro_cal_status.clone(),
+            vjoy_status: s.vjoy_status.clone(),
+            input_backend_status: s.input_backend_status.clone(),
+        }
+    }
+}
+
+// Debug panel: raw reports and the protocol tools' output
+#[derive(Clone, Serialize)]
+pub struct DebugInfo {
+    pub debug_active: bool,
+    pub raw_report: Vec<u8>,
+    pub last_packet_hex: String,
+    pub last_write_status: String,
+    pub device_path_str: String,
+    pub detected_devices_log: String,
+    pub protocol_log: String,
+    pub fuzzer_active: bool,
+    pub fuzzer_log: String,
+    pub fuzzer_step: usize,
+    pub fuzz_script_active: bool,
+    pub sweep_active: bool,
+    pub capture_active: bool,
+    pub capture_packets: usize,
+}
+
+impl DebugInfo {
+    pub fn from_state(s: &SharedState) -> Self {
+        Self {
+            debug_active: s.debug_active,
+            raw_report: s.raw_report.to_vec(),
+            last_packet_hex: s.last_packet_hex.clone(),
+            last_write_status: s.last_write_status.clone(),
+            device_path_str: s.device_path_str.clone(),
+            detected_devices_log: s.detected_devices_log.clone(),
+            protocol_log: s.protocol_log.clone(),
+            fuzzer_active: s.fuzzer_active,
+            fuzzer_log: s.fuzzer_log.clone(),
+            fuzzer_step: s.fuzzer_step,
+            fuzz_script_active: s.fuzz_script_active,
+            sweep_active: s.sweep_active,
+            capture_active: s.capture_active,
+            capture_packets: s.capture_packets,
+        }
+    }
+}
+
+// LED fuzzer schedule. Steps walk every combination, RGB offset fastest, then value, flag offset, report ID.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FuzzerParams {
+    pub report_ids: Vec<u8>,
+    pub flag_offsets: Vec<usize>,
+    pub rgb_offsets: Vec<usize>,
+    pub values: Vec<u8>,   // Written to the red channel at the RGB offset
+    pub burst: u8,         // Packets sent per step
+    pub delay_ms: u64,     // Time between steps
+}
+
+impl Default for FuzzerParams {
+    fn default() -> Self {
+        Self {
+            report_ids: vec![0x02, 0x31],
+            flag_offsets: (0..20).collect(),
+            rgb_offsets: (35..65).collect(),
+            values: vec![255],
+            burst: 3,
+            delay_ms: 50,
+        }
+    }
+}
+
+impl FuzzerParams {
+    pub fn step_count(&self) -> usize {
+        self.report_ids.len() * self.flag_offsets.len() * self.rgb_offsets.len() * self.values.len()
+    }
+
+    /// (report_id, flag_offset, rgb_offset, value) for `step` < step_count()
+    pub fn step(&self, step: usize) -> (u8, usize, usize, u8) {
+        let mut i = step;
+        let rgb_off = self.rgb_offsets[i % self.rgb_offsets.len()];
+        i /= self.rgb_offsets.len();
+        let value = self.values[i % self.values.len()];
+        i /= self.values.len();
+        let flag_off = self.flag_offsets[i % self.flag_offsets.len()];
+        i /= self.flag_offsets.len();
+        (self.report_ids[i % self.report_ids.len()], flag_off, rgb_off, value)
+    }
+}
+
+// Targeted RGB sweep: every `step`-th byte in start..=end gets each value in turn (value fastest).
+// BT 0x31 report, so end must stay below the CRC at 74.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SweepRange {
+    pub start: usize,
+    pub end: usize,
+    pub values: Vec<u8>,
+    pub step: usize,
+}
+
+impl SweepRange {
+    pub fn offset_count(&self) -> usize {
+        (self.end - self.start) / self.step + 1
+    }
+
+    pub fn step_count(&self) -> usize {
+        self.offset_count() * self.values.len()
+    }
+
+    /// (offset, value) for `step` < step_count()
+    pub fn step(&self, step: usize) -> (usize, u8) {
+        let value = self.values[step % self.values.len()];
+        let offset = self.start + (step / self.values.len()) * self.step;
+        (offset, value)
+    }
+}
+
+// Shared state between Controller Thread and GUI (runtime only, user settings are in settings.rs)
 #[derive(Clone, Serialize, Deserialize)]
 pub struct SharedState {
     pub gamepad: GamepadState,
-    pub status: String,
+    pub status: Status,
     pub device_name: String,
     pub connection_mode: String,
     #[serde(with = "BigArray")]
     pub raw_report: [u8; 80],
     pub last_update: u64,
     pub debug_active: bool,
-    pub hide_controller: bool,
+    pub report_diff_active: bool,
+    pub stick_preview_active: bool, // "stick-preview" events while the deadzone controls are in use
+    pub stick_stream_active: bool,  // "stick-samples" events (every raw sample) for the calibration view
+    pub capture_active: bool,
+    pub capture_packets: usize,
+    pub input_recording: bool,
     pub hidhide_available: bool,
     pub vigembus_available: bool,
     pub virtual_pad_active: bool,
     pub hidden_device_id: Option<String>,
-    pub mappings: Vec<ButtonMapping>,
-    pub mappings_changed: bool,
-    pub current_profile_name: String,
-    pub deadzone_left: f32,
-    pub deadzone_right: f32,
-    pub mouse_sens_left: f32,
-    pub mouse_sens_right: f32,
-    pub mouse_sens_touchpad: f32,
-    pub rgb_r: u8,
-    pub rgb_g: u8,
-    pub rgb_b: u8,
-    pub rgb_brightness: u8,
-    pub show_battery_led: bool,
     pub should_send_leds: bool,
     pub should_disconnect: bool,
     pub is_paused: bool,
-    // Adaptive Triggers
-    pub trigger_l2_mode: u8,      // 0=Off, 1=Rigid, 0x21=Section, 0x02=Pulse
-    pub trigger_l2_start: u8,     // 0-255 (resistance start zone)
-    pub trigger_l2_force: u8,     // 0-255 (resistance force)
-    pub trigger_r2_mode: u8,
-    pub trigger_r2_start: u8,
-    pub trigger_r2_force: u8,
     pub should_send_triggers: bool,
+    pub should_confirm_profile: bool, // Profile switched, play the in-hand confirmation
+    pub should_identify: bool,        // Flash the lightbar (identify_controller)
     // Fuzzer State
     pub fuzzer_active: bool,
     pub fuzzer_log: String,
     pub fuzzer_step: usize,
+    pub fuzzer_session_path: String,
+    pub fuzzer_params: FuzzerParams,
+    pub fuzz_script: String,
+    pub fuzz_script_active: bool,
     pub manual_report_id: u8,
     pub manual_flag_offset: usize,
     pub manual_rgb_offset: usize,
@@ -57,11 +338,11 @@ pub struct SharedState {
     pub manual_b: u8,
     pub last_write_status: String,
     pub should_send_manual: bool,
-    pub bt_sequence: u8,
     pub crc_seed_idx: u8,
     pub disable_periodic: bool,
     pub sweep_active: bool,
     pub sweep_timeout_ms: u64,
+    pub sweep_range: Option<SweepRange>, // None = full offset walk + flag phase
     pub pinpoint_offset: usize,
     pub pinpoint_value: u8,
     pub device_path_str: String,
@@ -77,60 +358,79 @@ pub struct SharedState {
     pub last_packet_hex: String,
     pub protocol_log: String,
     pub protocol_scan_active: bool,
+    pub should_dump_descriptor: bool,
+    pub should_scan_input_reports: bool,
     pub ui_visible: bool,
-    pub start_minimized: bool,
-    pub player_led_brightness: u8,
+    pub autostart: bool,
+    pub elevated_autostart: bool,
+    pub auto_sprint_engaged: bool, // Auto-sprint is holding its target right now
+    pub shift_layer_engaged: bool, // Shift button held
+    pub gyro_aim_engaged: bool,    // Gyro Aim target held
     pub should_exit: bool,
+    pub worker_stopped: bool,
+    pub pending_crash_report: Option<String>,
     pub should_reinit: bool,
+    pub system_suspended: bool, // Asleep or locked (power.rs): virtual pad released until resume
+    // Manual-connect mode: what the scanner sees, and the controller connect_device asked for
+    pub found_controllers: Vec<FoundController>,
+    pub connect_request: Option<String>,
+    pub foreground_process: String,
+    // Input report layouts (layout.rs)
+    pub device_key: String,                  // "VID:PID" of the connected controller
+    pub active_layout: Option<ReportLayout>, // None = built-in parser
+    pub layout_note: String,
+    pub descriptor_layout: Option<String>, // Summary of the layout derived from the descriptor
+    // Touchpad calibration (touch.rs)
+    pub touch_calibrating: bool,
+    pub touch_cal_progress: Option<TouchCalibration>, // Range seen so far while calibrating
+    // Stick drift calibration (stick_cal.rs)
+    pub stick_cal_key: String, // Serial of the connected controller (VID:PID if it has none)
+    pub should_calibrate_sticks: bool,
+    pub stick_cal_status: String,
+    // Gyro drift calibration (gyro_cal.rs), keyed by stick_cal_key too
+    pub should_calibrate_gyro: bool,
+    pub gyro_cal_status: String,
+    pub vjoy_status: String, // vJoy output (vjoy.rs): acquired device or why it failed
+    pub input_backend_status: String, // Interception (interception.rs) when the profile asks for it
 }
 
 impl SharedState {
     pub fn new(config: &AppConfig) -> Self {
         // Check ViGEmBus installation by file existence
         // This is more robust than trying to connect immediately at startup
-        let vigem_installed = Path::new("C:\\Windows\\System32\\drivers\\ViGEmBus.sys").exists() ||
-                              Path::new("C:\\Windows\\System32\\vigemclient.dll").exists();
+        let vigem_installed = Bus::is_installed();
 
         Self {
             gamepad: GamepadState::default(),
-            status: "Waiting for controller...".to_string(),
+            status: Status::WaitingForController,
             device_name: "None".to_string(),
             connection_mode: String::new(),
             raw_report: [0u8; 80],
             last_update: 0,
             debug_active: false,
-            hide_controller: config.hide_controller,
-            hidhide_available: hidhide::is_installed(),
+            report_diff_active: false,
+            stick_preview_active: false,
+            stick_stream_active: false,
+            capture_active: false,
+            capture_packets: 0,
+            input_recording: false,
+            hidhide_available: Hider::is_installed(),
             vigembus_available: vigem_installed,
             virtual_pad_active: false,
             hidden_device_id: None,
-            mappings: config.mappings.clone(),
-            mappings_changed: true,
-            current_profile_name: config.active_profile.clone(),
-            deadzone_left: config.deadzone_left,
-            deadzone_right: config.deadzone_right,
-            mouse_sens_left: config.mouse_sens_left,
-            mouse_sens_right: config.mouse_sens_right,
-            mouse_sens_touchpad: config.mouse_sens_touchpad,
-            rgb_r: config.rgb_r,
-            rgb_g: config.rgb_g,
-            rgb_b: config.rgb_b,
-            rgb_brightness: config.rgb_brightness,
-            show_battery_led: config.show_battery_led,
             should_send_leds: false,
             should_disconnect: false,
             is_paused: false,
-            // Adaptive Triggers
-            trigger_l2_mode: config.trigger_l2_mode,
-            trigger_l2_start: config.trigger_l2_start,
-            trigger_l2_force: config.trigger_l2_force,
-            trigger_r2_mode: config.trigger_r2_mode,
-            trigger_r2_start: config.trigger_r2_start,
-            trigger_r2_force: config.trigger_r2_force,
             should_send_triggers: false,
+            should_confirm_profile: false,
+            should_identify: false,
             fuzzer_active: false,
             fuzzer_log: "Ready to start fuzzing...".to_string(),
             fuzzer_step: 0,
+            fuzzer_session_path: String::new(),
+            fuzzer_params: FuzzerParams::default(),
+            fuzz_script: crate::script_fuzz::EXAMPLE.to_string(),
+            fuzz_script_active: false,
             manual_report_id: 0x31,
             manual_flag_offset: 2,
             manual_rgb_offset: 46,
@@ -139,11 +439,11 @@ impl SharedState {
             manual_b: 0,
             last_write_status: "None".to_string(),
             should_send_manual: false,
-            bt_sequence: 0,
             crc_seed_idx: 0,
             disable_periodic: false,
             sweep_active: false,
             sweep_timeout_ms: 250,
+            sweep_range: None,
             pinpoint_offset: 46,
             pinpoint_value: 255,
             device_path_str: "Unknown".to_string(),
@@ -159,11 +459,35 @@ impl SharedState {
             last_packet_hex: String::new(),
             protocol_log: "Ready to scan.".to_string(),
             protocol_scan_active: false,
+            should_dump_descriptor: false,
+            should_scan_input_reports: false,
             ui_visible: !config.start_minimized,
-            start_minimized: config.start_minimized,
-            player_led_brightness: config.player_led_brightness,
+            autostart: autostart::is_enabled(),
+            elevated_autostart: elevated::is_registered(),
+            auto_sprint_engaged: false,
+            shift_layer_engaged: false,
+            gyro_aim_engaged: false,
             should_exit: false,
+            worker_stopped: false,
+            pending_crash_report: crash::pending_report().map(|p| p.to_string_lossy().into_owned()),
             should_reinit: false,
+            system_suspended: false,
+            found_controllers: Vec::new(),
+            connect_request: None,
+            foreground_process: String::new(),
+            device_key: String::new(),
+            active_layout: None,
+            layout_note: String::new(),
+            descriptor_layout: None,
+            touch_calibrating: false,
+            touch_cal_progress: None,
+            stick_cal_key: String::new(),
+            should_calibrate_sticks: false,
+            stick_cal_status: String::new(),
+            should_calibrate_gyro: false,
+            gyro_cal_status: String::new(),
+            vjoy_status: String::new(),
+            input_backend_status: String::new(),
         }
     }
 }
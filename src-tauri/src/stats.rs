@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+use serde::Serialize;
+
+use crate::mapping::{GamepadState, PhysicalButton};
+
+// --- Input Statistics ---
+// Usage counters since the first report (or the last reset): presses per button, how far each
+// stick has travelled and how long that has been. A press that follows its own release within
+// CHATTER_MS is faster than a thumb can go and is counted as chatter as well; a worn switch
+// double-registering shows up there. Fed by the worker with every parsed report.
+
+const CHATTER_MS: u128 = 25;
+// Per-report stick movement below this is sensor noise, not travel
+const STICK_NOISE: f32 = 0.01;
+// Triggers count as pressed past this
+const TRIGGER_PRESS: f32 = 0.5;
+
+const BUTTONS: [PhysicalButton; 21] = [
+    PhysicalButton::Cross, PhysicalButton::Circle, PhysicalButton::Square, PhysicalButton::Triangle,
+    PhysicalButton::L1, PhysicalButton::R1, PhysicalButton::L3, PhysicalButton::R3,
+    PhysicalButton::Options, PhysicalButton::Share, PhysicalButton::PS, PhysicalButton::Touchpad, PhysicalButton::Mute,
+    PhysicalButton::DpadUp, PhysicalButton::DpadDown, PhysicalButton::DpadLeft, PhysicalButton::DpadRight,
+    PhysicalButton::FnLeft, PhysicalButton::FnRight, PhysicalButton::PaddleLeft, PhysicalButton::PaddleRight,
+];
+
+#[derive(Clone, Serialize)]
+pub struct InputStats {
+    pub session_secs: u64,
+    pub reports: u64,
+    pub presses: HashMap<PhysicalButton, u32>,
+    pub chatter: HashMap<PhysicalButton, u32>,
+    pub stick_travel: [f32; 2], // [left, right], in stick radii
+}
+
+struct Tracker {
+    started: Instant,
+    stats: InputStats,
+    prev: Option<GamepadState>,
+    released_at: HashMap<PhysicalButton, Instant>,
+}
+
+impl Tracker {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            stats: InputStats { session_secs: 0, reports: 0, presses: HashMap::new(), chatter: HashMap::new(), stick_travel: [0.0; 2] },
+            prev: None,
+            released_at: HashMap::new(),
+        }
+    }
+
+    fn edge(&mut self, button: PhysicalButton, was: bool, is: bool) {
+        if is && !was {
+            *self.stats.presses.entry(button).or_default() += 1;
+            if self.released_at.get(&button).is_some_and(|t| t.elapsed().as_millis() < CHATTER_MS) {
+                *self.stats.chatter.entry(button).or_default() += 1;
+            }
+        } else if was && !is {
+            self.released_at.insert(button, Instant::now());
+        }
+    }
+
+    fn record(&mut self, g: &GamepadState) {
+        self.stats.reports += 1;
+        let Some(prev) = self.prev.replace(*g) else { return };
+
+        for b in BUTTONS {
+            self.edge(b, b.get_value(&prev), b.get_value(g));
+        }
+        self.edge(PhysicalButton::L2, prev.l2 > TRIGGER_PRESS, g.l2 > TRIGGER_PRESS);
+        self.edge(PhysicalButton::R2, prev.r2 > TRIGGER_PRESS, g.r2 > TRIGGER_PRESS);
+
+        let moved = [
+            (g.left_x - prev.left_x).hypot(g.left_y - prev.left_y),
+            (g.right_x - prev.right_x).hypot(g.right_y - prev.right_y),
+        ];
+        for (travel, d) in self.stats.stick_travel.iter_mut().zip(moved) {
+            if d >= STICK_NOISE {
+                *travel += d;
+            }
+        }
+    }
+}
+
+static TRACKER: Mutex<Option<Tracker>> = Mutex::new(None);
+
+pub fn record(g: &GamepadState) {
+    TRACKER.lock().unwrap().get_or_insert_with(Tracker::new).record(g);
+}
+
+/// Forget the previous report so a reconnect doesn't count as movement or presses
+pub fn reset_baseline() {
+    if let Some(t) = TRACKER.lock().unwrap().as_mut() {
+        t.prev = None;
+    }
+}
+
+pub fn snapshot() -> InputStats {
+    let mut tracker = TRACKER.lock().unwrap();
+    let t = tracker.get_or_insert_with(Tracker::new);
+    let mut stats = t.stats.clone();
+    stats.session_secs = t.started.elapsed().as_secs();
+    stats
+}
+
+pub fn reset() {
+    *TRACKER.lock().unwrap() = Some(Tracker::new());
+}
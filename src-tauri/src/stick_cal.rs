@@ -0,0 +1,77 @@
+use std::time::{Duration, Instant};
+
+use crate::mapping::GamepadState;
+
+// --- Stick Drift Calibration ---
+// Worn sticks rest off-center, which otherwise needs a large deadzone to hide. Calibration samples
+// the resting sticks for a few seconds and stores the average per controller serial (config
+// `stick_centers`, [LX, LY, RX, RY]). The offsets are removed before deadzone processing, with the
+// remaining travel rescaled so full deflection still reaches 1.0.
+
+pub const SAMPLE_TIME: Duration = Duration::from_secs(3);
+// A stick that moves more than this while sampling is being touched, not drifting
+const MAX_SPREAD: f32 = 0.08;
+// Anything further off than this is a broken stick or a held one
+const MAX_OFFSET: f32 = 0.3;
+
+fn axes(g: &GamepadState) -> [f32; 4] {
+    [g.left_x, g.left_y, g.right_x, g.right_y]
+}
+
+fn recenter(v: f32, center: f32) -> f32 {
+    let range = if v >= center { 1.0 - center } else { 1.0 + center };
+    if range <= 0.0 {
+        return 0.0;
+    }
+    ((v - center) / range).clamp(-1.0, 1.0)
+}
+
+pub fn apply(centers: &[f32; 4], state: &mut GamepadState) {
+    state.left_x = recenter(state.left_x, centers[0]);
+    state.left_y = recenter(state.left_y, centers[1]);
+    state.right_x = recenter(state.right_x, centers[2]);
+    state.right_y = recenter(state.right_y, centers[3]);
+}
+
+pub struct StickSampler {
+    started: Instant,
+    sum: [f32; 4],
+    min: [f32; 4],
+    max: [f32; 4],
+    count: u32,
+}
+
+impl StickSampler {
+    pub fn new() -> Self {
+        Self { started: Instant::now(), sum: [0.0; 4], min: [f32::MAX; 4], max: [f32::MIN; 4], count: 0 }
+    }
+
+    /// Raw (uncalibrated) sticks
+    pub fn feed(&mut self, g: &GamepadState) {
+        for (i, v) in axes(g).into_iter().enumerate() {
+            self.sum[i] += v;
+            self.min[i] = self.min[i].min(v);
+            self.max[i] = self.max[i].max(v);
+        }
+        self.count += 1;
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.started.elapsed() >= SAMPLE_TIME
+    }
+
+    /// Average resting position, or why the sample can't be used
+    pub fn result(&self) -> Result<[f32; 4], String> {
+        if self.count == 0 {
+            return Err("No input received from the controller".to_string());
+        }
+        if (0..4).any(|i| self.max[i] - self.min[i] > MAX_SPREAD) {
+            return Err("Sticks moved during calibration, leave them untouched and try again".to_string());
+        }
+        let centers = self.sum.map(|s| s / self.count as f32);
+        if centers.iter().any(|c| c.abs() > MAX_OFFSET) {
+            return Err(format!("Offset too large ({:.2}), is a stick being held?", centers.iter().fold(0.0f32, |m, c| m.max(c.abs()))));
+        }
+        Ok(centers)
+    }
+}
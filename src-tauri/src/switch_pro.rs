@@ -0,0 +1,274 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::{Duration, Instant};
+use hidapi::HidDevice;
+use log::{info, warn};
+use crate::dualsense::write_report;
+use crate::mapping::{GamepadState, ChargeState};
+
+// --- Switch Pro Controller ---
+// Nintendo's Pro Controller (057E:2009), and the 8BitDo pads that present themselves as one in
+// Switch mode. Out of the box it sends a simple 0x3F report; `init` switches it to the full 0x30
+// report (12-bit sticks, battery) and reads the factory stick calibration. Over USB the controller
+// first needs the 0x80 handshake, or it sends nothing at all.
+//
+// Everything else goes out as report 0x01 (rumble + subcommand) or 0x10 (rumble only), each with a
+// 4-bit packet counter. Replies come back as input report 0x21; only `init` waits for them, later
+// writes are fire-and-forget while the reader thread takes the input reports.
+
+// Output report 0x01 is 48 bytes + ID (hidapi pads it to the USB report size)
+const OUTPUT_LEN: usize = 49;
+const REPLY_TIMEOUT: Duration = Duration::from_millis(300);
+
+// Subcommands
+const SUBCMD_SPI_READ: u8 = 0x10;
+const SUBCMD_INPUT_MODE: u8 = 0x03;
+const SUBCMD_PLAYER_LIGHTS: u8 = 0x30;
+const SUBCMD_HOME_LIGHT: u8 = 0x38;
+const SUBCMD_VIBRATION: u8 = 0x48;
+
+// Factory stick calibration in SPI flash: 9 bytes per stick
+const SPI_STICK_CAL: u32 = 0x603D;
+
+// Both actuators idle (320 Hz / 160 Hz, amplitude 0)
+const RUMBLE_NEUTRAL: [u8; 4] = [0x00, 0x01, 0x40, 0x40];
+
+static PACKET_COUNTER: AtomicU8 = AtomicU8::new(0);
+
+/// Center and reach (raw units on either side) of one 12-bit stick axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisCal {
+    pub center: u16,
+    pub below: u16,
+    pub above: u16,
+}
+
+impl Default for AxisCal {
+    fn default() -> Self {
+        Self { center: 2048, below: 1400, above: 1400 }
+    }
+}
+
+impl AxisCal {
+    /// -1.0..1.0, positive = right/up as the controller reports it
+    fn normalize(&self, raw: u16) -> f32 {
+        let d = raw as f32 - self.center as f32;
+        let reach = if d < 0.0 { self.below } else { self.above };
+        (d / reach.max(1) as f32).clamp(-1.0, 1.0)
+    }
+
+    /// Clones often ship blank (0xFFF) or zeroed flash
+    fn is_plausible(&self) -> bool {
+        (500..3600).contains(&self.center) && (500..2000).contains(&self.below) && (500..2000).contains(&self.above)
+    }
+}
+
+/// [LX, LY, RX, RY]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StickCalibration(pub [AxisCal; 4]);
+
+/// Two 12-bit values packed into 3 bytes
+fn unpack12(b: &[u8]) -> (u16, u16) {
+    (b[0] as u16 | ((b[1] as u16 & 0x0F) << 8), (b[1] as u16 >> 4) | ((b[2] as u16) << 4))
+}
+
+impl StickCalibration {
+    /// The 18 bytes at SPI_STICK_CAL. The left stick stores max/center/min, the right center/min/max.
+    fn from_spi(data: &[u8]) -> Self {
+        let (lx_above, ly_above) = unpack12(&data[0..3]);
+        let (lx_center, ly_center) = unpack12(&data[3..6]);
+        let (lx_below, ly_below) = unpack12(&data[6..9]);
+        let (rx_center, ry_center) = unpack12(&data[9..12]);
+        let (rx_below, ry_below) = unpack12(&data[12..15]);
+        let (rx_above, ry_above) = unpack12(&data[15..18]);
+        let cal = Self([
+            AxisCal { center: lx_center, below: lx_below, above: lx_above },
+            AxisCal { center: ly_center, below: ly_below, above: ly_above },
+            AxisCal { center: rx_center, below: rx_below, above: rx_above },
+            AxisCal { center: ry_center, below: ry_below, above: ry_above },
+        ]);
+        if cal.0.iter().all(AxisCal::is_plausible) { cal } else { Self::default() }
+    }
+}
+
+fn next_packet() -> u8 {
+    PACKET_COUNTER.fetch_add(1, Ordering::Relaxed) & 0x0F
+}
+
+/// Waits for the first report `matches` accepts, skipping regular input reports
+fn read_reply(device: &HidDevice, matches: impl Fn(&[u8]) -> bool) -> Option<[u8; 64]> {
+    let deadline = Instant::now() + REPLY_TIMEOUT;
+    let mut buf = [0u8; 64];
+    while Instant::now() < deadline {
+        match device.read_timeout(&mut buf, 20) {
+            Ok(n) if n > 0 && matches(&buf[..n]) => return Some(buf),
+            Ok(_) => {}
+            Err(_) => return None,
+        }
+    }
+    None
+}
+
+/// USB-only 0x80 commands: 0x02 handshake, 0x03 3 Mbit/s, 0x04 HID only (no USB timeout)
+fn usb_command(device: &HidDevice, cmd: u8, wait: bool) -> bool {
+    let mut report = [0u8; 64];
+    report[0] = 0x80;
+    report[1] = cmd;
+    if write_report(device, &report).is_err() {
+        return false;
+    }
+    !wait || read_reply(device, |r| r[0] == 0x81 && r.get(1) == Some(&cmd)).is_some()
+}
+
+/// Report 0x01 with idle rumble, not waiting for the reply
+pub fn send_subcommand(device: &HidDevice, id: u8, args: &[u8]) -> hidapi::HidResult<usize> {
+    let mut report = [0u8; OUTPUT_LEN];
+    report[0] = 0x01;
+    report[1] = next_packet();
+    report[2..6].copy_from_slice(&RUMBLE_NEUTRAL);
+    report[6..10].copy_from_slice(&RUMBLE_NEUTRAL);
+    report[10] = id;
+    let n = args.len().min(OUTPUT_LEN - 11);
+    report[11..11 + n].copy_from_slice(&args[..n]);
+    write_report(device, &report)
+}
+
+/// The 0x21 reply (ACK in byte 13, echoed ID in 14, data from 15), None on timeout
+fn subcommand(device: &HidDevice, id: u8, args: &[u8]) -> Option<[u8; 64]> {
+    send_subcommand(device, id, args).ok()?;
+    read_reply(device, |r| r.len() > 14 && r[0] == 0x21 && r[14] == id).filter(|r| r[13] & 0x80 != 0)
+}
+
+fn spi_read(device: &HidDevice, addr: u32, len: u8) -> Option<Vec<u8>> {
+    let mut args = [0u8; 5];
+    args[..4].copy_from_slice(&addr.to_le_bytes());
+    args[4] = len;
+    let reply = subcommand(device, SUBCMD_SPI_READ, &args)?;
+    (reply[15..20] == args).then(|| reply[20..20 + len as usize].to_vec())
+}
+
+/// Handshake (USB), full input reports and rumble on, and the factory stick calibration. Defaults
+/// are used for anything the controller doesn't answer; some 8BitDo firmwares skip parts of this.
+pub fn init(device: &HidDevice, usb: bool) -> StickCalibration {
+    PACKET_COUNTER.store(0, Ordering::Relaxed);
+    if usb && !(usb_command(device, 0x02, true) && usb_command(device, 0x03, true) && usb_command(device, 0x02, true)) {
+        warn!("Switch Pro: no reply to the USB handshake, continuing anyway");
+    }
+    if usb {
+        usb_command(device, 0x04, false);
+    }
+    if subcommand(device, SUBCMD_INPUT_MODE, &[0x30]).is_none() {
+        warn!("Switch Pro: full input report mode not acknowledged");
+    }
+    let _ = subcommand(device, SUBCMD_VIBRATION, &[0x01]);
+
+    match spi_read(device, SPI_STICK_CAL, 18) {
+        Some(data) => {
+            let cal = StickCalibration::from_spi(&data);
+            if cal == StickCalibration::default() {
+                warn!("Switch Pro: stick calibration looks blank, using defaults");
+            } else {
+                info!("Switch Pro: factory stick calibration loaded");
+            }
+            cal
+        }
+        None => {
+            warn!("Switch Pro: couldn't read stick calibration, using defaults");
+            StickCalibration::default()
+        }
+    }
+}
+
+/// Player lights (low 4 bits, left to right) and Home button ring (0-15)
+pub fn send_leds(device: &HidDevice, lights: u8, home: u8) {
+    let _ = send_subcommand(device, SUBCMD_PLAYER_LIGHTS, &[lights & 0x0F]);
+    // One mini cycle, held at `home` intensity
+    let home = home.min(0x0F) << 4;
+    let _ = send_subcommand(device, SUBCMD_HOME_LIGHT, &[0x01, home, home, 0x00]);
+}
+
+/// Lit player LEDs carried over from a DualSense mask (5 LEDs) as the same count, from the left
+pub fn player_lights(dualsense_mask: u8) -> u8 {
+    let count = (dualsense_mask & 0x1F).count_ones().min(4);
+    ((1u16 << count) - 1) as u8
+}
+
+/// HD rumble sample for one actuator at 320/160 Hz. Amplitude encoding as documented by
+/// dekuNukem's Nintendo_Switch_Reverse_Engineering (rumble_data_table).
+fn encode_rumble(strength: u8) -> [u8; 4] {
+    if strength == 0 {
+        return RUMBLE_NEUTRAL;
+    }
+    let amp = strength as f32 / 255.0;
+    let encoded = if amp > 0.23 {
+        ((amp * 8.7).log2() * 32.0).round()
+    } else if amp > 0.12 {
+        ((amp * 17.0).log2() * 16.0).round()
+    } else {
+        (amp / 0.12 * 16.0).round()
+    } as u16;
+    let hf_amp = (encoded * 2).min(0xC8) as u8;
+    let lf_amp = (encoded / 2 + 0x40).min(0x72) as u8;
+    [0x00, 0x01 + hf_amp, 0x40, lf_amp]
+}
+
+/// Rumble only (report 0x10). Stays on until the next rumble or subcommand report.
+pub fn send_rumble(device: &HidDevice, left: u8, right: u8) {
+    let mut report = [0u8; OUTPUT_LEN];
+    report[0] = 0x10;
+    report[1] = next_packet();
+    report[2..6].copy_from_slice(&encode_rumble(left));
+    report[6..10].copy_from_slice(&encode_rumble(right));
+    let _ = write_report(device, &report);
+}
+
+/// Full report (0x30) or a subcommand reply (0x21), which starts with the same input state.
+/// Face buttons go by position, so B (bottom) is Cross. ZL/ZR are digital.
+pub fn parse_switch_pro(report: &[u8], cal: &StickCalibration) -> Option<GamepadState> {
+    if report.len() < 12 || !matches!(report[0], 0x30 | 0x21) {
+        return None;
+    }
+    let mut state = GamepadState::default();
+    let (right, shared, left) = (report[3], report[4], report[5]);
+
+    state.btn_square = right & 0x01 != 0;   // Y
+    state.btn_triangle = right & 0x02 != 0; // X
+    state.btn_cross = right & 0x04 != 0;    // B
+    state.btn_circle = right & 0x08 != 0;   // A
+    state.btn_r1 = right & 0x40 != 0;
+    state.r2 = if right & 0x80 != 0 { 1.0 } else { 0.0 };
+
+    state.btn_share = shared & 0x01 != 0;   // Minus
+    state.btn_options = shared & 0x02 != 0; // Plus
+    state.btn_r3 = shared & 0x04 != 0;
+    state.btn_l3 = shared & 0x08 != 0;
+    state.btn_ps = shared & 0x10 != 0;      // Home
+    state.btn_touchpad = shared & 0x20 != 0; // Capture
+
+    state.dpad_down = left & 0x01 != 0;
+    state.dpad_up = left & 0x02 != 0;
+    state.dpad_right = left & 0x04 != 0;
+    state.dpad_left = left & 0x08 != 0;
+    state.btn_l1 = left & 0x40 != 0;
+    state.l2 = if left & 0x80 != 0 { 1.0 } else { 0.0 };
+
+    // Y grows upwards here, GamepadState has it pointing down
+    let (lx, ly) = unpack12(&report[6..9]);
+    let (rx, ry) = unpack12(&report[9..12]);
+    state.left_x = cal.0[0].normalize(lx);
+    state.left_y = -cal.0[1].normalize(ly);
+    state.right_x = cal.0[2].normalize(rx);
+    state.right_y = -cal.0[3].normalize(ry);
+
+    // High nibble: level 0-8 in steps of 2, bit 0 = charging
+    let battery = report[2] >> 4;
+    let level = battery & 0x0E;
+    state.battery = level * 100 / 8;
+    state.charge_state = match (battery & 0x01 != 0, level) {
+        (true, 8) => ChargeState::Full,
+        (true, _) => ChargeState::Charging,
+        _ => ChargeState::Discharging,
+    };
+    state.is_charging = state.charge_state != ChargeState::Discharging;
+
+    Some(state)
+}
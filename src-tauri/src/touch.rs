@@ -0,0 +1,63 @@
+use serde::{Serialize, Deserialize};
+
+use crate::mapping::GamepadState;
+
+// --- Touchpad Calibration ---
+// The touchpad nominally reports 0..1919 x 0..1079, but some units go past the edges (or never
+// reach them), which shows up as cursor jumps and dead zones along the border. Coordinates are
+// always clamped to the nominal range; a calibration learned per VID:PID (config
+// `touch_calibrations`) additionally maps the range the pad really reports onto it.
+
+pub const TOUCH_WIDTH: u16 = 1920;
+pub const TOUCH_HEIGHT: u16 = 1080;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TouchCalibration {
+    pub min_x: u16,
+    pub max_x: u16,
+    pub min_y: u16,
+    pub max_y: u16,
+}
+
+impl Default for TouchCalibration {
+    fn default() -> Self {
+        Self { min_x: 0, max_x: TOUCH_WIDTH - 1, min_y: 0, max_y: TOUCH_HEIGHT - 1 }
+    }
+}
+
+fn rescale(v: u16, min: u16, max: u16, size: u16) -> u16 {
+    if max <= min {
+        return v.min(size - 1);
+    }
+    let t = (v.clamp(min, max) - min) as u32 * (size - 1) as u32 / (max - min) as u32;
+    t as u16
+}
+
+impl TouchCalibration {
+    /// Empty range for learning, widened by `include`
+    pub fn learning() -> Self {
+        Self { min_x: u16::MAX, max_x: 0, min_y: u16::MAX, max_y: 0 }
+    }
+
+    pub fn include(&mut self, x: u16, y: u16) {
+        self.min_x = self.min_x.min(x);
+        self.max_x = self.max_x.max(x);
+        self.min_y = self.min_y.min(y);
+        self.max_y = self.max_y.max(y);
+    }
+
+    /// At least half the nominal size on both axes, so a few taps in the middle don't count
+    pub fn is_plausible(&self) -> bool {
+        self.max_x > self.min_x && self.max_y > self.min_y
+            && self.max_x - self.min_x >= TOUCH_WIDTH / 2
+            && self.max_y - self.min_y >= TOUCH_HEIGHT / 2
+    }
+
+    /// Maps the touch points onto the nominal range (clamping when uncalibrated)
+    pub fn apply(&self, state: &mut GamepadState) {
+        state.touch_x = rescale(state.touch_x, self.min_x, self.max_x, TOUCH_WIDTH);
+        state.touch_y = rescale(state.touch_y, self.min_y, self.max_y, TOUCH_HEIGHT);
+        state.touch2_x = rescale(state.touch2_x, self.min_x, self.max_x, TOUCH_WIDTH);
+        state.touch2_y = rescale(state.touch2_y, self.min_y, self.max_y, TOUCH_HEIGHT);
+    }
+}
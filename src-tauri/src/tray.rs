@@ -0,0 +1,178 @@
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use log::warn;
+use tauri::Manager;
+
+use crate::mapping::ChargeState;
+use crate::state::SharedState;
+use crate::config::APP_NAME;
+use crate::settings;
+
+// --- Tray Status ---
+// Keeps the notification-area tooltip in sync with the controller state, e.g. "DualSense 78% • Profile: FPS",
+// and redraws the icon: grey while searching/paused, colored with a battery bar once connected.
+
+const UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+static BASE_ICON_PNG: &[u8] = include_bytes!("../icons/app_icon.png");
+
+struct BaseIcon {
+    rgba: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+/// Only what changes the picture, so the icon is redrawn rarely
+#[derive(Clone, Copy, PartialEq)]
+struct IconKey {
+    connected: bool,
+    battery: u8, // rounded to 10%
+    charging: bool,
+}
+
+impl IconKey {
+    fn from_state(s: &SharedState) -> Self {
+        let connected = s.device_name != "None" && !s.is_paused;
+        Self {
+            connected,
+            battery: if connected { (s.gamepad.battery.min(100) + 5) / 10 * 10 } else { 0 },
+            charging: connected && s.gamepad.is_charging,
+        }
+    }
+}
+
+fn decode_base_icon() -> Option<BaseIcon> {
+    let mut decoder = png::Decoder::new(BASE_ICON_PNG);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().ok()?;
+    let mut rgba = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut rgba).ok()?;
+    if info.color_type != png::ColorType::Rgba {
+        return None;
+    }
+    rgba.truncate(info.buffer_size());
+    Some(BaseIcon { rgba, width: info.width, height: info.height })
+}
+
+fn fill_rect(icon: &mut [u8], width: u32, x0: u32, y0: u32, x1: u32, y1: u32, color: [u8; 4]) {
+    for y in y0..y1 {
+        for x in x0..x1 {
+            let i = ((y * width + x) * 4) as usize;
+            icon[i..i + 4].copy_from_slice(&color);
+        }
+    }
+}
+
+fn render_icon(base: &BaseIcon, key: IconKey) -> Vec<u8> {
+    let mut icon = base.rgba.clone();
+    let (w, h) = (base.width, base.height);
+
+    if !key.connected {
+        for px in icon.chunks_exact_mut(4) {
+            let lum = ((px[0] as u32 * 30 + px[1] as u32 * 59 + px[2] as u32 * 11) / 100 * 7 / 10) as u8;
+            px[0] = lum;
+            px[1] = lum;
+            px[2] = lum;
+        }
+        return icon;
+    }
+
+    // Battery bar along the bottom edge
+    let (x0, x1) = (w * 15 / 100, w * 85 / 100);
+    let (y0, y1) = (h * 76 / 100, h * 96 / 100);
+    let border = (h / 32).max(1);
+    fill_rect(&mut icon, w, x0, y0, x1, y1, [0, 0, 0, 230]);
+
+    let color = if key.charging {
+        [0, 170, 255, 255]
+    } else if key.battery >= 50 {
+        [0, 200, 80, 255]
+    } else if key.battery >= 20 {
+        [255, 190, 0, 255]
+    } else {
+        [230, 40, 40, 255]
+    };
+    let inner_w = (x1 - x0) - border * 2;
+    let level_w = (inner_w * key.battery.max(10) as u32 / 100).max(1);
+    fill_rect(&mut icon, w, x0 + border, y0 + border, x0 + border + level_w, y1 - border, color);
+    icon
+}
+
+/// "DualSense Wireless Controller" -> "DualSense", DS4 just reports "Wireless Controller"
+fn short_device_name(name: &str) -> String {
+    match name.trim().strip_suffix("Wireless Controller").map(str::trim) {
+        Some("") => "DualShock 4".to_string(),
+        Some(short) => short.to_string(),
+        None => name.trim().to_string(),
+    }
+}
+
+fn tooltip_text(s: &SharedState) -> String {
+    let head = if s.is_paused {
+        format!("{} • Paused", APP_NAME)
+    } else if s.device_name != "None" {
+        let charging = match s.gamepad.charge_state {
+            ChargeState::Discharging => "",
+            ChargeState::Charging => " (charging)",
+            ChargeState::Full => " (full)",
+            ChargeState::Error => " (charging error!)",
+        };
+        format!("{} {}%{}", short_device_name(&s.device_name), s.gamepad.battery, charging)
+    } else {
+        format!("{} • {}", APP_NAME, s.status)
+    };
+
+    let profile = &settings::read().current_profile_name;
+    if profile.is_empty() {
+        head
+    } else {
+        format!("{} • Profile: {}", head, profile)
+    }
+}
+
+pub fn spawn_updater(app_handle: tauri::AppHandle, state: Arc<Mutex<SharedState>>) {
+    // Battery changes redraw right away instead of on the next tick
+    let (wake_tx, wake_rx) = mpsc::channel();
+    app_handle.listen_global("battery-changed", move |_| {
+        let _ = wake_tx.send(());
+    });
+
+    thread::spawn(move || {
+        let base_icon = decode_base_icon();
+        if base_icon.is_none() {
+            warn!("Failed to decode tray icon, status icon disabled.");
+        }
+
+        let mut last_tooltip = String::new();
+        let mut last_icon: Option<IconKey> = None;
+        loop {
+            let (tooltip, icon_key) = {
+                let s = state.lock().unwrap();
+                if s.should_exit {
+                    break;
+                }
+                (tooltip_text(&s), IconKey::from_state(&s))
+            };
+
+            if let Some(base) = &base_icon {
+                if last_icon != Some(icon_key) {
+                    let icon = tauri::Icon::Rgba { rgba: render_icon(base, icon_key), width: base.width, height: base.height };
+                    if let Err(e) = app_handle.tray_handle().set_icon(icon) {
+                        warn!("Failed to update tray icon: {}", e);
+                    }
+                    last_icon = Some(icon_key);
+                }
+            }
+
+            if tooltip != last_tooltip {
+                if let Err(e) = app_handle.tray_handle().set_tooltip(&tooltip) {
+                    warn!("Failed to update tray tooltip: {}", e);
+                }
+                last_tooltip = tooltip;
+            }
+            let _ = wake_rx.recv_timeout(UPDATE_INTERVAL);
+        }
+    });
+}
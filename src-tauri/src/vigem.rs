@@ -0,0 +1,45 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use serde::Serialize;
+
+use crate::platform::{Bus, PadBus};
+
+// --- ViGEmBus Health ---
+// What the diagnostics panel shows instead of a bare "ViGEmBus Error": whether the driver is
+// installed, its file version (old 1.16 builds are a common cause of plugin failures), whether a
+// client connection works right now and how many virtual pads this app has plugged in.
+// On Linux the same fields describe /dev/uinput (version = kernel release).
+
+// Virtual pads currently plugged in by the worker (controller or keyboard/mouse mode)
+static PLUGGED: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Clone, Serialize)]
+pub struct VigemHealth {
+    pub installed: bool,
+    pub driver_version: Option<String>,
+    pub connected: bool,
+    pub error: Option<String>, // Why connecting failed
+    pub plugged_targets: u32,
+}
+
+pub fn plugged() {
+    PLUGGED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn unplugged() {
+    let _ = PLUGGED.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+}
+
+/// Opens a fresh client connection to test the bus, the worker's own one is left alone
+pub fn health() -> VigemHealth {
+    let (connected, error) = match Bus::connect() {
+        Ok(_) => (true, None),
+        Err(e) => (false, Some(e)),
+    };
+    VigemHealth {
+        installed: Bus::is_installed(),
+        driver_version: Bus::driver_version(),
+        connected,
+        error,
+        plugged_targets: PLUGGED.load(Ordering::Relaxed),
+    }
+}
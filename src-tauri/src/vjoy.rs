@@ -0,0 +1,196 @@
+use std::ffi::c_void;
+use log::info;
+use serde::{Serialize, Deserialize};
+
+use crate::platform;
+
+// --- vJoy Output ---
+// Optional second output next to the virtual Xbox pad, for flight/truck sims that want more axes
+// and buttons than XInput has. Mappings target vJoy axes (VJoyAxis/VJoyStick) and buttons; the
+// worker collects them into a VJoyFrame per update and only changed values are written.
+// vJoyInterface.dll ships with the vJoy driver and is loaded at runtime, so DX3 runs without it.
+
+const DLL_PATHS: &[&str] = &[r"C:\Program Files\vJoy\x64\vJoyInterface.dll", "vJoyInterface.dll"];
+
+// GetVJDStatus
+const VJD_STAT_OWN: i32 = 0;
+const VJD_STAT_FREE: i32 = 1;
+const VJD_STAT_BUSY: i32 = 2;
+const VJD_STAT_MISS: i32 = 3;
+
+const DEFAULT_AXIS_MAX: i32 = 0x8000;
+pub const BUTTON_COUNT: usize = 128;
+
+type EnabledFn = unsafe extern "C" fn() -> i32;
+type StatusFn = unsafe extern "C" fn(u32) -> i32;
+type AcquireFn = unsafe extern "C" fn(u32) -> i32;
+type RelinquishFn = unsafe extern "C" fn(u32);
+type ResetFn = unsafe extern "C" fn(u32) -> i32;
+type AxisExistFn = unsafe extern "C" fn(u32, u32) -> i32;
+type AxisMaxFn = unsafe extern "C" fn(u32, u32, *mut i32) -> i32;
+type SetAxisFn = unsafe extern "C" fn(i32, u32, u32) -> i32;
+type SetBtnFn = unsafe extern "C" fn(i32, u32, u8) -> i32;
+
+/// vJoy axes, by HID usage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum VJoyAxis {
+    X, Y, Z, Rx, Ry, Rz, Slider0, Slider1,
+}
+
+impl VJoyAxis {
+    pub const ALL: [VJoyAxis; 8] = [Self::X, Self::Y, Self::Z, Self::Rx, Self::Ry, Self::Rz, Self::Slider0, Self::Slider1];
+
+    fn usage(self) -> u32 {
+        0x30 + self as u32
+    }
+}
+
+/// Everything mapped to vJoy for one update. Axes are 0.0-1.0 (0.5 = centered), buttons 1-128 as bits 0-127.
+#[derive(Clone, Copy, PartialEq)]
+pub struct VJoyFrame {
+    pub axes: [f32; 8],
+    pub buttons: u128,
+}
+
+impl Default for VJoyFrame {
+    fn default() -> Self {
+        Self { axes: [0.5; 8], buttons: 0 }
+    }
+}
+
+impl VJoyFrame {
+    pub fn set_axis(&mut self, axis: VJoyAxis, value: f32) {
+        self.axes[axis as usize] = value.clamp(0.0, 1.0);
+    }
+
+    /// Stick/trigger values as mapped (-1.0..1.0 for sticks)
+    pub fn set_stick_axis(&mut self, axis: VJoyAxis, value: f32) {
+        self.set_axis(axis, (value + 1.0) / 2.0);
+    }
+
+    pub fn press(&mut self, button: u8) {
+        if (1..=BUTTON_COUNT as u8).contains(&button) {
+            self.buttons |= 1 << (button - 1);
+        }
+    }
+}
+
+struct Api {
+    module: *mut c_void,
+    reset: ResetFn,
+    relinquish: RelinquishFn,
+    set_axis: SetAxisFn,
+    set_btn: SetBtnFn,
+}
+
+/// One acquired vJoy device, released (and reset to neutral) on drop
+pub struct VJoy {
+    api: Api,
+    id: u32,
+    axis_max: [i32; 8],
+    axis_exists: [bool; 8],
+    sent: Option<VJoyFrame>,
+}
+
+// The raw module handle is only used from the worker thread that owns the device
+unsafe impl Send for VJoy {}
+
+impl VJoy {
+    /// Loads vJoyInterface.dll and acquires device `id` (1-16)
+    pub fn open(id: u8) -> Result<Self, String> {
+        let module = DLL_PATHS.iter().find_map(|p| {
+            let m = unsafe { platform::load_library(p) };
+            (!m.is_null()).then_some(m)
+        }).ok_or("vJoy is not installed (vJoyInterface.dll not found)")?;
+
+        let result = unsafe { Self::acquire(module, id as u32) };
+        if result.is_err() {
+            unsafe { platform::free_library(module); }
+        }
+        result
+    }
+
+    unsafe fn acquire(module: *mut c_void, id: u32) -> Result<Self, String> {
+        macro_rules! proc {
+            ($name:literal, $ty:ty) => {{
+                let f = platform::get_proc_address(module, concat!($name, "\0").as_ptr());
+                if f.is_null() {
+                    return Err(format!("vJoyInterface.dll has no {}", $name));
+                }
+                std::mem::transmute::<*mut c_void, $ty>(f)
+            }};
+        }
+        let enabled = proc!("vJoyEnabled", EnabledFn);
+        let status = proc!("GetVJDStatus", StatusFn);
+        let acquire = proc!("AcquireVJD", AcquireFn);
+        let axis_max = proc!("GetVJDAxisMax", AxisMaxFn);
+        let axis_exists = proc!("GetVJDAxisExist", AxisExistFn);
+        let api = Api {
+            module,
+            reset: proc!("ResetVJD", ResetFn),
+            relinquish: proc!("RelinquishVJD", RelinquishFn),
+            set_axis: proc!("SetAxis", SetAxisFn),
+            set_btn: proc!("SetBtn", SetBtnFn),
+        };
+
+        if enabled() == 0 {
+            return Err("vJoy driver is disabled".to_string());
+        }
+        match status(id) {
+            VJD_STAT_OWN | VJD_STAT_FREE => {}
+            VJD_STAT_BUSY => return Err(format!("vJoy device {} is in use by another program", id)),
+            VJD_STAT_MISS => return Err(format!("vJoy device {} isn't configured", id)),
+            _ => return Err(format!("vJoy device {} is in an unknown state", id)),
+        }
+        if acquire(id) == 0 {
+            return Err(format!("Failed to acquire vJoy device {}", id));
+        }
+        (api.reset)(id);
+
+        let mut max = [DEFAULT_AXIS_MAX; 8];
+        let mut exists = [false; 8];
+        for axis in VJoyAxis::ALL {
+            let i = axis as usize;
+            exists[i] = axis_exists(id, axis.usage()) != 0;
+            let mut m = 0;
+            if exists[i] && axis_max(id, axis.usage(), &mut m) != 0 && m > 0 {
+                max[i] = m;
+            }
+        }
+        info!("vJoy device {} acquired", id);
+        Ok(Self { api, id, axis_max: max, axis_exists: exists, sent: None })
+    }
+
+    /// Writes what changed since the last frame
+    pub fn update(&mut self, frame: &VJoyFrame) {
+        if self.sent.as_ref() == Some(frame) {
+            return;
+        }
+        let prev = self.sent.unwrap_or(VJoyFrame { axes: [f32::NAN; 8], buttons: !frame.buttons });
+        for axis in VJoyAxis::ALL {
+            let i = axis as usize;
+            if self.axis_exists[i] && frame.axes[i] != prev.axes[i] {
+                let value = (frame.axes[i] * self.axis_max[i] as f32).round() as i32;
+                unsafe { (self.api.set_axis)(value, self.id, axis.usage()); }
+            }
+        }
+        // Buttons the device isn't configured with are ignored by the driver
+        let changed = frame.buttons ^ prev.buttons;
+        for bit in (0..BUTTON_COUNT).filter(|b| changed & (1 << b) != 0) {
+            let pressed = frame.buttons & (1 << bit) != 0;
+            unsafe { (self.api.set_btn)(pressed as i32, self.id, bit as u8 + 1); }
+        }
+        self.sent = Some(*frame);
+    }
+}
+
+impl Drop for VJoy {
+    fn drop(&mut self) {
+        unsafe {
+            (self.api.reset)(self.id);
+            (self.api.relinquish)(self.id);
+            platform::free_library(self.api.module);
+        }
+        info!("vJoy device {} released", self.id);
+    }
+}
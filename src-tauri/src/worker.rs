@@ -1,46 +1,123 @@
 use hidapi::HidApi;
-use vigem_client::{Client, XGamepad, TargetId, Xbox360Wired};
 use std::thread;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
 use std::time::{Duration, Instant};
 use std::collections::HashSet;
 use log::{info, warn};
 use tauri::Manager; // For emit_all
 
-use crate::state::SharedState;
-use crate::mapping::{GamepadState, parse_dualsense, parse_ds4, MappingTarget};
-use crate::hidhide;
-use crate::dualsense::{send_dualsense_output, send_raw_output};
-use crate::crc;
-
-use windows::Win32::UI::Input::KeyboardAndMouse::{
-    SendInput, INPUT, INPUT_0, KEYBDINPUT, MOUSEINPUT, KEYBD_EVENT_FLAGS,
-    VIRTUAL_KEY, KEYEVENTF_KEYUP, KEYEVENTF_SCANCODE, KEYEVENTF_EXTENDEDKEY,
-    MapVirtualKeyW, MAPVK_VK_TO_VSC,
-    MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP, 
-    MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP,
-    MOUSEEVENTF_MIDDLEDOWN, MOUSEEVENTF_MIDDLEUP, 
-    MOUSEEVENTF_MOVE, MOUSEEVENTF_WHEEL,
-    INPUT_KEYBOARD, INPUT_MOUSE
-};
+use crate::state::{SharedState, Status, FoundController};
+use crate::mapping::{GamepadState, ChargeState, Orientation, AutoSprint, TriggerCurve, ResponseCurve, DeadzoneShape, KeyInjection, InputBackend, parse_dualsense, parse_ds4, MappingTarget, MacroStep, PhysicalButton};
+use crate::dualsense::{send_dualsense_output, trigger_effect, is_live_trigger_mode, send_raw_output, write_report, write_bt_report, send_feature};
+use crate::script_fuzz::ScriptFuzzer;
+use crate::layout::{ReportLayout, LayoutProbe, ProbeResult};
+use crate::hid_descriptor::{self, DescriptorLayout};
+use crate::touch::{TouchCalibration, TOUCH_WIDTH, TOUCH_HEIGHT};
+use crate::stick_cal::{self, StickSampler};
+use crate::gyro_cal::{self, GyroSampler};
+use crate::capture::{self, Direction};
+use crate::hooks::{self, HookEvent};
+use crate::kbm::KbmPad;
+use crate::stats;
+use crate::recording;
+use crate::fuzzlog;
+use crate::settings;
+use crate::vigem;
+use crate::vjoy::{VJoy, VJoyFrame};
+use crate::keys::KeyStroke;
+use crate::interception;
+use crate::switch_pro::{self, StickCalibration};
+use crate::platform::{self, Bus, Input, Hider, PadBus, VirtualPad, InputInjector, DeviceHider, PadReport, PadFeedback};
+
 
 const VID_SONY: u16 = 0x054C;
 const PID_DS4_V1: u16 = 0x05C4;
 const PID_DS4_V2: u16 = 0x09CC;
 const PID_DUALSENSE: u16 = 0x0CE6;
+const PID_DUALSENSE_EDGE: u16 = 0x0DF2;
+const VID_NINTENDO: u16 = 0x057E;
+const PID_SWITCH_PRO: u16 = 0x2009; // Also 8BitDo pads in Switch mode
+
+// Idle power-off: LED warning shown this long before the controller is switched off
+const IDLE_WARNING: Duration = Duration::from_secs(10);
+
+// BT DualSense: no input for this long means the pad went to sleep (or out of range). While it
+// stays quiet the handle is probed at this interval, a dead one is closed instead of waiting for a
+// read error.
+const SLEEP_GAP: Duration = Duration::from_secs(3);
+
+// Input reports are read on a thread of their own (second handle, blocking read) and handed over
+// through a single slot, so the worker wakes the moment one arrives and always gets the newest.
+// The worker waits at most IDLE_TICK for the next one to keep housekeeping and mouse output going;
+// the reader checks for shutdown at READER_POLL_MS.
+const IDLE_TICK: Duration = Duration::from_millis(10);
+const READER_POLL_MS: i32 = 100;
+
+// Dynamic and Rumble trigger resistance (dualsense::trigger_effect): resent when the force has moved
+// by at least DYNAMIC_TRIGGER_STEP, checked at most every DYNAMIC_TRIGGER_INTERVAL
+const DYNAMIC_TRIGGER_STEP: u8 = 8;
+const DYNAMIC_TRIGGER_INTERVAL: Duration = Duration::from_millis(33);
+
+// Raw stick stream for the calibration view: batched every 16ms, capped in case nothing drains it
+const MAX_STREAM_SAMPLES: usize = 4096;
+
+// Hold PS (alone) to bring up the DX3 window
+const PS_HOLD: Duration = Duration::from_secs(2);
+const PS_TAP: Duration = Duration::from_millis(80);
+
+/// PS is held back from the virtual pad until we know it isn't the long-press gesture.
+/// A short press is replayed as a brief Guide tap; PS + another button passes straight through.
+#[derive(Default)]
+struct PsGesture {
+    down_since: Option<Instant>,
+    fired: bool,
+    passthrough: bool,
+    tap_until: Option<Instant>,
+}
+
+impl PsGesture {
+    /// Rewrites `s.btn_ps` for the virtual pad. Returns true once when the hold completes.
+    fn update(&mut self, s: &mut GamepadState) -> bool {
+        let now = Instant::now();
+        let mut fire = false;
+
+        if s.btn_ps {
+            let since = *self.down_since.get_or_insert(now);
+            if any_button_besides_ps(s) {
+                self.passthrough = true;
+            }
+            if !self.passthrough && !self.fired && now.duration_since(since) >= PS_HOLD {
+                self.fired = true;
+                fire = true;
+            }
+        } else if self.down_since.take().is_some() {
+            if !self.fired && !self.passthrough {
+                self.tap_until = Some(now + PS_TAP);
+            }
+            self.fired = false;
+            self.passthrough = false;
+        }
+
+        let tap = self.tap_until.is_some_and(|t| now < t);
+        s.btn_ps = (s.btn_ps && self.passthrough) || tap;
+        fire
+    }
+}
 
 // --- Background Controller Thread ---
 
 pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppHandle) {
     // Helper to update status safely
-    let set_status = |s: &str, dev: &str| {
+    let set_status = |s: Status, dev: &str| {
         let mut locked = state.lock().unwrap();
-        locked.status = s.to_string();
-        locked.device_name = dev.to_string();
         // Clear visuals if we are not actively connected
-        if s.contains("Wait") || s.contains("Disconnected") || s.contains("Searching") {
+        if s.clears_input() {
             locked.gamepad = GamepadState::default();
         }
+        locked.status = s;
+        locked.device_name = dev.to_string();
     };
 
     let mut last_sent_state = GamepadState::default();
@@ -53,41 +130,23 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
             break;
         }
 
-        set_status("Initializing ViGEm...", "None");
+        set_status(Status::InitializingVigem, "None");
         
-        // Connect to ViGEmBus
-        let vigem = match Client::connect() {
-            Ok(c) => {
-                {
-                    let mut s = state.lock().unwrap();
-                    s.vigembus_available = true;
-                }
-                let _ = app_handle.emit_all("update-state", &*state.lock().unwrap());
-                c
-            },
+        // Connect to the pad bus (ViGEmBus, uinput). Without it the controller is still read and
+        // keyboard/mouse mappings work, there's just no virtual pad. The connection is retried with
+        // every re-init (the soft re-init while no controller is found, or the 'Check' button).
+        let (bus, bus_error) = match Bus::connect() {
+            Ok(b) => (Some(b), None),
             Err(e) => {
-                {
-                    let mut s = state.lock().unwrap();
-                    s.vigembus_available = false;
-                }
-                let err_msg = format!("ViGEmBus Error: {}", e);
-                set_status(&err_msg, "None");
-                let _ = app_handle.emit_all("update-state", &*state.lock().unwrap());
-                
-                // Manual Retry Loop
-                // Wait 2s before retrying. User can click 'Check' to set should_reinit, 
-                // which will be caught at the start of the next outer loop iteration.
-                thread::sleep(Duration::from_secs(2));
-                
-                let mut s = state.lock().unwrap();
-                if s.should_exit { return; }
-                s.should_reinit = false; // Clear any pending reinit to start fresh
-                continue;
+                warn!("Virtual pad bus unavailable, continuing with keyboard/mouse mappings only: {}", e);
+                (None, Some(e))
             }
         };
+        state.lock().unwrap().vigembus_available = bus.is_some();
+        let _ = app_handle.emit_all("update-state", &*state.lock().unwrap());
         
         // Attempt to whitelist self in HidHide
-        let hh_installed = hidhide::is_installed();
+        let hh_installed = Hider::is_installed();
         {
             let mut s = state.lock().unwrap();
             s.hidhide_available = hh_installed;
@@ -95,20 +154,19 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
         let _ = app_handle.emit_all("update-state", &*state.lock().unwrap());
 
         if hh_installed {
-            if let Err(e) = hidhide::whitelist_self() {
+            if let Err(e) = Hider::whitelist_self() {
                 warn!("Failed to whitelist self in HidHide: {}", e);
             }
             // Give Windows a moment to apply HidHide whitelist before opening HID
             thread::sleep(Duration::from_millis(500));
         }
 
-        set_status("Scanning for controllers...", "None");
+        set_status(Status::Scanning, "None");
         
         let mut hid = match HidApi::new() {
             Ok(h) => h,
             Err(e) => {
-                let err_msg = format!("HID Error: {}", e);
-                set_status(&err_msg, "None");
+                set_status(Status::HidError(e.to_string()), "None");
                 let _ = app_handle.emit_all("update-state", &*state.lock().unwrap());
                 
                 thread::sleep(Duration::from_secs(2));
@@ -123,16 +181,19 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
 
         // Main scanning loop
         loop {
-            // Check pause state
-            let paused = state.lock().unwrap().is_paused;
+            if state.lock().unwrap().should_exit {
+                break;
+            }
+
+            // Check pause state (control API, or asleep/locked)
+            let paused = {
+                let s = state.lock().unwrap();
+                s.is_paused || s.system_suspended
+            };
             if paused {
                 thread::sleep(Duration::from_secs(1));
                 continue;
             }
-            
-            if state.lock().unwrap().should_exit {
-                break;
-            }
 
             // Check for Manual Driver Refresh Request
             {
@@ -161,7 +222,7 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
             let mut best_candidate = None;
 
             for device_info in devices {
-            if device_info.vendor_id() == VID_SONY {
+            if matches!(device_info.vendor_id(), VID_SONY | VID_NINTENDO) {
                 let pid = device_info.product_id();
                 let iface = device_info.interface_number();
                 let up = device_info.usage_page();
@@ -170,10 +231,7 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
                 log_buf.push_str(&format!("PID:{:04X} Iface:{} UP:{} U:{} \nPath:{}\n\n", 
                     pid, iface, up, u, device_info.path().to_str().unwrap_or("?")));
 
-                let is_ds4 = pid == PID_DS4_V1 || pid == PID_DS4_V2;
-                let is_dualsense = pid == PID_DUALSENSE;
-
-                if is_ds4 || is_dualsense {
+                if is_supported(device_info.vendor_id(), pid) {
                     // Score candidates
                     // Priority 1: Generic Desktop (1) + Gamepad (5)
                     if up == 1 && u == 5 {
@@ -188,101 +246,122 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
             }
         }
 
+        // Manual-connect mode: list what's there and only attach the controller connect_device picked
+        if settings::read().manual_connect {
+            let controllers = list_controllers(&hid);
+            let mut s = state.lock().unwrap();
+            best_candidate = s.connect_request.as_ref().and_then(|path| hid.device_list().find(|d| d.path().to_str() == Ok(path.as_str())));
+            if best_candidate.is_none() {
+                s.connect_request = None; // Unplugged before we got to it
+            }
+            s.found_controllers = controllers;
+        } else {
+            state.lock().unwrap().found_controllers.clear();
+        }
+
         if let Some(device_info) = best_candidate {
             {
                 let name = device_info.product_string().unwrap_or("Unknown").to_string();
                 let dev_path_clone = device_info.path().to_str().unwrap_or("?").to_string();
                 let pid = device_info.product_id();
-                let is_dualsense = pid == PID_DUALSENSE;
+                let vid = device_info.vendor_id();
+                let is_dualsense = vid == VID_SONY && matches!(pid, PID_DUALSENSE | PID_DUALSENSE_EDGE);
+                let is_switch = vid == VID_NINTENDO && pid == PID_SWITCH_PRO;
+                let serial = device_info.serial_number().unwrap_or("").to_string();
+                let device_key = format!("{:04X}:{:04X}", vid, pid);
                 
                 // Identify Instance ID for HidHide EARLY (Pre-emptive Strike)
-                let instance_id = hidhide::path_to_instance_id(device_info.path().to_str().unwrap_or(""));
+                let instance_id = Hider::device_id(device_info.path().to_str().unwrap_or(""));
                 let mut is_hidden = false;
 
                 // Attempt to hide BEFORE opening the device to race against Steam/Games
                 if let Some(inst) = &instance_id {
                     let mut s = state.lock().unwrap();
-                    if s.hide_controller {
-                        if let Ok(_) = hidhide::hide_device(inst) {
+                    if settings::read().hide_controller {
+                        if let Ok(_) = Hider::hide(inst) {
                             s.hidden_device_id = Some(inst.clone());
                             is_hidden = true;
                         }
                     }
                 }
 
-                if let Ok(device) = device_info.open_device(&hid) {
-                    set_status(&format!("Active (Iface {})", device_info.interface_number()), &name);
+                if let (Ok(device), Ok(read_handle)) = (device_info.open_device(&hid), device_info.open_device(&hid)) {
+                    set_status(Status::Active { interface: device_info.interface_number() }, &name);
+                    let on_connect = settings::read().on_connect_cmd.clone();
+                    hooks::run(&on_connect, HookEvent::Connect, &name, &serial);
                     state.lock().unwrap().device_path_str = dev_path_clone;
+                    state.lock().unwrap().device_key = device_key.clone();
+                    crate::dualsense::reset_bt_seq();
                     state.lock().unwrap().detected_devices_log = log_buf.clone();
                     found = true;
 
-                    // Create Virtual Xbox 360 (but don't plugin yet)
-                    let mut target = Xbox360Wired::new(vigem.try_clone().unwrap(), TargetId::XBOX360_WIRED);
+                    // Create the virtual pad (but don't plugin yet)
+                    let mut pad_kind = settings::read().pad_kind;
+                    let mut target = bus.as_ref().map(|b| b.create_pad(pad_kind));
+                    if let Some(e) = &bus_error {
+                        set_status(Status::NoVirtualPad(e.clone()), &name);
+                    }
                     let mut is_plugged = false;
+                    let pad_feedback = Arc::new(PadFeedback::default()); // Player slot and game rumble, from ViGEm
+                    let mut shown_slot = 0u8;
                     
                     // DualSense Connection Mode
                     let is_bt = is_dualsense && device_info.interface_number() == -1;
 
                     // === CRITICAL: Enable Enhanced Mode for Bluetooth ===
-                    // DualSense defaults to Simple Mode (DirectInput) over BT,
-                    // where LED/Haptics/Triggers are unavailable. Reading Feature Report 0x09
-                    // (serial number) or 0x20 (firmware) activates Enhanced Mode.
                     if is_dualsense && is_bt {
-                        let mut feature_buf = [0u8; 64];
-                        feature_buf[0] = 0x09; // Feature Report ID for serial number
-                        match device.get_feature_report(&mut feature_buf) {
-                            Ok(len) => {
-                                info!("DualSense BT: Enhanced Mode activated via Feature Report 0x09 ({} bytes)", len);
-                            }
-                            Err(e) => {
-                                warn!("DualSense BT: Failed to read Feature Report 0x09: {} — LED may not work!", e);
-                                // Try alternative Feature Report 0x20
-                                feature_buf[0] = 0x20;
-                                if let Ok(len) = device.get_feature_report(&mut feature_buf) {
-                                    info!("DualSense BT: Enhanced Mode activated via Feature Report 0x20 ({} bytes)", len);
-                                }
-                            }
-                        }
+                        crate::dualsense::activate_enhanced_mode(&device);
                     }
 
+                    // Settings snapshot, re-read whenever settings::version() moves
+                    let mut cfg_version = settings::version();
+                    let mut cfg = settings::read().clone();
+
                     // Initial LED Setup
                     if is_dualsense {
-                        let (r, g, b, bright, show_bat, l2_m, l2_s, l2_f, r2_m, r2_s, r2_f, pled_bright) = {
-                            let s = state.lock().unwrap();
-                            (s.rgb_r, s.rgb_g, s.rgb_b, s.rgb_brightness, s.show_battery_led,
-                             s.trigger_l2_mode, s.trigger_l2_start, s.trigger_l2_force,
-                             s.trigger_r2_mode, s.trigger_r2_start, s.trigger_r2_force,
-                             s.player_led_brightness)
-                        };
-                        let pled = if show_bat {
-                            get_battery_led_mask(last_sent_state.battery)
+                        let pled = if cfg.show_battery_led {
+                            get_battery_led_mask(&last_sent_state, blink_phase())
                         } else {
                             0x04 // Standard Center LED
                         };
 
                         // Apply brightness scaling
-                        let bf = bright as f32 / 255.0;
-                        let fr = (r as f32 * bf) as u8;
-                        let fg = (g as f32 * bf) as u8;
-                        let fb = (b as f32 * bf) as u8;
+                        let bf = cfg.rgb_brightness as f32 / 255.0;
+                        let fr = (cfg.rgb_r as f32 * bf) as u8;
+                        let fg = (cfg.rgb_g as f32 * bf) as u8;
+                        let fb = (cfg.rgb_b as f32 * bf) as u8;
+                        let quiet = cfg.quiet_hours.is_active();
+                        let ((fr, fg, fb), pled) = if quiet {
+                            (cfg.quiet_hours.dim((fr, fg, fb)), cfg.quiet_hours.mask_player_leds(pled))
+                        } else {
+                            ((fr, fg, fb), pled)
+                        };
                         
                         // Wake-up to initialize controller LEDs (+ short rumble)
                         if is_bt {
-                            crate::dualsense::send_led_init(&device, 0, pled, fr, fg, fb);
+                            crate::dualsense::send_led_init(&device, pled, fr, fg, fb);
                         } else {
-                            crate::dualsense::send_led_init_usb(&device, pled, fr, fg, fb);
+                            crate::dualsense::send_led_init_usb(&device, pled, fr, fg, fb, !quiet);
                         }
                         thread::sleep(Duration::from_millis(50));
                         
-                        send_dualsense_output(&device, is_bt, fr, fg, fb, pled, pled_bright, 0, l2_m, l2_s, l2_f, r2_m, r2_s, r2_f);
+                        let [(l2_m, l2_s, l2_f), (r2_m, r2_s, r2_f)] = cfg.trigger_fx();
+                        let (l2_m, l2_s, l2_f) = trigger_effect(l2_m, l2_s, l2_f, 0.0, 0.0);
+                        let (r2_m, r2_s, r2_f) = trigger_effect(r2_m, r2_s, r2_f, 0.0, 0.0);
+                        send_dualsense_output(&device, is_bt, fr, fg, fb, pled, cfg.player_led_brightness, l2_m, l2_s, l2_f, r2_m, r2_s, r2_f);
                     }
 
+                    // Switch Pro: handshake and calibration, before the reader thread takes the input reports
+                    let switch_cal: Option<StickCalibration> = is_switch.then(|| switch_pro::init(&device, device_info.interface_number() != -1));
+
                     // Input Loop State
                     let mut simple_mode_counter = 0;
-                    let mut buf = [0u8; 128];
+                    let reader = InputReader::spawn(read_handle);
+                    stats::reset_baseline();
                     let mut last_led_update = Instant::now();
                     let mut last_sweep_update = Instant::now();
                     let mut last_fuzzer_update = Instant::now();
+                    let mut script_fuzzer: Option<(String, ScriptFuzzer)> = None; // (source, compiled)
                     let mut last_periodic_update = Instant::now();
                     let mut last_hidhide_check = Instant::now();
                     let mut last_ui_update = Instant::now();
@@ -299,23 +378,96 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
                     let mut last_touch_y = 0u16;
                     let mut last_touch_active = false;
                     let mut smoothed_touch = (0.0f32, 0.0f32); // [dx, dy]
-
-                    let mut local_mappings = {
+                    let mut pinch = PinchState::default();
+                    let mut trackball = Trackball::default();
+                    let mut vjoy: Option<VJoy> = None;
+                    let mut vjoy_device = 0u8;
+                    let mut input_backend = InputBackend::SendInput;
+
+                    let mut local_mappings = cfg.mappings.clone();
+                    let (mut local_deadzone_l, mut local_deadzone_r, mut local_mouse_sens_l, mut local_mouse_sens_r, mut local_mouse_sens_touchpad) =
+                        (cfg.deadzone_left, cfg.deadzone_right, cfg.mouse_sens_left, cfg.mouse_sens_right, cfg.mouse_sens_touchpad);
+                    let mut local_trigger_curves = [cfg.trigger_curve_l.clone(), cfg.trigger_curve_r.clone()];
+                    let mut local_stick_curves = [cfg.stick_curve_l.clone(), cfg.stick_curve_r.clone()];
+                    let mut local_anti_deadzones = [cfg.anti_deadzone_left, cfg.anti_deadzone_right];
+                    let mut local_deadzone_shapes = [cfg.deadzone_shape_left, cfg.deadzone_shape_right];
+
+                    // Idle Power-Off State (BT DualSense only)
+                    let mut local_idle_power_off = cfg.idle_power_off_min;
+                    let mut last_activity = Instant::now();
+                    let mut activity_ref = GamepadState::default();
+                    let mut idle_warning = false;
+
+                    let mut local_ps_gesture = cfg.ps_hold_opens_window;
+                    let mut local_desktop_pause = false;
+                    let mut ps_gesture = PsGesture::default();
+                    let mut show_window_requested = false;
+                    let mut auto_sprint = AutoSprintState::default();
+                    let mut macros = MacroPlayer::default();
+                    let mut turbo = Turbo::default();
+                    let mut stick_dpad = StickDpad::default();
+                    let mut frame_modes = FrameModes::default();
+                    let mut gyro_recenter_held = false;
+                    let mut alert: Option<Alert> = None;
+                    let mut local_trigger_fx = cfg.trigger_fx();
+                    let mut trigger_forces_sent = [0u8; 2];
+                    // Last LED/trigger payload written; None re-sends on the next tick (new connection, mode change)
+                    let mut last_output: Option<OutputPayload> = None;
+                    let mut last_output_sent = Instant::now();
+                    let mut last_trigger_check = Instant::now();
+                    let mut local_haptic_alerts = cfg.haptic_alerts;
+                    let mut battery_alerted: Option<u8> = None; // Lowest LOW_BATTERY_ALERTS level already signalled
+                    let mut battery_sent = BatteryChanged::from_gamepad(&GamepadState::default());
+                    {
                         let mut s = state.lock().unwrap();
-                        s.mappings_changed = false; 
-                        s.mappings.clone()
-                    };
-                    let (mut local_deadzone_l, mut local_deadzone_r, mut local_mouse_sens_l, mut local_mouse_sens_r, mut local_mouse_sens_touchpad) = {
-                        let s = state.lock().unwrap();
-                        (s.deadzone_left, s.deadzone_right, s.mouse_sens_left, s.mouse_sens_right, s.mouse_sens_touchpad)
-                    };
+                        s.should_confirm_profile = false; // Switched while disconnected
+                        s.should_identify = false;
+                    }
+
+                    // Sleep/Wake (BT DualSense)
+                    let mut last_report_at = Instant::now();
+                    let mut last_sleep_probe = Instant::now();
+                    let mut asleep = false;
+
+                    // Power-Off Chord State (BT DualSense only)
+                    let (mut local_power_combo, mut local_power_hold) = (cfg.power_off_combo.clone(), cfg.power_off_hold_ms);
+                    let mut combo_since: Option<Instant> = None;
+
+                    // Profile Cycle Combo (fires once per press)
+                    let mut cycle_combo_held = false;
+                    let mut cycle_profile_requested = false;
+
+                    // Live Raw Report Diff (debug panel)
+                    let mut local_report_diff = false;
+                    let mut report_diff = ReportDiff::default();
+                    let mut last_diff_emit = Instant::now();
+
+                    // Deadzone preview (raw vs processed sticks)
+                    let mut local_stick_preview = false;
+                    let mut last_preview_emit = Instant::now();
+                    let mut last_stream_emit = Instant::now();
+
+                    // Input Report Layout (clones, see layout.rs)
+                    let mut saved_layout = cfg.report_layouts.get(&device_key).cloned();
+                    let mut auto_layout: Option<(ReportLayout, isize)> = None;
+                    let mut layout_probe: Option<LayoutProbe> = None;
+                    let mut probed_report_id: Option<u8> = None;
+                    apply_layout_state(&mut state.lock().unwrap(), &saved_layout, &auto_layout);
+                    let descriptor_layout = read_descriptor_layout(&device);
+                    let mut local_descriptor_parsing = false;
+                    state.lock().unwrap().descriptor_layout = descriptor_layout.as_ref().map(|d| d.summary());
+
+                    // Per-device corrections applied right after parsing
+                    let mut correction = InputCorrection::default();
+                    let stick_key = if serial.is_empty() { device_key.clone() } else { serial.clone() };
+                    state.lock().unwrap().stick_cal_key = stick_key.clone();
                     
                         let mut last_report_buf = [0u8; 80];
                         let mut last_report_len = 0;
                         
                         // State tracking for UI optimization (Deduplication)
                         let mut last_emitted_gamepad = GamepadState::default();
-                        let mut last_emitted_status = String::new();
+                        let mut last_emitted_status: Option<Status> = None;
                         let mut last_emit_time = Instant::now();
                     
                         // Burst Loop
@@ -329,39 +481,168 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
                                     // We also disable adaptive triggers (0)
                                     send_dualsense_output(
                                         &device, is_bt, 
-                                        0, 0, 255, 0x04, s.player_led_brightness, s.bt_sequence,
+                                        0, 0, 255, 0x04, cfg.player_led_brightness,
                                         0, 0, 0, 0, 0, 0
                                     );
+                                } else if is_switch {
+                                    switch_pro::send_leds(&device, 0x01, 0);
                                 }
                                 true
                             } else {
-                                if s.mappings_changed {
-                                    local_mappings = s.mappings.clone();
-                                    s.mappings_changed = false;
+                                let version = settings::version();
+                                if version != cfg_version {
+                                    cfg_version = version;
+                                    let fresh = settings::read().clone();
+                                    s.should_send_leds |= fresh.leds_differ(&cfg);
+                                    s.should_send_triggers |= fresh.triggers_differ(&cfg);
+                                    cfg = fresh;
+                                    local_mappings = cfg.mappings.clone();
+                                    local_deadzone_l = cfg.deadzone_left;
+                                    local_deadzone_r = cfg.deadzone_right;
+                                    local_mouse_sens_l = cfg.mouse_sens_left;
+                                    local_mouse_sens_r = cfg.mouse_sens_right;
+                                    local_mouse_sens_touchpad = cfg.mouse_sens_touchpad;
+                                    local_trigger_curves = [cfg.trigger_curve_l.clone(), cfg.trigger_curve_r.clone()];
+                                    local_stick_curves = [cfg.stick_curve_l.clone(), cfg.stick_curve_r.clone()];
+                                    local_anti_deadzones = [cfg.anti_deadzone_left, cfg.anti_deadzone_right];
+                                    local_deadzone_shapes = [cfg.deadzone_shape_left, cfg.deadzone_shape_right];
+                                    local_idle_power_off = cfg.idle_power_off_min;
+                                    local_ps_gesture = cfg.ps_hold_opens_window;
+                                    local_haptic_alerts = cfg.haptic_alerts;
+                                    local_trigger_fx = cfg.trigger_fx();
+                                }
+                                local_desktop_pause = cfg.focus_pause.suspends(&s.foreground_process);
+                                if cfg.vjoy_device != vjoy_device {
+                                    vjoy_device = cfg.vjoy_device;
+                                    vjoy = None; // Release the old device before acquiring another
+                                    s.vjoy_status = String::new();
+                                    if vjoy_device > 0 {
+                                        match VJoy::open(vjoy_device) {
+                                            Ok(v) => {
+                                                vjoy = Some(v);
+                                                s.vjoy_status = format!("Device {} active", vjoy_device);
+                                            }
+                                            Err(e) => {
+                                                warn!("vJoy: {}", e);
+                                                s.vjoy_status = e;
+                                            }
+                                        }
+                                    }
+                                }
+                                // Another pad type: unplug the old one, the next report plugs in the new one
+                                if cfg.pad_kind != pad_kind {
+                                    pad_kind = cfg.pad_kind;
+                                    if let (true, Some(old)) = (is_plugged, target.as_mut()) {
+                                        let _ = old.unplug();
+                                        vigem::unplugged();
+                                        is_plugged = false;
+                                    }
+                                    target = bus.as_ref().map(|b| b.create_pad(pad_kind));
+                                    info!("Virtual pad switched to {}", pad_kind.name());
+                                }
+                                if cfg.input_backend != input_backend {
+                                    input_backend = cfg.input_backend;
+                                    s.input_backend_status = String::new();
+                                    match input_backend {
+                                        InputBackend::SendInput => interception::disable(),
+                                        InputBackend::Interception => match interception::enable() {
+                                            Ok(status) => s.input_backend_status = status,
+                                            Err(e) => {
+                                                warn!("Interception: {} (using SendInput)", e);
+                                                s.input_backend_status = e;
+                                            }
+                                        },
+                                    }
+                                }
+                                if s.should_confirm_profile {
+                                    s.should_confirm_profile = false;
+                                    if is_dualsense || is_switch {
+                                        alert = Some(Alert::profile_switch());
+                                    }
+                                }
+                                if s.should_identify {
+                                    s.should_identify = false;
+                                    if is_dualsense || is_switch {
+                                        alert = Some(Alert::identify());
+                                    } else {
+                                        warn!("{}: identify needs a DualSense lightbar or Switch Pro LEDs", name);
+                                    }
+                                }
+                                if cfg.trackball_friction != trackball.friction {
+                                    trackball = Trackball { friction: cfg.trackball_friction, ..Default::default() };
+                                }
+                                if cfg.auto_sprint != auto_sprint.config {
+                                    auto_sprint = AutoSprintState { config: cfg.auto_sprint.clone(), ..Default::default() };
+                                }
+                                if cfg.power_off_combo != local_power_combo {
+                                    local_power_combo = cfg.power_off_combo.clone();
+                                }
+                                local_power_hold = cfg.power_off_hold_ms;
+                                if s.report_diff_active != local_report_diff {
+                                    local_report_diff = s.report_diff_active;
+                                    report_diff = ReportDiff::default();
+                                }
+                                if cfg.report_layouts.get(&device_key) != saved_layout.as_ref() {
+                                    saved_layout = cfg.report_layouts.get(&device_key).cloned();
+                                    apply_layout_state(&mut s, &saved_layout, &auto_layout);
+                                }
+                                local_descriptor_parsing = cfg.descriptor_parsing;
+                                local_stick_preview = s.stick_preview_active;
+                                if s.stick_stream_active != correction.raw_sticks.is_some() {
+                                    correction.raw_sticks = s.stick_stream_active.then(Vec::new);
+                                }
+                                correction.touch = cfg.touch_calibrations.get(&device_key).copied().unwrap_or_default();
+                                correction.orientation = cfg.orientation;
+                                if s.touch_calibrating {
+                                    s.touch_cal_progress = Some(*correction.touch_learning.get_or_insert_with(TouchCalibration::learning));
+                                } else {
+                                    correction.touch_learning = None;
+                                }
+                                correction.stick_centers = cfg.stick_centers.get(&stick_key).copied().unwrap_or_default();
+                                if std::mem::take(&mut s.should_calibrate_sticks) {
+                                    info!("Calibrating stick centers for {}...", stick_key);
+                                    correction.stick_sampler = Some(StickSampler::new());
+                                }
+                                if let Some(sampler) = correction.stick_sampler.take_if(|c| c.is_done()) {
+                                    finish_stick_calibration(&mut s, &stick_key, sampler);
+                                }
+                                correction.gyro_bias = cfg.gyro_biases.get(&stick_key).copied().unwrap_or_default();
+                                if std::mem::take(&mut s.should_calibrate_gyro) {
+                                    info!("Calibrating gyro bias for {}...", stick_key);
+                                    correction.gyro_sampler = Some(GyroSampler::new());
+                                }
+                                if let Some(sampler) = correction.gyro_sampler.take_if(|c| c.is_done()) {
+                                    finish_gyro_calibration(&mut s, &stick_key, sampler);
                                 }
-                                local_deadzone_l = s.deadzone_left;
-                                local_deadzone_r = s.deadzone_right;
-                                local_mouse_sens_l = s.mouse_sens_left;
-                                local_mouse_sens_r = s.mouse_sens_right;
-                                local_mouse_sens_touchpad = s.mouse_sens_touchpad;
                                 false
                             }
                         };
 
-                        if should_thread_exit { return; }
+                        // Fall through to the cleanup below (unplug, release keys, unhide)
+                        if should_thread_exit { break; }
+
+                        // Paused (control API) or asleep/locked (power.rs): drop out so the pad is unplugged and held keys are released
+                        let (paused, suspended) = {
+                            let s = state.lock().unwrap();
+                            (s.is_paused, s.system_suspended)
+                        };
+                        if paused || suspended {
+                            info!("Emulation {}. Releasing virtual pad...", if paused { "paused" } else { "suspended" });
+                            break;
+                        }
 
                         // 2. HIDHIDE Check (Rarely)
                         if last_hidhide_check.elapsed().as_secs() >= 1 {
                             if let Some(inst_id) = &instance_id {
                                 let mut s = state.lock().unwrap();
-                                let want_hide = s.hide_controller;
+                                let want_hide = cfg.hide_controller;
                                 if want_hide && !is_hidden {
-                                    if let Ok(_) = hidhide::hide_device(inst_id) {
+                                    if let Ok(_) = Hider::hide(inst_id) {
                                         is_hidden = true;
                                         s.hidden_device_id = Some(inst_id.clone());
                                     }
                                 } else if !want_hide && is_hidden {
-                                    let _ = hidhide::unhide_device(inst_id);
+                                    let _ = Hider::unhide(inst_id);
                                     is_hidden = false;
                                     s.hidden_device_id = None;
                                 }
@@ -369,26 +650,74 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
                             last_hidhide_check = Instant::now();
                         }
 
-                        // 3. Read Packet (Burst Mode)
-                        // Read with timeout 10ms to allow housekeeping when idle
-                        match device.read_timeout(&mut buf, 10) {
-                            Ok(0) => {
-                                // Timeout - Controller Idle or slow connection
+                        // 3. Read Packet
+                        // Reports arrive from the reader thread as soon as the device delivers them; the
+                        // timeout only paces housekeeping while the controller is quiet
+                        match reader.recv_timeout(IDLE_TICK) {
+                            Err(RecvTimeoutError::Timeout) => {
+                                // Controller Idle or slow connection
                                 // We call update_virtual_pad with last_sent_state to keep mouse moving smoothly
                                 let dt = last_pad_update.elapsed().as_secs_f32();
                                 last_pad_update = Instant::now();
-                                update_virtual_pad(&mut target, &last_sent_state, &local_mappings, &mut active_keys, &mut active_mouse, &mut mouse_acc, &mut scroll_acc, false, local_deadzone_l, local_deadzone_r, &mut smoothed_axes, local_mouse_sens_l, local_mouse_sens_r, local_mouse_sens_touchpad, &mut last_touch_x, &mut last_touch_y, &mut last_touch_active, &mut smoothed_touch, dt);
+                                frame_modes = update_virtual_pad(target.as_mut(), &last_sent_state, &local_mappings, cfg.shift_button, cfg.swap_sticks, &mut active_keys, &mut active_mouse, &mut mouse_acc, &mut scroll_acc, false, local_deadzone_l, local_deadzone_r, local_deadzone_shapes, &mut smoothed_axes, local_mouse_sens_l, local_mouse_sens_r, local_mouse_sens_touchpad, &mut last_touch_x, &mut last_touch_y, &mut last_touch_active, &mut smoothed_touch, &mut pinch, &mut trackball, &mut vjoy, &mut auto_sprint, &mut macros, &mut turbo, &mut stick_dpad, &local_trigger_curves, &local_stick_curves, local_anti_deadzones, local_desktop_pause, dt);
                             },
-                            Ok(size) => {
+                            Ok(packet) => {
+                                let size = packet.len();
+                                last_report_at = Instant::now();
+                                if asleep {
+                                    // Enhanced Mode and output state don't survive sleep
+                                    asleep = false;
+                                    info!("{}: input resumed, restoring Enhanced Mode, LEDs and triggers", name);
+                                    if is_dualsense && is_bt {
+                                        crate::dualsense::activate_enhanced_mode(&device);
+                                    }
+                                    simple_mode_counter = 0;
+                                    let mut s = state.lock().unwrap();
+                                    s.connection_mode = String::new();
+                                    s.should_send_leds = true;
+                                    s.should_send_triggers = true;
+                                }
+
                                 // Process Packet
-                                let report = &buf[0..size];
-                                let parsed_state = if is_dualsense {
-                                    parse_dualsense(report, is_bt)
-                                } else {
-                                    parse_ds4(report)
-                                };
+                                let report = &packet[..];
+                                capture::record(Direction::In, report);
+                                if saved_layout.is_none() {
+                                    if let Some(detected) = probe_layout(report, is_dualsense, is_bt, &mut layout_probe, &mut probed_report_id) {
+                                        warn!("{}: input report 0x{:02X} looks shifted by {:+} bytes, using an adjusted layout", name, report[0], detected.1);
+                                        auto_layout = Some(detected);
+                                        apply_layout_state(&mut state.lock().unwrap(), &saved_layout, &auto_layout);
+                                    }
+                                }
+                                let layout = saved_layout.as_ref().or(auto_layout.as_ref().map(|(l, _)| l));
+                                let parsed_state = parse_report(report, is_dualsense, is_bt, switch_cal.as_ref(), layout, descriptor_layout.as_ref(), local_descriptor_parsing)
+                                    .map(|g| correction.apply(g));
+
+                                if let Some(mut s) = parsed_state {
+                                    stats::record(&s);
+                                    recording::record(&s);
+
+                                    // Chord is checked on the raw buttons, before the PS gesture rewrites them
+                                    let chord_held = !local_power_combo.is_empty() && local_power_combo.iter().all(|b| b.get_value(&s));
+                                    if !chord_held {
+                                        combo_since = None;
+                                    } else if combo_since.get_or_insert_with(Instant::now).elapsed().as_millis() >= local_power_hold as u128 {
+                                        combo_since = None;
+                                        if is_dualsense && is_bt {
+                                            info!("Power-off chord held. Powering off...");
+                                            crate::dualsense::send_power_off_burst(&device);
+                                            break;
+                                        }
+                                        warn!("Power-off chord ignored: only supported for DualSense over Bluetooth.");
+                                    }
+
+                                    let cycle_held = !cfg.profile_cycle_combo.is_empty() && cfg.profile_cycle_combo.iter().all(|b| b.get_value(&s));
+                                    cycle_profile_requested |= cycle_held && !cycle_combo_held;
+                                    cycle_combo_held = cycle_held;
+
+                                    if local_ps_gesture && ps_gesture.update(&mut s) {
+                                        show_window_requested = true;
+                                    }
 
-                                if let Some(s) = parsed_state {
                                     // Connection Mode Detection Logic (Tolerant to initial Simple Mode bursts)
                                     let report_id = report[0];
                                     
@@ -404,6 +733,7 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
                                             } else if report_id == 0x01 {
                                                 // WARNING: Simple mode detected
                                                 simple_mode_counter += 1;
+                                                last_output = None; // Whatever we sent didn't stick
                                                 
                                                 if locked.connection_mode.is_empty() {
                                                      locked.connection_mode = format!("Waiting... ({})", simple_mode_counter);
@@ -433,6 +763,8 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
                                         if locked.connection_mode.is_empty() {
                                             let mode = if is_dualsense {
                                                 "Native (USB 0x01)".to_string()
+                                            } else if is_switch {
+                                                format!("Switch Pro (0x{:02X})", report_id)
                                             } else {
                                                 format!("DS4 (0x{:02X})", report_id)
                                             };
@@ -441,67 +773,153 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
                                     }
 
                                     // Plugin Virtual Pad if needed
-                                    if !is_plugged {
+                                    if let (false, Some(target)) = (is_plugged, target.as_mut()) {
                                         if let Err(e) = target.plugin() {
-                                            set_status(&format!("ViGEm Error: {}", e), &name);
+                                            set_status(Status::VirtualPadError(e), &name);
                                             break; 
                                         }
-                                        let _ = target.wait_ready();
+                                        target.watch_feedback(&pad_feedback);
                                         is_plugged = true;
-                                        info!("Virtual Xbox 360 plugged in and ready.");
-                                        set_status("Virtual Pad: Ready", &name);
+                                        vigem::plugged();
+                                        info!("Virtual {} plugged in and ready.", pad_kind.name());
+                                        set_status(Status::VirtualPadReady, &name);
                                     }
 
                                     // Update Virtual Pad (Always for smooth mouse, but pass change flag for ViGEm)
                                     let changed = s != last_sent_state;
                                     let dt = last_pad_update.elapsed().as_secs_f32();
                                     last_pad_update = Instant::now();
-                                    update_virtual_pad(&mut target, &s, &local_mappings, &mut active_keys, &mut active_mouse, &mut mouse_acc, &mut scroll_acc, changed, local_deadzone_l, local_deadzone_r, &mut smoothed_axes, local_mouse_sens_l, local_mouse_sens_r, local_mouse_sens_touchpad, &mut last_touch_x, &mut last_touch_y, &mut last_touch_active, &mut smoothed_touch, dt);
+                                    frame_modes = update_virtual_pad(target.as_mut(), &s, &local_mappings, cfg.shift_button, cfg.swap_sticks, &mut active_keys, &mut active_mouse, &mut mouse_acc, &mut scroll_acc, changed, local_deadzone_l, local_deadzone_r, local_deadzone_shapes, &mut smoothed_axes, local_mouse_sens_l, local_mouse_sens_r, local_mouse_sens_touchpad, &mut last_touch_x, &mut last_touch_y, &mut last_touch_active, &mut smoothed_touch, &mut pinch, &mut trackball, &mut vjoy, &mut auto_sprint, &mut macros, &mut turbo, &mut stick_dpad, &local_trigger_curves, &local_stick_curves, local_anti_deadzones, local_desktop_pause, dt);
                                     last_sent_state = s;
 
+                                    // Gyro Recenter press: drop the sub-pixel remainder and pick up the
+                                    // stored drift bias now instead of at the next housekeeping pass
+                                    if frame_modes.gyro_recenter && !gyro_recenter_held {
+                                        mouse_acc = (0.0, 0.0);
+                                        correction.gyro_bias = settings::read().gyro_biases.get(&stick_key).copied().unwrap_or_default();
+                                    }
+                                    gyro_recenter_held = frame_modes.gyro_recenter;
+
                                     // Batch this packet
                                     last_report_len = size.min(80);
-                                    last_report_buf[..last_report_len].copy_from_slice(&report[..last_report_len]);
-                                }
-                                
-                                // DRAIN QUEUE: Check if more data is available immediately
-                                // This prevents building up latency if input > processing speed
-                                // We loop here up to 10 times to drain buffer
-                                for _ in 0..10 {
-                                    // Non-blocking read (timeout 0)
-                                    match device.read_timeout(&mut buf, 0) {
-                                        Ok(sz) if sz > 0 => {
-                                             // Process this packet too!
-                                             let sub_report = &buf[0..sz];
-                                             let sub_parsed = if is_dualsense {
-                                                 parse_dualsense(sub_report, is_bt)
-                                             } else {
-                                                 parse_ds4(sub_report)
-                                             };
-                                             
-                                             if let Some(sub_s) = sub_parsed {
-                                                 // Update Virtual Pad immediately for smooth motion
-                                                 let changed = sub_s != last_sent_state;
-                                                 let dt = last_pad_update.elapsed().as_secs_f32();
-                                                 last_pad_update = Instant::now();
-                                                 update_virtual_pad(&mut target, &sub_s, &local_mappings, &mut active_keys, &mut active_mouse, &mut mouse_acc, &mut scroll_acc, changed, local_deadzone_l, local_deadzone_r, &mut smoothed_axes, local_mouse_sens_l, local_mouse_sens_r, local_mouse_sens_touchpad, &mut last_touch_x, &mut last_touch_y, &mut last_touch_active, &mut smoothed_touch, dt);
-                                                 last_sent_state = sub_s;
-                                                 
-                                                 // Batch this packet (overwrite previous)
-                                                 last_report_len = sz.min(80);
-                                                 last_report_buf[..last_report_len].copy_from_slice(&sub_report[..last_report_len]);
-                                             }
-                                        }
-                                        _ => break, // Queue empty or error
+                                    if local_report_diff {
+                                        report_diff.feed(&report[..last_report_len]);
                                     }
+                                    last_report_buf[..last_report_len].copy_from_slice(&report[..last_report_len]);
                                 }
                             }
-                            Err(_) => {
+                            Err(RecvTimeoutError::Disconnected) => {
                                 warn!("Device read error, disconnecting...");
                                 break;
                             }
                         }
 
+                        if is_dualsense && is_bt && last_report_at.elapsed() >= SLEEP_GAP && last_sleep_probe.elapsed() >= SLEEP_GAP {
+                            last_sleep_probe = Instant::now();
+                            if !crate::dualsense::is_responding(&device) {
+                                warn!("{}: no input for {} s and no response, closing the handle.", name, last_report_at.elapsed().as_secs());
+                                break;
+                            }
+                            if !asleep {
+                                asleep = true;
+                                info!("{}: no input for {} s, controller asleep or out of range.", name, SLEEP_GAP.as_secs());
+                            }
+                        }
+
+                        if show_window_requested {
+                            show_window_requested = false;
+                            info!("PS long press: showing window.");
+                            crate::show_window(&app_handle);
+                        }
+
+                        // Loaded here rather than in the packet path: the profile list is read from disk
+                        // and loading takes the state lock. The lightbar confirms on the next sync.
+                        if cycle_profile_requested {
+                            cycle_profile_requested = false;
+                            let profile = crate::cycle_profile(&state);
+                            info!("Profile cycle combo: switched to '{}'", profile);
+                        }
+
+                        // Idle Power-Off
+                        if has_input_activity(&activity_ref, &last_sent_state) {
+                            activity_ref = last_sent_state;
+                            last_activity = Instant::now();
+                            if idle_warning {
+                                idle_warning = false;
+                                state.lock().unwrap().should_send_leds = true; // Restore normal LEDs
+                            }
+                        }
+                        if local_idle_power_off > 0 && is_dualsense && is_bt {
+                            let limit = Duration::from_secs(local_idle_power_off as u64 * 60);
+                            let idle = last_activity.elapsed();
+                            if idle >= limit {
+                                info!("Controller idle for {} min. Powering off...", local_idle_power_off);
+                                crate::dualsense::send_power_off_burst(&device);
+                                break;
+                            } else if !idle_warning && idle + IDLE_WARNING >= limit {
+                                idle_warning = true;
+                                state.lock().unwrap().should_send_leds = true;
+                            }
+                        }
+
+                        // Battery level/charging went out as its own event, even with the window closed
+                        let battery = BatteryChanged::from_gamepad(&last_sent_state);
+                        if battery != battery_sent {
+                            battery_sent = battery;
+                            {
+                                // Listeners read the rest from the state, don't wait for the UI block to copy it
+                                let mut s = state.lock().unwrap();
+                                s.gamepad.battery = battery.level;
+                                s.gamepad.is_charging = battery.charging;
+                                s.gamepad.charge_state = battery.charge_state;
+                            }
+                            emit_battery_changed(&app_handle, battery);
+                        }
+
+                        // Low battery (once per level until charged again)
+                        if last_sent_state.is_charging || last_sent_state.battery == 0 {
+                            battery_alerted = None; // 0 = no battery report yet
+                        } else if let Some(level) = low_battery_level(last_sent_state.battery) {
+                            if battery_alerted.is_none_or(|a| level < a) {
+                                battery_alerted = Some(level);
+                                if (is_dualsense || is_switch) && local_haptic_alerts && alert.is_none() {
+                                    info!("Battery at {}%, pulsing the controller", last_sent_state.battery);
+                                    alert = Some(Alert::low_battery());
+                                }
+                            }
+                        }
+
+                        // XInput slot of the virtual pad on the player LEDs
+                        let slot = pad_feedback.player_slot.load(Ordering::Relaxed);
+                        if slot != shown_slot {
+                            shown_slot = slot;
+                            if slot > 0 {
+                                info!("Virtual pad is player {}", slot);
+                            }
+                            state.lock().unwrap().should_send_leds = true;
+                        }
+
+                        // Dynamic/Rumble trigger resistance follows the pull or the game's rumble, resent through the LED block below
+                        if is_dualsense && local_trigger_fx.iter().any(|fx| is_live_trigger_mode(fx.0)) && last_trigger_check.elapsed() >= DYNAMIC_TRIGGER_INTERVAL {
+                            last_trigger_check = Instant::now();
+                            let pulls = [last_sent_state.l2, last_sent_state.r2];
+                            let rumble = game_rumble(&pad_feedback);
+                            let moved = local_trigger_fx.iter().zip(pulls).zip(rumble).zip(trigger_forces_sent).any(|(((&(m, s, f), pull), rumble), sent)| {
+                                let force = trigger_effect(m, s, f, pull, rumble).2;
+                                force.abs_diff(sent) >= DYNAMIC_TRIGGER_STEP || (force == 0 && sent != 0)
+                            });
+                            if moved {
+                                state.lock().unwrap().should_send_triggers = true;
+                            }
+                        }
+
+                        // Alerts (profile switch, low battery), sent by the LED block below
+                        if let Some(a) = alert.as_mut() {
+                            if a.advance() {
+                                state.lock().unwrap().should_send_leds = true;
+                            }
+                        }
+
                         // REMOVED AGGRESSIVE LOCKING HERE
 
                         // UI Update (Throttled & Deduplicated) 
@@ -509,25 +927,31 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
                         if last_ui_update.elapsed().as_millis() >= 32 {
                             let mut locked = state.lock().unwrap();
                             let should_emit = locked.ui_visible;
-                            
-                            if should_emit {
-                                locked.gamepad = last_sent_state;
-                                locked.virtual_pad_active = is_plugged;
-                                
-                                locked.gamepad.left_x = smoothed_axes[0];
-                                locked.gamepad.left_y = smoothed_axes[1];
-                                locked.gamepad.right_x = smoothed_axes[2];
-                                locked.gamepad.right_y = smoothed_axes[3];
 
+                            // Keep the snapshot fresh even while hidden (control API reads battery/connection)
+                            locked.gamepad = last_sent_state;
+                            locked.virtual_pad_active = is_plugged;
+                            locked.auto_sprint_engaged = auto_sprint.engaged;
+                            locked.shift_layer_engaged = frame_modes.shift_layer;
+                            locked.gyro_aim_engaged = frame_modes.gyro_aim;
+                            locked.capture_active = capture::is_active(); // Stops itself when full
+                            locked.capture_packets = capture::packet_count();
+                            locked.input_recording = recording::is_active(); // Stops itself on write errors
+                            locked.raw_report[..last_report_len].copy_from_slice(&last_report_buf[..last_report_len]);
+
+                            locked.gamepad.left_x = smoothed_axes[0];
+                            locked.gamepad.left_y = smoothed_axes[1];
+                            locked.gamepad.right_x = smoothed_axes[2];
+                            locked.gamepad.right_y = smoothed_axes[3];
+
+                            if should_emit {
                                 locked.last_update = locked.last_update.wrapping_add(1);
-                                locked.raw_report[..last_report_len].copy_from_slice(&last_report_buf[..last_report_len]);
 
                                 // OPTIMIZATION: Only emit if state changed visually or it's been >1s (keep-alive)
                                 // This prevents flooding JS with identical JSONs, stopping memory leaks.
                                 let changed = locked.gamepad != last_emitted_gamepad || 
-                                              locked.status != last_emitted_status ||
+                                              last_emitted_status.as_ref() != Some(&locked.status) ||
                                               locked.should_send_leds || 
-                                              locked.mappings_changed ||
                                               last_emit_time.elapsed().as_millis() > 1000;
 
                                 if changed {
@@ -542,7 +966,7 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
                                     
                                     // Update tracking vars
                                     last_emitted_gamepad = current_state.gamepad;
-                                    last_emitted_status = current_state.status.clone();
+                                    last_emitted_status = Some(current_state.status.clone());
                                     last_emit_time = Instant::now();
 
                                     drop(locked); // Unlock before emitting
@@ -552,9 +976,27 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
                             last_ui_update = Instant::now();
                         }
 
+                        if local_report_diff && last_diff_emit.elapsed().as_millis() >= 100 {
+                            let _ = app_handle.emit_all("report-diff", report_diff.take_event());
+                            last_diff_emit = Instant::now();
+                        }
+
+                        if local_stick_preview && last_preview_emit.elapsed().as_millis() >= 32 {
+                            let preview = StickPreview::new(&last_sent_state, local_deadzone_l, local_deadzone_r, local_deadzone_shapes, &local_stick_curves, &smoothed_axes);
+                            let _ = app_handle.emit_all("stick-preview", preview);
+                            last_preview_emit = Instant::now();
+                        }
+
+                        // Raw stick stream: every sample since the last batch, not one per UI frame
+                        if correction.raw_sticks.as_ref().is_some_and(|v| !v.is_empty()) && last_stream_emit.elapsed().as_millis() >= 16 {
+                            let samples = correction.raw_sticks.replace(Vec::new()).unwrap_or_default();
+                            let _ = app_handle.emit_all("stick-samples", StickSamples { samples, centers: correction.stick_centers });
+                            last_stream_emit = Instant::now();
+                        }
+
                         // 3. LED / Fuzzer Housekeeping (Throttled 1ms)
                         if last_led_update.elapsed().as_millis() >= 1 {
-                             let (active, step, manual_id, manual_flag, manual_rgb, manual_r, manual_g, manual_b, do_manual, seq, crc_mode, disable_period, pp_off, pp_val, do_pp, manual_pled, manual_pb, manual_pb_off, sweep_active, sweep_timeout, bt_flags, bt_flags2, bt_len, use_feature, do_proto_scan, force_leds, force_triggers, disconnect) = {
+                             let (active, step, manual_id, manual_flag, manual_rgb, manual_r, manual_g, manual_b, do_manual, crc_mode, disable_period, pp_off, pp_val, do_pp, manual_pled, manual_pb, manual_pb_off, sweep_active, sweep_timeout, bt_flags, bt_flags2, bt_len, use_feature, do_proto_scan, force_leds, force_triggers, disconnect) = {
                                 let mut s = state.lock().unwrap();
                                 let send = s.should_send_manual;
                                 let send_pp = s.should_send_pinpoint;
@@ -567,24 +1009,18 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
                                 s.should_send_leds = false;
                                 s.should_send_triggers = false;
                                 s.should_disconnect = false;
-                                let sq = s.bt_sequence;
-                                s.bt_sequence = s.bt_sequence.wrapping_add(1);
-                                (s.fuzzer_active, s.fuzzer_step, s.manual_report_id, s.manual_flag_offset, s.manual_rgb_offset, s.manual_r, s.manual_g, s.manual_b, send, sq, s.crc_seed_idx, s.disable_periodic, s.pinpoint_offset, s.pinpoint_value, send_pp, s.manual_player_led, s.manual_pled_bright, s.manual_pled_bright_off, s.sweep_active, s.sweep_timeout_ms, s.bt_flag_val, s.bt_flag_val2, s.manual_bt_len, s.send_as_feature, scan, f_leds, f_triggers, disc)
+                                (s.fuzzer_active, s.fuzzer_step, s.manual_report_id, s.manual_flag_offset, s.manual_rgb_offset, s.manual_r, s.manual_g, s.manual_b, send, s.crc_seed_idx, s.disable_periodic, s.pinpoint_offset, s.pinpoint_value, send_pp, s.manual_player_led, s.manual_pled_bright, s.manual_pled_bright_off, s.sweep_active, s.sweep_timeout_ms, s.bt_flag_val, s.bt_flag_val2, s.manual_bt_len, s.send_as_feature, scan, f_leds, f_triggers, disc)
                             };
 
                             if disconnect {
                                 info!("Reconnect requested.");
                                 {
                                     let mut s = state.lock().unwrap();
-                                    s.status = "Reconnecting...".to_string();
+                                    s.status = Status::Reconnecting;
                                 }
                                 
                                 if is_dualsense && is_bt {
-                                    // Send a series of power off packets
-                                    for i in 0..10 {
-                                        crate::dualsense::send_power_off(&device, true, seq.wrapping_add(i as u8));
-                                        thread::sleep(Duration::from_millis(10));
-                                    }
+                                    crate::dualsense::send_power_off_burst(&device);
                                 }
                                 
                                 let mut s = state.lock().unwrap();
@@ -597,13 +1033,31 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
                                 break; // Exits inner loop, triggering re-scan immediately
                             }
 
-                            if do_proto_scan {
-                                run_protocol_scan(&device, seq, &state);
+                            let (do_descriptor_dump, do_input_scan) = {
+                                let mut s = state.lock().unwrap();
+                                (std::mem::take(&mut s.should_dump_descriptor), std::mem::take(&mut s.should_scan_input_reports))
+                            };
+                            if do_proto_scan || do_descriptor_dump || do_input_scan {
+                                let firmware = crate::dualsense::read_firmware_info(&device, is_dualsense).unwrap_or_else(|| "unknown".to_string());
+                                let header = format!(
+                                    "{} {}\nDevice: {} (VID {:04X} / PID {:04X}, {})\nFirmware: {}\n",
+                                    crate::config::APP_NAME, env!("CARGO_PKG_VERSION"),
+                                    name, vid, pid, if is_bt { "Bluetooth" } else { "USB" }, firmware
+                                );
+                                if do_proto_scan {
+                                    run_protocol_scan(&device, &header, &state);
+                                }
+                                if do_descriptor_dump {
+                                    dump_report_descriptor(&device, &header, &state);
+                                }
+                                if do_input_scan {
+                                    run_input_report_scan(&device, &header, &state);
+                                }
                             }
 
                             // Manual / Pinpoint / Fuzzer / Periodic logic
                             if do_manual {
-                                let res = send_raw_output(&device, manual_id, manual_flag, manual_rgb, manual_r, manual_g, manual_b, seq, crc_mode, manual_pled, manual_pb, manual_pb_off, bt_flags, bt_flags2, bt_len, use_feature);
+                                let res = send_raw_output(&device, manual_id, manual_flag, manual_rgb, manual_r, manual_g, manual_b, crc_mode, manual_pled, manual_pb, manual_pb_off, bt_flags, bt_flags2, bt_len, use_feature);
                                 
                                 let (status, hex) = match res {
                                     Ok((n, hex)) => (format!("OK ({} bytes)", n), hex),
@@ -629,7 +1083,7 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
                                 let rep_id = if is_bt { 0x31 } else { 0x02 };
                                 report[0] = rep_id;
                                 if is_bt {
-                                    report[1] = (seq << 4) | 0x02; 
+                                    report[1] = 0x02; 
                                     report[2] = 0xF7; // Main Flags
                                     report[3] = 0x15; // LED Flags
                                     report[4] = 0x00; // No rumble
@@ -637,26 +1091,28 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
                                     report[2] = 0xF7; 
                                 }
                                 if pp_off < 78 { report[pp_off] = pp_val; }
-                                if is_bt {
-                                    let checksum = crc::crc32_bt(&report[0..74]);
-                                    report[74] = (checksum & 0xFF) as u8;
-                                    report[75] = ((checksum >> 8) & 0xFF) as u8;
-                                    report[76] = ((checksum >> 16) & 0xFF) as u8;
-                                    report[77] = ((checksum >> 24) & 0xFF) as u8;
-                                }
-                                let res = if is_bt { device.write(&report) } else { device.write(&report[0..64]) };
+                                let res = if is_bt { write_bt_report(&device, &mut report) } else { write_report(&device, &report[0..64]) };
                                 let status = match res { Ok(_) => format!("PP OK ({} -> [{}])", pp_val, pp_off), Err(e) => format!("Error: {}", e) };
                                 state.lock().unwrap().last_write_status = status;
                             }
                             
+                            let (script_active, fuzzer_delay) = {
+                                let s = state.lock().unwrap();
+                                (s.fuzz_script_active, s.fuzzer_params.delay_ms)
+                            };
                             if sweep_active {
                                 if last_sweep_update.elapsed().as_millis() >= sweep_timeout as u128 {
-                                    run_sweep_logic(&device, step, seq, &state, sweep_timeout);
+                                    run_sweep_logic(&device, step, &state, sweep_timeout);
                                     last_sweep_update = Instant::now();
                                 }
+                            } else if script_active {
+                                if last_fuzzer_update.elapsed().as_millis() >= fuzzer_delay as u128 {
+                                    run_script_fuzzer(&device, &mut script_fuzzer, step, crc_mode, &state);
+                                    last_fuzzer_update = Instant::now();
+                                }
                             } else if active {
-                                if last_fuzzer_update.elapsed().as_millis() >= 50 {
-                                    run_fuzzer_logic(&device, step, seq, crc_mode, bt_flags, bt_len, use_feature, &state);
+                                if last_fuzzer_update.elapsed().as_millis() >= fuzzer_delay as u128 {
+                                    run_fuzzer_logic(&device, step, crc_mode, bt_flags, bt_len, use_feature, &state);
                                     last_fuzzer_update = Instant::now();
                                 }
                             } else {
@@ -666,27 +1122,81 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
                                 let safe_to_send = simple_mode_counter == 0;
                                 
                                 if safe_to_send && (force_leds || force_triggers || (!disable_period && last_periodic_update.elapsed().as_millis() >= 1000)) {
-                                    let (r, g, b, bright, show_bat, l2_m, l2_s, l2_f, r2_m, r2_s, r2_f, pled_bright) = {
-                                        let s = state.lock().unwrap();
-                                        (s.rgb_r, s.rgb_g, s.rgb_b, s.rgb_brightness, s.show_battery_led,
-                                         s.trigger_l2_mode, s.trigger_l2_start, s.trigger_l2_force,
-                                         s.trigger_r2_mode, s.trigger_r2_start, s.trigger_r2_force,
-                                         s.player_led_brightness)
-                                    };
-                                    
-                                    let pled = if show_bat {
-                                        get_battery_led_mask(last_sent_state.battery)
+                                    let blink_on = blink_phase();
+                                    let pled = if cfg.show_battery_led {
+                                        get_battery_led_mask(&last_sent_state, blink_on)
                                     } else {
-                                        0x04 // Standard Center LED
+                                        player_slot_mask(shown_slot)
                                     };
 
                                     // Apply brightness scaling
-                                    let bf = bright as f32 / 255.0;
-                                    let fr = (r as f32 * bf) as u8;
-                                    let fg = (g as f32 * bf) as u8;
-                                    let fb = (b as f32 * bf) as u8;
+                                    let bf = cfg.rgb_brightness as f32 / 255.0;
+                                    let fr = (cfg.rgb_r as f32 * bf) as u8;
+                                    let fg = (cfg.rgb_g as f32 * bf) as u8;
+                                    let fb = (cfg.rgb_b as f32 * bf) as u8;
+
+                                    // Idle warning: blink amber until input resumes or the pad powers off
+                                    let (fr, fg, fb) = if !idle_warning {
+                                        (fr, fg, fb)
+                                    } else if last_activity.elapsed().as_secs() & 1 == 0 {
+                                        (255, 80, 0)
+                                    } else {
+                                        (0, 0, 0)
+                                    };
+
+                                    // Charging fault (temperature/voltage): blink the lightbar red
+                                    let (fr, fg, fb) = if !idle_warning && last_sent_state.charge_state == ChargeState::Error {
+                                        if blink_on { (255, 0, 0) } else { (0, 0, 0) }
+                                    } else {
+                                        (fr, fg, fb)
+                                    };
+
+                                    let alert_on = alert.as_ref().filter(|a| !a.is_done()).map(|a| a.is_on());
+                                    let (fr, fg, fb) = match (alert_on, alert.as_ref().is_some_and(|a| a.flash)) {
+                                        (Some(true), true) => (255, 255, 255),
+                                        (Some(false), true) => (0, 0, 0),
+                                        _ => (fr, fg, fb),
+                                    };
+
+                                    // Quiet hours win over the profile and the warnings above
+                                    let quiet = cfg.quiet_hours.is_active();
+                                    let ((fr, fg, fb), pled) = if quiet {
+                                        (cfg.quiet_hours.dim((fr, fg, fb)), cfg.quiet_hours.mask_player_leds(pled))
+                                    } else {
+                                        ((fr, fg, fb), pled)
+                                    };
 
-                                    send_dualsense_output(&device, is_bt, fr, fg, fb, pled, pled_bright, seq, l2_m, l2_s, l2_f, r2_m, r2_s, r2_f);
+                                    let [(l2_m, l2_s, l2_f), (r2_m, r2_s, r2_f)] = local_trigger_fx;
+                                    let [l2_rumble, r2_rumble] = game_rumble(&pad_feedback);
+                                    let (l2_m, l2_s, l2_f) = trigger_effect(l2_m, l2_s, l2_f, last_sent_state.l2, l2_rumble);
+                                    let (r2_m, r2_s, r2_f) = trigger_effect(r2_m, r2_s, r2_f, last_sent_state.r2, r2_rumble);
+
+                                    // Every write wakes the BT radio, so unchanged payloads wait for the keep-alive.
+                                    // Alerts always go out: the LED report is also what stops their rumble.
+                                    let payload = ((fr, fg, fb), pled, cfg.player_led_brightness, [(l2_m, l2_s, l2_f), (r2_m, r2_s, r2_f)]);
+                                    let keepalive_due = cfg.output_keepalive_secs > 0 && last_output_sent.elapsed().as_secs() >= cfg.output_keepalive_secs as u64;
+                                    if force_leds || force_triggers || alert.is_some() || last_output != Some(payload) || keepalive_due {
+                                        trigger_forces_sent = [l2_f, r2_f];
+                                        if is_switch {
+                                            // No lightbar: its brightness goes to the Home ring
+                                            switch_pro::send_leds(&device, switch_pro::player_lights(pled), fr.max(fg).max(fb) / 17);
+                                        } else {
+                                            send_dualsense_output(&device, is_bt, fr, fg, fb, pled, cfg.player_led_brightness, l2_m, l2_s, l2_f, r2_m, r2_s, r2_f);
+                                        }
+                                        last_output = Some(payload);
+                                        last_output_sent = Instant::now();
+                                    }
+                                    // The LED report zeroes the motors, so pulses go out after it
+                                    if alert_on == Some(true) && local_haptic_alerts && !quiet && alert.as_ref().is_some_and(|a| a.rumble) {
+                                        if is_switch {
+                                            switch_pro::send_rumble(&device, ALERT_RUMBLE, ALERT_RUMBLE);
+                                        } else {
+                                            crate::dualsense::send_rumble(&device, is_bt, ALERT_RUMBLE, ALERT_RUMBLE);
+                                        }
+                                    }
+                                    if alert.as_ref().is_some_and(|a| a.is_done()) {
+                                        alert = None;
+                                    }
                                     last_periodic_update = Instant::now();
                                 }
                             }
@@ -701,24 +1211,65 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
                         }
                     }
                     
-                    // Unplug if loop breaks
-                    if is_plugged {
-                        update_virtual_pad(&mut target, &GamepadState::default(), &[], &mut active_keys, &mut active_mouse, &mut mouse_acc, &mut scroll_acc, true, local_deadzone_l, local_deadzone_r, &mut [0.0f32; 4], local_mouse_sens_l, local_mouse_sens_r, 0.0, &mut 0, &mut 0, &mut false, &mut (0.0, 0.0), 0.0);
+                    // Release keys and buttons, unplug if loop breaks
+                    update_virtual_pad(target.as_mut(), &GamepadState::default(), &[], None, false, &mut active_keys, &mut active_mouse, &mut mouse_acc, &mut scroll_acc, true, local_deadzone_l, local_deadzone_r, local_deadzone_shapes, &mut [0.0f32; 4], local_mouse_sens_l, local_mouse_sens_r, 0.0, &mut 0, &mut 0, &mut false, &mut (0.0, 0.0), &mut PinchState::default(), &mut Trackball::default(), &mut vjoy, &mut AutoSprintState::default(), &mut MacroPlayer::default(), &mut Turbo::default(), &mut StickDpad::default(), &local_trigger_curves, &local_stick_curves, local_anti_deadzones, false, 0.0);
+                    // Exiting: hand the controller back in its default state (blue lightbar, player LEDs
+                    // and Home ring off, no trigger resistance) rather than keep the profile's output
+                    if state.lock().unwrap().should_exit {
+                        if is_switch {
+                            switch_pro::send_leds(&device, 0, 0);
+                        } else {
+                            send_dualsense_output(&device, is_bt, 0, 0, 255, 0, 0, 0, 0, 0, 0, 0, 0);
+                        }
+                    }
+                    if let (true, Some(target)) = (is_plugged, target.as_mut()) {
                         let _ = target.unplug();
+                        vigem::unplugged();
                     }
+                    interception::disable(); // Keys are released above, reopened with the next controller
                     if is_hidden {
                         if let Some(inst_id) = &instance_id {
-                            let _ = hidhide::unhide_device(inst_id);
+                            let _ = Hider::unhide(inst_id);
                             state.lock().unwrap().hidden_device_id = None;
                         }
                     }
-                    set_status("Disconnected", "None");
+                    let (paused, suspended) = {
+                        let s = state.lock().unwrap();
+                        (s.is_paused, s.system_suspended)
+                    };
+                    if paused || suspended {
+                        set_status(if paused { Status::Paused } else { Status::Suspended }, "None");
+                        state.lock().unwrap().gamepad = GamepadState::default();
+                    } else {
+                        set_status(Status::Disconnected, "None");
+                    }
                     {
                         let mut locked = state.lock().unwrap();
+                        if !paused && !suspended {
+                            locked.connect_request = None; // Manual-connect mode: stay off until the next connect_device
+                        }
                         locked.virtual_pad_active = false;
                         locked.connection_mode = String::new();
+                        locked.device_key = String::new();
+                        locked.descriptor_layout = None;
+                        locked.touch_calibrating = false;
+                        locked.touch_cal_progress = None;
+                        locked.stick_cal_key = String::new();
+                        locked.vjoy_status = String::new(); // Released with the controller
+                        locked.input_backend_status = String::new();
+                        locked.auto_sprint_engaged = false;
+                        locked.shift_layer_engaged = false;
+                        locked.gyro_aim_engaged = false;
+                        apply_layout_state(&mut locked, &None, &None);
                     }
                     let _ = app_handle.emit_all("update-state", &*state.lock().unwrap());
+                    let on_disconnect = settings::read().on_disconnect_cmd.clone();
+                    hooks::run(&on_disconnect, HookEvent::Disconnect, &name, &serial);
+
+                    if state.lock().unwrap().should_exit {
+                        info!("Controller released, worker exiting.");
+                        return;
+                    }
                     
                     // Pause to allow physical controller disconnection
                     thread::sleep(Duration::from_secs(2));
@@ -726,7 +1277,21 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
             }
         }
 
-        if !found {
+        // Keyboard/mouse mode reads the keyboard through Win32 hooks (kbm.rs)
+        let kbm_pad = !found && cfg!(windows) && settings::read().kbm_gamepad.enabled;
+        if let (true, Some(bus)) = (kbm_pad, &bus) {
+            state.lock().unwrap().detected_devices_log = log_buf;
+            run_kbm_pad(bus, &mut hid, &state, &app_handle);
+            no_device_counter = 0;
+        } else if !found && !state.lock().unwrap().found_controllers.is_empty() {
+            // Manual-connect mode with controllers present: poll quickly for connect_device
+            no_device_counter = 0;
+            let count = state.lock().unwrap().found_controllers.len();
+            state.lock().unwrap().detected_devices_log = log_buf;
+            set_status(Status::WaitingForConnect { found: count }, "None");
+            let _ = app_handle.emit_all("update-state", &*state.lock().unwrap());
+            thread::sleep(Duration::from_millis(250));
+        } else if !found {
             // SOFT REINIT: If no device found for 5 iterations (~10s), 
             // break to outer loop to refresh HID and whitelist.
             no_device_counter += 1;
@@ -736,7 +1301,7 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
             }
 
             state.lock().unwrap().detected_devices_log = log_buf;
-            set_status("Searching for controller...", "None");
+            set_status(Status::Searching, "None");
             let _ = app_handle.emit_all("update-state", &*state.lock().unwrap());
             thread::sleep(Duration::from_secs(2));
         } else {
@@ -749,14 +1314,26 @@ pub fn controller_thread(state: Arc<Mutex<SharedState>>, app_handle: tauri::AppH
 
 
 // Helper for Fuzzer/Sweep to keep main loop clean
-fn run_sweep_logic(device: &hidapi::HidDevice, current_step: usize, seq: u8, state: &Arc<Mutex<SharedState>>, _sweep_timeout: u64) {
+fn run_sweep_logic(device: &hidapi::HidDevice, current_step: usize, state: &Arc<Mutex<SharedState>>, _sweep_timeout: u64) {
     let mut report_bt = [0u8; 78];
     report_bt[0] = 0x31;
-    report_bt[1] = (seq << 4) | 0x02; 
+    report_bt[1] = 0x02; 
     report_bt[2] = 0x15;
     let log_msg;
+    let range = state.lock().unwrap().sweep_range.clone();
 
-    if current_step < 80 {
+    if let Some(range) = &range {
+        report_bt[2] = 0xF7; report_bt[3] = 0x15; report_bt[4] = 0x00;
+        if current_step >= range.step_count() {
+            let mut s = state.lock().unwrap();
+            s.fuzzer_step = 0;
+            log_msg = "TARGETED: Resetting...".to_string();
+        } else {
+            let (offset, value) = range.step(current_step);
+            report_bt[offset] = value;
+            log_msg = format!("TARGETED: [{}] = 0x{:02X} ({}/{})", offset, value, current_step + 1, range.step_count());
+        }
+    } else if current_step < 80 {
         report_bt[2] = 0xF7; report_bt[3] = 0x15; report_bt[4] = 0x00;
         if current_step < 75 {
             report_bt[current_step] = 255;
@@ -783,90 +1360,149 @@ fn run_sweep_logic(device: &hidapi::HidDevice, current_step: usize, seq: u8, sta
     let mut s = state.lock().unwrap();
     s.fuzzer_log = log_msg.clone();
     s.fuzzer_step += 1;
-    if s.fuzzer_step > 2000 { s.fuzzer_step = 0; }
+    if range.is_none() && s.fuzzer_step > 2000 { s.fuzzer_step = 0; }
     drop(s);
 
-    let checksum = crc::crc32_bt(&report_bt[0..74]); 
-    report_bt[74] = (checksum & 0xFF) as u8;
-    report_bt[75] = ((checksum >> 8) & 0xFF) as u8;
-    report_bt[76] = ((checksum >> 16) & 0xFF) as u8;
-    report_bt[77] = ((checksum >> 24) & 0xFF) as u8;
-    let _ = device.write(&report_bt);
+    let result = match write_bt_report(device, &mut report_bt) {
+        Ok(n) => format!("OK ({} bytes)", n),
+        Err(e) => format!("Error: {}", e),
+    };
     
     let hex_str = report_bt.iter().map(|b| format!("{:02X}", b)).collect::<Vec<String>>().join(" ");
+    fuzzlog::record(current_step, &log_msg, &hex_str, &result);
     
     let mut s = state.lock().unwrap();
     s.last_write_status = log_msg;
     s.last_packet_hex = hex_str;
 }
 
-fn run_fuzzer_logic(device: &hidapi::HidDevice, step: usize, seq: u8, crc_mode: u8, bt_flags: u8, bt_len: usize, use_feature: bool, state: &Arc<Mutex<SharedState>>) {
-    let report_id = if step < 10 { 0x02 } else { 0x31 };
-    let (flag_off, rgb_off, desc) = if step < 10 {
-        (step, 44 + step, format!("USB (0x02) | Flags @ {} | RGB @ {}", step, 44+step))
-    } else if step < 30 {
-        (step - 10, 45, format!("BT (0x31) | Flags @ {} | RGB @ {}", step-10, 45))
-    } else if step < 60 {
-        (3, 35 + (step - 30), format!("BT (0x31) | Flags @ 3 | RGB @ {}", 35 + (step - 30)))
-    } else {
+fn run_fuzzer_logic(device: &hidapi::HidDevice, step: usize, crc_mode: u8, bt_flags: u8, bt_len: usize, use_feature: bool, state: &Arc<Mutex<SharedState>>) {
+    let params = state.lock().unwrap().fuzzer_params.clone();
+    let total = params.step_count();
+    if step >= total {
         let mut s = state.lock().unwrap();
         s.fuzzer_step = 0;
-        (0, 0, "Reset".to_string())
-    };
+        s.fuzzer_log = "Reset".to_string();
+        return;
+    }
 
-    if desc != "Reset" {
-        {
-            let mut s = state.lock().unwrap();
-            s.fuzzer_log = format!("Step {}: {}", step, desc);
-            s.fuzzer_step += 1;
-        }
+    let (report_id, flag_off, rgb_off, value) = params.step(step);
+    let desc = format!("ID 0x{:02X} | Flags @ {} | RGB @ {} = {}", report_id, flag_off, rgb_off, value);
+    {
+        let mut s = state.lock().unwrap();
+        s.fuzzer_log = format!("Step {}/{}: {}", step, total, desc);
+        s.fuzzer_step += 1;
+    }
+
+    let mut last_res = String::new();
+    let mut last_hex = String::new();
+    // Burst
+    for _ in 0..params.burst {
+        let res = send_raw_output(device, report_id, flag_off, rgb_off, value, 0, 0, crc_mode, 0x04, 0, 0, bt_flags, 0x15, bt_len, use_feature);
+
+        let (status, hex) = match res {
+            Ok((n, h)) => (format!("OK ({} bytes)", n), h),
+            Err(e) => {
+                if let Some(idx) = e.find("| Hex: ") {
+                    (format!("Error: {}", &e[..idx]), e[idx + 7..].to_string())
+                } else {
+                    (format!("Error: {}", e), String::new())
+                }
+            }
+        };
+        fuzzlog::record(step, &desc, &hex, &status);
+        last_res = status;
+        last_hex = hex;
+
+        thread::sleep(Duration::from_millis(5));
+    }
+    let mut s = state.lock().unwrap();
+    s.last_write_status = last_res;
+    s.last_packet_hex = last_hex;
+}
+
+fn stop_script_fuzzer(state: &Arc<Mutex<SharedState>>, msg: String) {
+    warn!("Script fuzzer: {}", msg);
+    let mut s = state.lock().unwrap();
+    s.fuzz_script_active = false;
+    s.fuzzer_log = msg;
+    drop(s);
+    fuzzlog::end();
+}
 
-        let mut last_res = String::new();
-        let mut last_hex = String::new();
-        // Burst
-        for i in 0..3 {
-            let res = send_raw_output(device, report_id, flag_off, rgb_off, 255, 0, 0, seq.wrapping_add(i as u8), crc_mode, 0x04, 0, 0, bt_flags, 0x15, bt_len, use_feature);
-            
-            let (status, hex) = match res {
-                Ok((n, h)) => (format!("OK ({} bytes)", n), h),
+fn run_script_fuzzer(device: &hidapi::HidDevice, script: &mut Option<(String, ScriptFuzzer)>, step: usize, crc_mode: u8, state: &Arc<Mutex<SharedState>>) {
+    // (Re)compile when the source changed since the last step
+    {
+        let s = state.lock().unwrap();
+        if script.as_ref().map(|(src, _)| src) != Some(&s.fuzz_script) {
+            let source = s.fuzz_script.clone();
+            drop(s);
+            match ScriptFuzzer::compile(&source) {
+                Ok(compiled) => *script = Some((source, compiled)),
                 Err(e) => {
-                    if let Some(idx) = e.find("| Hex: ") {
-                        (format!("Error: {}", &e[..idx]), e[idx + 7..].to_string())
-                    } else {
-                        (format!("Error: {}", e), String::new())
-                    }
+                    *script = None;
+                    stop_script_fuzzer(state, format!("Compile error: {}", e));
+                    return;
                 }
-            };
-            last_res = status;
-            last_hex = hex;
-            
-            thread::sleep(Duration::from_millis(5));
+            }
         }
-        let mut s = state.lock().unwrap();
-        s.last_write_status = last_res;
-        s.last_packet_hex = last_hex;
     }
+    let Some((_, fuzzer)) = script.as_ref() else { return };
+
+    let next = fuzzer.next(step);
+    let output = fuzzer.take_output();
+    for line in &output {
+        info!("[script] {}", line);
+    }
+    let script_step = match next {
+        Ok(Some(s)) => s,
+        Ok(None) => return stop_script_fuzzer(state, format!("Script finished after {} steps.", step)),
+        Err(e) => return stop_script_fuzzer(state, format!("Step {}: {}", step, e)),
+    };
+
+    let mut data = script_step.data;
+    crate::script_fuzz::finalize(&mut data, crc_mode);
+    let res = if script_step.feature {
+        send_feature(device, &data).map(|_| data.len())
+    } else {
+        write_report(device, &data)
+    };
+    let status = match res {
+        Ok(n) => format!("OK ({} bytes)", n),
+        Err(e) => format!("Error: {}", e),
+    };
+    let hex = data.iter().map(|b| format!("{:02X}", b)).collect::<Vec<String>>().join(" ");
+    fuzzlog::record(step, &script_step.desc, &hex, &status);
+
+    let mut s = state.lock().unwrap();
+    s.fuzzer_log = match output.last() {
+        Some(line) => format!("Step {}: {} | {}", step, script_step.desc, line),
+        None => format!("Step {}: {}", step, script_step.desc),
+    };
+    s.fuzzer_step += 1;
+    s.last_write_status = status;
+    s.last_packet_hex = hex;
 }
 
-fn run_protocol_scan(device: &hidapi::HidDevice, seq: u8, state: &Arc<Mutex<SharedState>>) {
-    let mut log = String::from("--- PROTOCOL SCAN START ---\n");
+fn run_protocol_scan(device: &hidapi::HidDevice, header: &str, state: &Arc<Mutex<SharedState>>) {
+    let mut log = format!("{}--- PROTOCOL SCAN START ---\n", header);
     // 1. Output 0x31
     log.push_str(">> Report 0x31 (Output) Length Scan:\n");
     for l in 60..=80 {
-        let res = send_raw_output(device, 0x31, 2, 45, 255, 0, 0, seq, 0, 0, 0, 0, 0xF7, 0x15, l, false);
+        let res = send_raw_output(device, 0x31, 2, 45, 255, 0, 0, 0, 0, 0, 0, 0xF7, 0x15, l, false);
         log.push_str(&format!("Len {}: {}\n", l, match res { Ok(_) => "OK".to_string(), Err(e) => e }));
         thread::sleep(Duration::from_millis(10));
     }
     // 2. Feature 0x31
     log.push_str("\n>> Report 0x31 (Feature) Length Scan:\n");
     for l in 60..=80 {
-        let res = send_raw_output(device, 0x31, 2, 45, 255, 0, 0, seq, 0, 0, 0, 0, 0xF7, 0x15, l, true);
+        let res = send_raw_output(device, 0x31, 2, 45, 255, 0, 0, 0, 0, 0, 0, 0xF7, 0x15, l, true);
         log.push_str(&format!("Len {}: {}\n", l, match res { Ok(_) => "OK".to_string(), Err(e) => e }));
         thread::sleep(Duration::from_millis(10));
     }
     // 2.5 DS4
     log.push_str("\n>> Report 0x11 (DS4 Output):\n");
-    let res_11 = send_raw_output(device, 0x11, 2, 45, 255, 0, 0, seq, 0, 0, 0, 0, 0xF7, 0x15, 78, false);
+    let res_11 = send_raw_output(device, 0x11, 2, 45, 255, 0, 0, 0, 0, 0, 0, 0xF7, 0x15, 78, false);
     log.push_str(&format!("ID 11: {}\n", match res_11 { Ok(_) => "OK".to_string(), Err(e) => e }));
 
     log.push_str("--- END ---\n");
@@ -875,44 +1511,896 @@ fn run_protocol_scan(device: &hidapi::HidDevice, seq: u8, state: &Arc<Mutex<Shar
     s.protocol_scan_active = false;
 }
 
-fn apply_deadzone(x: f32, y: f32, deadzone: f32) -> (f32, f32) {
-    let magnitude = (x * x + y * y).sqrt();
-    if magnitude < deadzone {
-        (0.0, 0.0)
-    } else {
-        // Rescale magnitude to start from 0 at the edge of the deadzone
-        let rescaled_magnitude = (magnitude - deadzone) / (1.0 - deadzone);
-        let ratio = rescaled_magnitude / magnitude;
-        (x * ratio, y * ratio)
+/// Read side of the protocol scan: which report IDs answer GetInputReport / GetFeatureReport
+fn run_input_report_scan(device: &hidapi::HidDevice, header: &str, state: &Arc<Mutex<SharedState>>) {
+    let mut log = format!("{}--- INPUT REPORT SCAN START ---\n", header);
+    let mut silent = Vec::new();
+    for id in 0x01..=0x41u8 {
+        let mut responded = false;
+        for (kind, feature) in [("Input", false), ("Feature", true)] {
+            let mut buf = [0u8; 600];
+            buf[0] = id;
+            let res = if feature { device.get_feature_report(&mut buf) } else { device.get_input_report(&mut buf) };
+            if let Ok(len) = res {
+                if len > 0 {
+                    responded = true;
+                    let preview = buf[..len.min(16)].iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ");
+                    log.push_str(&format!("ID 0x{:02X} {:<7} {} bytes: {}{}\n", id, kind, len, preview, if len > 16 { " .." } else { "" }));
+                }
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        if !responded {
+            silent.push(format!("{:02X}", id));
+        }
     }
+    log.push_str(&format!("\nNo response: {}\n--- END ---\n", silent.join(" ")));
+    info!("{}", log);
+    state.lock().unwrap().protocol_log = log;
 }
 
-fn get_battery_led_mask(battery: u8) -> u8 {
-    // DualSense Player LEDs sequential filling (left to right):
-    // 0x01 - 1 LED
-    // 0x03 - 2 LEDs
-    // 0x07 - 3 LEDs
-    // 0x0F - 4 LEDs
-    // 0x1F - 5 LEDs
-    if battery >= 90 { 0x1F }
+fn dump_report_descriptor(device: &hidapi::HidDevice, header: &str, state: &Arc<Mutex<SharedState>>) {
+    let mut buf = [0u8; hid_descriptor::MAX_DESCRIPTOR_SIZE];
+    let log = match device.get_report_descriptor(&mut buf) {
+        Ok(len) => {
+            let derived = match DescriptorLayout::derive(&buf[..len]) {
+                Some(d) => d.summary(),
+                None => "none (no report with an X axis and buttons)".to_string(),
+            };
+            format!("{}--- REPORT DESCRIPTOR ---\n{}\nGamepad layout: {}\n", header, hid_descriptor::pretty_print(&buf[..len]), derived)
+        }
+        Err(e) => format!("{}Failed to read report descriptor: {}\n", header, e),
+    };
+    info!("{}", log);
+    state.lock().unwrap().protocol_log = log;
+}
+
+/// Which raw report bytes change, and how often, for reverse-engineering new fields
+struct ReportDiff {
+    prev: Option<[u8; 80]>,
+    counts: [u32; 80],
+    changed: [bool; 80], // Since the last emitted event
+    packets: u32,
+}
+
+impl Default for ReportDiff {
+    fn default() -> Self {
+        Self { prev: None, counts: [0; 80], changed: [false; 80], packets: 0 }
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+struct ReportDiffEvent {
+    changed: Vec<usize>,
+    counts: Vec<u32>,
+    packets: u32,
+}
+
+impl ReportDiff {
+    fn feed(&mut self, report: &[u8]) {
+        let mut cur = [0u8; 80];
+        cur[..report.len()].copy_from_slice(report);
+        if let Some(prev) = self.prev {
+            for i in 0..80 {
+                if cur[i] != prev[i] {
+                    self.counts[i] += 1;
+                    self.changed[i] = true;
+                }
+            }
+            self.packets += 1;
+        }
+        self.prev = Some(cur);
+    }
+
+    fn take_event(&mut self) -> ReportDiffEvent {
+        let changed = self.changed.iter().enumerate().filter(|(_, &c)| c).map(|(i, _)| i).collect();
+        self.changed = [false; 80];
+        ReportDiffEvent { changed, counts: self.counts.to_vec(), packets: self.packets }
+    }
+}
+
+/// Sticks at each processing stage, [LX, LY, RX, RY]
+#[derive(Clone, serde::Serialize)]
+struct StickPreview {
+    raw: [f32; 4],
+    deadzoned: [f32; 4], // With the response curve applied
+    output: [f32; 4], // After smoothing, what the virtual pad gets
+    deadzone: [f32; 2],
+    shape: [DeadzoneShape; 2],
+}
+
+impl StickPreview {
+    fn new(g: &GamepadState, deadzone_l: f32, deadzone_r: f32, shapes: [DeadzoneShape; 2], curves: &[ResponseCurve; 2], smoothed_axes: &[f32; 4]) -> Self {
+        let (lx, ly) = apply_curve(apply_deadzone(g.left_x, g.left_y, deadzone_l, shapes[0]), &curves[0]);
+        let (rx, ry) = apply_curve(apply_deadzone(g.right_x, g.right_y, deadzone_r, shapes[1]), &curves[1]);
+        Self {
+            raw: [g.left_x, g.left_y, g.right_x, g.right_y],
+            deadzoned: [lx, ly, rx, ry],
+            output: *smoothed_axes,
+            deadzone: [deadzone_l, deadzone_r],
+            shape: shapes,
+        }
+    }
+}
+
+/// Raw sticks [LX, LY, RX, RY] as reported, before drift calibration and orientation. `centers`
+/// are the calibrated resting positions, so the view can show what calibration removes.
+#[derive(Clone, serde::Serialize)]
+struct StickSamples {
+    samples: Vec<[f32; 4]>,
+    centers: [f32; 4],
+}
+
+/// "battery-changed" payload
+#[derive(Clone, Copy, PartialEq, serde::Serialize)]
+struct BatteryChanged {
+    level: u8,
+    charging: bool,
+    charge_state: ChargeState,
+}
+
+impl BatteryChanged {
+    fn from_gamepad(g: &GamepadState) -> Self {
+        Self { level: g.battery, charging: g.is_charging, charge_state: g.charge_state }
+    }
+}
+
+/// To the UI (if open) and to listeners in the app itself (tray), which emit_all doesn't reach
+fn emit_battery_changed(app_handle: &tauri::AppHandle, battery: BatteryChanged) {
+    let _ = app_handle.emit_all("battery-changed", battery);
+    app_handle.trigger_global("battery-changed", serde_json::to_string(&battery).ok());
+}
+
+fn button_states(g: &GamepadState) -> [bool; 22] {
+    [
+        g.btn_cross, g.btn_circle, g.btn_square, g.btn_triangle, g.btn_l1, g.btn_r1, g.btn_l3, g.btn_r3,
+        g.btn_options, g.btn_share, g.btn_ps, g.dpad_up, g.dpad_down, g.dpad_left, g.dpad_right,
+        g.btn_touchpad, g.btn_mute, g.touch_active,
+        g.btn_fn_l, g.btn_fn_r, g.btn_paddle_l, g.btn_paddle_r,
+    ]
+}
+
+fn any_button_besides_ps(g: &GamepadState) -> bool {
+    let mut other = *g;
+    other.btn_ps = false;
+    other.touch_active = false;
+    button_states(&other).iter().any(|&b| b)
+}
+
+/// Buttons, triggers or sticks moved past sensor noise (battery/touch coordinates alone don't count)
+fn has_input_activity(prev: &GamepadState, cur: &GamepadState) -> bool {
+    const AXIS_NOISE: f32 = 0.1;
+    button_states(prev) != button_states(cur)
+        || (prev.left_x - cur.left_x).abs() > AXIS_NOISE
+        || (prev.left_y - cur.left_y).abs() > AXIS_NOISE
+        || (prev.right_x - cur.right_x).abs() > AXIS_NOISE
+        || (prev.right_y - cur.right_y).abs() > AXIS_NOISE
+        || (prev.l2 - cur.l2).abs() > AXIS_NOISE
+        || (prev.r2 - cur.r2).abs() > AXIS_NOISE
+}
+
+/// The reader's slot: the newest report the worker hasn't taken yet
+#[derive(Default)]
+struct LatestReport {
+    report: Option<Vec<u8>>,
+    closed: bool, // Read error (controller gone) or shutdown
+}
+
+struct InputReader {
+    latest: Arc<(Mutex<LatestReport>, Condvar)>,
+    stop: Arc<AtomicBool>,
+}
+
+impl InputReader {
+    fn spawn(device: hidapi::HidDevice) -> Self {
+        let latest = Arc::new((Mutex::new(LatestReport::default()), Condvar::new()));
+        let shared = latest.clone();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = stop.clone();
+        thread::spawn(move || {
+            let (slot, ready) = &*shared;
+            let mut buf = [0u8; 128];
+            while !stop_flag.load(Ordering::Relaxed) {
+                match device.read_timeout(&mut buf, READER_POLL_MS) {
+                    Ok(0) => {}
+                    Ok(size) => {
+                        // Replaces one the worker was too busy to take: it only wants current input
+                        slot.lock().unwrap().report = Some(buf[..size].to_vec());
+                        ready.notify_one();
+                    }
+                    Err(e) => {
+                        warn!("Input read failed: {}", e);
+                        break;
+                    }
+                }
+            }
+            slot.lock().unwrap().closed = true;
+            ready.notify_one();
+        });
+        Self { latest, stop }
+    }
+
+    /// The newest report, waiting up to `timeout` for one. Disconnected once the reader has
+    /// stopped (controller gone).
+    fn recv_timeout(&self, timeout: Duration) -> Result<Vec<u8>, RecvTimeoutError> {
+        let (slot, ready) = &*self.latest;
+        let (mut latest, _) = ready
+            .wait_timeout_while(slot.lock().unwrap(), timeout, |l| l.report.is_none() && !l.closed)
+            .unwrap();
+        match latest.report.take() {
+            Some(report) => Ok(report),
+            None if latest.closed => Err(RecvTimeoutError::Disconnected),
+            None => Err(RecvTimeoutError::Timeout),
+        }
+    }
+}
+
+impl Drop for InputReader {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Auto-sprint: engaged once the left stick has been past the threshold for `hold_ms`, released
+/// when it drops back below (with some hysteresis so it doesn't flicker at the edge)
+#[derive(Default)]
+struct AutoSprintState {
+    config: Option<AutoSprint>,
+    since: Option<Instant>,
+    engaged: bool,
+}
+
+const AUTO_SPRINT_HYSTERESIS: f32 = 0.1;
+
+impl AutoSprintState {
+    fn update(&mut self, s: &GamepadState) -> Option<&MappingTarget> {
+        let Some(cfg) = &self.config else {
+            self.since = None;
+            self.engaged = false;
+            return None;
+        };
+        let magnitude = (s.left_x * s.left_x + s.left_y * s.left_y).sqrt();
+        if magnitude >= cfg.threshold {
+            let since = *self.since.get_or_insert_with(Instant::now);
+            if !self.engaged && since.elapsed().as_millis() >= cfg.hold_ms as u128 {
+                self.engaged = true;
+            }
+        } else if magnitude < cfg.threshold - AUTO_SPRINT_HYSTERESIS || !self.engaged {
+            self.since = None;
+            self.engaged = false;
+        }
+        if self.engaged { Some(&cfg.target) } else { None }
+    }
+}
+
+/// Turbo: a held source with a rate pulses its targets, starting with them pressed. Each press
+/// restarts the timing, so a quick tap always gets through.
+#[derive(Default)]
+struct Turbo {
+    held: Vec<(PhysicalButton, Instant, bool)>, // Source, pressed at, pulse on
+}
+
+impl Turbo {
+    /// Advances the pulses of held turbo sources. Returns true if any of them flipped.
+    fn update<'a>(&mut self, s: &GamepadState, mappings: impl Iterator<Item = &'a crate::mapping::ButtonMapping>) -> bool {
+        let now = Instant::now();
+        let mut changed = false;
+        let mut held = Vec::new();
+        for m in mappings.filter(|m| m.source.get_value(s)) {
+            let Some(hz) = m.turbo_hz else { continue };
+            let (since, was_on) = self.held.iter().find(|(b, ..)| *b == m.source).map_or((now, true), |&(_, since, on)| (since, on));
+            let on = (now.duration_since(since).as_secs_f32() * hz).fract() < 0.5;
+            changed |= on != was_on;
+            held.push((m.source, since, on));
+        }
+        self.held = held;
+        changed
+    }
+
+    /// Whether a pressed source's targets are down this frame
+    fn is_on(&self, source: PhysicalButton) -> bool {
+        self.held.iter().find(|(b, ..)| *b == source).is_none_or(|&(.., on)| on)
+    }
+}
+
+/// What a frame of update_virtual_pad had switched on, published as state::Modes
+#[derive(Default, Clone, Copy)]
+struct FrameModes {
+    shift_layer: bool,
+    gyro_aim: bool,
+    gyro_recenter: bool, // A GyroRecenter source is held; the worker resets gyro aiming on the press
+}
+
+// Stick to D-pad: a direction lets go this far below the threshold it went down at
+const STICK_DPAD_HYSTERESIS: f32 = 0.1;
+const XBOX_DPAD: [u16; 4] = [0x0001, 0x0002, 0x0004, 0x0008]; // Up, down, left, right
+
+/// Stick to D-pad directions. Without hysteresis a stick resting right at the threshold would
+/// chatter the direction on and off with sensor noise.
+#[derive(Default)]
+struct StickDpad {
+    held: [u16; 2], // D-pad bits down per stick [left, right]
+}
+
+impl StickDpad {
+    /// D-pad bits for `stick` this frame, given what it held last frame
+    fn press(&self, stick: usize, (x, y): (f32, f32), threshold: f32) -> u16 {
+        let held = self.held[stick];
+        [-y, y, -x, x].into_iter().zip(XBOX_DPAD).fold(0, |bits, (v, bit)| {
+            let limit = if held & bit != 0 { threshold - STICK_DPAD_HYSTERESIS } else { threshold };
+            if v >= limit { bits | bit } else { bits }
+        })
+    }
+}
+
+/// Macro playback, at most one running sequence per source button. Steps advance from the report
+/// loop, one per call, so a press and its release never land in the same frame and nothing here
+/// sleeps. Pressing the button again while its macro still runs does nothing.
+#[derive(Default)]
+struct MacroPlayer {
+    pressed: Vec<PhysicalButton>, // Macro sources down last frame, macros start on the press only
+    running: Vec<MacroRun>,
+    held: Vec<MappingTarget>, // What the running macros hold down right now
+}
+
+struct MacroRun {
+    source: PhysicalButton,
+    steps: Vec<MacroStep>,
+    next: usize,
+    due: Instant,
+    held: Vec<MappingTarget>,
+}
+
+impl MacroPlayer {
+    /// Starts the macros of freshly pressed sources and advances the running ones.
+    /// Returns true if the held targets changed.
+    fn update<'a>(&mut self, s: &GamepadState, sources: impl Iterator<Item = (PhysicalButton, &'a [MappingTarget])>) -> bool {
+        let now = Instant::now();
+        let mut pressed = Vec::new();
+        for (source, targets) in sources.filter(|(source, _)| source.get_value(s)) {
+            let Some(steps) = targets.iter().find_map(|t| match t { MappingTarget::Macro(steps) => Some(steps), _ => None }) else { continue };
+            pressed.push(source);
+            if !self.pressed.contains(&source) && !self.running.iter().any(|r| r.source == source) {
+                self.running.push(MacroRun { source, steps: steps.clone(), next: 0, due: now, held: Vec::new() });
+            }
+        }
+        self.pressed = pressed;
+
+        for run in &mut self.running {
+            let Some(step) = run.steps.get(run.next).filter(|_| now >= run.due) else { continue };
+            run.held.retain(|t| *t != step.target);
+            if step.press {
+                run.held.push(step.target.clone());
+            }
+            run.next += 1;
+            run.due = now + Duration::from_millis(step.delay_ms as u64);
+        }
+        // Done once the last step's delay is over; whatever it still holds is let go
+        self.running.retain(|r| r.next < r.steps.len() || now < r.due);
+
+        let held: Vec<MappingTarget> = self.running.iter().flat_map(|r| r.held.iter().cloned()).collect();
+        let changed = held != self.held;
+        self.held = held;
+        changed
+    }
+}
+
+// Lightbar color, player LED mask and brightness, and L2/R2 (mode, start, force): everything the
+// periodic output report carries, compared to skip writes that wouldn't change anything
+type OutputPayload = ((u8, u8, u8), u8, u8, [(u8, u8, u8); 2]);
+
+// Alerts felt on the controller: (ms since start, pulse on). Rumble while on (with haptic alerts
+// enabled), for players with headphones on who miss toasts. The speaker would need a real audio
+// stream, the motors only take a level. A profile switch also flashes the lightbar white, then shows
+// the new profile's LEDs. Identify only flashes, it's for telling pads apart, not for the player.
+const IDENTIFY_STEPS: [(u64, bool); 6] = [(0, true), (200, false), (400, true), (600, false), (800, true), (1000, false)];
+const PROFILE_CONFIRM_STEPS: [(u64, bool); 4] = [(0, true), (120, false), (240, true), (360, false)];
+const LOW_BATTERY_STEPS: [(u64, bool); 6] = [(0, true), (300, false), (450, true), (750, false), (900, true), (1200, false)];
+const ALERT_RUMBLE: u8 = 0x80;
+// Battery levels that trigger LOW_BATTERY_STEPS once each per discharge
+const LOW_BATTERY_ALERTS: [u8; 2] = [20, 10];
+
+struct Alert {
+    steps: &'static [(u64, bool)],
+    flash: bool,  // Lightbar white while on
+    rumble: bool, // Motors while on (haptic alerts permitting)
+    started: Instant,
+    step: usize,
+}
+
+impl Alert {
+    fn new(steps: &'static [(u64, bool)], flash: bool, rumble: bool) -> Self {
+        Self { steps, flash, rumble, started: Instant::now(), step: 0 }
+    }
+
+    fn profile_switch() -> Self {
+        Self::new(&PROFILE_CONFIRM_STEPS, true, true)
+    }
+
+    fn low_battery() -> Self {
+        Self::new(&LOW_BATTERY_STEPS, false, true)
+    }
+
+    fn identify() -> Self {
+        Self::new(&IDENTIFY_STEPS, true, false)
+    }
+
+    /// True when the next edge is due (the LEDs have to be resent)
+    fn advance(&mut self) -> bool {
+        match self.steps.get(self.step) {
+            Some(&(at, _)) if self.started.elapsed().as_millis() >= at as u128 => {
+                self.step += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn is_on(&self) -> bool {
+        self.step > 0 && self.steps[self.step - 1].1
+    }
+
+    fn is_done(&self) -> bool {
+        self.step >= self.steps.len()
+    }
+}
+
+/// Lowest LOW_BATTERY_ALERTS level reached at `battery`, None above all of them
+fn low_battery_level(battery: u8) -> Option<u8> {
+    LOW_BATTERY_ALERTS.iter().rev().find(|&&level| battery <= level).copied()
+}
+
+// Circular scroll: wheel notches per full turn at speed 1.0. Near the center a small movement is a
+// large angle, so it only counts beyond CIRCULAR_SCROLL_MIN_RADIUS (touchpad units).
+const CIRCULAR_SCROLL_NOTCHES: f32 = 12.0;
+const CIRCULAR_SCROLL_MIN_RADIUS: f32 = 150.0;
+
+/// Angle the finger moved around the touchpad center, clockwise positive (-PI..PI)
+fn touch_angle_delta(from: (u16, u16), to: (u16, u16)) -> f32 {
+    let (cx, cy) = (TOUCH_WIDTH as f32 / 2.0, TOUCH_HEIGHT as f32 / 2.0);
+    let angle = |(x, y): (u16, u16)| {
+        let (dx, dy) = (x as f32 - cx, y as f32 - cy);
+        (dx.hypot(dy) >= CIRCULAR_SCROLL_MIN_RADIUS).then(|| dy.atan2(dx)) // Y points down, so this is clockwise
+    };
+    match (angle(from), angle(to)) {
+        (Some(a), Some(b)) => {
+            let d = b - a;
+            if d > std::f32::consts::PI {
+                d - std::f32::consts::TAU
+            } else if d < -std::f32::consts::PI {
+                d + std::f32::consts::TAU
+            } else {
+                d
+            }
+        }
+        _ => 0.0,
+    }
+}
+
+// Pinch zoom: change in finger distance (touchpad units) per wheel notch at speed 1.0
+const PINCH_STEP: f32 = 100.0;
+const VK_CONTROL: u16 = 0x11;
+const VK_LCONTROL: u16 = 0xA2; // What physical Ctrl keys (keys.rs) translate to
+const VK_RCONTROL: u16 = 0xA3;
+
+/// Distance between two fingers from one report to the next, with the partial notches not sent yet
+#[derive(Default)]
+struct PinchState {
+    last_dist: Option<f32>,
+    acc: f32,
+}
+
+impl PinchState {
+    /// Change in finger distance since the last report, 0 unless two fingers were down for both
+    fn update(&mut self, g: &GamepadState) -> f32 {
+        let dist = (g.touch_active && g.touch2_active)
+            .then(|| (g.touch_x as f32 - g.touch2_x as f32).hypot(g.touch_y as f32 - g.touch2_y as f32));
+        let delta = match (self.last_dist, dist) {
+            (Some(a), Some(b)) => b - a,
+            _ => 0.0,
+        };
+        if dist.is_none() {
+            self.acc = 0.0;
+        }
+        self.last_dist = dist;
+        delta
+    }
+}
+
+// Trackball coasting: flick speed (mouse units per second) needed to start, and where it stops
+const TRACKBALL_MIN_FLING: f32 = 1500.0;
+const TRACKBALL_MIN_SPEED: f32 = 20.0;
+
+/// Touchpad trackball mode: a fast flick keeps the cursor moving after lift-off, slowing down
+/// by `friction` (share of the speed lost per second) until it stops or a finger lands again
+#[derive(Default)]
+struct Trackball {
+    friction: Option<f32>,
+    velocity: (f32, f32),
+}
+
+impl Trackball {
+    /// Lift-off with the last smoothed per-report movement
+    fn fling(&mut self, delta: (f32, f32), dt: f32) {
+        if self.friction.is_none() || dt <= 0.0 {
+            return;
+        }
+        let velocity = (delta.0 / dt, delta.1 / dt);
+        if velocity.0.hypot(velocity.1) >= TRACKBALL_MIN_FLING {
+            self.velocity = velocity;
+        }
+    }
+
+    fn stop(&mut self) {
+        self.velocity = (0.0, 0.0);
+    }
+
+    /// Movement for this tick, decaying the velocity
+    fn coast(&mut self, dt: f32) -> (f32, f32) {
+        let Some(friction) = self.friction else { return (0.0, 0.0) };
+        if self.velocity.0.hypot(self.velocity.1) < TRACKBALL_MIN_SPEED {
+            self.stop();
+            return (0.0, 0.0);
+        }
+        let step = (self.velocity.0 * dt, self.velocity.1 * dt);
+        let decay = (1.0 - friction.clamp(0.0, 0.999)).powf(dt);
+        self.velocity.0 *= decay;
+        self.velocity.1 *= decay;
+        step
+    }
+}
+
+fn apply_deadzone(x: f32, y: f32, deadzone: f32, shape: DeadzoneShape) -> (f32, f32) {
+    // Rescale to start from 0 at the edge of the deadzone
+    let rescale = |v: f32| (v - deadzone) / (1.0 - deadzone);
+    match shape {
+        DeadzoneShape::Axial => {
+            let axis = |v: f32| if v.abs() < deadzone { 0.0 } else { rescale(v.abs()).copysign(v) };
+            (axis(x), axis(y))
+        }
+        DeadzoneShape::Radial | DeadzoneShape::Square => {
+            // Square measures how far out the stick is by its larger axis instead of its distance
+            let magnitude = match shape {
+                DeadzoneShape::Square => x.abs().max(y.abs()),
+                _ => (x * x + y * y).sqrt(),
+            };
+            if magnitude < deadzone {
+                (0.0, 0.0)
+            } else {
+                let ratio = rescale(magnitude) / magnitude;
+                (x * ratio, y * ratio)
+            }
+        }
+    }
+}
+
+/// Reshapes the distance from center, keeping the direction
+fn apply_curve((x, y): (f32, f32), curve: &ResponseCurve) -> (f32, f32) {
+    let magnitude = (x * x + y * y).sqrt();
+    if magnitude <= 0.0 || *curve == ResponseCurve::Linear {
+        return (x, y);
+    }
+    // Past the rim (square-ish corners) the overshoot is kept as is
+    let ratio = curve.apply(magnitude.min(1.0)) / magnitude.min(1.0);
+    (x * ratio, y * ratio)
+}
+
+/// Lifts any output off center to at least `min` magnitude, rescaling the rest of the travel
+/// above it so full deflection stays full
+fn apply_anti_deadzone((x, y): (f32, f32), min: f32, active: bool) -> (f32, f32) {
+    if min <= 0.0 {
+        return (x, y);
+    }
+    let magnitude = (x * x + y * y).sqrt();
+    if !active || magnitude <= 0.0 {
+        return (0.0, 0.0);
+    }
+    let ratio = (min + (1.0 - min) * magnitude) / magnitude;
+    (x * ratio, y * ratio)
+}
+
+/// Stick value for an Xbox stick target: a physical stick gets its anti-deadzone version
+fn pad_stick(source: crate::mapping::PhysicalButton, axis: (f32, f32), pad_l: (f32, f32), pad_r: (f32, f32)) -> (f32, f32) {
+    match source {
+        crate::mapping::PhysicalButton::LeftStick => pad_l,
+        crate::mapping::PhysicalButton::RightStick => pad_r,
+        _ => axis,
+    }
+}
+
+/// Built-in parser, with sticks/triggers/buttons from a custom or detected layout when it applies.
+/// The descriptor layout is used when enabled, and for reports the built-in parsers don't know.
+fn parse_report(report: &[u8], is_dualsense: bool, is_bt: bool, switch_cal: Option<&StickCalibration>, layout: Option<&ReportLayout>, descriptor: Option<&DescriptorLayout>, use_descriptor: bool) -> Option<GamepadState> {
+    let builtin = match switch_cal {
+        Some(cal) => switch_pro::parse_switch_pro(report, cal),
+        None if is_dualsense => parse_dualsense(report, is_bt),
+        None => parse_ds4(report),
+    };
+    if let Some(l) = layout {
+        return l.parse(report, builtin).or(builtin);
+    }
+    match descriptor {
+        Some(d) if use_descriptor || builtin.is_none() => d.parse(report, builtin).or(builtin),
+        _ => builtin,
+    }
+}
+
+/// Touchpad range, stick centers, gyro bias and orientation, with running calibrations fed the raw values
+#[derive(Default)]
+struct InputCorrection {
+    touch: TouchCalibration,
+    touch_learning: Option<TouchCalibration>,
+    stick_centers: [f32; 4],
+    stick_sampler: Option<StickSampler>,
+    gyro_bias: [f32; 3],
+    gyro_sampler: Option<GyroSampler>,
+    orientation: Orientation,
+    raw_sticks: Option<Vec<[f32; 4]>>, // Every raw sample while the calibration view streams them
+}
+
+impl InputCorrection {
+    fn apply(&mut self, mut g: GamepadState) -> GamepadState {
+        if g.touch_active {
+            if let Some(l) = self.touch_learning.as_mut() {
+                l.include(g.touch_x, g.touch_y);
+            }
+        }
+        if let Some(sampler) = self.stick_sampler.as_mut() {
+            sampler.feed(&g);
+        }
+        if let Some(sampler) = self.gyro_sampler.as_mut() {
+            sampler.feed(&g);
+        }
+        if let Some(samples) = self.raw_sticks.as_mut() {
+            if samples.len() < MAX_STREAM_SAMPLES {
+                samples.push([g.left_x, g.left_y, g.right_x, g.right_y]);
+            }
+        }
+        self.touch.apply(&mut g);
+        stick_cal::apply(&self.stick_centers, &mut g);
+        gyro_cal::apply(&self.gyro_bias, &mut g);
+        self.orientation.apply(g)
+    }
+}
+
+fn finish_stick_calibration(s: &mut SharedState, key: &str, sampler: StickSampler) {
+    match sampler.result() {
+        Ok(centers) => {
+            info!("Stick centers for {}: LX {:+.3}, LY {:+.3}, RX {:+.3}, RY {:+.3}", key, centers[0], centers[1], centers[2], centers[3]);
+            settings::update(false, |st| st.stick_centers.insert(key.to_string(), centers));
+            s.stick_cal_status = format!("Calibrated (L {:+.2}/{:+.2}, R {:+.2}/{:+.2})", centers[0], centers[1], centers[2], centers[3]);
+        }
+        Err(e) => {
+            warn!("Stick calibration failed: {}", e);
+            s.stick_cal_status = e;
+        }
+    }
+}
+
+fn finish_gyro_calibration(s: &mut SharedState, key: &str, sampler: GyroSampler) {
+    match sampler.result() {
+        Ok(bias) => {
+            info!("Gyro bias for {}: pitch {:+.3}, yaw {:+.3}, roll {:+.3} deg/s", key, bias[0], bias[1], bias[2]);
+            settings::update(false, |st| st.gyro_biases.insert(key.to_string(), bias));
+            s.gyro_cal_status = format!("Calibrated ({:+.2}/{:+.2}/{:+.2} deg/s)", bias[0], bias[1], bias[2]);
+        }
+        Err(e) => {
+            warn!("Gyro calibration failed: {}", e);
+            s.gyro_cal_status = e;
+        }
+    }
+}
+
+fn is_supported(vid: u16, pid: u16) -> bool {
+    (vid == VID_SONY && matches!(pid, PID_DS4_V1 | PID_DS4_V2 | PID_DUALSENSE | PID_DUALSENSE_EDGE)) || (vid == VID_NINTENDO && pid == PID_SWITCH_PRO)
+}
+
+/// Supported controllers present right now, one entry per gamepad collection
+fn list_controllers(hid: &HidApi) -> Vec<FoundController> {
+    hid.device_list()
+        .filter(|d| is_supported(d.vendor_id(), d.product_id()) && ((d.usage_page() == 1 && d.usage() == 5) || d.usage_page() == 0))
+        .map(|d| FoundController {
+            path: d.path().to_str().unwrap_or("?").to_string(),
+            name: d.product_string().unwrap_or("Unknown").to_string(),
+            serial: d.serial_number().unwrap_or("").to_string(),
+            bluetooth: d.interface_number() == -1,
+        })
+        .collect()
+}
+
+/// Keyboard/mouse mode (kbm.rs): drives a virtual pad until a controller shows up, the mode is
+/// turned off, or the worker is paused, refreshed or exits
+fn run_kbm_pad(bus: &Bus, hid: &mut HidApi, state: &Arc<Mutex<SharedState>>, app_handle: &tauri::AppHandle) {
+    let mut target = bus.create_pad(settings::read().pad_kind);
+    if let Err(e) = target.plugin() {
+        warn!("Keyboard/mouse mode: failed to plug in virtual pad: {}", e);
+        state.lock().unwrap().status = Status::VirtualPadError(e);
+        let _ = app_handle.emit_all("update-state", &*state.lock().unwrap());
+        thread::sleep(Duration::from_secs(2));
+        return;
+    }
+    vigem::plugged();
+    info!("Keyboard/mouse mode started");
+    {
+        let mut s = state.lock().unwrap();
+        s.status = Status::KeyboardMouse;
+        s.virtual_pad_active = true;
+    }
+    let _ = app_handle.emit_all("update-state", &*state.lock().unwrap());
+
+    let mut pad = KbmPad::new();
+    let mut last_frame = Instant::now();
+    let mut last_scan = Instant::now();
+    loop {
+        let config = {
+            let s = state.lock().unwrap();
+            let kbm_gamepad = settings::read().kbm_gamepad.clone();
+            if s.should_exit || s.is_paused || s.system_suspended || s.should_reinit || s.connect_request.is_some() || !kbm_gamepad.enabled {
+                break;
+            }
+            kbm_gamepad
+        };
+
+        // Hand over to the normal path as soon as a controller is plugged in
+        if last_scan.elapsed() >= Duration::from_secs(2) {
+            last_scan = Instant::now();
+            if hid.refresh_devices().is_err() {
+                break;
+            }
+            // Manual-connect mode only hands over for connect_device (above) or to relist controllers
+            let handover = if settings::read().manual_connect {
+                list_controllers(hid) != state.lock().unwrap().found_controllers
+            } else {
+                hid.device_list().any(|d| is_supported(d.vendor_id(), d.product_id()))
+            };
+            if handover {
+                break;
+            }
+        }
+
+        let dt = last_frame.elapsed().as_secs_f32();
+        last_frame = Instant::now();
+        let frame = pad.poll(&config, dt);
+
+        let gamepad = PadReport {
+            buttons: frame.buttons,
+            left_trigger: (frame.left_trigger * 255.0) as u8,
+            right_trigger: (frame.right_trigger * 255.0) as u8,
+            thumb_lx: (frame.left.0 * 32767.0) as i16,
+            thumb_ly: (frame.left.1 * 32767.0) as i16,
+            thumb_rx: (frame.right.0 * 32767.0) as i16,
+            thumb_ry: (frame.right.1 * 32767.0) as i16,
+        };
+        let _ = target.update(&gamepad);
+
+        thread::sleep(Duration::from_millis(4));
+    }
+
+    drop(pad);
+    let _ = target.unplug();
+    vigem::unplugged();
+    info!("Keyboard/mouse mode stopped");
+    {
+        let mut s = state.lock().unwrap();
+        s.virtual_pad_active = false;
+        s.status = Status::Searching;
+    }
+    let _ = app_handle.emit_all("update-state", &*state.lock().unwrap());
+}
+
+fn read_descriptor_layout(device: &hidapi::HidDevice) -> Option<DescriptorLayout> {
+    let mut buf = [0u8; hid_descriptor::MAX_DESCRIPTOR_SIZE];
+    let len = match device.get_report_descriptor(&mut buf) {
+        Ok(len) => len,
+        Err(e) => {
+            warn!("Failed to read report descriptor: {}", e);
+            return None;
+        }
+    };
+    let layout = DescriptorLayout::derive(&buf[..len]);
+    match &layout {
+        Some(d) => info!("Descriptor layout: {}", d.summary()),
+        None => info!("Descriptor declares no gamepad input report"),
+    }
+    layout
+}
+
+/// Probes each report ID once per connection. Returns a layout when the report turns out shifted.
+fn probe_layout(report: &[u8], is_dualsense: bool, is_bt: bool, probe: &mut Option<LayoutProbe>, probed: &mut Option<u8>) -> Option<(ReportLayout, isize)> {
+    let id = report[0];
+    if *probed == Some(id) {
+        return None;
+    }
+    if probe.as_ref().map(|p| p.report_id()) != Some(id) {
+        *probe = ReportLayout::builtin(is_dualsense, is_bt, id).map(LayoutProbe::new);
+    }
+    let result = match probe.as_mut() {
+        Some(p) => p.feed(report),
+        None => ProbeResult::Builtin, // Not a report we parse
+    };
+    match result {
+        ProbeResult::Pending => None,
+        ProbeResult::Builtin => {
+            *probed = Some(id);
+            *probe = None;
+            None
+        }
+        ProbeResult::Shifted(layout, by) => {
+            *probed = Some(id);
+            *probe = None;
+            Some((layout, by))
+        }
+    }
+}
+
+fn apply_layout_state(s: &mut SharedState, saved: &Option<ReportLayout>, auto: &Option<(ReportLayout, isize)>) {
+    (s.active_layout, s.layout_note) = match (saved, auto) {
+        (Some(l), _) => (Some(l.clone()), format!("Saved layout for {}", s.device_key)),
+        (None, Some((l, by))) => (Some(l.clone()), format!("Auto-detected: report shifted by {:+} bytes", by)),
+        (None, None) => (None, String::new()),
+    };
+}
+
+fn get_battery_led_mask(gamepad: &GamepadState, blink_on: bool) -> u8 {
+    // DualSense Player LEDs sequential filling (left to right):
+    // 0x01 - 1 LED
+    // 0x03 - 2 LEDs
+    // 0x07 - 3 LEDs
+    // 0x0F - 4 LEDs
+    // 0x1F - 5 LEDs
+    let battery = gamepad.battery;
+    let level = if battery >= 90 { 0x1F }
     else if battery >= 70 { 0x0F }
     else if battery >= 50 { 0x07 }
     else if battery >= 30 { 0x03 }
     else if battery >= 10 { 0x01 }
-    else { 0x00 }
+    else { 0x00 };
+
+    match gamepad.charge_state {
+        ChargeState::Discharging => level,
+        // Next LED blinks while charging
+        ChargeState::Charging => if blink_on { ((level << 1) | 0x01) & 0x1F } else { level },
+        ChargeState::Full => 0x1F,
+        // Alternating pattern so a fault doesn't look like a battery level
+        ChargeState::Error => if blink_on { 0x15 } else { 0x0A },
+    }
+}
+
+/// Player LEDs for XInput slot 1-4, the pattern a PS5 uses for player numbers. Center dot while
+/// the slot isn't known.
+/// Rumble the game sends the virtual pad as [large, small] motor, 0.0-1.0
+fn game_rumble(feedback: &PadFeedback) -> [f32; 2] {
+    [&feedback.large_motor, &feedback.small_motor].map(|m| m.load(Ordering::Relaxed) as f32 / 255.0)
+}
+
+fn player_slot_mask(slot: u8) -> u8 {
+    match slot {
+        2 => 0x0A,
+        3 => 0x15,
+        4 => 0x1B,
+        _ => 0x04,
+    }
+}
+
+/// Shared 1 Hz phase for the battery LED animations
+fn blink_phase() -> bool {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs() & 1 == 0).unwrap_or(true)
 }
 
 fn update_virtual_pad(
-    target: &mut Xbox360Wired<Client>, 
+    target: Option<&mut platform::Pad>, 
     s: &GamepadState, 
     mappings: &[crate::mapping::ButtonMapping], 
-    active_keys: &mut HashSet<u16>, 
+    shift_button: Option<crate::mapping::PhysicalButton>,
+    swap_sticks: bool,
+    active_keys: &mut HashSet<KeyStroke>, 
     active_mouse: &mut HashSet<u8>,
     mouse_acc: &mut (f32, f32),
     scroll_acc: &mut f32,
     state_changed: bool,
     deadzone_l: f32,
     deadzone_r: f32,
+    deadzone_shapes: [DeadzoneShape; 2],
     smoothed_axes: &mut [f32; 4],
     sens_l: f32,
     sens_r: f32,
@@ -921,9 +2409,20 @@ fn update_virtual_pad(
     last_touch_y: &mut u16,
     last_touch_active: &mut bool,
     smoothed_touch: &mut (f32, f32),
+    pinch: &mut PinchState,
+    trackball: &mut Trackball,
+    vjoy: &mut Option<VJoy>,
+    auto_sprint: &mut AutoSprintState,
+    macros: &mut MacroPlayer,
+    turbo: &mut Turbo,
+    stick_dpad: &mut StickDpad,
+    trigger_curves: &[TriggerCurve; 2],
+    stick_curves: &[ResponseCurve; 2],
+    anti_deadzones: [f32; 2],
+    desktop_pause: bool,
     dt: f32
-) {
-    let mut gamepad = XGamepad::default();
+) -> FrameModes {
+    let mut gamepad = PadReport::default();
     let mut raw_buttons: u16 = 0;
     
     let mut current_keys = HashSet::new();
@@ -932,19 +2431,33 @@ fn update_virtual_pad(
     let mut mouse_dx = 0.0f32;
     let mut mouse_dy = 0.0f32;
     let mut scroll_dy = 0.0f32;
+    let mut zoom = 0.0f32;
+    let mut gyro_sens = 0.0f32; // Highest GyroMouse sensitivity held, 0 = gyro aiming off
+    let mut gyro_recenter = false;
     
     let mut xbox_lt = 0.0f32;
     let mut xbox_rt = 0.0f32;
     let mut xbox_ls = (0.0f32, 0.0f32);
     let mut xbox_rs = (0.0f32, 0.0f32);
+    let mut vjoy_frame = VJoyFrame::default();
 
     // Reference rate: 250Hz (4ms)
     // We scale by (dt / 0.004) to maintain consistency with the original USB 250Hz feeling
     let time_scale = dt / 0.004;
 
-    // Pre-calculate axis values with deadzone
-    let (lx_raw, ly_raw) = apply_deadzone(s.left_x, s.left_y, deadzone_l);
-    let (rx_raw, ry_raw) = apply_deadzone(s.right_x, s.right_y, deadzone_r);
+    // Swapped sticks trade roles before anything reads them, so every mapping, mouse path and
+    // Xbox stick follows. Deadzones stay with the physical stick they were set up for (drift).
+    let swapped;
+    let (s, deadzone_l, deadzone_r, deadzone_shapes) = if swap_sticks {
+        swapped = s.sticks_swapped();
+        (&swapped, deadzone_r, deadzone_l, [deadzone_shapes[1], deadzone_shapes[0]])
+    } else {
+        (s, deadzone_l, deadzone_r, deadzone_shapes)
+    };
+
+    // Pre-calculate axis values with deadzone and response curve
+    let (lx_raw, ly_raw) = apply_curve(apply_deadzone(s.left_x, s.left_y, deadzone_l, deadzone_shapes[0]), &stick_curves[0]);
+    let (rx_raw, ry_raw) = apply_curve(apply_deadzone(s.right_x, s.right_y, deadzone_r, deadzone_shapes[1]), &stick_curves[1]);
 
     // Apply smoothing (Exponential Moving Average)
     // alpha = 0.25 means 25% new data, 75% old data. 
@@ -959,10 +2472,16 @@ fn update_virtual_pad(
     let ly = smoothed_axes[1];
     let rx = smoothed_axes[2];
     let ry = smoothed_axes[3];
+    // Anti-deadzone goes on the Xbox stick only; the mouse paths have no game deadzone to jump.
+    // Whether the stick is out of its deadzone comes from the unsmoothed value, so letting go
+    // drops straight back to center instead of hanging at the minimum while the average decays.
+    let pad_l = apply_anti_deadzone((lx, ly), anti_deadzones[0], lx_raw != 0.0 || ly_raw != 0.0);
+    let pad_r = apply_anti_deadzone((rx, ry), anti_deadzones[1], rx_raw != 0.0 || ry_raw != 0.0);
 
     // Touchpad Delta Calculation (Smoothed)
     let mut target_dx = 0.0f32;
     let mut target_dy = 0.0f32;
+    let mut touch_turn = 0.0f32;
 
     if s.touch_active && *last_touch_active {
         // Calculate raw delta
@@ -976,8 +2495,12 @@ fn update_virtual_pad(
             let factor = 0.02f32; 
             target_dx = dx_raw as f32 * sens_touchpad * factor;
             target_dy = dy_raw as f32 * sens_touchpad * factor;
+            touch_turn = touch_angle_delta((*last_touch_x, *last_touch_y), (s.touch_x, s.touch_y));
         }
     } else if !s.touch_active {
+        if *last_touch_active && !s.touch2_active {
+            trackball.fling(*smoothed_touch, dt);
+        }
         // Reset smoothing momentum immediately on lift-off
         smoothed_touch.0 = 0.0;
         smoothed_touch.1 = 0.0;
@@ -987,6 +2510,14 @@ fn update_virtual_pad(
     *last_touch_y = s.touch_y;
     *last_touch_active = s.touch_active;
 
+    // A second finger makes it a gesture, the first one stops pointing
+    let pinch_delta = pinch.update(s);
+    if s.touch2_active {
+        target_dx = 0.0;
+        target_dy = 0.0;
+        touch_turn = 0.0;
+    }
+
     // Apply Smoothing (Exponential Moving Average) - Match Stick Alpha
     let alpha = 0.25f32;
     smoothed_touch.0 += alpha * (target_dx - smoothed_touch.0);
@@ -994,95 +2525,203 @@ fn update_virtual_pad(
 
     let touch_dx = smoothed_touch.0;
     let touch_dy = smoothed_touch.1;
-
-    for m in mappings {
-        if m.source.is_axis() {
-            let (ax, ay) = match m.source {
-                crate::mapping::PhysicalButton::LeftStick => (lx, ly),
-                crate::mapping::PhysicalButton::RightStick => (rx, ry),
-                crate::mapping::PhysicalButton::L2 => (s.l2, 0.0),
-                crate::mapping::PhysicalButton::R2 => (s.r2, 0.0),
-                crate::mapping::PhysicalButton::Touchpad => (0.0, 0.0), // Handled specifically
-                _ => (0.0, 0.0)
-            };
-            // Apply axis mappings
-            for t in &m.targets {
-                match t {
-                    MappingTarget::MouseMove { .. } => {
-                        if m.source == crate::mapping::PhysicalButton::Touchpad {
-                            mouse_dx += touch_dx;
-                            mouse_dy += touch_dy;
-                        } else {
-                            let sens = if m.source == crate::mapping::PhysicalButton::LeftStick { sens_l } else { sens_r };
-                            mouse_dx += ax * sens * time_scale;
-                            mouse_dy += ay * sens * time_scale;
-                        }
-                    }
-                    MappingTarget::MouseScroll { speed } => {
-                        // Touchpad delta is raw (e.g. 100), stick is 0.0-1.0. Scale touchpad WAY down.
-                        let val = if m.source == crate::mapping::PhysicalButton::Touchpad { touch_dy * 0.05 } else { ay };
-                        scroll_dy -= val * speed * time_scale; 
-                    }
-                    MappingTarget::XboxLT => {
-                        xbox_lt = xbox_lt.max(ax);
-                    }
-                    MappingTarget::XboxRT => {
-                        xbox_rt = xbox_rt.max(ax);
-                    }
-                    MappingTarget::XboxLS => {
-                        xbox_ls = (ax, ay);
-                    }
-                    MappingTarget::XboxRS => {
-                        xbox_rs = (ax, ay);
+    let (coast_dx, coast_dy) = if s.touch_active { trackball.stop(); (0.0, 0.0) } else { trackball.coast(dt) };
+
+    // Shift layer: the shift button only switches layers, its own targets stay idle. The layer is
+    // picked every frame, so letting go of shift mid-press hands the button back to its base targets.
+    let shifted = shift_button.is_some_and(|b| b.get_value(s));
+    let mappings = mappings.iter().filter(|m| Some(m.source) != shift_button);
+
+    let mut dpad_held = [0u16; 2];
+    for m in mappings.clone().filter(|m| m.source.is_axis()) {
+        let (ax, ay) = match m.source {
+            crate::mapping::PhysicalButton::LeftStick => (lx, ly),
+            crate::mapping::PhysicalButton::RightStick => (rx, ry),
+            crate::mapping::PhysicalButton::L2 => (s.l2, 0.0),
+            crate::mapping::PhysicalButton::R2 => (s.r2, 0.0),
+            crate::mapping::PhysicalButton::Touchpad => (0.0, 0.0), // Handled specifically
+            _ => (0.0, 0.0)
+        };
+        // Apply axis mappings
+        for t in m.active_targets(shifted) {
+            match t {
+                MappingTarget::MouseMove { .. } => {
+                    if m.source == crate::mapping::PhysicalButton::Touchpad {
+                        mouse_dx += touch_dx + coast_dx;
+                        mouse_dy += touch_dy + coast_dy;
+                    } else {
+                        let sens = if m.source == crate::mapping::PhysicalButton::LeftStick { sens_l } else { sens_r };
+                        mouse_dx += ax * sens * time_scale;
+                        mouse_dy += ay * sens * time_scale;
                     }
-                    _ => {}
                 }
+                MappingTarget::MouseScroll { speed } => {
+                    // Touchpad delta is raw (e.g. 100), stick is 0.0-1.0. Scale touchpad WAY down.
+                    let val = if m.source == crate::mapping::PhysicalButton::Touchpad { touch_dy * 0.05 } else { ay };
+                    scroll_dy -= val * speed * time_scale; 
+                }
+                MappingTarget::CircularScroll { speed } if m.source == crate::mapping::PhysicalButton::Touchpad => {
+                    scroll_dy -= touch_turn / std::f32::consts::TAU * CIRCULAR_SCROLL_NOTCHES * speed;
+                }
+                MappingTarget::PinchZoom { speed } if m.source == crate::mapping::PhysicalButton::Touchpad => {
+                    zoom += pinch_delta / PINCH_STEP * speed;
+                }
+                MappingTarget::GyroMouse { sensitivity } if ax > 0.5 => {
+                    gyro_sens = gyro_sens.max(*sensitivity);
+                }
+                MappingTarget::XboxLT => {
+                    xbox_lt = xbox_lt.max(ax);
+                }
+                MappingTarget::XboxRT => {
+                    xbox_rt = xbox_rt.max(ax);
+                }
+                MappingTarget::XboxLS => {
+                    xbox_ls = pad_stick(m.source, (ax, ay), pad_l, pad_r);
+                }
+                MappingTarget::XboxRS => {
+                    xbox_rs = pad_stick(m.source, (ax, ay), pad_l, pad_r);
+                }
+                MappingTarget::XboxDpad { threshold } if matches!(m.source, crate::mapping::PhysicalButton::LeftStick | crate::mapping::PhysicalButton::RightStick) => {
+                    let stick = (m.source == crate::mapping::PhysicalButton::RightStick) as usize;
+                    dpad_held[stick] |= stick_dpad.press(stick, (ax, ay), *threshold);
+                    raw_buttons |= dpad_held[stick];
+                }
+                // Triggers fill the axis from its low end, sticks swing around the center
+                MappingTarget::VJoyAxis(axis) if matches!(m.source, crate::mapping::PhysicalButton::L2 | crate::mapping::PhysicalButton::R2) => {
+                    vjoy_frame.set_axis(*axis, ax);
+                }
+                MappingTarget::VJoyAxis(axis) => {
+                    vjoy_frame.set_stick_axis(*axis, ax);
+                }
+                MappingTarget::VJoyStick { x, y } => {
+                    vjoy_frame.set_stick_axis(*x, ax);
+                    vjoy_frame.set_stick_axis(*y, ay);
+                }
+                _ => {}
             }
-        } else if m.source.get_value(s) {
-            for t in &m.targets {
-                match t {
-                    MappingTarget::Xbox(bit) => {
-                        raw_buttons |= bit;
-                    }
-                    MappingTarget::XboxLT => {
-                        xbox_lt = 1.0;
-                    }
-                    MappingTarget::XboxRT => {
-                        xbox_rt = 1.0;
-                    }
-                    MappingTarget::Keyboard(vk) => {
-                        current_keys.insert(*vk);
-                    }
-                    MappingTarget::Mouse(btn) => {
-                        current_mouse.insert(*btn);
-                    }
-                    _ => {}
+        }
+    }
+
+    stick_dpad.held = dpad_held;
+
+    // Digital targets: pressed buttons, plus whatever auto-sprint and running macros hold
+    let digital = mappings.filter(|m| !m.source.is_axis());
+    let macros_changed = macros.update(s, digital.clone().map(|m| (m.source, m.active_targets(shifted))));
+    let turbo_changed = turbo.update(s, digital.clone());
+    let pressed = digital
+        .filter(|m| m.source.get_value(s) && turbo.is_on(m.source))
+        .flat_map(|m| m.active_targets(shifted))
+        .chain(auto_sprint.update(s))
+        .chain(macros.held.iter());
+    let mut dpad_ls: Option<(f32, f32)> = None;
+    let mut dpad_rs: Option<(f32, f32)> = None;
+    for t in pressed {
+        match t {
+            MappingTarget::Xbox(bit) => {
+                raw_buttons |= bit;
+            }
+            MappingTarget::XboxLT => {
+                xbox_lt = 1.0;
+            }
+            MappingTarget::XboxRT => {
+                xbox_rt = 1.0;
+            }
+            MappingTarget::Keyboard(vk) => {
+                current_keys.insert(KeyStroke::from_vk(*vk, KeyInjection::Scancode));
+            }
+            MappingTarget::KeyboardVia { vk, via } => {
+                current_keys.insert(KeyStroke::from_vk(*vk, *via));
+            }
+            MappingTarget::Key { code, via } => {
+                // Held keys keep the VK they went down with, even if the layout changes meanwhile
+                let held = active_keys.iter().find(|k| k.via == *via && Some(k.scan) == crate::keys::scancode(code));
+                if let Some(key) = held.copied().or_else(|| KeyStroke::from_code(code, *via)) {
+                    current_keys.insert(key);
                 }
             }
+            MappingTarget::Mouse(btn) => {
+                current_mouse.insert(*btn);
+            }
+            MappingTarget::GyroMouse { sensitivity } => {
+                gyro_sens = gyro_sens.max(*sensitivity);
+            }
+            MappingTarget::GyroRecenter => {
+                gyro_recenter = true;
+            }
+            MappingTarget::VJoyAxis(axis) => {
+                vjoy_frame.set_axis(*axis, 1.0);
+            }
+            MappingTarget::VJoyButton(n) => {
+                vjoy_frame.press(*n);
+            }
+            MappingTarget::XboxLSDir(dir) => {
+                let (x, y) = dir.vector();
+                let push = dpad_ls.get_or_insert((0.0, 0.0));
+                *push = (push.0 + x, push.1 + y);
+            }
+            MappingTarget::XboxRSDir(dir) => {
+                let (x, y) = dir.vector();
+                let push = dpad_rs.get_or_insert((0.0, 0.0));
+                *push = (push.0 + x, push.1 + y);
+            }
+            _ => {}
         }
     }
 
-    gamepad.buttons = vigem_client::XButtons(raw_buttons);
-    gamepad.left_trigger = (xbox_lt * 255.0) as u8;
-    gamepad.right_trigger = (xbox_rt * 255.0) as u8;
+    // D-pad as a stick: held directions take over the stick at full deflection, diagonals on the
+    // rim rather than in the corner. Opposite directions cancel out to center.
+    let full = |(x, y): (f32, f32)| {
+        let magnitude = (x * x + y * y).sqrt();
+        if magnitude > 0.0 { (x / magnitude, y / magnitude) } else { (0.0, 0.0) }
+    };
+    if let Some(push) = dpad_ls {
+        xbox_ls = full(push);
+    }
+    if let Some(push) = dpad_rs {
+        xbox_rs = full(push);
+    }
+
+    // Gyro aiming: degrees turned since the last frame, yaw to X and pitch to Y. Recenter holds
+    // it still, so the controller can be brought back to a comfortable angle.
+    if gyro_sens > 0.0 && !gyro_recenter {
+        mouse_dx -= s.gyro_y * dt * gyro_sens;
+        mouse_dy -= s.gyro_x * dt * gyro_sens;
+    }
+
+    gamepad.buttons = raw_buttons;
+    gamepad.left_trigger = (trigger_curves[0].apply(xbox_lt) * 255.0) as u8;
+    gamepad.right_trigger = (trigger_curves[1].apply(xbox_rt) * 255.0) as u8;
     gamepad.thumb_lx = (xbox_ls.0 * 32767.0) as i16;
     gamepad.thumb_ly = (-xbox_ls.1 * 32767.0) as i16; 
     gamepad.thumb_rx = (xbox_rs.0 * 32767.0) as i16;
     gamepad.thumb_ry = (-xbox_rs.1 * 32767.0) as i16; 
 
-    if state_changed {
+    if let (true, Some(target)) = (state_changed || macros_changed || turbo_changed, target) {
         let _ = target.update(&gamepad);
     }
+    if let Some(v) = vjoy {
+        v.update(&vjoy_frame); // Writes only what changed
+    }
+
+    // No linked game in front (focus.rs): release keys and buttons, drop movement
+    if desktop_pause {
+        current_keys.clear();
+        current_mouse.clear();
+        mouse_dx = 0.0;
+        mouse_dy = 0.0;
+        scroll_dy = 0.0;
+        zoom = 0.0;
+    }
 
     // Keyboard Emulation
-    for vk in &current_keys {
-        if !active_keys.contains(vk) {
-            unsafe { send_key(*vk, true); }
+    for key in &current_keys {
+        if !active_keys.contains(key) {
+            Input::send_key(key, true);
         }
     }
-    for vk in active_keys.iter() {
-        if !current_keys.contains(vk) {
-            unsafe { send_key(*vk, false); }
+    for key in active_keys.iter() {
+        if !current_keys.contains(key) {
+            Input::send_key(key, false);
         }
     }
     *active_keys = current_keys;
@@ -1090,12 +2729,12 @@ fn update_virtual_pad(
     // Mouse Buttons
     for btn in &current_mouse {
         if !active_mouse.contains(btn) {
-            unsafe { send_mouse(*btn, true); }
+            Input::send_mouse_button(*btn, true);
         }
     }
     for btn in active_mouse.iter() {
         if !current_mouse.contains(btn) {
-            unsafe { send_mouse(*btn, false); }
+            Input::send_mouse_button(*btn, false);
         }
     }
     *active_mouse = current_mouse;
@@ -1110,22 +2749,7 @@ fn update_virtual_pad(
     if move_x != 0 || move_y != 0 {
         mouse_acc.0 -= move_x as f32;
         mouse_acc.1 -= move_y as f32;
-        unsafe {
-            let input = INPUT {
-                r#type: INPUT_MOUSE,
-                Anonymous: INPUT_0 {
-                    mi: MOUSEINPUT {
-                        dx: move_x,
-                        dy: move_y,
-                        mouseData: 0,
-                        dwFlags: MOUSEEVENTF_MOVE,
-                        time: 0,
-                        dwExtraInfo: 0,
-                    }
-                }
-            };
-            SendInput(&[input], std::mem::size_of::<INPUT>() as i32);
-        }
+        Input::send_mouse_move(move_x, move_y);
     }
 
     // Mouse Scroll with Accumulation
@@ -1137,74 +2761,20 @@ fn update_virtual_pad(
         let move_scroll = scroll_ticks * direction;
         *scroll_acc -= move_scroll as f32;
         
-        unsafe {
-            let input = INPUT {
-                r#type: INPUT_MOUSE,
-                Anonymous: INPUT_0 {
-                    mi: MOUSEINPUT {
-                        dx: 0,
-                        dy: 0,
-                        mouseData: (move_scroll * 120) as u32,
-                        dwFlags: MOUSEEVENTF_WHEEL,
-                        time: 0,
-                        dwExtraInfo: 0,
-                    }
-                }
-            };
-            SendInput(&[input], std::mem::size_of::<INPUT>() as i32);
-        }
+        Input::send_wheel(move_scroll);
     }
-}
 
-unsafe fn send_key(vk: u16, down: bool) {
-    let scancode = MapVirtualKeyW(vk as u32, MAPVK_VK_TO_VSC);
-    
-    let mut flags = if down { KEYBD_EVENT_FLAGS(0) } else { KEYEVENTF_KEYUP };
-    if scancode > 0 {
-        flags |= KEYEVENTF_SCANCODE;
+    // Pinch Zoom: Ctrl + wheel, spreading the fingers zooms in
+    pinch.acc += zoom;
+    let notches = pinch.acc.trunc() as i32;
+    if notches != 0 {
+        pinch.acc -= notches as f32;
+        let ctrl_held = active_keys.iter().any(|k| matches!(k.vk, VK_CONTROL | VK_LCONTROL | VK_RCONTROL));
+        let ctrl = KeyStroke::from_vk(VK_CONTROL, KeyInjection::Scancode);
+        if !ctrl_held { Input::send_key(&ctrl, true); }
+        Input::send_wheel(notches);
+        if !ctrl_held { Input::send_key(&ctrl, false); }
     }
-    
-    // Some keys need extended flag (arrows, numpad enter, etc)
-    if (vk >= 33 && vk <= 46) || (vk >= 91 && vk <= 93) || (vk >= 106 && vk <= 111) {
-        flags |= KEYEVENTF_EXTENDEDKEY;
-    }
-
-    let input = INPUT {
-        r#type: INPUT_KEYBOARD,
-        Anonymous: INPUT_0 {
-            ki: KEYBDINPUT {
-                wVk: VIRTUAL_KEY(vk),
-                wScan: scancode as u16,
-                dwFlags: flags,
-                time: 0,
-                dwExtraInfo: 0,
-            },
-        },
-    };
-    SendInput(&[input], std::mem::size_of::<INPUT>() as i32);
-}
-
-unsafe fn send_mouse(btn: u8, down: bool) {
-    let flags = match (btn, down) {
-        (0, true) => MOUSEEVENTF_LEFTDOWN,
-        (0, false) => MOUSEEVENTF_LEFTUP,
-        (1, true) => MOUSEEVENTF_MIDDLEDOWN,
-        (1, false) => MOUSEEVENTF_MIDDLEUP,
-        (2, true) => MOUSEEVENTF_RIGHTDOWN,
-        (2, false) => MOUSEEVENTF_RIGHTUP,
-        _ => return,
-    };
-    let input = INPUT {
-        r#type: INPUT_MOUSE,
-        Anonymous: INPUT_0 {
-            mi: MOUSEINPUT {
-                dx: 0, dy: 0, mouseData: 0,
-                dwFlags: flags,
-                time: 0,
-                dwExtraInfo: 0,
-            }
-        }
-    };
-    SendInput(&[input], std::mem::size_of::<INPUT>() as i32);
-}
 
+    FrameModes { shift_layer: shifted, gyro_aim: gyro_sens > 0.0 && !gyro_recenter, gyro_recenter }
+}
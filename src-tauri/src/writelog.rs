@@ -0,0 +1,78 @@
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::{info, warn};
+use serde::Serialize;
+
+use crate::capture::Direction;
+use crate::config::AppConfig;
+
+// --- Write History ---
+// Every output/feature report DX3 sends goes through dualsense::write_report/send_feature, which
+// record the result here. The last HISTORY_LEN writes are kept in memory for the debug panel;
+// optionally every write is also appended to %APPDATA%/DX3/writes.log as
+// "<unix ms> <out|feature> <status> <hex>", so intermittent BT failures can be caught over hours.
+
+const HISTORY_LEN: usize = 200;
+
+#[derive(Clone, Serialize)]
+pub struct WriteEntry {
+    pub ts_ms: u64,
+    pub kind: &'static str,
+    pub ok: bool,
+    pub status: String,
+    pub hex: String,
+}
+
+static HISTORY: Mutex<VecDeque<WriteEntry>> = Mutex::new(VecDeque::new());
+static FILE: Mutex<Option<File>> = Mutex::new(None);
+
+pub fn log_path() -> PathBuf {
+    AppConfig::config_path().parent().unwrap().join("writes.log")
+}
+
+pub fn record(dir: Direction, data: &[u8], result: Result<usize, String>) {
+    let ts_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+    let hex = data.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ");
+    let (ok, status) = match result {
+        Ok(n) => (true, format!("OK ({} bytes)", n)),
+        Err(e) => (false, format!("Error: {}", e)),
+    };
+
+    if let Some(file) = FILE.lock().unwrap().as_mut() {
+        if let Err(e) = writeln!(file, "{} {} {} {}", ts_ms, dir.name(), status, hex) {
+            warn!("Failed to write {:?}: {}", log_path(), e);
+        }
+    }
+
+    let mut history = HISTORY.lock().unwrap();
+    if history.len() >= HISTORY_LEN {
+        history.pop_front();
+    }
+    history.push_back(WriteEntry { ts_ms, kind: dir.name(), ok, status, hex });
+}
+
+/// Oldest first
+pub fn history() -> Vec<WriteEntry> {
+    HISTORY.lock().unwrap().iter().cloned().collect()
+}
+
+pub fn clear() {
+    HISTORY.lock().unwrap().clear();
+}
+
+/// Appends to writes.log while enabled. Returns the file path.
+pub fn set_file_logging(enabled: bool) -> anyhow::Result<PathBuf> {
+    let path = log_path();
+    let mut file = FILE.lock().unwrap();
+    if enabled {
+        *file = Some(OpenOptions::new().create(true).append(true).open(&path)?);
+        info!("Logging writes to {:?}", path);
+    } else if file.take().is_some() {
+        info!("Stopped logging writes to {:?}", path);
+    }
+    Ok(path)
+}
@@ -1,10 +1,13 @@
 const { invoke } = window.__TAURI__.tauri;
 const { listen } = window.__TAURI__.event;
 const { open } = window.__TAURI__.shell;
+const { save, open: openDialog } = window.__TAURI__.dialog;
 const appWindow = window.__TAURI__.window.getCurrent();
 
 // --- State & Assets ---
 let currentState = null;
+let currentApiPort = 7437;
+let currentPauseHotkey = '';
 const canvas = document.getElementById('gamepad-canvas');
 const ctx = canvas.getContext('2d');
 let bgImage = new Image();
@@ -59,8 +62,13 @@ const el = (id) => document.getElementById(id);
 
 const ui = {
     status: el('status-val'),
+    mode: el('mode-val'),
     device: el('device-val'),
+    controllingLine: el('controlling-line'),
+    controlling: el('controlling-val'),
     battery: el('battery-val'),
+    jack: el('jack-val'),
+    motion: el('motion-val'),
     connLine: el('conn-line'),
     connMode: el('conn-mode'),
     connWarning: el('conn-warning'),
@@ -73,21 +81,107 @@ const ui = {
     btnCheckHidHide: el('btn-check-hidhide'),
     chkHide: el('chk-hide'),
     chkMin: el('chk-minimized'),
+    chkAutostart: el('chk-autostart'),
+    chkElevated: el('chk-elevated'),
+    chkApi: el('chk-api'),
+    inpPauseHotkey: el('inp-pause-hotkey'),
+    selLogLevel: el('sel-log-level'),
+    chkDiscord: el('chk-discord'),
+    inpDiscordAppId: el('inp-discord-app-id'),
+    chkPsHold: el('chk-ps-hold'),
+    chkHapticAlerts: el('chk-haptic-alerts'),
+    inpVjoyDevice: el('inp-vjoy-device'),
+    vjoyStatus: el('vjoy-status'),
+    selOrientation: el('sel-orientation'),
+    chkKbm: el('chk-kbm'),
+    chkFocusPause: el('chk-focus-pause'),
+    chkQuietHours: el('chk-quiet-hours'),
+    inpQuietStart: el('inp-quiet-start'),
+    inpQuietEnd: el('inp-quiet-end'),
+    inpQuietBrightness: el('inp-quiet-brightness'),
+    chkQuietPled: el('chk-quiet-pled'),
+    inpFocusGames: el('inp-focus-games'),
+    btnFocusAdd: el('btn-focus-add'),
+    inpKbmSens: el('inp-kbm-sens'),
+    inpKbmRamp: el('inp-kbm-ramp'),
+    chkAutoSprint: el('chk-auto-sprint'),
+    selAutoSprintTarget: el('sel-auto-sprint-target'),
+    inpAutoSprintMs: el('inp-auto-sprint-ms'),
+    inpAutoSprintThreshold: el('inp-auto-sprint-threshold'),
+    btnStickCal: el('btn-stick-cal'),
+    btnStickCalReset: el('btn-stick-cal-reset'),
+    stickCalStatus: el('stick-cal-status'),
+    btnGyroCal: el('btn-gyro-cal'),
+    btnGyroCalReset: el('btn-gyro-cal-reset'),
+    gyroCalStatus: el('gyro-cal-status'),
+    btnStickPlot: el('btn-stick-plot'),
+    stickPlotBox: el('stick-plot-box'),
+    stickPlot: el('stick-plot'),
+    stickPlotInfo: el('stick-plot-info'),
+    btnInputStats: el('btn-input-stats'),
+    btnInputStatsReset: el('btn-input-stats-reset'),
+    inputStats: el('input-stats'),
+    chkInputRecording: el('chk-input-recording'),
+    inputRecordingPath: el('input-recording-path'),
+    inpIdleOff: el('inp-idle-off'),
+    selPowerCombo: el('sel-power-combo'),
+    inpPowerHold: el('inp-power-hold'),
+    selProfileCycleCombo: el('sel-profile-cycle-combo'),
+    inpHookConnect: el('inp-hook-connect'),
+    inpHookDisconnect: el('inp-hook-disconnect'),
+    chkMqtt: el('chk-mqtt'),
+    inpMqttBroker: el('inp-mqtt-broker'),
+    inpMqttTopic: el('inp-mqtt-topic'),
+    inpMqttUser: el('inp-mqtt-user'),
+    inpMqttPass: el('inp-mqtt-pass'),
     btnDebug: el('btn-debug-toggle'),
     debugPanel: el('debug-panel'),
     btnRemap: el('btn-remapping-toggle'),
     remapPanel: el('remapping-panel'),
     mappingList: el('mapping-list'),
     btnReset: el('btn-reset-mappings'),
+    selShiftButton: el('sel-shift-button'),
+    chkSwapSticks: el('chk-swap-sticks'),
+    chkEditShiftLayer: el('chk-edit-shift-layer'),
     btnDisconnect: el('btn-disconnect'),
+    foundControllers: el('found-controllers'),
+    chkManualConnect: el('chk-manual-connect'),
+    inpOutputKeepalive: el('inp-output-keepalive'),
+    btnIdentify: el('btn-identify'),
     // Profiles
     mainProfileCtrl: el('main-profile-ctrl'),
     selProfiles: el('sel-profiles'),
     selProfilesMain: el('sel-profiles-main'),
+    selProfileParent: el('sel-profile-parent'),
     btnDeleteProfile: el('btn-delete-profile'),
     btnSaveProfile: el('btn-save-profile'),
+    btnExportProfile: el('btn-export-profile'),
+    btnImportProfile: el('btn-import-profile'),
     inpProfileName: el('inp-profile-name'),
     hexGrid: el('hex-grid'),
+    chkReportDiff: el('chk-report-diff'),
+    layoutNote: el('layout-note'),
+    inpLayoutId: el('inp-layout-id'),
+    inpLayoutLx: el('inp-layout-lx'),
+    inpLayoutLy: el('inp-layout-ly'),
+    inpLayoutRx: el('inp-layout-rx'),
+    inpLayoutRy: el('inp-layout-ry'),
+    inpLayoutL2: el('inp-layout-l2'),
+    inpLayoutR2: el('inp-layout-r2'),
+    inpLayoutButtons: el('inp-layout-buttons'),
+    chkLayoutMute: el('chk-layout-mute'),
+    chkLayoutInvertY: el('chk-layout-invert-y'),
+    btnLayoutSave: el('btn-layout-save'),
+    btnLayoutReset: el('btn-layout-reset'),
+    chkDescriptorParsing: el('chk-descriptor-parsing'),
+    descriptorLayout: el('descriptor-layout'),
+    btnCapture: el('btn-capture'),
+    captureCount: el('capture-count'),
+    btnCaptureExport: el('btn-capture-export'),
+    writeHistory: el('write-history'),
+    writeHistorySummary: el('write-history-summary'),
+    btnWriteHistoryClear: el('btn-write-history-clear'),
+    chkWriteLog: el('chk-write-log'),
     reportMode: el('report-mode'),
     // Action Picker
     picker: el('action-picker'),
@@ -104,6 +198,16 @@ const ui = {
     sldDzRight: el('sld-dz-right'),
     inpDzLeft: el('inp-dz-left'),
     inpDzRight: el('inp-dz-right'),
+    selDzShapeLeft: el('sel-dz-shape-left'),
+    selDzShapeRight: el('sel-dz-shape-right'),
+    sldAdzLeft: el('sld-adz-left'),
+    sldAdzRight: el('sld-adz-right'),
+    inpAdzLeft: el('inp-adz-left'),
+    inpAdzRight: el('inp-adz-right'),
+    selCurveLeft: el('sel-curve-left'),
+    selCurveRight: el('sel-curve-right'),
+    inpCurveLeft: el('inp-curve-left'),
+    inpCurveRight: el('inp-curve-right'),
     boxSensLeft: el('sens-left-box'),
     boxSensRight: el('sens-right-box'),
     sldSensLeft: el('sld-sens-left'),
@@ -113,7 +217,15 @@ const ui = {
     // Touchpad Sens
     sensTouchCtrl: el('sens-touch-ctrl'),
     sldSensTouch: el('sld-sens-touch'),
+    btnTouchCal: el('btn-touch-cal'),
+    btnTouchCalReset: el('btn-touch-cal-reset'),
+    touchCalStatus: el('touch-cal-status'),
     inpSensTouch: el('inp-sens-touch'),
+    chkTrackball: el('chk-trackball'),
+    inpTrackballFriction: el('inp-trackball-friction'),
+    selInputBackend: el('sel-input-backend'),
+    selPadKind: el('sel-pad-kind'),
+    inputBackendStatus: el('input-backend-status'),
     // Main RGB
     rgbCtrl: el('rgb-ctrl'),
     mainSldR: el('main-sld-r'),
@@ -130,11 +242,37 @@ const ui = {
     selTriggerR2Mode: el('sel-trigger-r2-mode'),
     sldTriggerR2Start: el('sld-trigger-r2-start'),
     sldTriggerR2Force: el('sld-trigger-r2-force'),
+    inpTriggerL2Min: el('inp-trigger-l2-min'),
+    inpTriggerL2Max: el('inp-trigger-l2-max'),
+    inpTriggerL2Curve: el('inp-trigger-l2-curve'),
+    inpTriggerR2Min: el('inp-trigger-r2-min'),
+    inpTriggerR2Max: el('inp-trigger-r2-max'),
+    inpTriggerR2Curve: el('inp-trigger-r2-curve'),
+    inpTriggerL2Points: el('inp-trigger-l2-points'),
+    inpTriggerR2Points: el('inp-trigger-r2-points'),
     triggerL2Ctrl: el('trigger-l2-ctrl'),
     triggerR2Ctrl: el('trigger-r2-ctrl'),
     // Fuzzer
     btnFuzzer: el('btn-fuzzer'),
     fuzzerStatus: el('fuzzer-status'),
+    fuzzerStep: el('fuzzer-step'),
+    btnFuzzPrev: el('btn-fuzz-prev'),
+    btnFuzzNext: el('btn-fuzz-next'),
+    inpFuzzStep: el('inp-fuzz-step'),
+    btnFuzzJump: el('btn-fuzz-jump'),
+    inpFuzzNote: el('inp-fuzz-note'),
+    btnFuzzMark: el('btn-fuzz-mark'),
+    btnFuzzLog: el('btn-fuzz-log'),
+    inpFuzzIds: el('inp-fuzz-ids'),
+    inpFuzzFlags: el('inp-fuzz-flags'),
+    inpFuzzRgb: el('inp-fuzz-rgb'),
+    inpFuzzValues: el('inp-fuzz-values'),
+    inpFuzzBurst: el('inp-fuzz-burst'),
+    inpFuzzDelay: el('inp-fuzz-delay'),
+    btnFuzzApply: el('btn-fuzz-apply'),
+    btnFuzzScript: el('btn-fuzz-script'),
+    btnFuzzScriptCheck: el('btn-fuzz-script-check'),
+    inpFuzzScript: el('inp-fuzz-script'),
     devicePath: el('device-path'),
     lastWrite: el('last-write-status'),
     packetHex: el('packet-hex'),
@@ -153,12 +291,23 @@ const ui = {
     sldG: el('sld-g'),
     sldB: el('sld-b'),
     btnManual: el('btn-manual-send'),
+    selManualTpl: el('sel-manual-tpl'),
+    btnTplLoad: el('btn-tpl-load'),
+    btnTplDelete: el('btn-tpl-delete'),
+    inpTplName: el('inp-tpl-name'),
+    btnTplSave: el('btn-tpl-save'),
     // Config
     chkNoPeriod: el('chk-no-periodic'),
     selCrc: el('sel-crc'),
     // Scanner
     btnSweep: el('btn-sweep'),
     inpSweepSpeed: el('inp-sweep-speed'),
+    chkSweepTargeted: el('chk-sweep-targeted'),
+    inpSweepStart: el('inp-sweep-start'),
+    inpSweepEnd: el('inp-sweep-end'),
+    inpSweepStep: el('inp-sweep-step'),
+    inpSweepValues: el('inp-sweep-values'),
+    btnSweepApply: el('btn-sweep-apply'),
     // Pinpoint
     inpPpOff: el('inp-pp-off'),
     inpPpVal: el('inp-pp-val'),
@@ -166,6 +315,9 @@ const ui = {
     // Logs
     logDevices: el('log-devices'),
     btnProto: el('btn-proto-scan'),
+    btnProtoExport: el('btn-proto-export'),
+    btnHidDesc: el('btn-hid-desc'),
+    btnInputScan: el('btn-input-scan'),
     logProto: el('log-proto'),
     githubIcon: el('github-icon')
 };
@@ -202,6 +354,22 @@ ui.btnDlHidHide.addEventListener('click', () => {
 // Driver Check Flags
 let ignoreVigemUpdate = false;
 let ignoreHidHideUpdate = false;
+let vigemHealth = null; // get_vigem_health, shown as the ViGEmBus status tooltip
+
+const refreshVigemHealth = async () => {
+    try {
+        vigemHealth = await invoke('get_vigem_health');
+    } catch (e) {
+        console.error("ViGEm health query failed:", e);
+        return;
+    }
+    const h = vigemHealth;
+    ui.statusVigem.title = [
+        h.installed ? `Driver ${h.driver_version || 'installed (version unknown)'}` : 'Driver not installed',
+        h.connected ? 'Connection OK' : `Connection failed: ${h.error || 'unknown error'}`,
+        `Virtual pads plugged in: ${h.plugged_targets}`,
+    ].join('\n');
+};
 
 // Driver Check Logic
 const fakeCheck = (el, type) => {
@@ -217,7 +385,10 @@ const fakeCheck = (el, type) => {
 
     // Release lock after 1.5s to allow user to see "Checking..."
     setTimeout(() => {
-        if (type === 'vigem') ignoreVigemUpdate = false;
+        if (type === 'vigem') {
+            ignoreVigemUpdate = false;
+            refreshVigemHealth();
+        }
         if (type === 'hidhide') ignoreHidHideUpdate = false;
     }, 1500);
 };
@@ -254,13 +425,27 @@ const HITBOXES = {
     'Mute': { type: 'rect', x: 835, y: 625, w: 80, h: 30 },
     'Touchpad': { type: 'rect', x: 835, y: 200, w: 650, h: 310, isAxis: true },
     'TouchpadLeft': { type: 'rect', x: 672, y: 200, w: 280, h: 260 },
-    'TouchpadRight': { type: 'rect', x: 998, y: 200, w: 280, h: 260 }
+    'TouchpadRight': { type: 'rect', x: 998, y: 200, w: 280, h: 260 },
+    // DualSense Edge: Fn buttons below the sticks, back paddles drawn on the grips
+    'FnLeft': { type: 'rect', x: 640, y: 690, w: 80, h: 34, edge: true },
+    'FnRight': { type: 'rect', x: 1030, y: 690, w: 80, h: 34, edge: true },
+    'PaddleLeft': { type: 'rect', x: 330, y: 900, w: 170, h: 44, edge: true },
+    'PaddleRight': { type: 'rect', x: 1340, y: 900, w: 170, h: 44, edge: true }
 };
+const EDGE_DEVICE_KEY = '054C:0DF2';
+
+// Edge buttons only show up with an Edge connected, or when a profile already maps them
+function edgeButtonsVisible() {
+    if (currentState && currentState.device_key === EDGE_DEVICE_KEY) return true;
+    const mappings = currentState && currentState.mappings;
+    return !!mappings && mappings.some(m => HITBOXES[m.source] && HITBOXES[m.source].edge && isMappingModified(m));
+}
 
 let hoveredButton = null;
 let selectedButton = null;
 let layoutData = { offsetX: 0, offsetY: 0, scale: 1 };
 let isAppendingMapping = false; // If true, add to targets. If false, replace targets.
+let editingShiftLayer = false; // Mapping editor shows and edits the shift layer targets
 
 // Helper to get screen coords for a hitbox
 function getScreenBox(key) {
@@ -309,7 +494,9 @@ canvas.addEventListener('mousemove', (e) => {
     let found = null;
     // Iterate in reverse to catch axes (Sticks) which overlap with L3/R3
     const keys = Object.keys(HITBOXES);
+    const showEdge = edgeButtonsVisible();
     for (let i = keys.length - 1; i >= 0; i--) {
+        if (HITBOXES[keys[i]].edge && !showEdge) continue;
         if (isPointInBox(mx, my, keys[i])) {
             found = keys[i];
             break;
@@ -390,6 +577,26 @@ function showPicker(x, y) {
     // Show/Hide Axis specific options
     el('opt-mouse-move').style.display = isAxis ? 'block' : 'none';
     el('opt-mouse-scroll').style.display = isAxis ? 'block' : 'none';
+    el('opt-circular-scroll').style.display = isTouchpadWhole ? 'block' : 'none';
+    el('opt-pinch-zoom').style.display = isTouchpadWhole ? 'block' : 'none';
+    el('opt-gyro-mouse').style.display = (isStick || isTouchpadWhole) ? 'none' : 'block';
+    el('opt-gyro-recenter').style.display = isAxis ? 'none' : 'block';
+    el('opt-macro').style.display = isAxis ? 'none' : 'block';
+    el('opt-vjoy').style.display = (currentState.vjoy_device > 0 && !isTouchpadWhole) ? 'block' : 'none';
+}
+
+// Sends just this source's targets, so a stale copy of the other mappings can't overwrite them
+const saveMapping = (m) => invoke(m.shift ? 'update_shift_mapping' : 'update_mapping', { source: m.source, targets: m.targets });
+
+// The mapping as the editor sees it: on the shift layer, `targets` are its shift targets
+function layerView(m) {
+    if (!editingShiftLayer) return m;
+    return {
+        source: m.source,
+        shift: true,
+        get targets() { return m.shift_targets || (m.shift_targets = []); },
+        set targets(t) { m.shift_targets = t; },
+    };
 }
 
 function getMappingFor(source) {
@@ -399,9 +606,12 @@ function getMappingFor(source) {
         m = { source: source, targets: [] };
         currentState.mappings.push(m);
     }
-    return m;
+    return layerView(m);
 }
 
+const STICK_DPAD_THRESHOLD = 0.5;
+const STICK_ARROWS = { Up: '↑', Down: '↓', Left: '←', Right: '→' };
+
 el('opt-xbox').onclick = () => {
     ui.mainOptions.style.display = 'none';
     ui.xboxOptions.style.display = 'flex';
@@ -428,6 +638,52 @@ el('opt-xbox').onclick = () => {
         btn.onclick = () => pickXboxTriggerTarget(selectedButton, 'Xbox' + t);
         ui.xboxGrid.appendChild(btn);
     });
+
+    // Stick to D-pad, or a button pushing a stick all the way
+    const add = (label, target) => {
+        const btn = document.createElement('div');
+        btn.className = 'btn-xbox-pick';
+        btn.textContent = label;
+        btn.onclick = () => pickTarget(selectedButton, target);
+        ui.xboxGrid.appendChild(btn);
+    };
+    if (isStick) {
+        add('D-Pad', { XboxDpad: { threshold: STICK_DPAD_THRESHOLD } });
+    } else if (!HITBOXES[selectedButton].isAxis) {
+        ['LS', 'RS'].forEach(stick => Object.entries(STICK_ARROWS).forEach(([dir, arrow]) => {
+            add(`${stick} ${arrow}`, { [`Xbox${stick}Dir`]: dir });
+        }));
+    }
+};
+
+
+const VJOY_AXES = ['X', 'Y', 'Z', 'Rx', 'Ry', 'Rz', 'Slider0', 'Slider1'];
+const VJOY_STICKS = [['X', 'Y'], ['Rx', 'Ry'], ['Z', 'Rz'], ['Slider0', 'Slider1']];
+const VJOY_BUTTONS = 32; // Of 128; the usual vJoy configuration
+
+el('opt-vjoy').onclick = () => {
+    ui.mainOptions.style.display = 'none';
+    ui.xboxOptions.style.display = 'flex';
+    ui.pickerTitle.textContent = `Select vJoy Target`;
+
+    ui.xboxGrid.innerHTML = '';
+    const isStick = (selectedButton === 'LeftStick' || selectedButton === 'RightStick');
+    const add = (label, target) => {
+        const btn = document.createElement('div');
+        btn.className = 'btn-xbox-pick';
+        btn.textContent = label;
+        btn.onclick = () => pickTarget(selectedButton, target);
+        ui.xboxGrid.appendChild(btn);
+    };
+
+    if (isStick) {
+        VJOY_STICKS.forEach(([x, y]) => add(`${x}/${y}`, { VJoyStick: { x, y } }));
+        return;
+    }
+    VJOY_AXES.forEach(axis => add(axis, { VJoyAxis: axis }));
+    if (!HITBOXES[selectedButton].isAxis) {
+        for (let n = 1; n <= VJOY_BUTTONS; n++) add(`B${n}`, { VJoyButton: n });
+    }
 };
 
 el('opt-back').onclick = () => {
@@ -443,7 +699,7 @@ ui.optRecord.onclick = () => {
 el('opt-mouse-move').onclick = async () => {
     const m = getMappingFor(selectedButton);
     m.targets = [{ MouseMove: { x_speed: 25.0, y_speed: 25.0 } }];
-    await invoke('update_mappings', { mappings: currentState.mappings });
+    await saveMapping(m);
     ui.picker.style.display = 'none';
     selectedButton = null;
     renderMappings();
@@ -452,11 +708,104 @@ el('opt-mouse-move').onclick = async () => {
 el('opt-mouse-scroll').onclick = async () => {
     const m = getMappingFor(selectedButton);
     m.targets = [{ MouseScroll: { speed: 1.0 } }];
-    await invoke('update_mappings', { mappings: currentState.mappings });
+    await saveMapping(m);
+    ui.picker.style.display = 'none';
+    selectedButton = null;
+    renderMappings();
+};
+
+// Touchpad gestures and gyro aiming, added next to whatever the source already does
+const addExtraTarget = async (target) => {
+    const m = getMappingFor(selectedButton);
+    // Unit variants serialize as a bare string, the rest as { Kind: {...} }
+    const kind = typeof target === 'string' ? target : Object.keys(target)[0];
+    m.targets = m.targets.filter(t => t !== kind && t[kind] === undefined).concat([target]);
+    await saveMapping(m);
     ui.picker.style.display = 'none';
     selectedButton = null;
     renderMappings();
 };
+el('opt-circular-scroll').onclick = () => addExtraTarget({ CircularScroll: { speed: 1.0 } });
+el('opt-pinch-zoom').onclick = () => addExtraTarget({ PinchZoom: { speed: 1.0 } });
+el('opt-gyro-mouse').onclick = () => addExtraTarget({ GyroMouse: { sensitivity: 10.0 } });
+el('opt-gyro-recenter').onclick = () => addExtraTarget('GyroRecenter');
+
+// --- Macros ---
+// Edited as text: "KeyE 100, +LT, MouseLeft 50, -LT". A bare name taps the input (press, hold
+// MACRO_TAP_MS, release) and the number is the wait after it; "+"/"-" only press or only release.
+// Names are Xbox buttons (A, LB, Up, LT...), MouseLeft/MouseRight/MouseMiddle, or keys by position
+// (KeyW, Space, ShiftLeft, like recorded keys).
+const MACRO_TAP_MS = 50;
+const MACRO_MOUSE = ['MouseLeft', 'MouseRight', 'MouseMiddle']; // MappingTarget::Mouse order
+
+function macroTargetName(t) {
+    if (t.Xbox !== undefined) return XBOX_NAMES[t.Xbox];
+    if (t === 'XboxLT' || t === 'XboxRT') return t.slice(4);
+    if (t.Mouse !== undefined) return MACRO_MOUSE[t.Mouse];
+    if (t.Key !== undefined) return t.Key.code;
+    return '?';
+}
+
+function parseMacroTarget(name) {
+    const bit = Object.keys(XBOX_NAMES).find(b => XBOX_NAMES[b].toLowerCase() === name.toLowerCase());
+    if (bit !== undefined) return { Xbox: parseInt(bit) };
+    if (/^[lr]t$/i.test(name)) return 'Xbox' + name.toUpperCase();
+    const mouse = MACRO_MOUSE.findIndex(m => m.toLowerCase() === name.toLowerCase());
+    if (mouse >= 0) return { Mouse: mouse };
+    if (keyNames[name] !== undefined) return { Key: { code: name, via: 'scancode' } };
+    return null;
+}
+
+function formatMacro(steps) {
+    const parts = [];
+    for (let i = 0; i < steps.length; i++) {
+        const step = steps[i];
+        const name = macroTargetName(step.target);
+        const next = steps[i + 1];
+        const isTap = step.press && step.delay_ms === MACRO_TAP_MS && next && !next.press
+            && JSON.stringify(next.target) === JSON.stringify(step.target);
+        const token = isTap ? name : (step.press ? '+' : '-') + name;
+        const delay = isTap ? next.delay_ms : step.delay_ms;
+        parts.push(delay ? `${token} ${delay}` : token);
+        if (isTap) i++;
+    }
+    return parts.join(', ');
+}
+
+// Steps for the text form, or throws with what's wrong
+function parseMacro(text) {
+    const steps = [];
+    for (const part of text.split(',').map(p => p.trim()).filter(p => p)) {
+        const match = part.match(/^([+-]?)(\S+)(?:\s+(\d+))?$/);
+        const target = match && parseMacroTarget(match[2]);
+        if (!target) throw new Error(`"${part}" isn't a key, mouse or Xbox button`);
+        const delay = match[3] ? parseInt(match[3]) : 0;
+        if (match[1]) {
+            steps.push({ target, press: match[1] === '+', delay_ms: delay });
+        } else {
+            steps.push({ target, press: true, delay_ms: MACRO_TAP_MS });
+            steps.push({ target, press: false, delay_ms: delay });
+        }
+    }
+    return steps;
+}
+
+el('opt-macro').onclick = () => {
+    const current = getMappingFor(selectedButton).targets.find(t => t.Macro !== undefined);
+    let text = current ? formatMacro(current.Macro) : 'KeyE 100, KeyR';
+    for (;;) {
+        text = prompt('Macro steps, e.g. "KeyE 100, +LT, MouseLeft 50, -LT"\nA name taps it, + presses, - releases. The number is the wait after it (ms).', text);
+        if (text === null) return;
+        try {
+            const steps = parseMacro(text);
+            if (steps.length === 0) throw new Error('No steps');
+            addExtraTarget({ Macro: steps });
+            return;
+        } catch (err) {
+            alert(err.message);
+        }
+    }
+};
 
 el('opt-clear').textContent = 'Reset to Default';
 el('opt-clear').onclick = async () => {
@@ -472,7 +821,7 @@ el('opt-clear').onclick = async () => {
 
     m.targets = JSON.parse(JSON.stringify(defTargets));
 
-    await invoke('update_mappings', { mappings: currentState.mappings });
+    await saveMapping(m);
     ui.picker.style.display = 'none';
     selectedButton = null;
     renderMappings();
@@ -487,6 +836,156 @@ for (let i = 0; i < 80; i++) {
     ui.hexGrid.appendChild(d);
 }
 
+// Live Diff: flash bytes that changed since the last event, tint by how often they change
+ui.chkReportDiff.addEventListener('change', (e) => {
+    invoke('set_report_diff', { val: e.target.checked });
+    if (!e.target.checked) {
+        for (let i = 0; i < 80; i++) {
+            const cell = document.getElementById('hex-' + i);
+            cell.classList.remove('changed');
+            cell.style.boxShadow = '';
+            cell.title = '';
+        }
+    }
+});
+
+listen('report-diff', (event) => {
+    if (!ui.chkReportDiff.checked) return;
+    const { changed, counts, packets } = event.payload;
+    const changedSet = new Set(changed);
+    for (let i = 0; i < 80; i++) {
+        const cell = document.getElementById('hex-' + i);
+        const rate = packets > 0 ? counts[i] / packets : 0;
+        cell.classList.toggle('changed', changedSet.has(i));
+        cell.style.boxShadow = rate > 0 ? `inset 0 -3px 0 rgba(229, 192, 123, ${Math.max(0.2, rate)})` : '';
+        cell.title = `Byte ${i}: changed ${counts[i]} / ${packets} packets (${(rate * 100).toFixed(1)}%)`;
+    }
+});
+
+// Input Layout (clones)
+let lastLayoutJSON = null;
+function syncLayoutInputs(layout) {
+    const json = JSON.stringify(layout);
+    if (json === lastLayoutJSON) return; // Don't overwrite what the user is typing
+    lastLayoutJSON = json;
+    if (!layout) return;
+    ui.inpLayoutId.value = layout.report_id.toString(16).toUpperCase().padStart(2, '0');
+    ui.inpLayoutLx.value = layout.lx;
+    ui.inpLayoutLy.value = layout.ly;
+    ui.inpLayoutRx.value = layout.rx;
+    ui.inpLayoutRy.value = layout.ry;
+    ui.inpLayoutL2.value = layout.l2;
+    ui.inpLayoutR2.value = layout.r2;
+    ui.inpLayoutButtons.value = layout.buttons;
+    ui.chkLayoutMute.checked = layout.mute;
+    ui.chkLayoutInvertY.checked = layout.invert_y;
+}
+ui.btnLayoutSave.addEventListener('click', () => {
+    const num = (inp) => parseInt(inp.value) || 0;
+    const layout = {
+        report_id: parseInt(ui.inpLayoutId.value, 16) || 0,
+        lx: num(ui.inpLayoutLx), ly: num(ui.inpLayoutLy),
+        rx: num(ui.inpLayoutRx), ry: num(ui.inpLayoutRy),
+        l2: num(ui.inpLayoutL2), r2: num(ui.inpLayoutR2),
+        buttons: num(ui.inpLayoutButtons),
+        mute: ui.chkLayoutMute.checked,
+        invert_y: ui.chkLayoutInvertY.checked,
+    };
+    invoke('set_report_layout', { layout }).catch(err => alert(`Could not save layout: ${err}`));
+});
+ui.btnLayoutReset.addEventListener('click', () => {
+    invoke('set_report_layout', { layout: null }).catch(err => alert(err));
+});
+ui.chkDescriptorParsing.addEventListener('change', (e) => {
+    invoke('set_descriptor_parsing', { val: e.target.checked });
+});
+
+// Traffic Capture
+ui.btnCapture.addEventListener('click', () => {
+    if (!currentState) return;
+    invoke('set_capture_active', { val: !currentState.capture_active });
+    currentState.capture_active = !currentState.capture_active;
+    setText(ui.btnCapture, currentState.capture_active ? 'Stop Capture' : 'Start Capture');
+});
+ui.btnCaptureExport.addEventListener('click', async () => {
+    const path = await save({
+        defaultPath: 'dx3-capture.pcapng',
+        filters: [
+            { name: 'pcapng (Wireshark)', extensions: ['pcapng'] },
+            { name: 'JSON Lines', extensions: ['jsonl'] },
+        ],
+    });
+    if (!path) return;
+    invoke('export_capture', { path })
+        .then(n => setText(ui.captureCount, `${n} packets exported`))
+        .catch(err => alert(`Export failed: ${err}`));
+});
+
+// Known output report offsets (output_layout.rs), used to annotate hex dumps
+let outputLayouts = [];
+invoke('get_output_layouts').then(layouts => { outputLayouts = layouts; });
+
+// One line per known field with a non-zero value, plus any non-zero byte we have no name for
+function annotateHex(hex) {
+    const bytes = (hex || '').trim().split(/\s+/).map(b => parseInt(b, 16));
+    if (bytes.length < 2 || bytes.some(isNaN)) return '';
+    const layout = outputLayouts.find(l => l.report_id === bytes[0]);
+    if (!layout) return '';
+
+    const known = new Set();
+    const lines = [];
+    for (const f of layout.fields) {
+        const slice = bytes.slice(f.offset, f.offset + f.len);
+        for (let i = f.offset; i < f.offset + f.len; i++) known.add(i);
+        if (slice.some(b => b !== 0)) {
+            lines.push(`@${f.offset} ${f.name}: ${slice.map(b => b.toString(16).toUpperCase().padStart(2, '0')).join(' ')}`);
+        }
+    }
+    bytes.forEach((b, i) => {
+        if (i > 0 && b !== 0 && !known.has(i)) lines.push(`@${i} ?: ${b.toString(16).toUpperCase().padStart(2, '0')}`);
+    });
+    return `${layout.name}\n${lines.join('\n')}`;
+}
+
+// Write History (polled, too big to ride along with every state update)
+let lastWriteHistoryKey = '';
+function refreshWriteHistory() {
+    invoke('get_write_history').then(entries => {
+        const failed = entries.filter(e => !e.ok).length;
+        const logging = ui.chkWriteLog.checked ? ` (logging to ${ui.chkWriteLog.dataset.path})` : '';
+        setText(ui.writeHistorySummary, `${entries.length} writes, ${failed} failed${logging}`);
+
+        const key = entries.length ? `${entries.length}:${entries[entries.length - 1].ts_ms}` : '';
+        if (key === lastWriteHistoryKey) return;
+        lastWriteHistoryKey = key;
+        ui.writeHistory.replaceChildren(...entries.slice().reverse().map(e => {
+            const time = new Date(e.ts_ms).toLocaleTimeString([], { hour12: false }) + '.' + String(e.ts_ms % 1000).padStart(3, '0');
+            const line = document.createElement('div');
+            line.textContent = `${time} ${e.kind.padEnd(7)} ${e.status}  ${e.hex}`;
+            line.title = annotateHex(e.hex); // Hover for field names
+            if (!e.ok) line.style.color = '#e06c75';
+            return line;
+        }));
+    });
+}
+setInterval(() => {
+    if (currentState && currentState.debug_active && ui.debugPanel.style.display !== 'none') refreshWriteHistory();
+}, 1000);
+ui.btnWriteHistoryClear.addEventListener('click', () => {
+    invoke('clear_write_history').then(refreshWriteHistory);
+});
+ui.chkWriteLog.addEventListener('change', (e) => {
+    invoke('set_write_log_file', { val: e.target.checked })
+        .then(path => {
+            ui.chkWriteLog.dataset.path = path;
+            refreshWriteHistory();
+        })
+        .catch(err => {
+            e.target.checked = false;
+            alert(`Could not open write log: ${err}`);
+        });
+});
+
 // --- Event Listeners ---
 
 // Top Bar
@@ -519,19 +1018,32 @@ ui.btnRemap.addEventListener('click', () => {
 });
 
 async function refreshProfilesList() {
-    const profiles = await invoke('get_profiles');
+    const profiles = (await invoke('get_profiles')).sort((a, b) => a.localeCompare(b));
     const updateSelect = (sel) => {
         sel.innerHTML = '<option value="Default">Default</option>';
+        // "Folder/Name" profiles are grouped under their folder
+        const groups = {};
         profiles.forEach(p => {
             if (p === 'Default') return;
+            const slash = p.lastIndexOf('/');
             const opt = document.createElement('option');
             opt.value = p;
-            opt.textContent = p;
+            opt.textContent = slash < 0 ? p : p.slice(slash + 1);
             if (currentState && currentState.current_profile_name === p) {
                 opt.selected = true;
             }
-            sel.appendChild(opt);
+            if (slash < 0) {
+                sel.appendChild(opt);
+                return;
+            }
+            const folder = p.slice(0, slash);
+            if (!groups[folder]) {
+                groups[folder] = document.createElement('optgroup');
+                groups[folder].label = folder;
+            }
+            groups[folder].appendChild(opt);
         });
+        Object.keys(groups).sort((a, b) => a.localeCompare(b)).forEach(f => sel.appendChild(groups[f]));
         // Ensure Default is selected if active
         if (currentState && currentState.current_profile_name === 'Default') {
             sel.value = 'Default';
@@ -540,10 +1052,32 @@ async function refreshProfilesList() {
 
     updateSelect(ui.selProfiles);
     updateSelect(ui.selProfilesMain);
+
+    const current = currentState ? currentState.current_profile_name : '';
+    ui.selProfileParent.innerHTML = '<option value="">(none)</option>';
+    ['Default', ...profiles.filter(p => p !== 'Default')].forEach(p => {
+        if (p === current) return;
+        const opt = document.createElement('option');
+        opt.value = p;
+        opt.textContent = p;
+        ui.selProfileParent.appendChild(opt);
+    });
+    ui.selProfileParent.value = (currentState && currentState.profile_parent) || '';
 }
 
+ui.selProfileParent.addEventListener('change', async () => {
+    const parent = ui.selProfileParent.value || null;
+    try {
+        await invoke('set_profile_parent', { parent });
+        currentState.profile_parent = parent;
+    } catch (err) {
+        alert(err);
+        ui.selProfileParent.value = currentState.profile_parent || '';
+    }
+});
+
 ui.btnSaveProfile.addEventListener('click', async () => {
-    const name = ui.inpProfileName.value.trim();
+    const name = ui.inpProfileName.value.trim().replace(/\\/g, '/');
     if (!name) {
         alert('Please enter a profile name.');
         return;
@@ -560,12 +1094,12 @@ const handleProfileChange = async (e) => {
     if (!name) return;
     await invoke('load_profile', { name: name });
     
-    // Fetch updated state to sync UI (sliders, colors, deadzones)
-    const json = await invoke('get_initial_state');
-    currentState = JSON.parse(json);
+    // Fetch the new profile's settings to sync UI (sliders, colors, deadzones)
+    Object.assign(currentState, await invoke('get_settings'));
     syncUiToState(currentState);
     
     renderMappings();
+    refreshProfilesList();
 };
 
 ui.selProfiles.addEventListener('change', handleProfileChange);
@@ -580,12 +1114,37 @@ ui.btnDeleteProfile.addEventListener('click', async () => {
     }
 });
 
+ui.btnExportProfile.addEventListener('click', async () => {
+    const name = ui.selProfiles.value || (currentState && currentState.current_profile_name);
+    if (!name) return;
+    const path = await save({
+        defaultPath: `${name.split('/').pop()}.dx3profile.json`,
+        filters: [{ name: 'DX3 Profile', extensions: ['json'] }],
+    });
+    if (!path) return;
+    invoke('export_profile', { name, path }).catch(err => alert(`Export failed: ${err}`));
+});
+
+ui.btnImportProfile.addEventListener('click', async () => {
+    const path = await openDialog({ multiple: false, filters: [{ name: 'DX3 Profile', extensions: ['json'] }] });
+    if (!path) return;
+    const name = ui.inpProfileName.value.trim() || null;
+    try {
+        const imported = await invoke('import_profile', { path, name });
+        ui.inpProfileName.value = '';
+        await refreshProfilesList();
+        alert(`Imported as "${imported}".`);
+    } catch (err) {
+        alert(`Import failed: ${err}`);
+    }
+});
+
 ui.btnReset.addEventListener('click', async () => {
     if (confirm('Reset all button mappings to default Xbox layout? This will also switch to the Default profile.')) {
         await invoke('reset_mappings');
-        // Refresh state
-        invoke('get_initial_state').then(json => {
-            currentState = JSON.parse(json);
+        // Refresh settings
+        invoke('get_settings').then(settings => {
+            Object.assign(currentState, settings);
             syncUiToState(currentState); // Sync UI elements
             renderMappings();
             refreshProfilesList();
@@ -607,7 +1166,8 @@ const PHYSICAL_TO_XBOX = {
     'L1': 0x0100, 'R1': 0x0200, 'L3': 0x0040, 'R3': 0x0080,
     'Options': 0x0010, 'Share': 0x0020, 'PS': 0x0400,
     'DpadUp': 0x0001, 'DpadDown': 0x0002, 'DpadLeft': 0x0004, 'DpadRight': 0x0008,
-    'L2': null, 'R2': null, 'Mute': null, 'Touchpad': null, 'TouchpadLeft': null, 'TouchpadRight': null
+    'L2': null, 'R2': null, 'Mute': null, 'Touchpad': null, 'TouchpadLeft': null, 'TouchpadRight': null,
+    'FnLeft': null, 'FnRight': null, 'PaddleLeft': null, 'PaddleRight': null
 };
 
 const DEFAULT_MAPPINGS = {
@@ -615,6 +1175,7 @@ const DEFAULT_MAPPINGS = {
     'L1': { Xbox: 0x0100 }, 'R1': { Xbox: 0x0200 }, 'L3': { Xbox: 0x0040 }, 'R3': { Xbox: 0x0080 },
     'Options': { Xbox: 0x0010 }, 'Share': { Xbox: 0x0020 }, 'PS': { Xbox: 0x0400 },
     'Mute': [], 'Touchpad': [], 'TouchpadLeft': [], 'TouchpadRight': [],
+    'FnLeft': [], 'FnRight': [], 'PaddleLeft': [], 'PaddleRight': [],
     'DpadUp': { Xbox: 0x0001 }, 'DpadDown': { Xbox: 0x0002 }, 'DpadLeft': { Xbox: 0x0004 }, 'DpadRight': { Xbox: 0x0008 },
     'LeftStick': 'XboxLS', 'RightStick': 'XboxRS', 'L2': 'XboxLT', 'R2': 'XboxRT'
 };
@@ -648,13 +1209,32 @@ function getKeyName(vk) {
     return special[vk] || `Key ${vk}`;
 }
 
+// Physical key (KeyboardEvent.code) → what the key cap says in the active layout, from get_keys
+let keyNames = {};
+
+const refreshKeyNames = async () => {
+    try {
+        const keys = await invoke('get_keys');
+        const names = Object.fromEntries(keys.map(k => [k.code, k.name]));
+        if (JSON.stringify(names) === JSON.stringify(keyNames)) return;
+        keyNames = names;
+    } catch (e) {
+        console.error("Key list query failed:", e);
+        return;
+    }
+    renderMappings();
+};
+// Layouts can be switched while the window is in the background
+window.addEventListener('focus', refreshKeyNames);
+
 // Build the mapping list DOM (call only when data changes)
 function renderMappings() {
     if (!currentState || !currentState.mappings) return;
 
     ui.mappingList.innerHTML = '';
 
-    currentState.mappings.forEach(m => {
+    currentState.mappings.forEach(mapping => {
+        const m = layerView(mapping);
         const container = document.createElement('div');
         container.style.display = 'flex';
         container.style.flexDirection = 'column';
@@ -667,6 +1247,10 @@ function renderMappings() {
         const source = document.createElement('div');
         source.className = 'mapping-source';
         source.textContent = m.source;
+        if (m.source === currentState.shift_button) {
+            source.textContent += ' ⇧';
+            source.title = 'Shift button: only switches layers, its own targets are ignored';
+        }
 
         const targets = document.createElement('div');
         targets.className = 'mapping-targets';
@@ -691,9 +1275,19 @@ function renderMappings() {
             } else if (t === 'XboxRS') {
                 type = 'xbox';
                 label = 'Xbox RS';
-            } else if (t.Keyboard !== undefined) {
+            } else if (t.XboxDpad !== undefined) {
+                type = 'xbox';
+                label = `Xbox D-Pad <input type="number" class="target-speed" min="0.1" max="0.95" step="0.05" value="${t.XboxDpad.threshold}" title="Threshold: how far the stick has to be pushed for a direction" style="width: 40px;">`;
+            } else if (t.XboxLSDir !== undefined || t.XboxRSDir !== undefined) {
+                type = 'xbox';
+                label = t.XboxLSDir ? `Xbox LS ${STICK_ARROWS[t.XboxLSDir]}` : `Xbox RS ${STICK_ARROWS[t.XboxRSDir]}`;
+            } else if (t.Keyboard !== undefined || t.KeyboardVia !== undefined || t.Key !== undefined) {
                 type = 'kb';
-                label = getKeyName(t.Keyboard);
+                const via = t.KeyboardVia ? t.KeyboardVia.via : t.Key ? t.Key.via : 'scancode';
+                const opts = [['scancode', 'SC'], ['vk', 'VK'], ['both', 'SC+VK']]
+                    .map(([v, name]) => `<option value="${v}"${v === via ? ' selected' : ''}>${name}</option>`).join('');
+                const name = t.Key ? (keyNames[t.Key.code] || t.Key.code) : getKeyName(t.Keyboard ?? t.KeyboardVia.vk);
+                label = `${name} <select class="target-via" title="Injection: scancode (DirectInput games), virtual-key code (launchers) or both">${opts}</select>`;
             } else if (t.Mouse !== undefined) {
                 type = 'mouse';
                 label = `Mouse ${['Left', 'Middle', 'Right'][t.Mouse] || t.Mouse}`;
@@ -703,6 +1297,31 @@ function renderMappings() {
             } else if (t.MouseScroll !== undefined) {
                 type = 'mouse';
                 label = 'Mouse Scroll';
+            } else if (t.CircularScroll !== undefined) {
+                type = 'mouse';
+                label = `Circular Scroll <input type="number" class="target-speed" min="0.1" max="10" step="0.1" value="${t.CircularScroll.speed}" title="Speed (1.0 = 12 notches per turn)" style="width: 40px;">`;
+            } else if (t.VJoyAxis !== undefined) {
+                type = 'xbox';
+                label = `vJoy ${t.VJoyAxis}`;
+            } else if (t.VJoyStick !== undefined) {
+                type = 'xbox';
+                label = `vJoy ${t.VJoyStick.x}/${t.VJoyStick.y}`;
+            } else if (t.VJoyButton !== undefined) {
+                type = 'xbox';
+                label = `vJoy B${t.VJoyButton}`;
+            } else if (t.PinchZoom !== undefined) {
+                type = 'mouse';
+                label = `Pinch Zoom <input type="number" class="target-speed" min="0.1" max="10" step="0.1" value="${t.PinchZoom.speed}" title="Speed (1.0 = one zoom step per 100 touchpad units)" style="width: 40px;">`;
+            } else if (t.Macro !== undefined) {
+                type = 'kb';
+                const text = formatMacro(t.Macro).replace(/"/g, '&quot;');
+                label = `<span title="${text}">Macro (${t.Macro.length} steps)</span>`;
+            } else if (t.GyroMouse !== undefined) {
+                type = 'mouse';
+                label = `Gyro Aim <input type="number" class="target-speed" min="0.5" max="100" step="0.5" value="${t.GyroMouse.sensitivity}" title="Sensitivity (pixels per degree turned)" style="width: 40px;">`;
+            } else if (t === 'GyroRecenter') {
+                type = 'mouse';
+                label = 'Gyro Recenter';
             }
 
             tag.className = `target-tag ${type}`;
@@ -712,6 +1331,33 @@ function renderMappings() {
                 e.stopPropagation();
                 removeTarget(m.source, idx);
             });
+            const viaSelect = tag.querySelector('.target-via');
+            if (viaSelect) {
+                viaSelect.addEventListener('click', (e) => e.stopPropagation());
+                viaSelect.addEventListener('change', () => {
+                    if (t.Key) {
+                        m.targets[idx] = { Key: { code: t.Key.code, via: viaSelect.value } };
+                    } else {
+                        const vk = t.Keyboard ?? t.KeyboardVia.vk;
+                        m.targets[idx] = viaSelect.value === 'scancode' ? { Keyboard: vk } : { KeyboardVia: { vk, via: viaSelect.value } };
+                    }
+                    saveMapping(m);
+                });
+            }
+            const speedInput = tag.querySelector('.target-speed');
+            if (speedInput) {
+                speedInput.addEventListener('click', (e) => e.stopPropagation());
+                speedInput.addEventListener('change', () => {
+                    if (t.GyroMouse) {
+                        t.GyroMouse.sensitivity = Math.min(100, Math.max(0.5, parseFloat(speedInput.value) || 10));
+                    } else if (t.XboxDpad) {
+                        t.XboxDpad.threshold = Math.min(0.95, Math.max(0.1, parseFloat(speedInput.value) || STICK_DPAD_THRESHOLD));
+                    } else {
+                        (t.CircularScroll || t.PinchZoom).speed = Math.min(10, Math.max(0.1, parseFloat(speedInput.value) || 1));
+                    }
+                    saveMapping(m);
+                });
+            }
 
             targets.appendChild(tag);
         });
@@ -732,6 +1378,25 @@ function renderMappings() {
 
         row.appendChild(source);
         row.appendChild(targets);
+        // Turbo (both layers): held targets pulse on and off. Digital sources only, like the backend.
+        if (!['LeftStick', 'RightStick', 'L2', 'R2', 'Touchpad'].includes(m.source)) {
+            const turbo = document.createElement('input');
+            turbo.type = 'number';
+            turbo.min = 1;
+            turbo.max = 30;
+            turbo.step = 1;
+            turbo.placeholder = 'Turbo';
+            turbo.title = 'Turbo: pulse the targets this many times a second while held (empty = off)';
+            turbo.style.width = '48px';
+            turbo.value = mapping.turbo_hz || '';
+            turbo.addEventListener('change', () => {
+                const hz = parseFloat(turbo.value);
+                mapping.turbo_hz = hz > 0 ? Math.min(30, hz) : null;
+                turbo.value = mapping.turbo_hz || '';
+                invoke('set_turbo', { source: m.source, hz: mapping.turbo_hz });
+            });
+            row.appendChild(turbo);
+        }
         row.appendChild(btnAdd);
         container.appendChild(row);
 
@@ -799,6 +1464,31 @@ function renderMappings() {
                 };
                 picker.appendChild(mouseScrollBtn);
             }
+            if (isTouchpad) {
+                const circularBtn = document.createElement('div');
+                circularBtn.className = 'btn-xbox-pick';
+                circularBtn.style.background = '#5c6370';
+                circularBtn.textContent = 'Circular Scroll';
+                circularBtn.onclick = (e) => {
+                    e.stopPropagation();
+                    selectedButton = m.source;
+                    isAppendingMapping = true;
+                    el('opt-circular-scroll').click();
+                };
+                picker.appendChild(circularBtn);
+
+                const pinchBtn = document.createElement('div');
+                pinchBtn.className = 'btn-xbox-pick';
+                pinchBtn.style.background = '#5c6370';
+                pinchBtn.textContent = 'Pinch Zoom';
+                pinchBtn.onclick = (e) => {
+                    e.stopPropagation();
+                    selectedButton = m.source;
+                    isAppendingMapping = true;
+                    el('opt-pinch-zoom').click();
+                };
+                picker.appendChild(pinchBtn);
+            }
 
             container.appendChild(picker);
         }
@@ -819,7 +1509,7 @@ function pickXboxTarget(source, bit) {
         } else {
             m.targets = [{ Xbox: bit }];
         }
-        invoke('update_mappings', { mappings: currentState.mappings });
+        saveMapping(m);
     }
     recordingFor = null;
     isAppendingMapping = false;
@@ -836,7 +1526,26 @@ function pickXboxTriggerTarget(source, target) {
         } else {
             m.targets = [target];
         }
-        invoke('update_mappings', { mappings: currentState.mappings });
+        saveMapping(m);
+    }
+    recordingFor = null;
+    isAppendingMapping = false;
+    renderMappings();
+}
+
+// Targets that carry data (vJoy, stick/D-pad), compared by value
+function pickTarget(source, target) {
+    const m = getMappingFor(source);
+    if (m) {
+        const key = JSON.stringify(target);
+        if (isAppendingMapping) {
+            if (!m.targets.some(t => JSON.stringify(t) === key)) {
+                m.targets.push(target);
+            }
+        } else {
+            m.targets = [target];
+        }
+        saveMapping(m);
     }
     recordingFor = null;
     isAppendingMapping = false;
@@ -869,7 +1578,7 @@ function updateMappingsActiveState() {
                 if (m) {
                     if (bit !== null) {
                         m.targets = [{ Xbox: bit }];
-                        invoke('update_mappings', { mappings: currentState.mappings });
+                        saveMapping(m);
                     }
                 }
                 recordingFor = null;
@@ -900,6 +1609,7 @@ function getGamepadField(phys) {
         'L1': 'btn_l1', 'R1': 'btn_r1', 'L3': 'btn_l3', 'R3': 'btn_r3',
         'Options': 'btn_options', 'Share': 'btn_share', 'PS': 'btn_ps', 'Touchpad': 'btn_touchpad',
         'Mute': 'btn_mute', 'TouchpadLeft': 'btn_touchpad_left', 'TouchpadRight': 'btn_touchpad_right',
+        'FnLeft': 'btn_fn_l', 'FnRight': 'btn_fn_r', 'PaddleLeft': 'btn_paddle_l', 'PaddleRight': 'btn_paddle_r',
         'DpadUp': 'dpad_up', 'DpadDown': 'dpad_down', 'DpadLeft': 'dpad_left', 'DpadRight': 'dpad_right'
     };
     return map[phys] || phys;
@@ -911,10 +1621,11 @@ function startRecording(source) {
 }
 
 function removeTarget(source, index) {
-    const m = currentState.mappings.find(x => x.source === source);
-    if (m) {
+    const mapping = currentState.mappings.find(x => x.source === source);
+    if (mapping) {
+        const m = layerView(mapping);
         m.targets.splice(index, 1);
-        invoke('update_mappings', { mappings: currentState.mappings });
+        saveMapping(m);
         renderMappings();
     }
 }
@@ -932,17 +1643,20 @@ window.addEventListener('keydown', (e) => {
         }
 
         e.preventDefault();
+        // Physical keys follow the layout at injection; keys get_keys doesn't know stay plain VKs
         const vk = e.keyCode;
+        const target = keyNames[e.code] ? { Key: { code: e.code, via: 'scancode' } } : { Keyboard: vk };
         const m = getMappingFor(recordingFor);
         if (m) {
             if (isAppendingMapping) {
-                if (!m.targets.some(t => t.Keyboard === vk)) {
-                    m.targets.push({ Keyboard: vk });
+                const dup = m.targets.some(t => t.Keyboard === vk || (t.KeyboardVia && t.KeyboardVia.vk === vk) || (t.Key && t.Key.code === e.code));
+                if (!dup) {
+                    m.targets.push(target);
                 }
             } else {
-                m.targets = [{ Keyboard: vk }];
+                m.targets = [target];
             }
-            invoke('update_mappings', { mappings: currentState.mappings });
+            saveMapping(m);
         }
         recordingFor = null;
         selectedButton = null;
@@ -984,7 +1698,7 @@ window.addEventListener('mousedown', (e) => {
             } else {
                 m.targets = [{ Mouse: e.button }];
             }
-            invoke('update_mappings', { mappings: currentState.mappings });
+            saveMapping(m);
         }
         recordingFor = null;
         selectedButton = null;
@@ -994,14 +1708,270 @@ window.addEventListener('mousedown', (e) => {
     }
 });
 
+ui.btnIdentify.addEventListener('click', () => {
+    invoke('identify_controller', { device: currentState.stick_cal_key }).catch(err => alert(err));
+});
+
 ui.btnDisconnect.addEventListener('click', () => {
-    if (confirm('Reconnect controller? (This will attempt to fix connection issues)')) {
+    // Manual-connect mode doesn't reattach on its own, so this just lets go of the controller
+    const question = currentState.manual_connect ? 'Disconnect controller?' : 'Reconnect controller? (This will attempt to fix connection issues)';
+    if (confirm(question)) {
         invoke('disconnect_controller');
     }
 });
 
+// Manual-connect mode: one button per controller the scanner found
+let lastFoundControllersKey = '';
+function renderFoundControllers(isConnected) {
+    const list = (currentState.manual_connect && !isConnected && currentState.found_controllers) || [];
+    const key = JSON.stringify(list);
+    if (key === lastFoundControllersKey) return;
+    lastFoundControllersKey = key;
+
+    ui.foundControllers.innerHTML = '';
+    ui.foundControllers.style.display = list.length ? 'inline-flex' : 'none';
+    list.forEach(c => {
+        const btn = document.createElement('button');
+        btn.style.fontSize = '10px';
+        btn.style.padding = '2px 8px';
+        btn.textContent = `Connect ${c.name}${c.bluetooth ? ' (BT)' : ''}`;
+        btn.title = c.serial ? `Serial ${c.serial}` : c.path;
+        btn.addEventListener('click', () => invoke('connect_device', { path: c.path }).catch(err => alert(err)));
+        ui.foundControllers.appendChild(btn);
+    });
+}
+
 ui.chkHide.addEventListener('change', (e) => invoke('set_hide_controller', { hide: e.target.checked }));
 ui.chkMin.addEventListener('change', (e) => invoke('set_start_minimized', { val: e.target.checked }));
+ui.chkAutostart.addEventListener('change', (e) => invoke('set_autostart', { val: e.target.checked }));
+ui.chkElevated.addEventListener('change', (e) => invoke('set_elevated_autostart', { val: e.target.checked }));
+ui.chkApi.addEventListener('change', (e) => invoke('set_api_settings', { enabled: e.target.checked, port: currentApiPort }));
+ui.selLogLevel.addEventListener('change', (e) => invoke('set_log_level', { level: e.target.value }).catch(err => console.error("Error setting log level:", err)));
+const updateDiscordSettings = () => invoke('set_discord_settings', {
+    enabled: ui.chkDiscord.checked,
+    appId: ui.inpDiscordAppId.value.trim()
+});
+ui.chkDiscord.addEventListener('change', updateDiscordSettings);
+ui.inpDiscordAppId.addEventListener('change', updateDiscordSettings);
+ui.chkPsHold.addEventListener('change', (e) => invoke('set_ps_hold_opens_window', { val: e.target.checked }));
+ui.chkHapticAlerts.addEventListener('change', (e) => invoke('set_haptic_alerts', { val: e.target.checked }));
+ui.chkManualConnect.addEventListener('change', (e) => invoke('set_manual_connect', { enabled: e.target.checked }));
+ui.inpOutputKeepalive.addEventListener('change', (e) => {
+    const secs = Math.min(600, Math.max(0, parseInt(e.target.value) || 0));
+    e.target.value = secs;
+    invoke('set_output_keepalive', { secs });
+});
+ui.inpVjoyDevice.addEventListener('change', (e) => {
+    const device = Math.min(16, Math.max(0, parseInt(e.target.value) || 0));
+    e.target.value = device;
+    invoke('set_vjoy_device', { device });
+});
+ui.selOrientation.addEventListener('change', (e) => invoke('set_orientation', { val: e.target.value }));
+const updateKbm = () => invoke('set_kbm_gamepad', {
+    config: {
+        enabled: ui.chkKbm.checked,
+        mouse_sensitivity: Math.max(0.1, parseFloat(ui.inpKbmSens.value) || 1),
+        ramp_ms: Math.max(0, parseInt(ui.inpKbmRamp.value) || 0)
+    }
+});
+[ui.chkKbm, ui.inpKbmSens, ui.inpKbmRamp].forEach(e => e.addEventListener('change', updateKbm));
+
+// Linked games for pausing keyboard/mouse outputs (focus.rs)
+const focusGames = () => ui.inpFocusGames.value.split(',').map(g => g.trim()).filter(g => g);
+const updateFocusPause = () => invoke('set_focus_pause', {
+    config: { enabled: ui.chkFocusPause.checked, games: focusGames() }
+});
+[ui.chkFocusPause, ui.inpFocusGames].forEach(e => e.addEventListener('change', updateFocusPause));
+ui.btnFocusAdd.addEventListener('click', () => {
+    // Our own window is never recorded as foreground, so this is whatever was in front before DX3
+    const last = currentState && currentState.foreground_process;
+    if (!last || focusGames().some(g => g.toLowerCase() === last.toLowerCase())) return;
+    ui.inpFocusGames.value = [...focusGames(), last].join(', ');
+    updateFocusPause();
+});
+const focusPaused = (st) => st.focus_pause.enabled && !!st.foreground_process
+    && !st.focus_pause.games.some(g => g.trim().toLowerCase() === st.foreground_process.toLowerCase());
+
+// Quiet hours (quiet.rs): times are minutes after midnight in the config
+const timeToMinutes = (v) => { const [h, m] = v.split(':').map(n => parseInt(n) || 0); return h * 60 + m; };
+const minutesToTime = (n) => `${String(Math.floor(n / 60)).padStart(2, '0')}:${String(n % 60).padStart(2, '0')}`;
+const updateQuietHours = () => invoke('set_quiet_hours', {
+    config: {
+        enabled: ui.chkQuietHours.checked,
+        start: timeToMinutes(ui.inpQuietStart.value || '22:00'),
+        end: timeToMinutes(ui.inpQuietEnd.value || '07:00'),
+        led_brightness: Math.min(255, Math.max(0, parseInt(ui.inpQuietBrightness.value) || 0)),
+        player_leds: ui.chkQuietPled.checked
+    }
+});
+[ui.chkQuietHours, ui.inpQuietStart, ui.inpQuietEnd, ui.inpQuietBrightness, ui.chkQuietPled].forEach(e => e.addEventListener('change', updateQuietHours));
+const quietHoursActive = (st) => {
+    const q = st.quiet_hours;
+    if (!q || !q.enabled) return false;
+    const now = new Date();
+    const minute = now.getHours() * 60 + now.getMinutes();
+    return q.start <= q.end ? (minute >= q.start && minute < q.end) : (minute >= q.start || minute < q.end);
+};
+const updateAutoSprint = () => invoke('set_auto_sprint', {
+    config: ui.chkAutoSprint.checked ? {
+        target: JSON.parse(ui.selAutoSprintTarget.value),
+        hold_ms: Math.max(0, parseInt(ui.inpAutoSprintMs.value) || 0),
+        threshold: Math.min(1, Math.max(0.5, parseFloat(ui.inpAutoSprintThreshold.value) || 0.9))
+    } : null
+});
+[ui.chkAutoSprint, ui.selAutoSprintTarget, ui.inpAutoSprintMs, ui.inpAutoSprintThreshold].forEach(e => e.addEventListener('change', updateAutoSprint));
+ui.btnStickCal.addEventListener('click', () => invoke('calibrate_sticks').catch(err => alert(err)));
+ui.btnStickCalReset.addEventListener('click', () => invoke('reset_stick_calibration'));
+ui.btnGyroCal.addEventListener('click', () => invoke('calibrate_gyro').catch(err => alert(err)));
+ui.btnGyroCalReset.addEventListener('click', () => invoke('reset_gyro_calibration'));
+
+// Stick plot: every raw sample ("stick-samples", not the 30 FPS state), with the calibrated center.
+// Circularity is how far the outermost sample in each of 36 sectors falls short of / beyond the rim.
+const STICK_PLOT_POINTS = 3000;
+const STICK_PLOT_SECTORS = 36;
+let stickPlot = null;
+const newStickPlot = () => ({ points: [[], []], rim: [new Array(STICK_PLOT_SECTORS).fill(0), new Array(STICK_PLOT_SECTORS).fill(0)], centers: [0, 0, 0, 0], drawPending: false });
+const circularityError = (rim) => {
+    const hit = rim.filter(r => r > 0.5); // Sectors the stick has actually been rolled through
+    if (hit.length < STICK_PLOT_SECTORS / 2) return null;
+    return hit.reduce((sum, r) => sum + Math.abs(r - 1), 0) / hit.length * 100;
+};
+const drawStickPlot = () => {
+    stickPlot.drawPending = false;
+    const ctx = ui.stickPlot.getContext('2d');
+    const size = ui.stickPlot.height, radius = size / 2 - 4;
+    ctx.clearRect(0, 0, ui.stickPlot.width, size);
+    const info = [];
+    [0, 1].forEach(side => {
+        const cx = size / 2 + side * size, cy = size / 2;
+        ctx.strokeStyle = '#555';
+        ctx.beginPath();
+        ctx.arc(cx, cy, radius, 0, Math.PI * 2);
+        ctx.moveTo(cx - radius, cy); ctx.lineTo(cx + radius, cy);
+        ctx.moveTo(cx, cy - radius); ctx.lineTo(cx, cy + radius);
+        ctx.stroke();
+        ctx.fillStyle = '#61afef';
+        for (const [x, y] of stickPlot.points[side]) ctx.fillRect(cx + x * radius - 0.5, cy - y * radius - 0.5, 1, 1);
+        const [ox, oy] = stickPlot.centers.slice(side * 2, side * 2 + 2);
+        ctx.fillStyle = '#e5c07b';
+        ctx.fillRect(cx + ox * radius - 2, cy - oy * radius - 2, 4, 4);
+        const err = circularityError(stickPlot.rim[side]);
+        info.push(`${side ? 'R' : 'L'}: ${err === null ? 'roll around the rim' : `circularity error ${err.toFixed(1)}%`}`);
+    });
+    setText(ui.stickPlotInfo, info.join(', '));
+};
+listen('stick-samples', (event) => {
+    if (!stickPlot) return;
+    stickPlot.centers = event.payload.centers;
+    for (const s of event.payload.samples) {
+        [0, 1].forEach(side => {
+            const x = s[side * 2], y = s[side * 2 + 1];
+            const pts = stickPlot.points[side];
+            pts.push([x, y]);
+            if (pts.length > STICK_PLOT_POINTS) pts.shift();
+            const r = Math.hypot(x, y);
+            const sector = Math.floor((Math.atan2(y, x) + Math.PI) / (Math.PI * 2) * STICK_PLOT_SECTORS) % STICK_PLOT_SECTORS;
+            stickPlot.rim[side][sector] = Math.max(stickPlot.rim[side][sector], r);
+        });
+    }
+    if (!stickPlot.drawPending) {
+        stickPlot.drawPending = true;
+        requestAnimationFrame(drawStickPlot);
+    }
+});
+ui.btnStickPlot.addEventListener('click', () => {
+    const show = !stickPlot;
+    stickPlot = show ? newStickPlot() : null;
+    ui.stickPlotBox.style.display = show ? 'block' : 'none';
+    setText(ui.btnStickPlot, show ? 'Close' : 'Plot');
+    setText(ui.stickPlotInfo, show ? 'Waiting for input...' : '');
+    invoke('set_stick_stream', { val: show });
+});
+
+// Input statistics: text heatmap, most pressed first
+let inputStatsTimer = null;
+const renderInputStats = async () => {
+    const st = await invoke('get_input_stats');
+    const rows = Object.entries(st.presses).sort((a, b) => b[1] - a[1]);
+    const max = rows.length ? rows[0][1] : 1;
+    const mins = Math.floor(st.session_secs / 60);
+    const lines = [
+        `Session ${Math.floor(mins / 60)}h ${mins % 60}m, stick travel L ${st.stick_travel[0].toFixed(0)} / R ${st.stick_travel[1].toFixed(0)}`
+    ];
+    for (const [btn, count] of rows) {
+        const bar = '█'.repeat(Math.max(1, Math.round(count / max * 20)));
+        const chatter = st.chatter[btn] ? `  chatter ${st.chatter[btn]}` : '';
+        lines.push(`${btn.padEnd(9)} ${bar} ${count}${chatter}`);
+    }
+    setText(ui.inputStats, lines.join('\n'));
+};
+ui.btnInputStats.addEventListener('click', () => {
+    const show = ui.inputStats.style.display === 'none';
+    ui.inputStats.style.display = show ? 'block' : 'none';
+    setText(ui.btnInputStats, show ? 'Hide' : 'Show');
+    clearInterval(inputStatsTimer);
+    if (show) {
+        renderInputStats();
+        inputStatsTimer = setInterval(renderInputStats, 1000);
+    }
+});
+ui.btnInputStatsReset.addEventListener('click', async () => {
+    await invoke('reset_input_stats');
+    if (ui.inputStats.style.display !== 'none') renderInputStats();
+});
+ui.chkInputRecording.addEventListener('change', (e) => {
+    invoke('set_input_recording', { val: e.target.checked })
+        .then(path => setText(ui.inputRecordingPath, path))
+        .catch(err => {
+            e.target.checked = false;
+            alert(`Could not start recording: ${err}`);
+        });
+});
+ui.inpIdleOff.addEventListener('change', (e) => {
+    const minutes = Math.max(0, parseInt(e.target.value) || 0);
+    e.target.value = minutes;
+    invoke('set_idle_power_off', { minutes });
+});
+const updatePowerOffCombo = () => {
+    const buttons = ui.selPowerCombo.value ? ui.selPowerCombo.value.split(',') : [];
+    const holdMs = Math.round((parseFloat(ui.inpPowerHold.value) || 3) * 1000);
+    invoke('set_power_off_combo', { buttons, holdMs });
+};
+ui.selPowerCombo.addEventListener('change', updatePowerOffCombo);
+ui.inpPowerHold.addEventListener('change', updatePowerOffCombo);
+ui.selProfileCycleCombo.addEventListener('change', (e) => {
+    invoke('set_profile_cycle_combo', { buttons: e.target.value ? e.target.value.split(',') : [] });
+});
+const updateHookCommands = () => invoke('set_hook_commands', {
+    onConnect: ui.inpHookConnect.value,
+    onDisconnect: ui.inpHookDisconnect.value,
+});
+ui.inpHookConnect.addEventListener('change', updateHookCommands);
+ui.inpHookDisconnect.addEventListener('change', updateHookCommands);
+const updateMqttSettings = () => {
+    const [host, port] = ui.inpMqttBroker.value.trim().split(':');
+    invoke('set_mqtt_settings', {
+        settings: {
+            enabled: ui.chkMqtt.checked,
+            host: host || 'localhost',
+            port: parseInt(port) || 1883,
+            username: ui.inpMqttUser.value.trim(),
+            password: ui.inpMqttPass.value,
+            topic: ui.inpMqttTopic.value.trim() || 'dx3'
+        }
+    });
+};
+[ui.chkMqtt, ui.inpMqttBroker, ui.inpMqttTopic, ui.inpMqttUser, ui.inpMqttPass]
+    .forEach(input => input.addEventListener('change', updateMqttSettings));
+ui.inpPauseHotkey.addEventListener('change', (e) => {
+    const hotkey = e.target.value.trim();
+    invoke('set_pause_hotkey', { hotkey })
+        .then(() => { currentPauseHotkey = hotkey; })
+        .catch(err => {
+            alert(`Could not register hotkey "${hotkey}": ${err}`);
+            e.target.value = currentPauseHotkey;
+        });
+});
 
 // Fuzzer
 ui.btnFuzzer.addEventListener('click', () => {
@@ -1009,14 +1979,20 @@ ui.btnFuzzer.addEventListener('click', () => {
     invoke('set_fuzzer_active', { val: !active });
 });
 
+// fuzzer_step is the next step to send, so the one the controller is showing is one behind
+const lastFuzzerStep = () => Math.max(0, (currentState ? currentState.fuzzer_step : 0) - 1);
+ui.btnFuzzJump.addEventListener('click', () => invoke('set_fuzzer_step', { step: Math.max(0, parseInt(ui.inpFuzzStep.value) || 0) }));
+ui.btnFuzzPrev.addEventListener('click', () => invoke('set_fuzzer_step', { step: Math.max(0, lastFuzzerStep() - 1) }));
+ui.btnFuzzNext.addEventListener('click', () => invoke('set_fuzzer_step', { step: lastFuzzerStep() + 1 }));
+
 // Manual Params Update
-const updateManual = () => {
+const currentManualParams = () => {
     const rid = parseInt(ui.inpRepId.value, 16) || 0;
     const flg = parseInt(ui.inpBtFlags.value, 16) || 0;
     const flg2 = parseInt(ui.inpBtFlags2.value, 16) || 0;
 
     // Explicitly use snake_case for Rust compatibility
-    const params = {
+    return {
         report_id: rid,
         flag_off: parseInt(ui.inpFlagOff.value) || 0,
         rgb_off: parseInt(ui.inpRgbOff.value) || 0,
@@ -1031,7 +2007,10 @@ const updateManual = () => {
         g: parseInt(ui.sldG.value),
         b: parseInt(ui.sldB.value)
     };
+};
 
+const updateManual = () => {
+    const params = currentManualParams();
     console.log("Updating manual params:", params);
     return invoke('set_manual_params', { params: params }).catch(e => console.error("Error setting params:", e));
 };
@@ -1047,16 +2026,136 @@ ui.btnManual.addEventListener('click', async () => {
     invoke('trigger_manual_send').catch(e => console.error("Send failed:", e));
 });
 
+// Manual Templates
+async function refreshManualTemplates(selected) {
+    const names = await invoke('list_manual_templates');
+    ui.selManualTpl.innerHTML = '';
+    names.sort().forEach(name => {
+        const opt = document.createElement('option');
+        opt.value = name;
+        opt.textContent = name;
+        ui.selManualTpl.appendChild(opt);
+    });
+    if (selected) ui.selManualTpl.value = selected;
+}
+
+function fillManualInputs(p) {
+    ui.inpRepId.value = p.report_id.toString(16).toUpperCase().padStart(2, '0');
+    ui.inpFlagOff.value = p.flag_off;
+    ui.inpRgbOff.value = p.rgb_off;
+    ui.inpPled.value = p.player_led;
+    ui.inpPledBright.value = p.pled_bright;
+    ui.inpPledBrightOff.value = p.pled_bright_off;
+    ui.inpBtFlags.value = p.bt_flags.toString(16).toUpperCase().padStart(2, '0');
+    ui.inpBtFlags2.value = p.bt_flags2.toString(16).toUpperCase().padStart(2, '0');
+    ui.inpBtLen.value = p.bt_len;
+    ui.chkFeature.checked = p.as_feature;
+    ui.sldR.value = p.r;
+    ui.sldG.value = p.g;
+    ui.sldB.value = p.b;
+}
+
+ui.btnTplSave.addEventListener('click', async () => {
+    const name = ui.inpTplName.value.trim() || ui.selManualTpl.value;
+    if (!name) return;
+    await updateManual();
+    invoke('save_manual_template', { name, params: currentManualParams() })
+        .then(() => { ui.inpTplName.value = ''; refreshManualTemplates(name); })
+        .catch(err => alert(`Save failed: ${err}`));
+});
+ui.btnTplLoad.addEventListener('click', () => {
+    if (!ui.selManualTpl.value) return;
+    invoke('load_manual_template', { name: ui.selManualTpl.value })
+        .then(fillManualInputs)
+        .catch(err => alert(err));
+});
+ui.btnTplDelete.addEventListener('click', () => {
+    const name = ui.selManualTpl.value;
+    if (!name || !confirm(`Delete template "${name}"?`)) return;
+    invoke('delete_manual_template', { name }).then(() => refreshManualTemplates());
+});
+refreshManualTemplates();
+
 // Config
 ui.chkNoPeriod.addEventListener('change', (e) => invoke('set_disable_periodic', { val: e.target.checked }));
 ui.selCrc.addEventListener('change', (e) => invoke('set_crc_seed', { val: parseInt(e.target.value) }));
 
 // Scanner
+ui.btnFuzzMark.addEventListener('click', () => {
+    invoke('mark_fuzzer_hit', { note: ui.inpFuzzNote.value })
+        .then(() => { ui.inpFuzzNote.value = ''; })
+        .catch(err => setText(ui.fuzzerStatus, err));
+});
+ui.btnFuzzLog.addEventListener('click', () => invoke('open_fuzzer_session'));
+
+// "0-3,10" -> [0,1,2,3,10]
+function parseList(text, radix = 10) {
+    const out = [];
+    for (const part of text.split(',').map(p => p.trim()).filter(p => p)) {
+        const [a, b] = part.split('-').map(n => parseInt(n, radix));
+        if (isNaN(a)) continue;
+        const end = isNaN(b) ? a : b;
+        for (let i = a; i <= end; i++) out.push(i);
+    }
+    return out;
+}
+
+// [0,1,2,3,10] -> "0-3,10"
+function formatList(values, radix = 10) {
+    const fmt = v => radix === 16 ? v.toString(16).toUpperCase().padStart(2, '0') : String(v);
+    const parts = [];
+    for (let i = 0; i < values.length; i++) {
+        let j = i;
+        while (j + 1 < values.length && values[j + 1] === values[j] + 1) j++;
+        parts.push(j > i ? `${fmt(values[i])}-${fmt(values[j])}` : fmt(values[i]));
+        i = j;
+    }
+    return parts.join(',');
+}
+
+ui.btnFuzzApply.addEventListener('click', () => {
+    invoke('set_fuzzer_params', {
+        params: {
+            report_ids: parseList(ui.inpFuzzIds.value, 16).filter(v => v <= 0xFF),
+            flag_offsets: parseList(ui.inpFuzzFlags.value),
+            rgb_offsets: parseList(ui.inpFuzzRgb.value),
+            values: parseList(ui.inpFuzzValues.value).filter(v => v <= 0xFF),
+            burst: Math.min(255, Math.max(1, parseInt(ui.inpFuzzBurst.value) || 1)),
+            delay_ms: Math.max(0, parseInt(ui.inpFuzzDelay.value) || 50),
+        }
+    }).then(() => setText(ui.fuzzerStatus, 'Parameters applied.'))
+      .catch(err => setText(ui.fuzzerStatus, err));
+});
+ui.btnFuzzScriptCheck.addEventListener('click', () => {
+    invoke('set_fuzz_script', { script: ui.inpFuzzScript.value })
+        .then(() => setText(ui.fuzzerStatus, 'Script OK.'))
+        .catch(err => setText(ui.fuzzerStatus, err));
+});
+ui.btnFuzzScript.addEventListener('click', () => {
+    if (currentState && currentState.fuzz_script_active) {
+        invoke('set_fuzz_script_active', { val: false });
+        return;
+    }
+    invoke('set_fuzz_script', { script: ui.inpFuzzScript.value })
+        .then(() => invoke('set_fuzz_script_active', { val: true }))
+        .catch(err => setText(ui.fuzzerStatus, err));
+});
 ui.btnSweep.addEventListener('click', () => {
     const active = ui.btnSweep.textContent.includes('STOP');
     invoke('set_sweep_active', { val: !active });
 });
 ui.inpSweepSpeed.addEventListener('change', (e) => invoke('set_sweep_speed', { val: parseInt(e.target.value) || 250 }));
+ui.btnSweepApply.addEventListener('click', () => {
+    const range = ui.chkSweepTargeted.checked ? {
+        start: parseInt(ui.inpSweepStart.value) || 0,
+        end: parseInt(ui.inpSweepEnd.value) || 0,
+        values: parseList(ui.inpSweepValues.value).filter(v => v <= 0xFF),
+        step: Math.max(1, parseInt(ui.inpSweepStep.value) || 1),
+    } : null;
+    invoke('set_sweep_range', { range })
+        .then(() => setText(ui.fuzzerStatus, range ? 'Targeted sweep range applied.' : 'Full sweep restored.'))
+        .catch(err => setText(ui.fuzzerStatus, err));
+});
 
 // Pinpoint
 const updatePp = () => {
@@ -1071,6 +2170,16 @@ ui.btnPp.addEventListener('click', () => invoke('trigger_pinpoint_send'));
 
 // Proto
 ui.btnProto.addEventListener('click', () => invoke('trigger_protocol_scan'));
+ui.btnHidDesc.addEventListener('click', () => invoke('trigger_descriptor_dump'));
+ui.btnInputScan.addEventListener('click', () => invoke('trigger_input_report_scan'));
+ui.btnProtoExport.addEventListener('click', async () => {
+    const path = await save({
+        defaultPath: 'dx3-protocol-scan.txt',
+        filters: [{ name: 'Text', extensions: ['txt'] }],
+    });
+    if (!path) return;
+    invoke('export_protocol_log', { path }).catch(err => alert(`Export failed: ${err}`));
+});
 
 
 // --- State Handling ---
@@ -1079,22 +2188,31 @@ let hasNewState = false;
 let lastRenderedGamepadJSON = "";
 let lastRenderedMappingsJSON = "";
 
+// Runtime state; settings arrive separately (update-settings), both merge into currentState
 listen('update-state', (event) => {
-    const newState = event.payload;
+    if (!currentState) return; // Not initialised yet
+    Object.assign(currentState, event.payload);
+    hasNewState = true;
+
+    if (ui.remapPanel.style.display === 'flex') {
+        updateMappingsActiveState();
+    }
+});
+
+listen('update-settings', (event) => {
+    if (!currentState) return;
+    const settings = event.payload;
 
     // Check if mappings have changed to refresh the list UI
-    const mappingsChanged = currentState && JSON.stringify(newState.mappings) !== JSON.stringify(currentState.mappings);
-    const profileChanged = currentState && newState.current_profile_name !== currentState.current_profile_name;
+    const mappingsChanged = JSON.stringify(settings.mappings) !== JSON.stringify(currentState.mappings);
+    const profileChanged = settings.current_profile_name !== currentState.current_profile_name;
 
-    currentState = newState;
+    Object.assign(currentState, settings);
     hasNewState = true;
 
-    if (ui.remapPanel.style.display === 'flex') {
-        if (mappingsChanged || profileChanged) {
-            renderMappings();
-            refreshProfilesList();
-        }
-        updateMappingsActiveState();
+    if (ui.remapPanel.style.display === 'flex' && (mappingsChanged || profileChanged)) {
+        renderMappings();
+        refreshProfilesList();
     }
 });
 
@@ -1111,10 +2229,71 @@ requestAnimationFrame(animationLoop);
 function syncUiToState(state) {
     ui.chkHide.checked = state.hide_controller;
     ui.chkMin.checked = state.start_minimized;
+    ui.chkAutostart.checked = state.autostart;
+    ui.chkElevated.checked = state.elevated_autostart;
+    ui.chkApi.checked = state.api_enabled;
+    currentApiPort = state.api_port;
+    currentPauseHotkey = state.pause_hotkey;
+    ui.selLogLevel.value = state.log_level;
+    ui.chkDiscord.checked = state.discord_enabled;
+    if (document.activeElement !== ui.inpDiscordAppId) ui.inpDiscordAppId.value = state.discord_app_id;
+    ui.chkPsHold.checked = state.ps_hold_opens_window;
+    ui.chkHapticAlerts.checked = state.haptic_alerts;
+    ui.chkManualConnect.checked = state.manual_connect;
+    if (document.activeElement !== ui.inpOutputKeepalive) ui.inpOutputKeepalive.value = state.output_keepalive_secs;
+    if (document.activeElement !== ui.inpVjoyDevice) ui.inpVjoyDevice.value = state.vjoy_device;
+    ui.selOrientation.value = state.orientation;
+    ui.chkInputRecording.checked = state.input_recording;
+    if (!state.input_recording) setText(ui.inputRecordingPath, '');
+    ui.chkKbm.checked = state.kbm_gamepad.enabled;
+    ui.chkFocusPause.checked = state.focus_pause.enabled;
+    if (state.quiet_hours) {
+        ui.chkQuietHours.checked = state.quiet_hours.enabled;
+        if (document.activeElement !== ui.inpQuietStart) ui.inpQuietStart.value = minutesToTime(state.quiet_hours.start);
+        if (document.activeElement !== ui.inpQuietEnd) ui.inpQuietEnd.value = minutesToTime(state.quiet_hours.end);
+        if (document.activeElement !== ui.inpQuietBrightness) ui.inpQuietBrightness.value = state.quiet_hours.led_brightness;
+        ui.chkQuietPled.checked = state.quiet_hours.player_leds;
+    }
+    if (document.activeElement !== ui.inpFocusGames) ui.inpFocusGames.value = state.focus_pause.games.join(', ');
+    if (document.activeElement !== ui.inpKbmSens) ui.inpKbmSens.value = state.kbm_gamepad.mouse_sensitivity;
+    if (document.activeElement !== ui.inpKbmRamp) ui.inpKbmRamp.value = state.kbm_gamepad.ramp_ms;
+    ui.chkAutoSprint.checked = !!state.auto_sprint;
+    if (state.auto_sprint) {
+        if (document.activeElement !== ui.selAutoSprintTarget) ui.selAutoSprintTarget.value = JSON.stringify(state.auto_sprint.target);
+        if (document.activeElement !== ui.inpAutoSprintMs) ui.inpAutoSprintMs.value = state.auto_sprint.hold_ms;
+        if (document.activeElement !== ui.inpAutoSprintThreshold) ui.inpAutoSprintThreshold.value = state.auto_sprint.threshold;
+    }
+    setText(ui.stickCalStatus, state.stick_cal_status);
+    setText(ui.gyroCalStatus, state.gyro_cal_status);
+    if (document.activeElement !== ui.inpIdleOff) ui.inpIdleOff.value = state.idle_power_off_min;
+    if (document.activeElement !== ui.selPowerCombo) ui.selPowerCombo.value = state.power_off_combo.join(',');
+    if (document.activeElement !== ui.inpPowerHold) ui.inpPowerHold.value = state.power_off_hold_ms / 1000;
+    if (document.activeElement !== ui.selProfileCycleCombo) ui.selProfileCycleCombo.value = state.profile_cycle_combo.join(',');
+    if (document.activeElement !== ui.inpHookConnect) ui.inpHookConnect.value = state.on_connect_cmd;
+    if (document.activeElement !== ui.inpHookDisconnect) ui.inpHookDisconnect.value = state.on_disconnect_cmd;
+    ui.chkMqtt.checked = state.mqtt.enabled;
+    const mqttFields = [
+        [ui.inpMqttBroker, `${state.mqtt.host}:${state.mqtt.port}`],
+        [ui.inpMqttTopic, state.mqtt.topic],
+        [ui.inpMqttUser, state.mqtt.username],
+        [ui.inpMqttPass, state.mqtt.password],
+    ];
+    mqttFields.forEach(([input, value]) => {
+        if (document.activeElement !== input) input.value = value;
+    });
+    if (document.activeElement !== ui.inpPauseHotkey) ui.inpPauseHotkey.value = state.pause_hotkey;
     ui.sldDzLeft.value = state.deadzone_left;
     ui.inpDzLeft.value = state.deadzone_left;
     ui.sldDzRight.value = state.deadzone_right;
     ui.inpDzRight.value = state.deadzone_right;
+    ui.selDzShapeLeft.value = state.deadzone_shape_left || 'radial';
+    ui.selDzShapeRight.value = state.deadzone_shape_right || 'radial';
+    ui.sldAdzLeft.value = state.anti_deadzone_left || 0;
+    ui.inpAdzLeft.value = state.anti_deadzone_left || 0;
+    ui.sldAdzRight.value = state.anti_deadzone_right || 0;
+    ui.inpAdzRight.value = state.anti_deadzone_right || 0;
+    syncStickCurve(ui.selCurveLeft, ui.inpCurveLeft, state.stick_curve_l);
+    syncStickCurve(ui.selCurveRight, ui.inpCurveRight, state.stick_curve_r);
     ui.sldSensLeft.value = state.mouse_sens_left;
     ui.inpSensLeft.value = state.mouse_sens_left;
     ui.sldSensRight.value = state.mouse_sens_right;
@@ -1123,6 +2302,12 @@ function syncUiToState(state) {
         ui.sldSensTouch.value = state.mouse_sens_touchpad;
         ui.inpSensTouch.value = state.mouse_sens_touchpad;
     }
+    ui.chkTrackball.checked = state.trackball_friction != null;
+    if (state.trackball_friction != null && document.activeElement !== ui.inpTrackballFriction) ui.inpTrackballFriction.value = state.trackball_friction;
+    if (state.input_backend) ui.selInputBackend.value = state.input_backend;
+    if (state.pad_kind) ui.selPadKind.value = state.pad_kind;
+    ui.selShiftButton.value = state.shift_button || '';
+    ui.chkSwapSticks.checked = !!state.swap_sticks;
     ui.mainSldR.value = state.rgb_r;
     ui.mainSldG.value = state.rgb_g;
     ui.mainSldB.value = state.rgb_b;
@@ -1138,6 +2323,17 @@ function syncUiToState(state) {
     ui.selTriggerR2Mode.value = state.trigger_r2_mode || 0;
     ui.sldTriggerR2Start.value = state.trigger_r2_start || 0;
     ui.sldTriggerR2Force.value = state.trigger_r2_force || 0;
+    // Trigger output curves
+    if (state.trigger_curve_l) {
+        ui.inpTriggerL2Min.value = state.trigger_curve_l.min;
+        ui.inpTriggerL2Max.value = state.trigger_curve_l.max;
+        ui.inpTriggerL2Curve.value = state.trigger_curve_l.curve;
+        ui.inpTriggerR2Min.value = state.trigger_curve_r.min;
+        ui.inpTriggerR2Max.value = state.trigger_curve_r.max;
+        ui.inpTriggerR2Curve.value = state.trigger_curve_r.curve;
+        if (document.activeElement !== ui.inpTriggerL2Points) ui.inpTriggerL2Points.value = formatCurvePoints(state.trigger_curve_l.points);
+        if (document.activeElement !== ui.inpTriggerR2Points) ui.inpTriggerR2Points.value = formatCurvePoints(state.trigger_curve_r.points);
+    }
     
     updateTriggerL2();
     updateTriggerR2();
@@ -1148,6 +2344,8 @@ invoke('get_initial_state').then((json) => {
     if (json && json !== "{}") {
         currentState = JSON.parse(json);
         syncUiToState(currentState);
+        refreshVigemHealth();
+        refreshKeyNames();
         
         ui.debugPanel.style.display = currentState.debug_active ? 'flex' : 'none';
         updatePanelButtonTexts();
@@ -1156,9 +2354,51 @@ invoke('get_initial_state').then((json) => {
         hasNewState = true; 
         renderMappings();
         refreshProfilesList();
+
+        if (currentState.pending_crash_report) {
+            // Let the window finish showing before blocking on a dialog
+            setTimeout(offerCrashReport, 500);
+        }
     }
 });
 
+function offerCrashReport() {
+    const msg = 'DX3 closed unexpectedly last time and saved a crash report.\n\n' +
+        'Open it and the GitHub issue page so you can submit it?';
+    if (confirm(msg)) {
+        invoke('open_crash_report');
+        open('https://github.com/mantukin/dx3/issues/new');
+    } else {
+        invoke('dismiss_crash_report');
+    }
+}
+
+// Backend status codes -> display text (translate here)
+const STATUS_TEXT = {
+    waiting_for_controller: () => 'Waiting for controller...',
+    initializing_vigem: () => 'Initializing ViGEm...',
+    scanning: () => 'Scanning for controllers...',
+    hid_error: (e) => `HID Error: ${e}`,
+    searching: () => 'Searching for controller...',
+    active: (d) => `Active (Iface ${d.interface})`,
+    virtual_pad_error: (e) => `ViGEm Error: ${e}`,
+    no_virtual_pad: (e) => `Keyboard/mouse only (ViGEmBus: ${e})`,
+    virtual_pad_ready: () => 'Virtual Pad: Ready',
+    reconnecting: () => 'Reconnecting...',
+    refreshing_drivers: () => 'Refreshing drivers...',
+    paused: () => 'Paused',
+    disconnected: () => 'Disconnected',
+    keyboard_mouse: () => 'Keyboard/Mouse Mode',
+    waiting_for_connect: (d) => `${d.found} controller(s) found, waiting for Connect`,
+    suspended: () => 'Suspended (sleep/lock)',
+};
+
+function statusText(status) {
+    if (!status) return '';
+    const fmt = STATUS_TEXT[status.code];
+    return fmt ? fmt(status.detail) : status.code;
+}
+
 function setText(el, text) {
     if (el.textContent !== text) el.textContent = text;
 }
@@ -1180,8 +2420,28 @@ function render() {
 
     const isConnected = currentState.device_name !== 'None';
     setText(ui.status, isConnected ? 'Connected' : 'Disconnected');
+
+    // Touchpad calibration progress
+    setText(ui.btnTouchCal, currentState.touch_calibrating ? 'Done' : 'Calibrate');
+    const cal = currentState.touch_cal_progress;
+    if (currentState.touch_calibrating && cal && cal.max_x >= cal.min_x) {
+        setText(ui.touchCalStatus, `X ${cal.min_x}-${cal.max_x}, Y ${cal.min_y}-${cal.max_y}`);
+    }
+    ui.status.title = statusText(currentState.status);
     ui.status.className = 'value ' + (isConnected ? 'active' : 'error');
 
+    // Active modes (same as the overlay "mode" events)
+    const modes = [];
+    if (currentState.is_paused) modes.push('BYPASS');
+    if (currentState.status && currentState.status.code === 'keyboard_mouse') modes.push('KBM');
+    if (currentState.auto_sprint_engaged) modes.push('SPRINT');
+    if (isConnected && !currentState.vigembus_available) modes.push('NO PAD'); // Keyboard/mouse mappings only
+    if (currentState.virtual_pad_active && focusPaused(currentState)) modes.push('DESKTOP');
+    if (quietHoursActive(currentState)) modes.push('QUIET');
+    setText(ui.mode, modes.join(' · '));
+    setText(ui.vjoyStatus, currentState.vjoy_status);
+    setText(ui.inputBackendStatus, currentState.input_backend_status);
+
     // ViGEmBus
     if (!ignoreVigemUpdate) {
         if (currentState.vigembus_available) {
@@ -1189,7 +2449,9 @@ function render() {
             ui.statusVigem.className = "value active";
             ui.btnDlVigem.style.display = 'none';
         } else {
-            setText(ui.statusVigem, "Not Found");
+            // Installed but unreachable is a different fix than a missing driver
+            const installed = vigemHealth && vigemHealth.installed;
+            setText(ui.statusVigem, installed ? "Error" : "Not Found");
             ui.statusVigem.className = "value error";
             ui.btnDlVigem.style.display = 'inline-block';
         }
@@ -1219,12 +2481,20 @@ function render() {
 
     setText(ui.device, currentState.device_name);
 
+    const showControlling = isConnected && !!currentState.foreground_process;
+    const controllingDisplay = showControlling ? 'flex' : 'none';
+    if (ui.controllingLine.style.display !== controllingDisplay) ui.controllingLine.style.display = controllingDisplay;
+    if (showControlling) setText(ui.controlling, currentState.foreground_process);
+
     const isPaused = currentState.is_paused;
 
     const discDisplay = (isConnected || isPaused) ? 'inline-block' : 'none';
+    const identifyDisplay = isConnected ? 'inline-block' : 'none';
+    if (ui.btnIdentify.style.display !== identifyDisplay) ui.btnIdentify.style.display = identifyDisplay;
+    renderFoundControllers(isConnected);
+    setText(ui.btnDisconnect, currentState.manual_connect ? 'Disconnect' : 'Reconnect');
     if (ui.btnDisconnect.style.display !== discDisplay) {
         ui.btnDisconnect.style.display = discDisplay;
-        ui.btnDisconnect.textContent = 'Reconnect';
         ui.btnDisconnect.style.background = '#722f37';
         ui.btnDisconnect.style.borderColor = '#a33';
     }
@@ -1243,15 +2513,36 @@ function render() {
         ui.connWarning.style.display = 'none';
     }
 
-    if (s.battery > 0 || s.is_charging) {
-        const batText = `🔋 ${s.battery}%${s.is_charging ? '⚡' : ''}`;
+    if (s.charge_state === 'error') {
+        setText(ui.battery, '🔋 ⚠');
+        ui.battery.title = 'Charging error (temperature or voltage). Unplug and let the controller cool down.';
+        if (ui.battery.className !== 'value warn') ui.battery.className = 'value warn';
+    } else if (s.battery > 0 || s.is_charging) {
+        const batText = `🔋 ${s.battery}%${s.charge_state === 'full' ? '✔' : (s.is_charging ? '⚡' : '')}`;
         setText(ui.battery, batText);
+        ui.battery.title = { charging: 'Charging', full: 'Fully charged' }[s.charge_state] || '';
         const batClass = 'value ' + (s.battery <= 20 ? 'warn' : 'active');
         if (ui.battery.className !== batClass) ui.battery.className = batClass;
     } else {
         setText(ui.battery, '');
     }
 
+    // DualSense 3.5mm jack
+    setText(ui.jack, s.mic ? '🎤' : (s.headphones ? '🎧' : ''));
+    ui.jack.title = s.mic ? 'Headset with mic connected' : (s.headphones ? 'Headphones connected' : '');
+
+    // Tilt from the accelerometer (gravity), hidden for pads that don't report motion
+    const g = Math.hypot(s.accel_x, s.accel_y, s.accel_z);
+    if (g > 0.5) {
+        const deg = (r) => Math.round(r * 180 / Math.PI);
+        const pitch = deg(Math.atan2(-s.accel_z, Math.hypot(s.accel_x, s.accel_y)));
+        const roll = deg(Math.atan2(s.accel_x, s.accel_y));
+        setText(ui.motion, `∠ ${pitch}° / ${roll}°`);
+        ui.motion.title = `Tilt: pitch ${pitch}°, roll ${roll}°\nAccel: ${s.accel_x.toFixed(2)} / ${s.accel_y.toFixed(2)} / ${s.accel_z.toFixed(2)} g\nGyro: ${s.gyro_x.toFixed(0)} / ${s.gyro_y.toFixed(0)} / ${s.gyro_z.toFixed(0)} °/s`;
+    } else {
+        setText(ui.motion, '');
+    }
+
     const mappingsJSON = JSON.stringify(currentState.mappings);
     const gamepadJSON = JSON.stringify(s);
 
@@ -1274,7 +2565,7 @@ function render() {
                 const hex = val.toString(16).toUpperCase().padStart(2, '0');
                 if (!cell.innerHTML.startsWith(hex)) {
                     cell.innerHTML = `${hex}<span>${i}</span>`;
-                    cell.className = 'hex-cell ' + (val > 0 ? 'nonzero' : '');
+                    cell.classList.toggle('nonzero', val > 0);
                 }
             }
         }
@@ -1285,12 +2576,21 @@ function render() {
 
         setText(ui.btnFuzzer, currentState.fuzzer_active ? "STOP Fuzzing" : "START Auto-Discovery");
         setText(ui.fuzzerStatus, currentState.fuzzer_log);
+        setText(ui.btnFuzzScript, currentState.fuzz_script_active ? 'Stop Script' : 'Run Script');
+        const fuzzing = currentState.fuzzer_active || currentState.sweep_active || currentState.fuzz_script_active;
+        setText(ui.fuzzerStep, fuzzing && currentState.fuzzer_step > 0 ? String(currentState.fuzzer_step - 1) : '-');
         setText(ui.devicePath, currentState.device_path_str);
         setText(ui.lastWrite, currentState.last_write_status);
-        setText(ui.packetHex, currentState.last_packet_hex);
+        const annotation = annotateHex(currentState.last_packet_hex);
+        setText(ui.packetHex, annotation ? `${currentState.last_packet_hex}\n\n${annotation}` : currentState.last_packet_hex);
         setText(ui.btnSweep, currentState.sweep_active ? "STOP Sweep" : "Start RGB Sweep");
         setText(ui.logDevices, currentState.detected_devices_log);
         setText(ui.logProto, currentState.protocol_log);
+        setText(ui.layoutNote, currentState.layout_note || 'Built-in');
+        syncLayoutInputs(currentState.active_layout);
+        setText(ui.descriptorLayout, currentState.descriptor_layout || 'No gamepad report in descriptor');
+        setText(ui.btnCapture, currentState.capture_active ? 'Stop Capture' : 'Start Capture');
+        if (currentState.capture_active) setText(ui.captureCount, `${currentState.capture_packets} packets`);
     }
 }
 
@@ -1309,6 +2609,23 @@ function syncManualInputs(s) {
     ui.sldR.value = s.manual_r;
     ui.sldG.value = s.manual_g;
     ui.sldB.value = s.manual_b;
+    ui.selCrc.value = s.crc_seed_idx;
+    const fp = s.fuzzer_params;
+    ui.inpFuzzIds.value = formatList(fp.report_ids, 16);
+    ui.inpFuzzFlags.value = formatList(fp.flag_offsets);
+    ui.inpFuzzRgb.value = formatList(fp.rgb_offsets);
+    ui.inpFuzzValues.value = formatList(fp.values);
+    ui.inpFuzzBurst.value = fp.burst;
+    ui.inpFuzzDelay.value = fp.delay_ms;
+    ui.inpFuzzScript.value = s.fuzz_script;
+    ui.chkDescriptorParsing.checked = s.descriptor_parsing;
+    ui.chkSweepTargeted.checked = !!s.sweep_range;
+    if (s.sweep_range) {
+        ui.inpSweepStart.value = s.sweep_range.start;
+        ui.inpSweepEnd.value = s.sweep_range.end;
+        ui.inpSweepStep.value = s.sweep_range.step;
+        ui.inpSweepValues.value = formatList(s.sweep_range.values);
+    }
 }
 
 // --- Canvas Drawing Logic ---
@@ -1595,6 +2912,42 @@ function drawVisualizer(s, mappings) {
         ctx.stroke();
     }
 
+    // DualSense Edge Fn buttons and back paddles, labeled since the image has no Edge parts
+    if (edgeButtonsVisible()) {
+        const edgeBox = (key, label, active) => {
+            const b = HITBOXES[key];
+            const x = p(b.x, b.y).x - sz(b.w) / 2, y = p(b.x, b.y).y - sz(b.h) / 2;
+            const m = mappings && mappings.find(x => x.source === key);
+            if (m && isMappingModified(m)) {
+                ctx.strokeStyle = '#c678dd';
+                ctx.lineWidth = 2;
+                ctx.beginPath();
+                ctx.roundRect(x - 2, y - 2, sz(b.w) + 4, sz(b.h) + 4, 4);
+                ctx.stroke();
+            }
+            ctx.fillStyle = active ? cActive : cInactiveBg;
+            ctx.beginPath();
+            ctx.roundRect(x, y, sz(b.w), sz(b.h), 4);
+            ctx.fill();
+            if (hoveredButton === key) {
+                ctx.strokeStyle = '#61afef';
+                ctx.lineWidth = 2;
+                ctx.beginPath();
+                ctx.roundRect(x, y, sz(b.w), sz(b.h), 4);
+                ctx.stroke();
+            }
+            ctx.fillStyle = '#eee';
+            ctx.font = '11px "Segoe UI", sans-serif';
+            ctx.textAlign = 'center';
+            ctx.textBaseline = 'middle';
+            ctx.fillText(label, x + sz(b.w) / 2, y + sz(b.h) / 2 + 1);
+        };
+        edgeBox('FnLeft', 'Fn', s.btn_fn_l);
+        edgeBox('FnRight', 'Fn', s.btn_fn_r);
+        edgeBox('PaddleLeft', 'Paddle L', s.btn_paddle_l);
+        edgeBox('PaddleRight', 'Paddle R', s.btn_paddle_r);
+    }
+
     // --- Touchpad (Refined Shape) ---
     const drawTouchpadShape = (inset = 0, side = 'full') => {
         let c = {
@@ -1782,6 +3135,34 @@ function drawVisualizer(s, mappings) {
             ctx.fill();
         }
 
+        // Deadzone preview: ring = deadzone, red = raw, green = what the virtual pad gets
+        if (stickPreview) {
+            const i = key === 'L3' ? 0 : 2;
+            const dot = (vx, vy, color) => {
+                ctx.beginPath();
+                ctx.arc(center.x + vx * sz(strav), center.y + vy * sz(strav), sz(6), 0, Math.PI * 2);
+                ctx.fillStyle = color;
+                ctx.fill();
+            };
+            const dz = stickPreview.deadzone[i / 2] * sz(strav);
+            const reach = sz(strav);
+            ctx.beginPath();
+            if (stickPreview.shape[i / 2] === 'square') {
+                ctx.rect(center.x - dz, center.y - dz, dz * 2, dz * 2);
+            } else if (stickPreview.shape[i / 2] === 'axial') {
+                // Cross: a band along each axis where that axis reads zero
+                ctx.rect(center.x - dz, center.y - reach, dz * 2, reach * 2);
+                ctx.rect(center.x - reach, center.y - dz, reach * 2, dz * 2);
+            } else {
+                ctx.arc(center.x, center.y, dz, 0, Math.PI * 2);
+            }
+            ctx.strokeStyle = 'rgba(229, 192, 123, 0.8)';
+            ctx.lineWidth = 1;
+            ctx.stroke();
+            dot(stickPreview.raw[i], stickPreview.raw[i + 1], '#e06c75');
+            dot(stickPreview.output[i], stickPreview.output[i + 1], '#98c379');
+        }
+
         const stickKey = (key === 'L3' ? 'LeftStick' : 'RightStick');
         const isStickHover = (hoveredButton === stickKey);
         const isBtnHover = (hoveredButton === key);
@@ -1851,7 +3232,7 @@ function drawVisualizer(s, mappings) {
     const tpTopY = offsetY + 45 * scale;
     // Centered at cx. Width is 230px, so offset is 115px.
     ui.sensTouchCtrl.style.left = `${canvX + cx - 115}px`; 
-    ui.sensTouchCtrl.style.top = `${canvY + tpTopY - 85}px`;
+    ui.sensTouchCtrl.style.top = `${canvY + tpTopY - 127}px`;
 
     // Position Trigger Controls (Above L2/R2 indicators)
     // Indicators are 160px wide, centered at cx +/- colOffset
@@ -1996,11 +3377,97 @@ const updateDeadzones = (e) => {
 
     invoke('set_deadzones', { left: l, right: r });
 };
+// Deadzone preview: raw vs processed stick positions while the pointer is over a deadzone control
+let stickPreview = null;
+listen('stick-preview', (event) => {
+    stickPreview = event.payload;
+    window._forceRedraw = true;
+    hasNewState = true;
+});
+const setStickPreview = (on) => {
+    invoke('set_stick_preview', { val: on });
+    if (!on) {
+        stickPreview = null;
+        window._forceRedraw = true;
+    }
+};
+for (const panel of [ui.dzLeft, ui.dzRight]) {
+    panel.addEventListener('mouseenter', () => setStickPreview(true));
+    panel.addEventListener('mouseleave', () => setStickPreview(false));
+}
 ui.sldDzLeft.addEventListener('input', updateDeadzones);
 ui.inpDzLeft.addEventListener('change', updateDeadzones);
 ui.sldDzRight.addEventListener('input', updateDeadzones);
 ui.inpDzRight.addEventListener('change', updateDeadzones);
 
+const updateDeadzoneShapes = () => {
+    const left = ui.selDzShapeLeft.value;
+    const right = ui.selDzShapeRight.value;
+    if (currentState) {
+        currentState.deadzone_shape_left = left;
+        currentState.deadzone_shape_right = right;
+    }
+    invoke('set_deadzone_shapes', { left, right });
+};
+ui.selDzShapeLeft.addEventListener('change', updateDeadzoneShapes);
+ui.selDzShapeRight.addEventListener('change', updateDeadzoneShapes);
+
+const updateAntiDeadzones = (e) => {
+    const v = Math.min(0.9, Math.max(0, parseFloat(e.target.value) || 0));
+    if (e.target.id.includes('left')) {
+        ui.sldAdzLeft.value = v;
+        ui.inpAdzLeft.value = v;
+    } else {
+        ui.sldAdzRight.value = v;
+        ui.inpAdzRight.value = v;
+    }
+    const l = parseFloat(ui.inpAdzLeft.value) || 0;
+    const r = parseFloat(ui.inpAdzRight.value) || 0;
+    if (currentState) {
+        currentState.anti_deadzone_left = l;
+        currentState.anti_deadzone_right = r;
+    }
+    invoke('set_anti_deadzones', { left: l, right: r });
+};
+ui.sldAdzLeft.addEventListener('input', updateAntiDeadzones);
+ui.inpAdzLeft.addEventListener('change', updateAntiDeadzones);
+ui.sldAdzRight.addEventListener('input', updateAntiDeadzones);
+ui.inpAdzRight.addEventListener('change', updateAntiDeadzones);
+
+// Stick response curves: "linear", { exponential: 2 } or { custom: [[0.5, 0.25], ...] }
+const formatCurvePoints = (points) => (points || []).map(([x, y]) => `${x}:${y}`).join(', ');
+const parseCurvePoints = (text) => text.split(',')
+    .map(p => p.split(':').map(v => parseFloat(v)))
+    .filter(p => p.length === 2 && p.every(v => Number.isFinite(v)));
+const syncStickCurve = (select, input, curve) => {
+    const kind = !curve || typeof curve === 'string' ? 'linear' : Object.keys(curve)[0];
+    select.value = kind;
+    input.disabled = kind === 'linear';
+    if (document.activeElement === input) return;
+    if (kind === 'exponential') input.value = curve.exponential;
+    else if (kind === 'custom') input.value = formatCurvePoints(curve.custom);
+    else input.value = '';
+};
+const readStickCurve = (select, input) => {
+    if (select.value === 'exponential') return { exponential: Math.max(0.1, parseFloat(input.value) || 2) };
+    if (select.value === 'custom') return { custom: parseCurvePoints(input.value) };
+    return 'linear';
+};
+const updateStickCurves = () => {
+    const left = readStickCurve(ui.selCurveLeft, ui.inpCurveLeft);
+    const right = readStickCurve(ui.selCurveRight, ui.inpCurveRight);
+    ui.inpCurveLeft.disabled = left === 'linear';
+    ui.inpCurveRight.disabled = right === 'linear';
+    if (currentState) {
+        currentState.stick_curve_l = left;
+        currentState.stick_curve_r = right;
+    }
+    invoke('set_stick_curves', { left, right });
+};
+[ui.selCurveLeft, ui.inpCurveLeft, ui.selCurveRight, ui.inpCurveRight].forEach(el => {
+    el.addEventListener('change', updateStickCurves);
+});
+
 const updateSens = (e) => {
     let l, r;
     if (e.target.id.includes('left')) {
@@ -2028,7 +3495,47 @@ const updateSensTouch = (e) => {
     invoke('set_touchpad_sens', { sens: val });
 };
 ui.sldSensTouch.addEventListener('input', updateSensTouch);
+
+// Touchpad Calibration
+ui.btnTouchCal.addEventListener('click', async () => {
+    const start = !currentState.touch_calibrating;
+    try {
+        const cal = await invoke('set_touch_calibrating', { val: start });
+        currentState.touch_calibrating = start;
+        setText(ui.btnTouchCal, start ? 'Done' : 'Calibrate');
+        setText(ui.touchCalStatus, start ? 'Drag along all edges' : `X ${cal.min_x}-${cal.max_x}, Y ${cal.min_y}-${cal.max_y}`);
+    } catch (err) {
+        currentState.touch_calibrating = false;
+        setText(ui.btnTouchCal, 'Calibrate');
+        setText(ui.touchCalStatus, '');
+        alert(err);
+    }
+});
+ui.btnTouchCalReset.addEventListener('click', () => {
+    invoke('reset_touch_calibration');
+    setText(ui.touchCalStatus, 'Default range');
+});
 ui.inpSensTouch.addEventListener('change', updateSensTouch);
+const updateTrackball = () => invoke('set_trackball', {
+    friction: ui.chkTrackball.checked ? Math.min(0.99, Math.max(0.05, parseFloat(ui.inpTrackballFriction.value) || 0.9)) : null
+});
+[ui.chkTrackball, ui.inpTrackballFriction].forEach(e => e.addEventListener('change', updateTrackball));
+ui.selInputBackend.addEventListener('change', () => invoke('set_input_backend', { backend: ui.selInputBackend.value }));
+ui.selPadKind.addEventListener('change', () => invoke('set_pad_kind', { kind: ui.selPadKind.value }));
+ui.chkSwapSticks.addEventListener('change', () => {
+    currentState.swap_sticks = ui.chkSwapSticks.checked;
+    invoke('set_swap_sticks', { enabled: ui.chkSwapSticks.checked });
+});
+ui.selShiftButton.addEventListener('change', () => {
+    currentState.shift_button = ui.selShiftButton.value || null;
+    invoke('set_shift_button', { button: currentState.shift_button });
+    renderMappings();
+});
+ui.chkEditShiftLayer.addEventListener('change', () => {
+    editingShiftLayer = ui.chkEditShiftLayer.checked;
+    recordingFor = null;
+    renderMappings();
+});
 
 // Adaptive Triggers
 const updateTriggerL2 = () => {
@@ -2060,6 +3567,21 @@ const updateTriggerR2 = () => {
     if (el.type === 'range') el.addEventListener('input', updateTriggerR2);
 });
 
+// Trigger output curves
+const readTriggerCurve = (min, max, curve, points) => ({
+    min: Math.min(1, Math.max(0, parseFloat(min.value) || 0)),
+    max: Math.min(1, Math.max(0, parseFloat(max.value) || 0)),
+    curve: Math.max(0.1, parseFloat(curve.value) || 1),
+    points: parseCurvePoints(points.value)
+});
+const updateTriggerCurves = () => invoke('set_trigger_curves', {
+    left: readTriggerCurve(ui.inpTriggerL2Min, ui.inpTriggerL2Max, ui.inpTriggerL2Curve, ui.inpTriggerL2Points),
+    right: readTriggerCurve(ui.inpTriggerR2Min, ui.inpTriggerR2Max, ui.inpTriggerR2Curve, ui.inpTriggerR2Points)
+});
+[ui.inpTriggerL2Min, ui.inpTriggerL2Max, ui.inpTriggerL2Curve, ui.inpTriggerL2Points, ui.inpTriggerR2Min, ui.inpTriggerR2Max, ui.inpTriggerR2Curve, ui.inpTriggerR2Points].forEach(el => {
+    el.addEventListener('change', updateTriggerCurves);
+});
+
 window.addEventListener('resize', () => {
     if (currentState) {
         window._forceRedraw = true;